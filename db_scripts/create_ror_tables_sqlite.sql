@@ -0,0 +1,101 @@
+drop table if exists version_details;
+create table version_details
+(
+      version           varchar     not null
+    , data_date         varchar     not null
+    , data_days         int         not null
+    , import_datetime   timestamp   not null  default current_timestamp
+);
+
+drop table if exists core_data;
+create table core_data
+(
+      id                varchar     not null primary key
+    , ror_full_id       varchar     not null
+    , status            varchar     not null
+    , established       int         null
+);
+
+drop table if exists admin_data;
+create table admin_data
+(
+      id                varchar     not null primary key
+    , created           date        not null
+    , cr_schema         varchar     not null
+    , last_modified     date        not null
+    , lm_schema         varchar     not null
+);
+
+drop table if exists names;
+create table names
+(
+      id                varchar     not null
+    , value             varchar     not null
+    , name_type         varchar     not null
+    , is_ror_name       bool        null
+    , lang              varchar     null
+);
+create index names_idx on names(id);
+
+drop table if exists locations;
+create table locations
+(
+      id                varchar     not null
+    , geonames_id       int         null
+    , name              varchar     null
+    , lat               real        null
+    , lng               real        null
+    , continent_code    varchar     null
+    , continent_name    varchar     null
+    , country_code      varchar     null
+    , country_name      varchar     null
+    , country_subdivision_code      varchar     null
+    , country_subdivision_name      varchar     null
+    , is_primary        bool        not null default false
+);
+create index locations_idx on locations(id);
+
+drop table if exists external_ids;
+create table external_ids
+(
+      id                varchar     not null
+    , id_type           varchar     not null
+    , id_value          varchar     not null
+    , is_preferred      bool        null
+);
+create index external_ids_idx on external_ids(id);
+
+drop table if exists links;
+create table links
+(
+      id                varchar	    not null
+    , link_type         varchar     not null
+    , value             varchar     not null
+);
+create index links_idx on links(id);
+
+drop table if exists type;
+create table type
+(
+      id                varchar	    not null
+    , org_type          varchar     not null
+);
+create index type_idx on type(id);
+
+drop table if exists relationships;
+create table relationships
+(
+      id                varchar     not null
+    , rel_type          varchar     not null
+    , related_id        varchar     not null
+    , related_label     varchar     not null
+);
+create index relationships_idx on relationships(id);
+
+drop table if exists domains;
+create table domains
+(
+      id                varchar     not null
+    , value             varchar     not null
+);
+create index domains_idx on domains(id);
@@ -3,7 +3,12 @@
 // into the same error type, allowing function signatures returning a result type 
 // to propogate any error up the call stack by simply using the '?' operator.
 // Also defines a 'custom error' type to deal with cases not covered by 
-// the errors returned from the standard or external crates.
+// the errors returned from the standard or external crates, and a 'network 
+// error' type that wraps the error from any transport (e.g. HTTP) library.
+// An interruption of the run (by Ctrl-C) is also returned as an error, so
+// that it ends the program in the same way, but with its own exit code, as
+// is source data that cannot be used (e.g. is not a JSON array of ROR
+// records), so that it can be told apart from a configuration error.
 
 use std::fmt;
 use std::error::Error;
@@ -14,8 +19,11 @@ pub enum AppError {
     SqErr(sqlx::Error),
     IoErr(std::io::Error),
     SdErr(serde_json::Error),
-    LgErr(log::SetLoggerError),
+    LgErr(Box<dyn Error + Send + Sync>),
     CsErr(CustomError),
+    NetErr(NetworkError),
+    IrErr(CustomError),
+    DtErr(CustomError),
 }
 
 impl std::error::Error for AppError {}
@@ -30,6 +38,31 @@ impl fmt::Display for AppError { // Error message for users.
             AppError::SdErr(ref err) => write!(f, "serde json error: {}", err),
             AppError::LgErr(ref err) => write!(f, "log set config error: {}", err),
             AppError::CsErr(ref err) => write!(f, "file error: {}", err),
+            AppError::NetErr(ref err) => write!(f, "network error: {}", err),
+            AppError::IrErr(ref err) => write!(f, "interrupted: {}", err),
+            AppError::DtErr(ref err) => write!(f, "source data error: {}", err),
+        }
+    }
+}
+
+impl AppError {
+
+    // The process exit code for each class of error, allowing scripts that
+    // call the program to distinguish between them. A clap 'error' that is
+    // really a request for the help or version text returns 0.
+
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            AppError::DeErr(_) => 2,
+            AppError::CpErr(ref err) => if err.exit_code() == 0 { 0 } else { 2 },
+            AppError::CsErr(_) => 2,
+            AppError::IoErr(_) => 3,
+            AppError::SqErr(_) => 4,
+            AppError::SdErr(_) => 5,
+            AppError::DtErr(_) => 5,
+            AppError::NetErr(_) => 6,
+            AppError::IrErr(_) => 130,
+            AppError::LgErr(_) => 1,
         }
     }
 }
@@ -77,7 +110,7 @@ impl From<serde_json::Error> for AppError {
 
 impl From<log::SetLoggerError> for AppError {
     fn from(err: log::SetLoggerError) -> AppError {
-        AppError::LgErr(err)
+        AppError::LgErr(Box::new(err))
     }
 }
 
@@ -87,6 +120,12 @@ impl From<CustomError> for AppError {
     }
 }
 
+impl From<NetworkError> for AppError {
+    fn from(err: NetworkError) -> AppError {
+        AppError::NetErr(err)
+    }
+}
+
 
 #[derive(Debug)]
 pub struct CustomError {
@@ -110,4 +149,69 @@ impl fmt::Display for CustomError {
 }
 
 
+// Wraps the underlying transport error, with the resource being accessed. 
 
+#[derive(Debug)]
+pub struct NetworkError {
+    resource: String,
+    source: Box<dyn Error + Send + Sync>,
+}
+
+impl NetworkError {
+    pub fn new(resource: &str, source: impl Into<Box<dyn Error + Send + Sync>>) -> NetworkError {
+        NetworkError {
+            resource: resource.to_string(),
+            source: source.into(),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unable to access {} ({}) - check the network connection and that the server is available", 
+               self.resource, self.source)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_network_error_display() {
+        let io_err = std::io::Error::new(std::io::ErrorKind::TimedOut, "connection timed out");
+        let net_err = NetworkError::new("https://zenodo.org/api/records", io_err);
+        let app_err: AppError = net_err.into();
+        assert!(matches!(app_err, AppError::NetErr(_)));
+        assert_eq!(app_err.to_string(), "network error: unable to access https://zenodo.org/api/records \
+                    (connection timed out) - check the network connection and that the server is available");
+        
+        let net_err = NetworkError::new("https://zenodo.org", "no response");
+        assert_eq!(net_err.source().unwrap().to_string(), "no response");
+    }
+
+    #[test]
+    fn check_exit_codes_distinguish_error_classes() {
+        let cs_err: AppError = CustomError::new("folder does not exist").into();
+        assert_eq!(cs_err.exit_code(), 2);
+        let io_err: AppError = std::io::Error::new(std::io::ErrorKind::NotFound, "no file").into();
+        assert_eq!(io_err.exit_code(), 3);
+        let sq_err: AppError = sqlx::Error::PoolTimedOut.into();
+        assert_eq!(sq_err.exit_code(), 4);
+        let sd_err: AppError = serde_json::from_str::<serde_json::Value>("[{").unwrap_err().into();
+        assert_eq!(sd_err.exit_code(), 5);
+        let dt_err = AppError::DtErr(CustomError::new("expected a JSON array of ROR records, found an object"));
+        assert_eq!(dt_err.exit_code(), 5);
+        let net_err: AppError = NetworkError::new("https://zenodo.org", "no response").into();
+        assert_eq!(net_err.exit_code(), 6);
+        let ir_err = AppError::IrErr(CustomError::new("stopped after 250 records"));
+        assert_eq!(ir_err.exit_code(), 130);
+    }
+}
@@ -1,75 +1,119 @@
+use futures_util::TryStreamExt;
 use sqlx::{Pool, Postgres};
-use std::path::PathBuf;
+use sqlx::postgres::PgPoolCopyExt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use crate::error_defs::{AppError, CustomError};
-use chrono::Local;
+use crate::setup::table_selected;
+use crate::setup::get_timestamp;
+use crate::summarise::{fetch_country_counts, CountryCount};
 
-pub async fn generate_csv(output_folder : &PathBuf, data_version: &String, pool : &Pool<Postgres>) -> Result<(), AppError>
+const UTF8_BOM: &str = "\u{feff}";
+
+// The field delimiter of the csv files, and whether they start with a UTF-8 
+// byte order mark, as some spreadsheet programs (e.g. Excel in many European
+// locales) expect semicolons, and only recognise UTF-8 files with a BOM.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CsvFormat {
+    pub delimiter: char,
+    pub bom: bool,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        CsvFormat { delimiter: ',', bom: false }
+    }
+}
+
+// The details shared by each of the files of a set of csv exports.
+
+struct CsvFileSet<'a> {
+    output_folder: &'a PathBuf,
+    data_version: &'a String,
+    datetime_string: Option<String>,
+    tables: &'a [String],
+    format: &'a CsvFormat,
+}
+
+pub async fn generate_csv(output_folder : &PathBuf, data_version: &String, tables: &[String], append: bool, 
+                          format: &CsvFormat, pool : &Pool<Postgres>) -> Result<Vec<(PathBuf, u64)>, AppError>
 {
-    let datetime_string = Local::now().format("%m-%d %H%M%S").to_string();
+    // Returns the path of each file written, with its number of rows. If 
+    // appending, no date-time is used, as the rows are added to a single
+    // file for each table, accumulating the data of successive versions.
+
+    let mut files = Vec::new();
+    let datetime_string = if append { None } else { Some(get_timestamp()) };
+    let file_set = CsvFileSet { output_folder, data_version, datetime_string, tables, format };
 
     // 1) Version Summary 
 
     let table_type = "summary".to_string();
     let select_statement = r#"select * from smm.version_summaries where vcode = '"#.to_string() + data_version + r#"'"#;
-    generate_file(output_folder, data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
     // 2) Attribute Summaries
     
     let table_type = "attributes".to_string();
     let select_statement = r#"select * from smm.attributes_summary where vcode = '"#.to_string() 
                            + data_version + r#"' order by att_name, id"#;
-    generate_file(output_folder, data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
     
     // 3) Count distributions
 
     let table_type = "counts".to_string();
     let select_statement = r#"select * from smm.count_distributions where vcode = '"#.to_string() 
                            + data_version + r#"' order by count_type, count"#;
-    generate_file(output_folder, data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
     // 4) Ranked count distributions
 
     let table_type = "ranked_counts".to_string();
     let select_statement = r#"select * from smm.ranked_distributions where vcode = '"#.to_string() 
                            + data_version + r#"' order by dist_type, rank"#;
-    generate_file(output_folder, data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
     // 5) Singletons
 
     let table_type = "singletons".to_string();
     let select_statement = r#"select * from smm.singletons where vcode = '"#.to_string() + data_version + r#"'"#;
-    generate_file(output_folder, data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
     // 6) Org types and WOLC
 
     let table_type = "orgtypes and names wolc".to_string();
     let select_statement = r#"select * from smm.org_type_and_lang_code where vcode = '"#.to_string() 
                         + data_version + r#"' order by org_type, name_type"#;
-    generate_file(output_folder, data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
     // 7) Orgs types and relationships
 
     let table_type = "orgtypes and relationships".to_string();
     let select_statement = r#"select * from smm.org_type_and_relationships where vcode = '"#.to_string() 
                         + data_version + r#"' order by org_type, rel_type"#;
-    generate_file(output_folder, data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
-    Ok(())
+    Ok(files)
 }
 
 
 
-pub async fn generate_all_versions_csv(output_folder : &PathBuf, pool : &Pool<Postgres>) -> Result<(), AppError>
+pub async fn generate_all_versions_csv(output_folder : &PathBuf, tables: &[String], format: &CsvFormat, 
+                                       pool : &Pool<Postgres>) -> Result<Vec<(PathBuf, u64)>, AppError>
 {
+    let mut files = Vec::new();
 
-    let datetime_string = Local::now().format("%m-%d %H%M%S").to_string();
+    let datetime_string = Some(get_timestamp());
     let data_version = "All versions".to_string();
+    let file_set = CsvFileSet { output_folder, data_version: &data_version, datetime_string, tables, format };
 
     // 1) Version Summary 
 
     let table_type = "summary".to_string();
     let select_statement = r#"select * from smm.version_summaries where vcode <> 'v1.57' order by vcode"#.to_string();
-    generate_file(output_folder, &data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
     // 2) Attribute Summaries
     
@@ -80,7 +124,7 @@ pub async fn generate_all_versions_csv(output_folder : &PathBuf, pool : &Pool<Po
                              inner join smm.attributes_summary s
                              on vs.vcode = s.vcode
                              where vs.vcode <> 'v1.57' order by vcode, att_name, id"#.to_string();
-    generate_file(output_folder, &data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
     // 3) Count distributions
 
@@ -91,7 +135,7 @@ pub async fn generate_all_versions_csv(output_folder : &PathBuf, pool : &Pool<Po
                              inner join smm.count_distributions s
                              on vs.vcode = s.vcode
                              where vs.vcode <> 'v1.57' order by vcode, count_type, count"#.to_string();
-    generate_file(output_folder, &data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
     // 4) Ranked count distributions
 
@@ -102,7 +146,7 @@ pub async fn generate_all_versions_csv(output_folder : &PathBuf, pool : &Pool<Po
                              inner join smm.ranked_distributions s
                              on vs.vcode = s.vcode
                              where vs.vcode <> 'v1.57' order by vcode, dist_type, rank"#.to_string();
-    generate_file(output_folder, &data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
     // 5) Singletons
 
@@ -113,7 +157,7 @@ pub async fn generate_all_versions_csv(output_folder : &PathBuf, pool : &Pool<Po
                              inner join smm.singletons s
                              on vs.vcode = s.vcode
                              where vs.vcode <> 'v1.57' order by vcode"#.to_string();
-    generate_file(output_folder, &data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
     // 6) Org types and WOLC
 
@@ -124,7 +168,7 @@ pub async fn generate_all_versions_csv(output_folder : &PathBuf, pool : &Pool<Po
                              inner join smm.org_type_and_lang_code s
                              on vs.vcode = s.vcode
                              where vs.vcode <> 'v1.57' order by vcode, org_type, name_type"#.to_string();
-    generate_file(output_folder, &data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
 
     // 7) Orgs types and relationships
@@ -137,18 +181,85 @@ pub async fn generate_all_versions_csv(output_folder : &PathBuf, pool : &Pool<Po
                              on vs.vcode = s.vcode
                              where vs.vcode <> 'v1.57' order by vcode, org_type, rel_type"#.to_string();
                              r#"select * from smm.org_type_and_relationships where vcode <> 'v1.57' order by vcode, org_type, rel_type"#;
-    generate_file(output_folder, &data_version, &select_statement, &datetime_string, &table_type, pool).await?;
+    files.extend(generate_file(&file_set, &select_statement, &table_type, pool).await?);
 
-    Ok(())
+    Ok(files)
 }
 
 
 
-async fn  generate_file(output_folder: &PathBuf, data_version: &String, select_statement: &String,
-                    datetime_string: &String, table_type: &String, pool : &Pool<Postgres>) -> Result<(), AppError> {
+pub async fn generate_country_csv(output_folder : &PathBuf, format: &CsvFormat, pool : &Pool<Postgres>) -> Result<(PathBuf, u64), AppError>
+{
+    // The counts come from the src tables, so the file is named using the version 
+    // currently in those tables. Unlike the smm csv files, this file is written 
+    // by the program rather than by the database server.
+
+    let sql = "SELECT version from src.version_details;";
+    let src_version: String = sqlx::query_scalar(sql).fetch_one(pool).await?;
+
+    let countries = fetch_country_counts(pool).await?;
+    let output_file_name = PathBuf::from(format!("{} country counts {}.csv", src_version, get_timestamp()));
+    let output_file_path: PathBuf = [output_folder, &output_file_name].iter().collect();
+    fs::write(&output_file_path, get_country_csv(&countries, format))?;
+    Ok((output_file_path, countries.len() as u64))
+}
+
+
+fn get_country_csv(countries: &[CountryCount], format: &CsvFormat) -> String {
+    let d = format.delimiter;
+    let mut csv = if format.bom { UTF8_BOM.to_string() } else { String::new() };
+    csv += &format!("country_code{d}country_name{d}org_count\n");
+    for c in countries.iter() {
+        csv += &format!("{}{d}{}{d}{}\n", csv_field(&c.country_code, d), csv_field(&c.country_name, d), c.num_orgs);
+    }
+    csv
+}
+
+
+fn csv_field(value: &str, delimiter: char) -> String {
+
+    // Values that include the delimiter, a quote or a line break (e.g. 'Korea, 
+    // Republic of') are quoted, with any quotes within them doubled.
+
+    if value.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    }
+    else {
+        value.to_string()
+    }
+}
+
+
+async fn  generate_file(file_set: &CsvFileSet<'_>, select_statement: &String, table_type: &String, 
+                    pool : &Pool<Postgres>) -> Result<Option<(PathBuf, u64)>, AppError> {
+
+    // If a selection of tables has been made only those files are generated.
+    // The number of rows copied is returned with the path of the file.
+    // Without a date-time the rows are appended to the table's existing file.
+
+    if !table_selected(&table_type.replace(' ', "_"), file_set.tables) {
+        return Ok(None)
+    }
+
+    let (output_folder, data_version, format) = (file_set.output_folder, file_set.data_version, file_set.format);
+    let datetime_string = match &file_set.datetime_string {
+        Some(dt) => dt,
+        None => return append_file(output_folder, data_version, select_statement, table_type, format, pool).await.map(Some),
+    };
 
     let output_file_name = PathBuf::from(format!("{} {} {}.csv", data_version, table_type, datetime_string));
     let output_file_path: PathBuf = [output_folder, &output_file_name].iter().collect();
+
+    // The server cannot start a file with a BOM, so the rows are instead 
+    // copied to the program, and the file written by it.
+
+    if format.bom {
+        let sql = format!("copy ({}) to stdout {}", select_statement, get_copy_options(format));
+        let csv = fetch_csv(&sql, table_type, pool).await?;
+        fs::write(&output_file_path, format!("{}{}", UTF8_BOM, csv))?;
+        let rows = csv.split_once('\n').map(|(_, rows)| count_csv_records(rows)).unwrap_or(0);
+        return Ok(Some((output_file_path, rows)))
+    }
     let output_file = match output_file_path.to_str() {
         Some(s) => s.to_string(),
         None => {
@@ -157,8 +268,188 @@ async fn  generate_file(output_folder: &PathBuf, data_version: &String, select_s
             return Err(AppError::CsErr(cf_err))
         },
     };
-    let sql = r#"copy ("#.to_string() + select_statement + r#") to '"# + &output_file + r#"' DELIMITER ',' CSV HEADER"#;
-    sqlx::raw_sql(&sql).execute(pool).await?;
-    Ok(())
+    let sql = r#"copy ("#.to_string() + select_statement + r#") to '"# + &output_file + r#"' "# + &get_copy_options(format);
+    let res = sqlx::raw_sql(&sql).execute(pool).await?;
+    Ok(Some((output_file_path, res.rows_affected())))
 
-}
\ No newline at end of file
+}
+
+
+async fn append_file(output_folder: &Path, data_version: &str, select_statement: &str,
+                    table_type: &str, format: &CsvFormat, pool : &Pool<Postgres>) -> Result<(PathBuf, u64), AppError> {
+
+    // The rows, tagged with the data version, are copied to the program rather
+    // than written by the server, as the server cannot append to a file.
+
+    let output_file_path = output_folder.join(format!("{} appended.csv", table_type));
+    let sql = format!("copy (select '{}' as data_version, t.* from ({}) t) to stdout {}", 
+                       data_version, select_statement, get_copy_options(format));
+    let csv = fetch_csv(&sql, table_type, pool).await?;
+    let rows = append_csv_rows(&output_file_path, &csv, format.bom)?;
+    Ok((output_file_path, rows))
+}
+
+
+async fn fetch_csv(sql: &str, table_type: &str, pool : &Pool<Postgres>) -> Result<String, AppError> {
+    let data: Vec<u8> = pool.copy_out_raw(sql).await?
+                    .try_fold(Vec::new(), |mut data, chunk| async move { data.extend_from_slice(&chunk); Ok(data) }).await?;
+    match String::from_utf8(data) {
+        Ok(csv) => Ok(csv),
+        Err(_) => {
+            let msg = format!("The {} data could not be read as UTF-8 text", table_type);
+            Err(AppError::CsErr(CustomError::new(&msg)))
+        },
+    }
+}
+
+
+fn get_copy_options(format: &CsvFormat) -> String {
+
+    // A tab is given as an escape string, as the delimiter of the copy statement.
+
+    if format.delimiter == '\t' {
+        r"DELIMITER E'\t' CSV HEADER".to_string()
+    }
+    else {
+        format!("DELIMITER '{}' CSV HEADER", format.delimiter)
+    }
+}
+
+
+fn append_csv_rows(file_path: &Path, csv: &str, bom: bool) -> Result<u64, AppError> {
+
+    // A new file is written with the header line, after a BOM if one is used.
+    // The header of an existing file must match that of the new rows, so that
+    // a change in the columns of a table (or in the delimiter) does not produce
+    // a file with rows of different shapes.
+
+    let (header, rows) = csv.split_once('\n').unwrap_or((csv, ""));
+    let existing = match fs::read_to_string(file_path) {
+        Ok(contents) => Some(contents),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    match existing {
+        Some(contents) if !contents.is_empty() => {
+            let existing_header = contents.trim_start_matches(UTF8_BOM).lines().next().unwrap_or("");
+            if existing_header != header.trim_end_matches('\r') {
+                let msg = format!("The columns of {} ({}) do not match those of the rows to be appended ({})", 
+                                   file_path.display(), existing_header, header);
+                return Err(AppError::CsErr(CustomError::new(&msg)))
+            }
+            let mut file = fs::OpenOptions::new().append(true).open(file_path)?;
+            if !contents.ends_with('\n') {
+                file.write_all(b"\n")?;
+            }
+            file.write_all(rows.as_bytes())?;
+        },
+        _ if bom => fs::write(file_path, format!("{}{}", UTF8_BOM, csv))?,
+        _ => fs::write(file_path, csv)?,
+    }
+    Ok(count_csv_records(rows))
+}
+
+
+fn count_csv_records(rows: &str) -> u64 {
+
+    // Line breaks within quoted values do not end a record.
+
+    let mut in_quotes = false;
+    let mut count = 0;
+    for c in rows.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '\n' if !in_quotes => count += 1,
+            _ => {},
+        }
+    }
+    if !rows.is_empty() && !rows.ends_with('\n') {
+        count += 1;
+    }
+    count
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn country(code: &str, name: &str, num_orgs: i64) -> CountryCount {
+        CountryCount { country_code: code.to_string(), country_name: name.to_string(), num_orgs }
+    }
+
+    #[test]
+    fn check_country_csv_lines() {
+        let countries = vec![country("AU", "Australia", 7), country("KR", "Korea, Republic of", 2),
+                             country("unknown", "unknown", 1)];
+        assert_eq!(get_country_csv(&countries, &CsvFormat::default()), "country_code,country_name,org_count\n\
+                                                 AU,Australia,7\n\
+                                                 KR,\"Korea, Republic of\",2\n\
+                                                 unknown,unknown,1\n");
+        assert_eq!(csv_field(r#"The "Best" University"#, ','), r#""The ""Best"" University""#);
+    }
+
+    #[test]
+    fn check_country_csv_with_semicolons_and_bom() {
+        let countries = vec![country("KR", "Korea, Republic of", 2), country("XX", "Semi; colon", 1)];
+        let format = CsvFormat { delimiter: ';', bom: true };
+        let csv = get_country_csv(&countries, &format);
+        assert!(csv.starts_with(UTF8_BOM));
+        assert_eq!(&csv.as_bytes()[..3], &[0xef, 0xbb, 0xbf]);
+        assert_eq!(csv.trim_start_matches(UTF8_BOM), "country_code;country_name;org_count\n\
+                                                      KR;Korea, Republic of;2\n\
+                                                      XX;\"Semi; colon\";1\n");
+        assert!(!get_country_csv(&countries, &CsvFormat::default()).starts_with(UTF8_BOM));
+    }
+
+    #[test]
+    fn check_copy_options_use_delimiter() {
+        assert_eq!(get_copy_options(&CsvFormat::default()), "DELIMITER ',' CSV HEADER");
+        assert_eq!(get_copy_options(&CsvFormat { delimiter: ';', bom: true }), "DELIMITER ';' CSV HEADER");
+        assert_eq!(get_copy_options(&CsvFormat { delimiter: '\t', bom: false }), r"DELIMITER E'\t' CSV HEADER");
+    }
+
+    #[test]
+    fn check_appended_csv_file_created() {
+        let folder = std::env::temp_dir().join("ror1_append_csv_test_new");
+        fs::create_dir_all(&folder).unwrap();
+        let file_path = folder.join("summary appended.csv");
+        let _ = fs::remove_file(&file_path);
+
+        let csv = "data_version,vcode,num_orgs\nv1.59,v1.59,120\n";
+        assert_eq!(append_csv_rows(&file_path, csv, false).unwrap(), 1);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), csv);
+    }
+
+    #[test]
+    fn check_bom_only_at_start_of_appended_csv_file() {
+        let folder = std::env::temp_dir().join("ror1_append_csv_test_bom");
+        fs::create_dir_all(&folder).unwrap();
+        let file_path = folder.join("counts appended.csv");
+        let _ = fs::remove_file(&file_path);
+
+        assert_eq!(append_csv_rows(&file_path, "data_version;vcode\nv1.59;v1.59\n", true).unwrap(), 1);
+        assert_eq!(append_csv_rows(&file_path, "data_version;vcode\nv1.60;v1.60\n", true).unwrap(), 1);
+        let bytes = fs::read(&file_path).unwrap();
+        assert_eq!(&bytes[..3], &[0xef, 0xbb, 0xbf]);
+        assert_eq!(&bytes[3..], b"data_version;vcode\nv1.59;v1.59\nv1.60;v1.60\n");
+    }
+
+    #[test]
+    fn check_csv_rows_appended_to_existing_file() {
+        let folder = std::env::temp_dir().join("ror1_append_csv_test_existing");
+        fs::create_dir_all(&folder).unwrap();
+        let file_path = folder.join("singletons appended.csv");
+        fs::write(&file_path, "data_version,id,description\nv1.59,1,First\n").unwrap();
+
+        let csv = "data_version,id,description\nv1.60,1,First\nv1.60,2,\"Second,\nand more\"\n";
+        assert_eq!(append_csv_rows(&file_path, csv, false).unwrap(), 2);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), 
+                   "data_version,id,description\nv1.59,1,First\nv1.60,1,First\nv1.60,2,\"Second,\nand more\"\n");
+
+        let res = append_csv_rows(&file_path, "data_version,id,details\nv1.61,1,First\n", false);
+        assert!(res.unwrap_err().to_string().contains("do not match"));
+        assert!(fs::read_to_string(&file_path).unwrap().ends_with("and more\"\n"));
+    }
+}
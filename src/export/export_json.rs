@@ -0,0 +1,143 @@
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::error_defs::AppError;
+
+// A simplified ROR-style record, reconstructed from the processed data in
+// the src schema. Type codes are translated back to names using the lup tables.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JsonOrg {
+    pub id: String,
+    pub ror_name: String,
+    pub status: String,
+    pub established: Option<i32>,
+    pub location: Option<JsonLocation>,
+    pub names: Vec<JsonName>,
+    pub external_ids: Vec<JsonExtId>,
+    pub types: Vec<String>,
+    pub domains: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, sqlx::FromRow, Debug, Clone, PartialEq)]
+pub struct JsonLocation {
+    #[serde(skip)]
+    pub id: String,
+    pub geonames_id: Option<i32>,
+    pub name: Option<String>,
+    pub lat: Option<f32>,
+    pub lng: Option<f32>,
+    pub country_code: Option<String>,
+    pub country_name: Option<String>,
+    pub country_subdivision_code: Option<String>,
+    pub country_subdivision_name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, sqlx::FromRow, Debug, Clone, PartialEq)]
+pub struct JsonName {
+    #[serde(skip)]
+    pub id: String,
+    pub value: String,
+    pub name_type: String,
+    pub lang: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, sqlx::FromRow, Debug, Clone, PartialEq)]
+pub struct JsonExtId {
+    #[serde(skip)]
+    pub id: String,
+    pub id_type: String,
+    pub value: String,
+    pub preferred: bool,
+}
+
+#[derive(sqlx::FromRow)]
+struct CoreRow {
+    id: String,
+    ror_full_id: String,
+    ror_name: String,
+    status: String,
+    established: Option<i32>,
+}
+
+#[derive(sqlx::FromRow)]
+struct IdValueRow {
+    id: String,
+    value: String,
+}
+
+
+pub async fn export_json(output_file_path: &PathBuf, pool: &Pool<Postgres>) -> Result<usize, AppError> {
+
+    // Each table is read in full, ordered by id, and the rows then grouped
+    // by id, rather than running separate queries for each organisation.
+
+    let sql = r#"select c.id, c.ror_full_id, c.ror_name, coalesce(s.name, c.status::varchar) as status, c.established
+                 from src.core_data c
+                 left join lup.ror_status_types s on c.status = s.id
+                 order by c.id"#;
+    let core_rows: Vec<CoreRow> = sqlx::query_as(sql).fetch_all(pool).await?;
+
+    // The location given is the primary location, i.e. the one that provided
+    // the location of the organisation in src.core_data.
+
+    let sql = r#"select distinct on (l.id) l.id, l.geonames_id, l.location as name, l.lat, l.lng,
+                 l.country_code, l.country_name, l.csubdiv_code as country_subdivision_code,
+                 l.csubdiv_name as country_subdivision_name
+                 from src.locations l
+                 order by l.id, l.is_primary desc, l.geonames_id"#;
+    let locations: Vec<JsonLocation> = sqlx::query_as(sql).fetch_all(pool).await?;
+    let mut locations: HashMap<String, JsonLocation> = locations.into_iter().map(|l| (l.id.clone(), l)).collect();
+
+    let sql = r#"select n.id, n.value, coalesce(t.name, n.name_type::varchar) as name_type, n.lang_code as lang
+                 from src.names n
+                 left join lup.ror_name_types t on n.name_type = t.id
+                 order by n.id, n.name_type, n.value"#;
+    let names: Vec<JsonName> = sqlx::query_as(sql).fetch_all(pool).await?;
+    let mut names = group_by_id(names, |n| &n.id);
+
+    let sql = r#"select e.id, coalesce(t.name, e.id_type::varchar) as id_type, e.id_value as value, e.is_preferred as preferred
+                 from src.external_ids e
+                 left join lup.ror_id_types t on e.id_type = t.id
+                 order by e.id, e.id_type, e.id_value"#;
+    let ext_ids: Vec<JsonExtId> = sqlx::query_as(sql).fetch_all(pool).await?;
+    let mut ext_ids = group_by_id(ext_ids, |e| &e.id);
+
+    let sql = r#"select y.id, coalesce(t.name, y.org_type::varchar) as value
+                 from src.type y
+                 left join lup.ror_org_types t on y.org_type = t.id
+                 order by y.id, y.org_type"#;
+    let types: Vec<IdValueRow> = sqlx::query_as(sql).fetch_all(pool).await?;
+    let mut types = group_by_id(types, |t| &t.id);
+
+    let sql = r#"select id, domain as value from src.domains order by id, domain"#;
+    let domains: Vec<IdValueRow> = sqlx::query_as(sql).fetch_all(pool).await?;
+    let mut domains = group_by_id(domains, |d| &d.id);
+
+    let orgs: Vec<JsonOrg> = core_rows.into_iter().map(|c| JsonOrg {
+        location: locations.remove(&c.id),
+        names: names.remove(&c.id).unwrap_or_default(),
+        external_ids: ext_ids.remove(&c.id).unwrap_or_default(),
+        types: types.remove(&c.id).unwrap_or_default().into_iter().map(|t| t.value).collect(),
+        domains: domains.remove(&c.id).unwrap_or_default().into_iter().map(|d| d.value).collect(),
+        id: c.ror_full_id,
+        ror_name: c.ror_name,
+        status: c.status,
+        established: c.established,
+    }).collect();
+
+    let json = serde_json::to_string_pretty(&orgs)?;
+    fs::write(output_file_path, json)?;
+    Ok(orgs.len())
+}
+
+
+fn group_by_id<T>(rows: Vec<T>, get_id: impl Fn(&T) -> &String) -> HashMap<String, Vec<T>> {
+    let mut groups: HashMap<String, Vec<T>> = HashMap::new();
+    for row in rows {
+        groups.entry(get_id(&row).clone()).or_default().push(row);
+    }
+    groups
+}
@@ -0,0 +1,84 @@
+/***************************************************************************
+ * Lists the files written by the export phase in a 'manifest.json' file, 
+ * in the output folder, so that a pipeline running the program can find 
+ * what has been produced without reading the log. Each file is listed with
+ * its size, its number of rows (for the csv and json files), and the data 
+ * version and date it was derived from. The size of a csv file written by 
+ * the Postgres server is only available if the file can also be read by 
+ * the program. The manifest is replaced by each run that exports data.
+ ***************************************************************************/
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::Local;
+use crate::AppError;
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportedFile {
+    pub path: String,
+    pub size_bytes: Option<u64>,
+    pub row_count: Option<u64>,
+    pub data_version: String,
+    pub data_date: Option<String>,
+}
+
+impl ExportedFile {
+    pub fn new(path: &Path, row_count: Option<u64>, data_version: &str, data_date: &Option<String>) -> Self {
+        ExportedFile {
+            path: path.display().to_string(),
+            size_bytes: fs::metadata(path).ok().map(|m| m.len()),
+            row_count,
+            data_version: data_version.to_string(),
+            data_date: data_date.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ExportManifest<'a> {
+    created: String,
+    files: &'a [ExportedFile],
+}
+
+
+pub fn write_manifest(output_folder: &Path, files: &[ExportedFile]) -> Result<PathBuf, AppError> {
+    let manifest = ExportManifest {
+        created: Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+        files,
+    };
+    let manifest_path = output_folder.join(MANIFEST_FILE_NAME);
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+    Ok(manifest_path)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_manifest_lists_files() {
+        let folder = std::env::temp_dir().join("ror1_manifest_test");
+        fs::create_dir_all(&folder).unwrap();
+        let csv_path = folder.join("v1.60 country counts.csv");
+        fs::write(&csv_path, "country_code,country_name,org_count\nAU,Australia,7\n").unwrap();
+        let date = Some("2025-02-11".to_string());
+        let files = vec![ExportedFile::new(&csv_path, Some(1), "v1.60", &date),
+                         ExportedFile::new(&folder.join("not written.csv"), None, "All versions", &None)];
+        let manifest_path = write_manifest(&folder, &files).unwrap();
+
+        let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(manifest_path).unwrap()).unwrap();
+        let listed = manifest["files"].as_array().unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0]["path"], csv_path.display().to_string());
+        assert_eq!(listed[0]["size_bytes"], 51);
+        assert_eq!(listed[0]["row_count"], 1);
+        assert_eq!(listed[0]["data_version"], "v1.60");
+        assert_eq!(listed[0]["data_date"], "2025-02-11");
+        assert!(listed[1]["size_bytes"].is_null());
+        assert!(listed[1]["data_date"].is_null());
+    }
+}
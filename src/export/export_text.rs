@@ -1,7 +1,8 @@
 use sqlx::{Pool, Postgres};
 use std::{collections::HashMap, path::PathBuf};
 use crate::AppError;
-use std::fs::OpenOptions;
+use crate::setup::log_helper::{PhaseTimings, format_duration};
+use std::fs::{File, OpenOptions};
 use std::io::prelude::*;
 use chrono::{NaiveDateTime, Local};
 use super::export_structs::{VSummary, TypeRow, DistribRow, RankedRow, 
@@ -10,7 +11,7 @@ use log::info;
 
 
 pub async fn generate_text(output_folder : &PathBuf, output_file_name: &String, 
-            data_version: &String, pool : &Pool<Postgres>) -> Result<(), AppError>
+            data_version: &String, timings: &PhaseTimings, pool : &Pool<Postgres>) -> Result<(), AppError>
 {
     // If data version and date not given explicitly derive them from the data version table
     // as being the version, date of the currently stored version
@@ -27,13 +28,16 @@ pub async fn generate_text(output_folder : &PathBuf, output_file_name: &String,
     let output_file_str = output_file_path.to_str().unwrap();
             
     let singvals:HashMap<String, Singleton> = collect_singleton_values(&vcode, pool).await?;
+    start_file(output_file_str)?;
     write_header_and_summary(output_file_str, &vcode, pool).await?;
+    write_phase_timings(output_file_str, timings)?;
     write_explanation(output_file_str).await?;
     write_name_info(output_file_str, &vcode, pool, &singvals).await?;
     write_name_wolc_info(output_file_str, &vcode, pool, &singvals).await?;
     write_ror_name_details(output_file_str, &singvals).await?;
     write_ranked_name_info(output_file_str, &vcode, pool, &singvals).await?;
     write_type_details(output_file_str, &vcode, pool).await?;
+    write_established_details(output_file_str, &vcode, pool).await?;
     write_location_details(output_file_str, &vcode, pool).await?;
     write_links_and_extid_details(output_file_str, &vcode, pool).await?;
     write_relationship_details(output_file_str, &vcode, pool, &singvals).await?;
@@ -92,6 +96,28 @@ async fn write_header_and_summary(output_file_str: &str, vcode: &String, pool: &
     Ok(())
 }
 
+fn write_phase_timings(output_file_str: &str, timings: &PhaseTimings) -> Result<(), AppError> {
+    
+    // Only phases run earlier in this run (i.e. not export itself) are listed.
+
+    let phases = [("Import", timings.import), ("Transform", timings.transform), 
+                  ("Summary", timings.summary)];
+    let mut timings_txt = "".to_string();
+    for (phase, duration) in phases {
+        if let Some(d) = duration {
+            timings_txt += &get_timing_line(phase, &format_duration(&d));
+        }
+    }
+
+    if !timings_txt.is_empty() {
+        let section_txt = "\n\tPROCESSING TIMES (THIS RUN)\n\t".to_string() 
+                    + "----------------------------------------------------------------------------------" 
+                    + &timings_txt + "\n";
+        append_to_file(output_file_str, &section_txt)?;
+    }
+    Ok(())
+}
+
 async fn write_explanation(output_file_str: &str) -> Result<(), AppError> {
     
     append_to_file(output_file_str, &get_hdr_line("ABBREVIATIONS AND TOTALS"))?;
@@ -270,6 +296,36 @@ async fn write_type_details(output_file_str: &str, vcode: &String, pool: &Pool<P
     Ok(())
 }
 
+async fn write_established_details(output_file_str: &str, vcode: &String, pool: &Pool<Postgres>) -> Result<(), AppError> {
+    
+    append_to_file(output_file_str, &get_hdr_line("YEAR ESTABLISHED"))?;
+
+    // The years are not held in the smm tables, so are only available 
+    // for the version currently in the src tables.
+
+    let sql = "SELECT version from src.version_details;";
+    let src_version: String = sqlx::query_scalar(sql).fetch_one(pool).await?;
+    if src_version != *vcode {
+        let note_text = "\n\n\tOnly available for the version currently in the src tables (".to_string() 
+                        + &src_version + ").\n";
+        return append_to_file(output_file_str, &note_text);
+    }
+
+    let histogram = crate::summarise::fetch_established_histogram(pool).await?;
+    let mut tbl_text = format!("\n\n\t{:<43}{}", "Numbers of organisations established", "number")
+                       + &format!("\n\t{:<42}{}", "in each decade", "of orgs")
+                       + "\n\t----------------------------------------------------------------------------------";
+    let mut rt_numberorgs: i64 = 0;
+    for d in histogram.decades.iter() {
+        tbl_text += &get_data_line(&format!("{}s", d.decade), d.num_orgs as i32);
+        rt_numberorgs += d.num_orgs;
+    }
+    tbl_text = tbl_text + "\n\t----------------------------------------------------------------------------------"
+               + &get_data_line("TOTAL", rt_numberorgs as i32)
+               + &get_data_line("No established year", histogram.num_without as i32) + "\n";
+    append_to_file(output_file_str, &tbl_text)
+}
+
 async fn write_location_details(output_file_str: &str, vcode: &String, pool: &Pool<Postgres>) -> Result<(), AppError> {
        
     append_to_file(output_file_str, &get_hdr_line("LOCATIONS"))?;
@@ -515,6 +571,11 @@ fn get_hdr_line(topic: &str) -> String {
     + "\n\t=================================================================================="
 }
 
+fn get_timing_line(topic: &str, time: &str) -> String {
+    let spacer = " ".repeat(49_usize.saturating_sub(topic.len() + time.len()));
+    "\n\t".to_string() + topic + &spacer + time
+}
+
 fn get_sing_hdr() -> String {
     "\n\t                                                          number          %age\n".to_string()
 }
@@ -556,6 +617,15 @@ fn get_singleton_line(topic: &str, num: i32, pc: Option<f32>) -> String {
     }
 }
 
+fn start_file(output_file_path: &str) -> Result<(), AppError> {
+
+    // Any existing file of the same name (only likely with a fixed name) 
+    // is emptied, so that it is overwritten rather than appended to.
+
+    File::create(output_file_path)?;
+    Ok(())
+}
+
 fn append_to_file(output_file_path: &str, contents: &str) -> Result<(), AppError> {
 
     let mut file = OpenOptions::new()
@@ -571,3 +641,24 @@ fn append_to_file(output_file_path: &str, contents: &str) -> Result<(), AppError
     }
     
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn check_existing_file_overwritten() {
+        let folder = std::env::temp_dir().join("ror1_export_text_test");
+        fs::create_dir_all(&folder).unwrap();
+        let path = folder.join("v1.60 summary.txt");
+        let path_str = path.to_str().unwrap();
+        fs::write(&path, "an earlier summary").unwrap();
+
+        start_file(path_str).unwrap();
+        append_to_file(path_str, "first").unwrap();
+        append_to_file(path_str, " second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first second");
+    }
+}
@@ -1,25 +1,39 @@
 mod export_text;
 mod export_csv;
 mod export_structs;
+mod export_json;
+mod export_manifest;
+
+pub use export_json::{export_json, JsonOrg, JsonLocation, JsonName, JsonExtId};
+pub use export_manifest::{ExportedFile, MANIFEST_FILE_NAME};
+pub use export_csv::CsvFormat;
 
 use log::{info, error};
 use sqlx::{Pool, Postgres};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use crate::setup::get_timestamp;
 use crate::error_defs::{AppError, CustomError};
+use crate::setup::log_helper::PhaseTimings;
+
+// The csv files that can be generated, as may be named in a --tables selection.
+
+pub const CSV_TABLES: [&str; 7] = ["summary", "attributes", "counts", "ranked_counts", "singletons", 
+                                   "orgtypes_and_names_wolc", "orgtypes_and_relationships"];
 
 pub async fn export_as_text(output_folder : &PathBuf, output_file_name: &String, 
-               data_version: &String, pool : &Pool<Postgres>) -> Result<(), AppError>
+               data_version: &String, timings: &PhaseTimings, pool : &Pool<Postgres>) -> Result<ExportedFile, AppError>
 {
     // Write out summary data for this dataset into the designated file
 
     check_data_version_present_in_summ_data(data_version, pool).await?;
 
     let r = export_text::generate_text(output_folder, output_file_name, 
-            data_version, pool).await;
+            data_version, timings, pool).await;
     match r {
         Ok(()) => {
             info!("Data summary generated as text file"); 
-            return Ok(())
+            let data_date = fetch_version_date(data_version, pool).await?;
+            return Ok(ExportedFile::new(&output_folder.join(output_file_name), None, data_version, &data_date))
         },
         Err(e) => {
             error!("An error occured while writing out the text file: {}", e);
@@ -29,17 +43,19 @@ pub async fn export_as_text(output_folder : &PathBuf, output_file_name: &String,
 }
 
 
-pub async fn export_as_csv(output_folder : &PathBuf, data_version: &String, pool : &Pool<Postgres>) -> Result<(), AppError>
+pub async fn export_as_csv(output_folder : &PathBuf, data_version: &String, tables: &[String], append: bool, 
+                           format: &CsvFormat, pool : &Pool<Postgres>) -> Result<Vec<ExportedFile>, AppError>
 {
     // Write out summary data for this as a set of csv files into the designated folder
 
     check_data_version_present_in_summ_data(data_version, pool).await?;
 
-    let r = export_csv::generate_csv(output_folder, data_version, pool).await;
+    let r = export_csv::generate_csv(output_folder, data_version, tables, append, format, pool).await;
     match r {
-        Ok(()) => {
+        Ok(files) => {
             info!("Data summary generated as csv files"); 
-            return Ok(())
+            let data_date = fetch_version_date(data_version, pool).await?;
+            return Ok(files.iter().map(|(path, rows)| ExportedFile::new(path, Some(*rows), data_version, &data_date)).collect())
         },
         Err(e) => {
             error!("An error occured while writing out the csv files: {}", e);
@@ -49,15 +65,59 @@ pub async fn export_as_csv(output_folder : &PathBuf, data_version: &String, pool
 }
 
 
-pub async fn export_all_as_csv(output_folder : &PathBuf, pool : &Pool<Postgres>) -> Result<(), AppError>
+pub async fn export_as_json(output_folder : &PathBuf, data_version: &String, pool : &Pool<Postgres>) -> Result<ExportedFile, AppError>
+{
+    // Write out the processed records of the current version as a ROR-style json file.
+
+    let datetime_string = get_timestamp();
+    let output_file_name = PathBuf::from(format!("{} ror records {}.json", data_version, datetime_string));
+    let output_file_path: PathBuf = [output_folder, &output_file_name].iter().collect();
+
+    let r = export_json::export_json(&output_file_path, pool).await;
+    match r {
+        Ok(n) => {
+            info!("{} records written to json file {}", n, output_file_path.display()); 
+            let (src_version, data_date) = fetch_src_version_and_date(pool).await?;
+            Ok(ExportedFile::new(&output_file_path, Some(n as u64), &src_version, &Some(data_date)))
+        },
+        Err(e) => {
+            error!("An error occured while writing out the json file: {}", e);
+            Err(e)
+        },
+    }
+}
+
+
+pub async fn export_country_csv(output_folder : &PathBuf, format: &CsvFormat, pool : &Pool<Postgres>) -> Result<ExportedFile, AppError>
+{
+    // Write out the number of organisations in each country, for the version 
+    // in the src tables, as a csv file that can be joined to mapping data.
+
+    let r = export_csv::generate_country_csv(output_folder, format, pool).await;
+    match r {
+        Ok((file_path, rows)) => {
+            info!("Country counts written to csv file {}", file_path.display()); 
+            let (src_version, data_date) = fetch_src_version_and_date(pool).await?;
+            Ok(ExportedFile::new(&file_path, Some(rows), &src_version, &Some(data_date)))
+        },
+        Err(e) => {
+            error!("An error occured while writing out the country csv file: {}", e);
+            Err(e)
+        },
+    }
+}
+
+
+pub async fn export_all_as_csv(output_folder : &PathBuf, tables: &[String], format: &CsvFormat, 
+                               pool : &Pool<Postgres>) -> Result<Vec<ExportedFile>, AppError>
 {
     // Write out summary data for all versions as a set of csv files into the designated folder
 
-    let r = export_csv::generate_all_versions_csv(output_folder, pool).await;
+    let r = export_csv::generate_all_versions_csv(output_folder, tables, format, pool).await;
     match r {
-        Ok(()) => {
+        Ok(files) => {
             info!("Data summary generated as csv files"); 
-            return Ok(())
+            return Ok(files.iter().map(|(path, rows)| ExportedFile::new(path, Some(*rows), "All versions", &None)).collect())
         },
         Err(e) => {
             error!("An error occured while writing out the csv files: {}", e);
@@ -67,6 +127,35 @@ pub async fn export_all_as_csv(output_folder : &PathBuf, pool : &Pool<Postgres>)
 }
 
 
+pub fn write_manifest(output_folder : &Path, files: &[ExportedFile]) -> Result<(), AppError>
+{
+    // List the files written by the export phase in the output folder.
+
+    match export_manifest::write_manifest(output_folder, files) {
+        Ok(manifest_path) => {
+            info!("{} exported files listed in {}", files.len(), manifest_path.display());
+            Ok(())
+        },
+        Err(e) => {
+            error!("An error occured while writing out the export manifest: {}", e);
+            Err(e)
+        },
+    }
+}
+
+
+async fn fetch_version_date(data_version: &String, pool: &Pool<Postgres>) -> Result<Option<String>, AppError> {
+    let sql = "select vdate::varchar from smm.version_summaries where vcode = $1";
+    Ok(sqlx::query_scalar(sql).bind(data_version).fetch_optional(pool).await?)
+}
+
+
+async fn fetch_src_version_and_date(pool: &Pool<Postgres>) -> Result<(String, String), AppError> {
+    let sql = "select version, data_date from src.version_details";
+    Ok(sqlx::query_as(sql).fetch_one(pool).await?)
+}
+
+
 async fn check_data_version_present_in_summ_data(data_version: &String, pool: &Pool<Postgres>)-> Result<(), AppError> {
     
     let sql = r#"SELECT EXISTS(select vcode from smm.version_summaries where vcode = '"#.to_string() + &data_version + r#"')"#;
@@ -5,19 +5,82 @@
 mod ror_json_models;
 mod ror_data_vectors;
 mod ror_create_tables;
+mod ror_validator;
+mod ror_import_runs;
+mod ror_counter;
+mod ror_import_progress;
+mod ror_store_profile;
+mod ror_store_retry;
+mod ror_schema_detector;
+mod ror_id_diff;
+mod ror_record_stream;
+mod ror_duplicate_ids;
+mod ror_version_compare;
+mod ror_table_counts;
+mod ror_record_trace;
+mod ror_checkpoint;
+mod ror_utf8;
+mod ror_batch_dump;
+mod ror_store;
+mod ror_sqlite;
 
-use log::{info, error};
-use std::path::PathBuf;
+use log::{info, warn, error};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::io::{self, BufReader, Read};
+use std::time::Duration;
 use sqlx::{Pool, Postgres};
 use crate::AppError;
+use crate::error_defs::CustomError;
+use crate::setup::{Flags, SourceFile, table_selected, schema_tables_exist};
+use crate::setup::env_reader;
+use crate::setup::interrupt;
+use crate::setup::log_helper::truncate_for_log;
 use chrono::NaiveDate;
+use sha2::{Digest, Sha256};
 
-use ror_json_models::RorRecord;
-use ror_data_vectors::{CoreDataVecs, RequiredDataVecs, NonRequiredDataVecs, extract_id_from};
+use ror_json_models::{RorRecord, RorRecordV1, SchemaVersion};
+use ror_validator::ValidationReport;
+use ror_import_progress::ImportProgress;
+use ror_store_profile::StoreProfile;
+use ror_record_stream::{ParseStrategy, RecordStream};
+use ror_duplicate_ids::IdScan;
+use ror_checkpoint::Checkpoint;
+use ror_store_retry::with_retry;
+use ror_store::{RorStore, AtomicStore};
+pub use ror_import_runs::{record_import_run, fetch_import_runs, ImportRun};
+pub use ror_id_diff::IdDiff;
+pub use ror_version_compare::{compare_versions, VersionDiff, ChangeCounts};
+use ror_data_vectors::{CoreDataVecs, RequiredDataVecs, NonRequiredDataVecs};
+pub use ror_data_vectors::extract_id_from;
+pub use ror_record_trace::set_trace_id;
+pub use ror_batch_dump::set_dump_folder;
+pub use ror_sqlite::{get_sqlite_pool, create_sqlite_tables, import_into_sqlite};
 
-pub async fn create_ror_tables(pool : &Pool<Postgres>) -> Result<(), AppError>
+// The ror schema tables, as may be named in a --tables selection.
+
+pub const ROR_TABLES: [&str; 9] = ["core_data", "admin_data", "names", "locations", "external_ids", 
+                                   "links", "type", "relationships", "domains"];
+
+// Identifies coordinates that are out of range, or the (0,0) placeholder.
+
+pub const SUSPECT_COORDS_CONDITION: &str = "(lat < -90 or lat > 90 or lng < -180 or lng > 180 or (lat = 0 and lng = 0))";
+
+// A batch is retried if it fails with a deadlock or serialization error, 
+// after a wait that increases with each retry.
+
+const RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+pub async fn create_ror_tables(keep_existing: bool, pool : &Pool<Postgres>) -> Result<(), AppError>
 {
+    // If requested (by --no-recreate) any existing tables are retained, 
+    // but only if all of them are present - otherwise they are recreated.
+
+    if keep_existing && schema_tables_exist("ror", pool).await? {
+        info!("Existing tables in ror schema retained");
+        return Ok(());
+    }
     match ror_create_tables::create_tables(pool).await {
         Ok(()) => info!("Tables created for ror schema"),
         Err(e) => {
@@ -28,26 +91,115 @@ pub async fn create_ror_tables(pool : &Pool<Postgres>) -> Result<(), AppError>
     Ok(())
 }
 
-pub async fn import_data(data_folder : &PathBuf, source_file_name: &String, 
-                        data_version: &String, data_date: &String, 
-                        pool : &Pool<Postgres>) -> Result<(), AppError>
+// Summarises the outcome of importing one or more source files.
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImportReport {
+    pub files_imported: usize,
+    pub records_found: usize,
+    pub records_processed: usize,
+    pub records_skipped: usize,
+    pub records_type_skipped: usize,
+    pub records_status_skipped: usize,
+    pub records_invalid_id: usize,
+    pub source_digests: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn add(&mut self, other: &ImportReport) {
+        self.files_imported += other.files_imported;
+        self.records_found += other.records_found;
+        self.records_processed += other.records_processed;
+        self.records_skipped += other.records_skipped;
+        self.records_type_skipped += other.records_type_skipped;
+        self.records_status_skipped += other.records_status_skipped;
+        self.records_invalid_id += other.records_invalid_id;
+        self.source_digests.extend(other.source_digests.iter().cloned());
+    }
+}
+
+
+// The selection of records (by --since, --types and --exclude-withdrawn or 
+// --active-only) made before they are stored.
+
+struct RecordFilter<'a> {
+    since_date: &'a str,
+    types: &'a [String],
+    excluded_statuses: &'static [&'static str],
+}
+
+impl<'a> RecordFilter<'a> {
+    fn new(since_date: &'a str, types: &'a [String], flags: &Flags) -> Self {
+        RecordFilter { since_date, types, excluded_statuses: get_excluded_statuses(flags) }
+    }
+}
+
+
+// With --atomic the records of a file are stored within a single transaction,
+// committed only once the whole file has been imported, so that an import
+// that fails, or is interrupted, keeps none of them.
+
+pub async fn import_data_atomic(data_folder : &PathBuf, sf: &SourceFile, since_date: &str, types: &[String], 
+                        flags: &Flags, max_memory_mb: Option<u64>, pool : &Pool<Postgres>) -> Result<ImportReport, AppError>
 {
-    // Record data version, date and elapsed days in single record table.
-    
-    let end_of_period = NaiveDate::parse_from_str(data_date, "%Y-%m-%d").unwrap();
-    let start_of_period = NaiveDate::parse_from_str("2024-04-29", "%Y-%m-%d").unwrap();
-    let duration = end_of_period - start_of_period;
- 
-    let sql = r#"INSERT into ror.version_details (version, data_date, data_days)
-                    values ($1, $2, $3);"#;
-    sqlx::query(&sql).bind(data_version).bind(data_date).bind(duration.num_days())
-    .execute(pool).await?;
-
-    // Import data into matching tables. First obtain the raw data as text
-    // This also checks the file exists...by opening it and checking no error
+    let store = AtomicStore::begin(pool).await?;
+    match import_data(data_folder, sf, since_date, types, flags, max_memory_mb, &store).await {
+        Ok(report) => {
+            store.commit().await?;
+            Ok(report)
+        },
+        Err(e) => {
+            match store.rollback().await {
+                Ok(()) => warn!("The import of {} was rolled back - none of its records were kept", sf.file_name),
+                Err(re) => error!("The import of {} could not be rolled back: {}", sf.file_name, re),
+            }
+            Err(e)
+        },
+    }
+}
 
-    let source_file_path: PathBuf = [data_folder, &PathBuf::from(source_file_name)].iter().collect();
-    let data: String = match fs::read_to_string(source_file_path)
+
+pub async fn import_data<S: RorStore>(data_folder : &PathBuf, sf: &SourceFile, since_date: &str, types: &[String], 
+                        flags: &Flags, max_memory_mb: Option<u64>, pool : &S) -> Result<ImportReport, AppError>
+{
+    // Data piped to the program (with --stdin) is read in the same way as a file.
+
+    if flags.stdin {
+        return import_from_reader(io::stdin().lock(), sf, since_date, types, flags, pool).await;
+    }
+    let filter = RecordFilter::new(since_date, types, flags);
+
+    // The size of the file, and any memory limit, decide whether the file 
+    // is read and parsed as a whole, or streamed, one record at a time.
+    // Getting the size also checks that the file exists.
+
+    let source_file_path: PathBuf = [data_folder, &PathBuf::from(&sf.file_name)].iter().collect();
+    let file_size = match fs::metadata(&source_file_path) {
+        Ok(m) => m.len(),
+        Err(e) => {
+            error!("An error occured while opening or reading from the source file: {}", e);
+            return Err(AppError::IoErr(e))
+            },
+    };
+    // With --checkpoint (or --resume) progress through the file is recorded
+    // in a checkpoint, from which the import can be resumed if it does not
+    // complete. Otherwise no checkpoint is read, written or removed.
+
+    let checkpoint = match flags.checkpoint {
+        true => Some(Checkpoint::for_source(&source_file_path, flags.resume)?),
+        false => None,
+    };
+    let strategy = ror_record_stream::choose_parse_strategy(file_size, max_memory_mb);
+    info!("{}", get_strategy_line(&sf.file_name, file_size, strategy, max_memory_mb));
+    if strategy == ParseStrategy::Streamed {
+        return import_streamed(&source_file_path, sf, &filter, checkpoint.as_ref(), flags, pool).await;
+    }
+
+    // Import data into matching tables. First obtain the raw data as text.
+    // The SHA-256 digest of the file is calculated from the same bytes, so 
+    // that exactly what was loaded is recorded without reading the file twice.
+
+    let (data, digest) = match read_source_file(&source_file_path, &sf.file_name, flags.strict_utf8)
     {
         Ok(d) => {
             info!("Got the data from the file");
@@ -55,13 +207,78 @@ pub async fn import_data(data_folder : &PathBuf, source_file_name: &String,
         }, 
         Err(e) => {
             error!("An error occured while opening or reading from the source file: {}", e);
-            return Err(AppError::IoErr(e))
+            return Err(get_read_error(e))
             },
     };
+    import_source_data(&data, digest, sf, &filter, checkpoint.as_ref(), flags, pool).await
+}
+
+
+fn get_strategy_line(source_file_name: &str, file_size: u64, strategy: ParseStrategy, max_memory_mb: Option<u64>) -> String {
+    let size_mb = file_size as f64 / (1024.0 * 1024.0);
+    let strategy = match strategy {
+        ParseStrategy::InMemory => "parsed in memory",
+        ParseStrategy::Streamed => "streamed",
+    };
+    match max_memory_mb {
+        Some(mb) => format!("Source file {} is {:.1} MB - {} (max memory {} MB)", source_file_name, size_mb, strategy, mb),
+        None => format!("Source file {} is {:.1} MB - {}", source_file_name, size_mb, strategy),
+    }
+}
+
+
+pub async fn import_from_reader<R: Read, S: RorStore>(mut reader: R, sf: &SourceFile, since_date: &str, types: &[String], 
+                        flags: &Flags, pool : &S) -> Result<ImportReport, AppError>
+{
+    // Piped data may be too large to hold in memory, and can only be read 
+    // once, so it is always streamed, in a single pass. The start of the data
+    // is read first, to check it and find its schema, and is then passed on 
+    // ahead of the rest, so that the records and digest include it.
+
+    let filter = RecordFilter::new(since_date, types, flags);
+    let source_file_name = &sf.file_name;
+    let sample_bytes = read_sample(reader.by_ref()).map_err(|e| {
+        error!("An error occured while reading from {}: {}", source_file_name, e);
+        AppError::IoErr(e)
+    })?;
+    let schema_version = check_sample(&get_sample_text(&sample_bytes), source_file_name)?;
+    info!("Source data assumed to use the {:?} ROR schema", schema_version);
+
+    // The records are stored as they are read, so ids that occur more than 
+    // once cannot be found beforehand. A repeated id instead stops the import
+    // when it is found. With --stdin the whole import is within a single
+    // transaction (as with --atomic), so nothing is then kept, but a store
+    // that commits each batch keeps those stored before it.
+
+    let since = parse_since_date(since_date);
+    let mut records = RecordStream::new(io::Cursor::new(sample_bytes).chain(reader), schema_version, &sf.data_date, 
+                                        since, types, filter.excluded_statuses, flags.strict_utf8);
+    let mut ids_found = HashSet::new();
+    let checked_records = records.by_ref()
+                .map(|r| r.and_then(|r| check_id_not_repeated(&mut ids_found, r, source_file_name, pool.commits_batches())));
+    let (records_processed, records_invalid_id) = store_records(checked_records, None, sf, None, 
+                                                                flags.strict, flags.profile, pool).await?;
+    report_streamed_import(records, records_processed, records_invalid_id, sf, &filter, schema_version)
+}
+
+
+async fn import_source_data<S: RorStore>(data: &str, digest: String, sf: &SourceFile, filter: &RecordFilter<'_>, 
+                        checkpoint: Option<&Checkpoint>, flags: &Flags, pool : &S) -> Result<ImportReport, AppError>
+{
+    let (since_date, types) = (filter.since_date, filter.types);
+    let source_file_name = &sf.file_name;
+    let data_date = &sf.data_date;
+
+    info!("SHA-256 digest of {}: {}", source_file_name, digest);
+    check_not_empty(data, source_file_name)?;
+
+    // Parse into an internal JSON structure. Files using the v1 schema are 
+    // parsed into the v1 structure and then mapped to the v2 equivalent.
 
-    // Parse into an internal JSON structure
+    let schema_version = get_checked_schema_version(source_file_name, data);
+    info!("Source file assumed to use the {:?} ROR schema", schema_version);
 
-    let res:Vec<RorRecord> = match serde_json::from_str(&data)
+    let mut all_recs:Vec<RorRecord> = match parse_records(data, schema_version, data_date)
     {
         Ok(r) => {
             info!("Parsed the data into ROR json objects");
@@ -69,11 +286,188 @@ pub async fn import_data(data_folder : &PathBuf, source_file_name: &String,
         }, 
         Err(e) => {
             error!("An error occured while attempting tp parse the source data into json: {}", e);
-            return Err(AppError::SdErr(e))
+            return Err(e)
             },
     };
     
-    info!("{} records found", res.len());
+    let num_fallback = all_recs.iter_mut().map(|r| r.fill_missing_admin(data_date, schema_version))
+                                .filter(|filled| *filled).count();
+    log_admin_fallback(num_fallback, data_date, schema_version);
+
+    // If a 'since' date has been given only records modified on or after 
+    // that date are retained, so that only recent changes are imported.
+
+    let records_found = all_recs.len();
+    info!("{} records found", records_found);
+    let res = filter_by_last_modified(all_recs, since_date);
+    let records_skipped = records_found - res.len();
+    if !since_date.is_empty() {
+        info!("{} records skipped as last modified before {}", records_skipped, since_date);
+    }
+
+    // If a selection of types has been given (by --types) only records with 
+    // at least one of those types are retained.
+
+    let num_retained = res.len();
+    let res: Vec<RorRecord> = res.into_iter().filter(|r| has_selected_type(r, types)).collect();
+    let records_type_skipped = num_retained - res.len();
+    if !types.is_empty() {
+        info!("{} records skipped as not of the selected types ({})", records_type_skipped, types.join(", "));
+    }
+
+    // With --exclude-withdrawn or --active-only records with the excluded 
+    // statuses are also dropped.
+
+    let num_retained = res.len();
+    let res: Vec<RorRecord> = res.into_iter().filter(|r| has_allowed_status(r, filter.excluded_statuses)).collect();
+    let records_status_skipped = num_retained - res.len();
+    if !filter.excluded_statuses.is_empty() {
+        info!("{} records skipped as {}", records_status_skipped, filter.excluded_statuses.join(" or "));
+    }
+
+    // Any ids that occur more than once stop the import before anything is
+    // stored, unless --replace has been given, when the last of each is kept.
+
+    let mut id_scan = IdScan::new();
+    for (i, r) in res.iter().enumerate() {
+        if let Some(id) = extract_id_from(&r.id) {
+            id_scan.add(i, id);
+        }
+    }
+    check_duplicate_ids(&id_scan, source_file_name, flags.replace)?;
+    let res: Vec<RorRecord> = res.into_iter().enumerate()
+                .filter(|(i, r)| is_last_occurrence(&id_scan, *i, r))
+                .map(|(_, r)| r).collect();
+
+    if res.is_empty() {
+        warn!("The source file {} contains no ROR records to import - nothing imported", source_file_name);
+        if let Some(checkpoint) = checkpoint {
+            checkpoint.clear()?;
+        }
+        return Ok(ImportReport {
+            records_found,
+            records_skipped,
+            records_type_skipped,
+            records_status_skipped,
+            source_digests: vec![digest],
+            ..ImportReport::default()
+        });
+    }
+
+    let total = res.len();
+    let (records_processed, records_invalid_id) = store_records(res.into_iter().map(Ok), Some(total), 
+                                                                sf, checkpoint, flags.strict, flags.profile, pool).await?;
+    Ok(ImportReport {
+        files_imported: 1,
+        records_found,
+        records_processed,
+        records_skipped,
+        records_type_skipped,
+        records_status_skipped,
+        records_invalid_id,
+        source_digests: vec![digest],
+    })
+}
+
+
+async fn import_streamed<S: RorStore>(source_file_path: &Path, sf: &SourceFile, filter: &RecordFilter<'_>, 
+                        checkpoint: Option<&Checkpoint>, flags: &Flags, pool : &S) -> Result<ImportReport, AppError>
+{
+    let (since_date, types) = (filter.since_date, filter.types);
+    let source_file_name = &sf.file_name;
+
+    // The start of the file is enough to check that it holds an array, and 
+    // to find the schema its records use. The file is then read again, from
+    // the start, as the records are parsed, with the digest calculated as it is read.
+
+    let file = fs::File::open(source_file_path).map_err(AppError::IoErr)?;
+    let sample = get_sample_text(&read_sample(file).map_err(AppError::IoErr)?);
+    let schema_version = check_sample(&sample, source_file_name)?;
+    info!("Source file assumed to use the {:?} ROR schema", schema_version);
+
+    // The ids are checked in a first pass through the file, so that any 
+    // duplicates are found before anything is stored.
+
+    let since = parse_since_date(since_date);
+    let file = fs::File::open(source_file_path).map_err(AppError::IoErr)?;
+    let mut id_scan = IdScan::new();
+    for (i, r) in RecordStream::new(file, schema_version, &sf.data_date, since, types, 
+                                    filter.excluded_statuses, flags.strict_utf8).enumerate() {
+        if let Some(id) = extract_id_from(&r?.id) {
+            id_scan.add(i, id);
+        }
+    }
+    check_duplicate_ids(&id_scan, source_file_name, flags.replace)?;
+
+    let file = fs::File::open(source_file_path).map_err(AppError::IoErr)?;
+    let mut records = RecordStream::new(file, schema_version, &sf.data_date, since, types, 
+                                        filter.excluded_statuses, flags.strict_utf8);
+    let kept_records = records.by_ref().enumerate()
+                .filter(|(i, r)| r.as_ref().map_or(true, |r| is_last_occurrence(&id_scan, *i, r)))
+                .map(|(_, r)| r);
+    let (records_processed, records_invalid_id) = store_records(kept_records, None, sf, checkpoint, 
+                                                                flags.strict, flags.profile, pool).await?;
+    report_streamed_import(records, records_processed, records_invalid_id, sf, filter, schema_version)
+}
+
+
+fn report_streamed_import<R: Read>(records: RecordStream<R>, records_processed: usize, records_invalid_id: usize, 
+                        sf: &SourceFile, filter: &RecordFilter<'_>, schema_version: SchemaVersion) -> Result<ImportReport, AppError>
+{
+    // The counts, and the digest, are only known once the whole source has been read.
+
+    let (since_date, types) = (filter.since_date, filter.types);
+    let source_file_name = &sf.file_name;
+    let (records_found, records_skipped) = (records.records_found, records.records_skipped);
+    let records_type_skipped = records.records_type_skipped;
+    let records_status_skipped = records.records_status_skipped;
+    log_admin_fallback(records.records_admin_fallback, &sf.data_date, schema_version);
+    ror_utf8::log_replaced_sequences(source_file_name, records.invalid_utf8_offsets());
+    let digest = records.finish().map_err(AppError::IoErr)?;
+    info!("SHA-256 digest of {}: {}", source_file_name, digest);
+
+    info!("{} records found", records_found);
+    if !since_date.is_empty() {
+        info!("{} records skipped as last modified before {}", records_skipped, since_date);
+    }
+    if !types.is_empty() {
+        info!("{} records skipped as not of the selected types ({})", records_type_skipped, types.join(", "));
+    }
+    if !filter.excluded_statuses.is_empty() {
+        info!("{} records skipped as {}", records_status_skipped, filter.excluded_statuses.join(" or "));
+    }
+    if records_found == records_skipped + records_type_skipped + records_status_skipped {
+        warn!("The source file {} contains no ROR records to import - nothing imported", source_file_name);
+        return Ok(ImportReport {
+            records_found,
+            records_skipped,
+            records_type_skipped,
+            records_status_skipped,
+            source_digests: vec![digest],
+            ..ImportReport::default()
+        });
+    }
+
+    Ok(ImportReport {
+        files_imported: 1,
+        records_found,
+        records_processed,
+        records_skipped,
+        records_type_skipped,
+        records_status_skipped,
+        records_invalid_id,
+        source_digests: vec![digest],
+    })
+}
+
+
+async fn store_records<I, S>(records: I, total: Option<usize>, sf: &SourceFile, checkpoint: Option<&Checkpoint>, 
+                             strict: bool, profile: bool, pool : &S) -> Result<(usize, usize), AppError>
+where
+    I: Iterator<Item = Result<RorRecord, AppError>>,
+    S: RorStore,
+{
+    let source_file_name = &sf.file_name;
 
     // Set up vector variables.
     // Vectors are grouped into structs for ease of reference.
@@ -86,51 +480,676 @@ pub async fn import_data(data_folder : &PathBuf, source_file_name: &String,
     // Run through each record and store contents in relevant vectors.
     // After every (vector_size) records store vector contents to database
     // and clear vectors, but continue looping through records.
+    // A record without a valid ROR id is an error in strict mode, 
+    // otherwise it is skipped (and counted). Progress, with the throughput,
+    // is logged after a batch at most once every 5 seconds.
+    // When resuming, the records dealt with before the checkpoint are 
+    // skipped. The checkpoint is updated after each batch is stored.
     
+    let start = checkpoint.map_or(0, |c| c.start);
+    let mut records_read = 0;
+    let mut progress = ImportProgress::new(total.map(|t| t.saturating_sub(start)), Duration::from_secs(5));
+    let mut store_profile = StoreProfile::new();
+    let max_retries = env_reader::fetch_store_retries();
+    let trace_id = ror_record_trace::fetch_trace_id();
+    let mut traced = false;
     let mut n = 0;
-    for (i, r) in res.iter().enumerate() {
+    let mut i = 0;
+    let mut records_invalid_id = 0;
+    let mut version_recorded = false;
+    let _storing = interrupt::watch_for_interrupt();
+    for r in records.skip(start) {
     
-        let db_id = extract_id_from(&r.id).to_string();
+        let r = match r {
+            Ok(r) => r,
+            Err(e) => {
+                error!("An error occured while attempting tp parse the source data into json: {}", e);
+                if pool.commits_batches() && n > 0 {
+                    warn!("{} records from {} had already been committed to the ror tables, and are kept - run the import again in full to replace them", 
+                          n, source_file_name);
+                }
+                return Err(e)
+            },
+        };
+        records_read += 1;
+
+        // Record data version, date and elapsed days in single record table,
+        // once there is a record to import.
+        // If several files of the same version are being imported into the same 
+        // tables the record will already exist after the first file.
+
+        if !version_recorded {
+            pool.store_version_details(sf).await?;
+            version_recorded = true;
+        }
+
+        let db_id = match extract_id_from(&r.id) {
+            Some(id) => id.to_string(),
+            None => {
+                if strict {
+                    let msg = format!("Record with invalid ROR id '{}' found in {}", r.id, source_file_name);
+                    return Result::Err(AppError::DtErr(CustomError::new(&msg)));
+                }
+                warn!("Record with invalid ROR id '{}' skipped", truncate_for_log(&r.id));
+                records_invalid_id += 1;
+                continue;
+            },
+        };
 
-        cdv.add_core_data(r, &db_id); 
-        rdv.add_required_data(r, &db_id); 
-        ndv.add_non_required_data(r, &db_id); 
+        if trace_id.as_ref() == Some(&db_id) {
+            ror_record_trace::log_record_rows(&r, &db_id);
+            traced = true;
+        }
+
+        cdv.add_core_data(&r, &db_id); 
+        rdv.add_required_data(&r, &db_id); 
+        ndv.add_non_required_data(&r, &db_id); 
+        i += 1;
         
         //if i > 705 { break;  }
 
-        if (i + 1) % vector_size == 0 {  
+        if i % vector_size == 0 {  
             
             n += vector_size;
-            if n % 5000 == 0 { 
-                info!("{} records processed", n);
-            }
+            progress.update(n);
             
             // store records to DB and clear vectors
-            cdv.store_data(&pool).await;
+            store_batch(&cdv, &rdv, &ndv, max_retries, &mut store_profile, source_file_name, pool).await?;
+            if let Some(checkpoint) = checkpoint {
+                checkpoint.save(start + records_read)?;
+            }
             cdv = CoreDataVecs::new(vector_size);
-            rdv.store_data(&pool).await;
             rdv = RequiredDataVecs::new(vector_size);
-            ndv.store_data(&pool).await;
             ndv = NonRequiredDataVecs::new(vector_size);
+
+            // An interrupt is acted on once the current batch has been stored.
+
+            if interrupt::stop_requested() {
+                return Err(get_interrupted_error(n, source_file_name, pool.commits_batches()));
+            }
         }
     }
     
     //store any residual vector contents
 
-    cdv.store_data(&pool).await;
-    rdv.store_data(&pool).await;
-    ndv.store_data(&pool).await;
+    store_batch(&cdv, &rdv, &ndv, max_retries, &mut store_profile, source_file_name, pool).await?;
+
+    let records_processed = n + cdv.db_ids.len();
+    if interrupt::stop_requested() {
+        if let Some(checkpoint) = checkpoint {
+            checkpoint.save(start + records_read)?;
+        }
+        return Err(get_interrupted_error(records_processed, source_file_name, pool.commits_batches()));
+    }
+    if let Some(checkpoint) = checkpoint {
+        checkpoint.clear()?;
+    }
+    if !version_recorded {
+        return Ok((0, 0));
+    }
+    info!("Total records processed: {}", records_processed);
+    progress.finish(records_processed);
+    if profile {
+        info!("Time spent storing each group of tables:");
+        for line in store_profile.get_profile_lines() {
+            info!("    {}", line);
+        }
+    }
+    if records_invalid_id > 0 {
+        warn!("{} records skipped as having an invalid ROR id", records_invalid_id);
+    }
+    if let Some(id) = trace_id.filter(|_| !traced) {
+        info!("The trace id {} was not among the records imported from {}", id, source_file_name);
+    }
+    Ok((records_processed, records_invalid_id))
+}
+
+
+fn log_admin_fallback(num_records: usize, data_date: &str, schema_version: SchemaVersion) {
+    if num_records > 0 {
+        warn!("{} records without complete admin data - the data date ({}) and schema version {} used where missing", 
+               num_records, data_date, schema_version.admin_schema_version());
+    }
+}
+
+
+fn check_duplicate_ids(id_scan: &IdScan, source_file_name: &str, replace: bool) -> Result<(), AppError> {
+    if id_scan.duplicates.is_empty() {
+        return Ok(());
+    }
+    for id in id_scan.duplicates.iter() {
+        warn!("ROR id {} occurs more than once in {}", id, source_file_name);
+    }
+    if replace {
+        info!("{} earlier occurrences of duplicated ROR ids replaced by the last occurrence", id_scan.num_replaced());
+        Ok(())
+    }
+    else {
+        let msg = format!("{} ROR ids occur more than once in {} - nothing imported (use --replace to import the last occurrence of each)", 
+                           id_scan.duplicates.len(), source_file_name);
+        error!("{}", msg);
+        Err(AppError::DtErr(CustomError::new(&msg)))
+    }
+}
+
+
+fn check_id_not_repeated(ids_found: &mut HashSet<String>, record: RorRecord, source_name: &str, 
+                         commits_batches: bool) -> Result<RorRecord, AppError> {
+    if let Some(id) = extract_id_from(&record.id) {
+        if !ids_found.insert(id.to_string()) {
+            let outcome = if commits_batches { "with only the records stored before it imported" } else { "and none of its records kept" };
+            let msg = format!("ROR id {} occurs more than once in {} - the import was stopped, {}", 
+                               id, source_name, outcome);
+            error!("{}", msg);
+            return Err(AppError::DtErr(CustomError::new(&msg)));
+        }
+    }
+    Ok(record)
+}
+
+
+fn is_last_occurrence(id_scan: &IdScan, position: usize, record: &RorRecord) -> bool {
+    match extract_id_from(&record.id) {
+        Some(id) => id_scan.is_last(position, id),
+        None => true,    // invalid ids are dealt with as the records are stored
+    }
+}
+
+
+fn get_interrupted_error(records_committed: usize, source_file_name: &str, committed: bool) -> AppError {
+    if committed {
+        warn!("Import interrupted - {} records from {} had been committed to the ror tables", 
+               records_committed, source_file_name);
+    }
+    else {
+        warn!("Import interrupted - the {} records stored from {} will be rolled back (--atomic)", 
+               records_committed, source_file_name);
+    }
+    let msg = format!("the import of {} was stopped after {} records had been stored", 
+                       source_file_name, records_committed);
+    AppError::IrErr(CustomError::new(&msg))
+}
+
+
+fn read_source_file(source_file_path: &PathBuf, source_name: &str, strict_utf8: bool) -> Result<(String, String), std::io::Error> {
+    let bytes = fs::read(source_file_path)?;
+    get_data_and_digest(bytes, source_name, strict_utf8)
+}
+
+
+fn read_sample<R: Read>(reader: R) -> Result<Vec<u8>, std::io::Error> {
+
+    // Up to 1 MB is read, enough for the first record of any ROR file.
+
+    let mut bytes = Vec::new();
+    reader.take(1024 * 1024).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+
+fn get_sample_text(bytes: &[u8]) -> String {
+
+    // The sample may end part way through a character, which is then dropped.
+
+    String::from_utf8_lossy(bytes).trim_end_matches('\u{fffd}').to_string()
+}
+
+
+fn check_sample(sample: &str, source_file_name: &str) -> Result<SchemaVersion, AppError> {
+    check_not_empty(sample, source_file_name)?;
+    if let Some(found) = describe_non_array(sample) {
+        let msg = format!("expected a JSON array of ROR records, found {}", found);
+        return Result::Err(AppError::DtErr(CustomError::new(&msg)));
+    }
+    Ok(get_checked_schema_version(source_file_name, sample))
+}
+
+
+fn get_data_and_digest(bytes: Vec<u8>, source_name: &str, strict_utf8: bool) -> Result<(String, String), std::io::Error> {
+
+    // The digest is of the bytes as read, before any invalid UTF-8 is replaced.
+
+    let digest = get_sha256_digest(&bytes);
+    let (data, offsets) = ror_utf8::decode_utf8(bytes, strict_utf8)?;
+    ror_utf8::log_replaced_sequences(source_name, &offsets);
+    Ok((data, digest))
+}
+
+
+fn get_sha256_digest(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+
+async fn store_batch<S: RorStore>(cdv: &CoreDataVecs, rdv: &RequiredDataVecs, ndv: &NonRequiredDataVecs, max_retries: u32, 
+                     store_profile: &mut StoreProfile, source_file_name: &str, pool: &S) -> Result<(), AppError>
+{
+    // The three groups of vectors are stored concurrently (see ror_store),
+    // and a batch is either stored in full or not at all - the ror tables
+    // never hold only some of the rows of a record, and a failed import can
+    // be resumed (or a dumped batch replayed) without duplicating rows. Any
+    // failed insert ends the import, after any retries of a deadlock or
+    // serialization error. Each group is timed separately.
+    // With --dump-failed the rows of a batch that fails are first written to
+    // a replay file - any failure to write it is logged, but the error 
+    // returned is still that of the insert.
+
+    cdv.check_alignment()?;
+    rdv.check_alignment()?;
+    ndv.check_alignment()?;
+
+    match with_retry("batch", max_retries, RETRY_BACKOFF, || pool.insert_batch(cdv, rdv, ndv)).await {
+        Ok((cd, rd, nd)) => {
+            store_profile.add("core data", cdv.row_count(), cd);
+            store_profile.add("required data", rdv.row_count(), rd);
+            store_profile.add("non-required data", ndv.row_count(), nd);
+            Ok(())
+        },
+        Err(e) => {
+            error!("An error occured while storing a batch of records in the ror tables: {}", e);
+            if let Some(folder) = ror_batch_dump::fetch_dump_folder() {
+                if let Err(we) = ror_batch_dump::write_replay_file(&folder, source_file_name, &e.to_string(), cdv, rdv, ndv) {
+                    error!("The failed batch could not be written to {}: {}", folder.display(), we);
+                }
+            }
+            Err(e)
+        },
+    }
+}
+
+
+fn parse_records(data: &str, schema_version: SchemaVersion, data_date: &str) -> Result<Vec<RorRecord>, AppError> {
+
+    // A common mistake is to use a file that is valid JSON but not a ROR data 
+    // dump, e.g. a single ROR record or a Zenodo metadata file. Checking the 
+    // first character (after any whitespace or BOM) allows a clear error 
+    // to be given in those cases, without parsing the whole file twice.
+
+    if let Some(found) = describe_non_array(data) {
+        let msg = format!("expected a JSON array of ROR records, found {}", found);
+        return Result::Err(AppError::DtErr(CustomError::new(&msg)));
+    }
+
+    let parse_result = match schema_version {
+        SchemaVersion::V2 => serde_json::from_str::<Vec<RorRecord>>(data),
+        SchemaVersion::V1 => serde_json::from_str::<Vec<RorRecordV1>>(data)
+                 .map(|v1_recs| v1_recs.into_iter().map(|r| r.into_v2(data_date)).collect()),
+    };
+
+    parse_result.map_err(|e| get_parse_error(e, schema_version))
+}
+
+
+// Invalid data found when reading a source (invalid UTF-8 with --strict-utf8,
+// or a stream that is not a JSON array) is an error in the source data,
+// rather than a failure to read it.
+
+fn get_read_error(e: io::Error) -> AppError {
+    if e.kind() == io::ErrorKind::InvalidData {
+        AppError::DtErr(CustomError::new(&e.to_string()))
+    }
+    else {
+        AppError::IoErr(e)
+    }
+}
+
+
+fn get_parse_error(e: serde_json::Error, schema_version: SchemaVersion) -> AppError {
+    if e.classify() == serde_json::error::Category::Data {
+        let msg = format!("the JSON array does not contain records in the {:?} ROR schema format: {}", schema_version, e);
+        AppError::DtErr(CustomError::new(&msg))
+    }
+    else {
+        AppError::SdErr(e)
+    }
+}
+
+
+fn filter_by_last_modified(records: Vec<RorRecord>, since_date: &str) -> Vec<RorRecord> {
+    let since = parse_since_date(since_date);
+    records.into_iter().filter(|r| is_modified_since(r, since)).collect()
+}
+
+
+fn parse_since_date(since_date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(since_date, "%Y-%m-%d").ok()   // no (valid) since date - nothing filtered
+}
+
+
+fn is_modified_since(record: &RorRecord, since: Option<NaiveDate>) -> bool {
+
+    // Dates are compared as dates rather than strings. A record whose last 
+    // modified date cannot be parsed is retained, so it is not silently lost.
+
+    match since {
+        Some(since) => match NaiveDate::parse_from_str(&record.admin.last_modified.date, "%Y-%m-%d") {
+            Ok(lm_date) => lm_date >= since,
+            Err(_) => true,
+        },
+        None => true,
+    }
+}
+
+
+fn has_selected_type(record: &RorRecord, types: &[String]) -> bool {
+    types.is_empty() || record.types.iter().any(|t| types.iter().any(|s| s.eq_ignore_ascii_case(t)))
+}
+
+
+fn get_excluded_statuses(flags: &Flags) -> &'static [&'static str] {
+    if flags.active_only {
+        &["inactive", "withdrawn"]
+    }
+    else if flags.exclude_withdrawn {
+        &["withdrawn"]
+    }
+    else {
+        &[]
+    }
+}
+
+
+fn has_allowed_status<S: AsRef<str>>(record: &RorRecord, excluded_statuses: &[S]) -> bool {
+    !excluded_statuses.iter().any(|s| s.as_ref().eq_ignore_ascii_case(record.status.trim()))
+}
+
+
+fn check_not_empty(data: &str, source_file_name: &str) -> Result<(), AppError> {
+
+    // An empty (or blank) file, or one holding only an empty array, is most 
+    // likely a failed download or export, and is reported as such, rather 
+    // than by the parser's 'EOF while parsing', or as a successful import.
+
+    let content = data.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+    let msg = if content.is_empty() {
+        format!("source file {} is empty", source_file_name)
+    }
+    else if content.strip_prefix('[').and_then(|c| c.strip_suffix(']')).is_some_and(|c| c.trim().is_empty()) {
+        format!("source file {} holds an empty array ([]) - it contains no ROR records", source_file_name)
+    }
+    else {
+        return Ok(());
+    };
+    error!("{}", msg);
+    Err(AppError::DtErr(CustomError::new(&msg)))
+}
+
+
+fn describe_non_array(data: &str) -> Option<&'static str> {
+    match data.trim_start_matches(|c: char| c.is_whitespace() || c == '\u{feff}').chars().next() {
+        Some('[') => None,
+        Some('{') => Some("an object"),
+        Some('"') => Some("a string"),
+        Some('t') | Some('f') => Some("a boolean"),
+        Some('n') => Some("null"),
+        Some(c) if c == '-' || c.is_ascii_digit() => Some("a number"),
+        Some(_) => None,     // not valid JSON - left to the parser to report
+        None => Some("an empty file"),
+    }
+}
+
+
+fn get_checked_schema_version(source_file_name: &str, data: &str) -> SchemaVersion {
+
+    // The version indicated by the file name is checked against the shape of 
+    // the first record. If they disagree the file has probably been mislabelled, 
+    // and the version found in the content is used. Without any record there 
+    // is nothing to check, and the name's version is retained.
+
+    let named_version = get_schema_version(source_file_name);
+    if !ror_schema_detector::has_first_record(data) {
+        return named_version;
+    }
+    let detected_version = ror_schema_detector::detect_schema_version(data);
+    if detected_version != named_version {
+        warn!("The name of {} indicates the {:?} ROR schema, but its records use the {:?} schema, which will be used instead",
+               source_file_name, named_version, detected_version);
+    }
+    detected_version
+}
+
+
+fn get_schema_version(source_file_name: &str) -> SchemaVersion {
+
+    // ROR's own file names indicate v2 files by a 'schema_v2' suffix, 
+    // whereas v1 files were simply '...-ror-data.json'. Any other name
+    // is assumed to be a (current) v2 file.
+
+    if source_file_name.contains("schema_v2") {
+        SchemaVersion::V2
+    }
+    else if source_file_name.contains("schema_v1") || source_file_name.ends_with("ror-data.json") {
+        SchemaVersion::V1
+    }
+    else {
+        SchemaVersion::V2
+    }
+}
+
+
+// Counts of organisations that lack each of the optional data elements,
+// giving a quick view of the completeness of the imported data.
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ImportQuality {
+    pub num_orgs: i64,
+    pub no_location: i64,
+    pub no_external_id: i64,
+    pub no_link: i64,
+    pub no_non_ror_name: i64,
+    pub suspect_coords: i64,
+}
+
+
+// The number of names of each type, with the ror names (which are
+// also labels) counted separately, after the types.
+
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct NameTypeCount {
+    pub name_type: String,
+    pub num_names: i64,
+}
 
-    info!("Total records processed: {}", n + cdv.db_ids.len());
 
+pub fn validate_files(data_folder : &PathBuf, source_files: &[SourceFile]) -> Result<(), AppError>
+{
+    // Parses each file and reports on any problems found, without any 
+    // database access. Returns an error if any file fails validation.
+
+    let mut num_invalid = 0;
+    for sf in source_files {
+        let report = validate_file(data_folder, &sf.file_name)?;
+        log_validation_report(&report);
+        if !report.is_valid() {
+            num_invalid += 1;
+        }
+    }
+
+    if num_invalid > 0 {
+        let msg = format!("{} of {} source file(s) failed validation", num_invalid, source_files.len());
+        let cf_err = CustomError::new(&msg);
+        return Result::Err(AppError::DtErr(cf_err));
+    }
+    info!("All source files validated successfully");
     Ok(())
+}
 
+
+fn validate_file(data_folder : &PathBuf, source_file_name: &str) -> Result<ValidationReport, AppError>
+{
+    let source_file_path: PathBuf = [data_folder, &PathBuf::from(source_file_name)].iter().collect();
+    let data: String = match fs::read_to_string(source_file_path)
+    {
+        Ok(d) => d,
+        Err(e) => {
+            error!("An error occured while opening or reading from the source file {}: {}", source_file_name, e);
+            return Err(AppError::IoErr(e))
+            },
+    };
+    let schema_version = get_checked_schema_version(source_file_name, &data);
+    Ok(ror_validator::validate_data(&data, source_file_name, schema_version))
 }
 
 
-pub async fn summarise_import(pool : &Pool<Postgres>) -> Result<(), AppError>
+pub async fn compare_source_ids(data_folder : &PathBuf, source_files: &[&SourceFile], output_folder: &Path, 
+                                pool: &Pool<Postgres>) -> Result<IdDiff, AppError>
 {
-    // Goes through each table and get total record number.
+    // Compares the ids in the source file(s) of a version with those currently 
+    // in ror.core_data (if it exists), and writes the ids added and removed to 
+    // the output folder. Nothing is imported. Records without a valid id are ignored.
+
+    let mut source_ids: HashSet<String> = HashSet::new();
+    for sf in source_files {
+        let source_file_path: PathBuf = [data_folder, &PathBuf::from(&sf.file_name)].iter().collect();
+        let (data, _) = read_source_file(&source_file_path, &sf.file_name, false)?;
+        let schema_version = get_checked_schema_version(&sf.file_name, &data);
+        for r in parse_records(&data, schema_version, &sf.data_date)?.iter() {
+            if let Some(id) = extract_id_from(&r.id) {
+                source_ids.insert(id.to_string());
+            }
+        }
+    }
+
+    let sql = "select to_regclass('ror.core_data') is not null";
+    let present: bool = sqlx::query_scalar(sql).fetch_one(pool).await?;
+    let existing_ids: HashSet<String> = if present {
+        sqlx::query_scalar("select id from ror.core_data").fetch_all(pool).await?.into_iter().collect()
+    } else {
+        HashSet::new()
+    };
+
+    let diff = ror_id_diff::diff_ids(&source_ids, &existing_ids);
+    let data_version = &source_files[source_files.len() - 1].data_version;
+    ror_id_diff::write_id_files(&diff, data_version, output_folder)?;
+
+    info!("");
+    info!("************************************");
+    info!("Ids in version {}: {} (in the ror tables: {})", data_version, source_ids.len(), existing_ids.len());
+    info!("New ids: {}", diff.added.len());
+    info!("Removed ids: {}", diff.removed.len());
+    info!("Written to '{} new_ids.txt' and '{} removed_ids.txt' in {}", data_version, data_version, output_folder.display());
+    info!("************************************");
+    info!("");
+    Ok(diff)
+}
+
+
+pub fn count_files(data_folder : &PathBuf, source_files: &[SourceFile]) -> Result<(), AppError>
+{
+    // Prints the number of records in each file, and how many have each of the 
+    // optional sections populated, to the console, without any database access.
+
+    for sf in source_files {
+        let source_file_path: PathBuf = [data_folder, &PathBuf::from(&sf.file_name)].iter().collect();
+        let file = match fs::File::open(source_file_path)
+        {
+            Ok(f) => f,
+            Err(e) => {
+                error!("An error occured while opening the source file {}: {}", sf.file_name, e);
+                return Err(AppError::IoErr(e))
+                },
+        };
+        let report = match ror_counter::count_records(BufReader::new(file), &sf.file_name) {
+            Ok(r) => r,
+            Err(e) if e.is_io() => {
+                error!("An error occured while reading from the source file {}: {}", sf.file_name, e);
+                return Err(AppError::IoErr(e.into()))
+            },
+            Err(e) => {
+                let msg = format!("Unable to count the records in {}: {}", sf.file_name, e);
+                let cf_err = CustomError::new(&msg);
+                return Result::Err(AppError::DtErr(cf_err));
+            },
+        };
+        for line in ror_counter::get_count_lines(&report) {
+            println!("{}", line);
+        }
+        info!("{} records counted in {}", report.records_found, report.file_name);
+    }
+    Ok(())
+}
+
+
+fn log_validation_report(report: &ValidationReport)
+{
+    info!("");
+    info!("************************************");
+    info!("Validation of {} ({:?} schema)", report.file_name, report.schema_version);
+    info!("Records found: {}", report.records_found);
+    info!("Records valid: {}", report.records_valid);
+    for p in report.problems.iter() {
+        warn!("{}", p);
+    }
+    info!("Result: {}", if report.is_valid() { "passed" } else { "FAILED" });
+    info!("************************************");
+    info!("");
+}
+
+
+pub async fn fetch_current_version(pool: &Pool<Postgres>) -> Result<Option<String>, AppError>
+{
+    // Returns the version of the data currently in the ror tables, if any. 
+    // Used when processing or exporting without an import, and without a 
+    // version being specified.
+
+    let sql = "select to_regclass('ror.version_details') is not null";
+    let present: bool = sqlx::query_scalar(sql).fetch_one(pool).await?;
+    if !present {
+        return Ok(None);
+    }
+    let sql = "select version from ror.version_details";
+    let version: Option<String> = sqlx::query_scalar(sql).fetch_optional(pool).await?;
+    Ok(version)
+}
+
+
+pub async fn show_import_runs(num_runs: i64, pool: &Pool<Postgres>) -> Result<(), AppError>
+{
+    // Prints the most recent import runs, most recent first, to the console.
+
+    let runs = fetch_import_runs(num_runs, pool).await?;
+    if runs.is_empty() {
+        println!("No import runs have been recorded");
+    }
+    else {
+        println!("Last {} import run(s), most recent first:", runs.len());
+        for run in runs.iter() {
+            println!("{}", ror_import_runs::get_import_run_line(run));
+        }
+    }
+    Ok(())
+}
+
+
+pub fn log_import_report(report: &ImportReport)
+{
+    info!("");
+    info!("************************************");
+    info!("Files imported: {}", report.files_imported);
+    info!("Total records found: {}", report.records_found);
+    info!("Total records processed: {}", report.records_processed);
+    if report.records_skipped > 0 {
+        info!("Total records skipped (by date): {}", report.records_skipped);
+    }
+    if report.records_type_skipped > 0 {
+        info!("Total records skipped (by type): {}", report.records_type_skipped);
+    }
+    if report.records_status_skipped > 0 {
+        info!("Total records skipped (by status): {}", report.records_status_skipped);
+    }
+    if report.records_invalid_id > 0 {
+        info!("Total records skipped (invalid id): {}", report.records_invalid_id);
+    }
+    info!("************************************");
+    info!("");
+}
+
+
+pub async fn summarise_import(tables: &[String], pool : &Pool<Postgres>) -> Result<ImportQuality, AppError>
+{
+    // Gets the total record number of each table, the tables being counted 
+    // concurrently but listed in their usual order. If a selection of tables 
+    // has been made only those tables are included.
 
     info!("");
     info!("************************************");
@@ -138,33 +1157,218 @@ pub async fn summarise_import(pool : &Pool<Postgres>) -> Result<(), AppError>
     info!("************************************");
     info!("");
   
-    write_record_num("core_data", pool).await?;
-    write_record_num("admin_data", pool).await?;
-    write_record_num("names", pool).await?;
-    write_record_num("locations", pool).await?;
-    write_record_num("external_ids", pool).await?;
-    write_record_num("links", pool).await?;
-    write_record_num("type", pool).await?;
-    write_record_num("relationships", pool).await?;
-    write_record_num("domains", pool).await?;
+    let selected_tables: Vec<&str> = ROR_TABLES.iter().copied()
+                        .filter(|t| table_selected(t, tables)).collect();
+    for tc in ror_table_counts::fetch_table_counts(&selected_tables, pool).await {
+        if let Some(n) = tc.num_records {
+            info!("Total records in ror.{}: {}", tc.table_name, n);
+            if tc.table_name == "names" {
+                write_name_type_breakdown(pool).await?;
+            }
+        }
+    }
     
     info!("");
     info!("************************************");
     info!("");
    
-    Ok(())
+    summarise_quality(pool).await
 }
 
-  
-pub async fn write_record_num (table_name: &str, pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
-    let sql = "SELECT COUNT(*) FROM ror.".to_owned() + table_name;
-    let res: i64 = sqlx::query_scalar(&sql)
-    .fetch_one(pool)
-    .await?;
-    info!("Total records in ror.{}: {}", table_name, res);
-    Ok(())
+
+pub async fn summarise_quality(pool : &Pool<Postgres>) -> Result<ImportQuality, AppError>
+{
+    // Counts the organisations in the ror tables without any location, 
+    // external id or link, and those without any name other than the 
+    // ror (display) name. Also counts the locations with suspect coordinates.
+
+    let num_orgs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ror.core_data").fetch_one(pool).await?;
+    let sql = format!("SELECT COUNT(*) FROM ror.locations WHERE {}", SUSPECT_COORDS_CONDITION);
+    let suspect_coords: i64 = sqlx::query_scalar(&sql).fetch_one(pool).await?;
+
+    let quality = ImportQuality {
+        num_orgs,
+        no_location: get_missing_count("locations", "", pool).await?,
+        no_external_id: get_missing_count("external_ids", "", pool).await?,
+        no_link: get_missing_count("links", "", pool).await?,
+        no_non_ror_name: get_missing_count("names", " and (t.is_ror_name is null or t.is_ror_name = false)", pool).await?,
+        suspect_coords,
+    };
+
+    info!("************************************");
+    info!("Organisations missing optional data:");
+    info!("************************************");
+    info!("");
+    info!("Organisations without a location: {} (of {})", quality.no_location, num_orgs);
+    info!("Organisations without an external id: {} (of {})", quality.no_external_id, num_orgs);
+    info!("Organisations without a link: {} (of {})", quality.no_link, num_orgs);
+    info!("Organisations without a non-ror name: {} (of {})", quality.no_non_ror_name, num_orgs);
+    info!("Locations with suspect coordinates: {}", quality.suspect_coords);
+    info!("");
+    info!("************************************");
+    info!("");
+
+    Ok(quality)
 }
+
+
+async fn get_missing_count(table_name: &str, condition: &str, pool: &Pool<Postgres>) -> Result<i64, sqlx::Error> {
+    let sql = format!(r#"SELECT COUNT(*) FROM ror.core_data c
+                WHERE NOT EXISTS (SELECT 1 FROM ror.{} t WHERE t.id = c.id{})"#, table_name, condition);
+    sqlx::query_scalar(&sql).fetch_one(pool).await
+}
+
   
-  
-  
+pub async fn write_name_type_breakdown (pool: &Pool<Postgres>) -> Result<Vec<NameTypeCount>, sqlx::Error> {
+
+    // The types are listed in the order of their ids in the lookup table, with
+    // any type not in the lookup (i.e. new in the source data) listed at the end.
+
+    let sql = r#"SELECT n.name_type, COUNT(*) as num_names
+                 FROM ror.names n
+                 LEFT JOIN lup.ror_name_types t on n.name_type = t.name
+                 GROUP BY n.name_type, t.id
+                 ORDER BY t.id nulls last, n.name_type"#;
+    let mut counts: Vec<NameTypeCount> = sqlx::query_as(sql).fetch_all(pool).await?;
+    let sql = "SELECT COUNT(*) FROM ror.names WHERE is_ror_name = true";
+    let num_ror_names: i64 = sqlx::query_scalar(sql).fetch_one(pool).await?;
+    counts.push(NameTypeCount { name_type: "ror name".to_string(), num_names: num_ror_names });
+
+    for c in counts.iter() {
+        info!("    of which {}: {}", c.name_type, c.num_names);
+    }
+    Ok(counts)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_object_rather_than_array_gives_clear_error() {
+        let data = r#"  {"id": "https://ror.org/04ttjf776", "names": []}"#;
+        let res = parse_records(data, SchemaVersion::V2, "2030-01-01");
+        match res {
+            Err(AppError::DtErr(e)) => assert_eq!(e.to_string(), "expected a JSON array of ROR records, found an object"),
+            _ => panic!("expected a source data error"),
+        }
+
+        let res = parse_records("\u{feff}\n\"ror\"", SchemaVersion::V2, "2030-01-01");
+        match res {
+            Err(AppError::DtErr(e)) => assert_eq!(e.to_string(), "expected a JSON array of ROR records, found a string"),
+            _ => panic!("expected a source data error"),
+        }
+    }
+
+    #[test]
+    fn check_mislabelled_file_uses_content_schema() {
+        let data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v1.json").unwrap();
+        assert_eq!(get_checked_schema_version("v99-2030-01-01-test-data_schema_v2.json", &data), SchemaVersion::V1);
+        assert_eq!(get_checked_schema_version("v99-2030-01-01-test-data_schema_v1.json", &data), SchemaVersion::V1);
+        assert_eq!(get_checked_schema_version("v1.50-ror-data.json", "[]"), SchemaVersion::V1);
+    }
 
+    #[test]
+    fn check_empty_array_gives_no_records() {
+        let res = parse_records(" [ ] ", SchemaVersion::V2, "2030-01-01").unwrap();
+        assert_eq!(res.len(), 0);
+        let res = parse_records("[]", SchemaVersion::V1, "2030-01-01").unwrap();
+        assert_eq!(res.len(), 0);
+    }
+
+    #[test]
+    fn check_empty_file_gives_clear_error() {
+        for data in ["", "  \n\t ", "\u{feff}\n"] {
+            match check_not_empty(data, "v1.60-empty.json") {
+                Err(AppError::DtErr(e)) => assert_eq!(e.to_string(), "source file v1.60-empty.json is empty"),
+                _ => panic!("expected a source data error"),
+            }
+        }
+    }
+
+    #[test]
+    fn check_empty_array_file_gives_clear_error() {
+        for data in ["[]", " [\n ]\n", "\u{feff}[ ]"] {
+            match check_not_empty(data, "v1.60-empty.json") {
+                Err(AppError::DtErr(e)) => assert_eq!(e.to_string(), 
+                        "source file v1.60-empty.json holds an empty array ([]) - it contains no ROR records"),
+                _ => panic!("expected a source data error"),
+            }
+        }
+        assert!(check_not_empty(r#"[{"id": "https://ror.org/04ttjf776"}]"#, "v1.60.json").is_ok());
+        assert!(check_not_empty("[[]]", "v1.60.json").is_ok());
+    }
+
+    #[test]
+    fn check_array_of_wrong_shape_gives_clear_error() {
+        let data = r#"[{"doi": "10.5281/zenodo.6347574", "title": "ROR Data"}]"#;
+        let res = parse_records(data, SchemaVersion::V2, "2030-01-01");
+        match res {
+            Err(AppError::DtErr(e)) => assert!(e.to_string().starts_with("the JSON array does not contain records in the V2 ROR schema format")),
+            _ => panic!("expected a source data error"),
+        }
+
+        let res = parse_records(r#"[{"id": "https://ror.org/04ttjf776""#, SchemaVersion::V2, "2030-01-01");
+        assert!(matches!(res, Err(AppError::SdErr(_))));
+    }
+
+    #[test]
+    fn check_digest_of_known_file() {
+        let path = PathBuf::from("tests/test_data/v98-2030-01-01-sparse-test-data_schema_v2.json");
+        let (data, digest) = read_source_file(&path, "sparse", true).unwrap();
+        assert!(data.starts_with('['));
+        assert_eq!(digest, "e16c878f448e4c604320a8ba8fd03f020c4eb4e1397c4d4121fa03430b57d422");
+        assert_eq!(get_sha256_digest(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    }
+
+    #[test]
+    fn check_invalid_utf8_in_file_replaced_unless_strict() {
+        let path = PathBuf::from("tests/test_data/v99-2030-01-01-utf8-test-data.json");
+        let bytes = fs::read(&path).unwrap();
+        let (data, digest) = read_source_file(&path, "utf8", false).unwrap();
+        assert!(data.contains("Fraunhofer-Einrichtung f\u{fffd}r Wertstoffkreisläufe"));
+        assert_eq!(digest, get_sha256_digest(&bytes));
+        assert!(parse_records(&data, SchemaVersion::V2, "2030-01-01").is_ok());
+
+        let err = read_source_file(&path, "utf8", true).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn check_since_date_filters_older_records() {
+        // The test file has records last modified on 2024-04-29 (2), 2024-05-13 (8) and 2024-12-11 (10).
+        let data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        let recs = || parse_records(&data, SchemaVersion::V2, "2030-01-01").unwrap();
+        assert_eq!(filter_by_last_modified(recs(), "").len(), 20);
+        assert_eq!(filter_by_last_modified(recs(), "2024-05-13").len(), 18);
+        assert_eq!(filter_by_last_modified(recs(), "2024-05-14").len(), 10);
+        assert_eq!(filter_by_last_modified(recs(), "2025-01-01").len(), 0);
+    }
+
+    #[test]
+    fn check_type_selection_filters_records() {
+        let data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        let recs = parse_records(&data, SchemaVersion::V2, "2030-01-01").unwrap();
+        let count = |types: &[&str]| {
+            let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+            recs.iter().filter(|r| has_selected_type(r, &types)).count()
+        };
+        assert_eq!(count(&[]), 20);
+        assert_eq!(count(&["education", "healthcare"]), 10);
+        assert_eq!(count(&["funder"]), 10);
+        assert_eq!(count(&["Facility"]), 4);
+        assert_eq!(count(&["archive"]), 0);
+    }
+
+    #[test]
+    fn check_status_selection_filters_records() {
+        let data = fs::read_to_string("tests/test_data/v93-2030-01-01-status-test-data_schema_v2.json").unwrap();
+        let recs = parse_records(&data, SchemaVersion::V2, "2030-01-01").unwrap();
+        let count = |flags: Flags| recs.iter().filter(|r| has_allowed_status(r, get_excluded_statuses(&flags))).count();
+        assert_eq!(count(Flags::default()), 6);
+        assert_eq!(count(Flags { exclude_withdrawn: true, ..Flags::default() }), 4);
+        assert_eq!(count(Flags { active_only: true, ..Flags::default() }), 3);
+        assert_eq!(count(Flags { active_only: true, exclude_withdrawn: true, ..Flags::default() }), 3);
+    }
+}
@@ -0,0 +1,164 @@
+/***************************************************************************
+ * Writes a batch of records that could not be stored in the ror tables to
+ * a replay file (requested by --dump-failed), so that the rows of the batch
+ * can be inspected, and the inserts retried by hand once the cause has been
+ * found. The file, in the output folder, holds an insert statement for each
+ * row generated for the batch, in the order in which the tables are stored,
+ * within a single transaction, with the error that stopped the import as a
+ * comment at its head. It can be run as it stands, e.g. with psql -f. The
+ * folder is set once, before the import, in the same way as the trace id.
+ ***************************************************************************/
+
+use chrono::NaiveDate;
+use log::info;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::setup::get_timestamp;
+use super::ror_data_vectors::{CoreDataVecs, RequiredDataVecs, NonRequiredDataVecs};
+
+static DUMP_FOLDER: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+
+pub fn set_dump_folder(folder: Option<&Path>) {
+    *DUMP_FOLDER.lock().unwrap_or_else(|e| e.into_inner()) = folder.map(|f| f.to_path_buf());
+}
+
+
+pub fn fetch_dump_folder() -> Option<PathBuf> {
+    DUMP_FOLDER.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+
+pub fn write_replay_file(folder: &Path, source_file_name: &str, error_text: &str, cdv: &CoreDataVecs, 
+                         rdv: &RequiredDataVecs, ndv: &NonRequiredDataVecs) -> Result<PathBuf, std::io::Error> {
+    let stem = Path::new(source_file_name).file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let file_path = folder.join(format!("{} failed batch {}.sql", stem, get_timestamp()));
+    let sql = get_replay_sql(source_file_name, error_text, cdv, rdv, ndv);
+    fs::write(&file_path, sql)?;
+    info!("The {} records of the failed batch written to {}", cdv.row_count(), file_path.display());
+    Ok(file_path)
+}
+
+
+pub fn get_replay_sql(source_file_name: &str, error_text: &str, cdv: &CoreDataVecs, 
+                      rdv: &RequiredDataVecs, ndv: &NonRequiredDataVecs) -> String {
+    let mut statements = cdv.replay_statements();
+    statements.extend(rdv.replay_statements());
+    statements.extend(ndv.replay_statements());
+
+    let mut lines = vec![format!("-- A batch of {} records from {} that could not be stored in the ror tables, with {} rows.", 
+                                  cdv.row_count(), source_file_name, statements.len())];
+    lines.extend(error_text.lines().map(|l| format!("-- Error: {}", l)));
+    lines.push("".to_string());
+    lines.push("begin;".to_string());
+    lines.extend(statements);
+    lines.push("commit;".to_string());
+    lines.join("\n") + "\n"
+}
+
+
+pub fn get_insert_statement(table: &str, columns: &str, values: &[String]) -> String {
+    format!("insert into ror.{} ({}) values ({});", table, columns, values.join(", "))
+}
+
+
+// The values of the rows, as SQL literals - strings are quoted, with any 
+// quotes within them doubled, and missing values are given as null.
+
+pub trait SqlLiteral {
+    fn to_sql(&self) -> String;
+}
+
+impl SqlLiteral for String {
+    fn to_sql(&self) -> String {
+        format!("'{}'", self.replace('\'', "''"))
+    }
+}
+
+impl SqlLiteral for NaiveDate {
+    fn to_sql(&self) -> String {
+        format!("'{}'", self.format("%Y-%m-%d"))
+    }
+}
+
+impl SqlLiteral for bool {
+    fn to_sql(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SqlLiteral for i16 {
+    fn to_sql(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SqlLiteral for i64 {
+    fn to_sql(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SqlLiteral for f64 {
+    fn to_sql(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl<T: SqlLiteral> SqlLiteral for Option<T> {
+    fn to_sql(&self) -> String {
+        match self {
+            Some(v) => v.to_sql(),
+            None => "null".to_string(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::ror_json_models::RorRecord;
+
+    #[test]
+    fn check_values_given_as_sql_literals() {
+        assert_eq!("King's College".to_string().to_sql(), "'King''s College'");
+        assert_eq!(Some("en".to_string()).to_sql(), "'en'");
+        assert_eq!(None::<String>.to_sql(), "null");
+        assert_eq!(Some(1887_i16).to_sql(), "1887");
+        assert_eq!((-37.8136_f64).to_sql(), "-37.8136");
+        assert_eq!(NaiveDate::from_ymd_opt(2024, 12, 11).unwrap().to_sql(), "'2024-12-11'");
+        assert_eq!(None::<bool>.to_sql(), "null");
+        assert_eq!(get_insert_statement("type", "id, org_type", &["'04ttjf776'".to_string(), "'education'".to_string()]),
+                   "insert into ror.type (id, org_type) values ('04ttjf776', 'education');");
+    }
+
+    #[test]
+    fn check_replay_sql_holds_each_row_of_batch() {
+        let data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        let records: Vec<RorRecord> = serde_json::from_str(&data).unwrap();
+        let mut cdv = CoreDataVecs::new(20);
+        let mut rdv = RequiredDataVecs::new(20);
+        let mut ndv = NonRequiredDataVecs::new(20);
+        for r in records.iter() {
+            let db_id = crate::import::extract_id_from(&r.id).unwrap().to_string();
+            cdv.add_core_data(r, &db_id);
+            rdv.add_required_data(r, &db_id);
+            ndv.add_non_required_data(r, &db_id);
+        }
+
+        let sql = get_replay_sql("v99.json", "duplicate key value\nviolates constraint", &cdv, &rdv, &ndv);
+        let lines: Vec<&str> = sql.lines().collect();
+        assert!(lines[0].starts_with("-- A batch of 20 records from v99.json"));
+        assert_eq!(lines[1], "-- Error: duplicate key value");
+        assert_eq!(lines[2], "-- Error: violates constraint");
+        assert_eq!(lines[4], "begin;");
+        assert_eq!(*lines.last().unwrap(), "commit;");
+        assert_eq!(lines.iter().filter(|l| l.starts_with("insert into ror.core_data ")).count(), 20);
+        assert_eq!(lines.iter().filter(|l| l.starts_with("insert into ror.names ")).count(), rdv.name_db_ids.len());
+        assert_eq!(lines.iter().filter(|l| l.starts_with("insert into ")).count(), 
+                   2 * cdv.row_count() + rdv.row_count() + ndv.row_count());
+        assert!(lines.contains(&"insert into ror.core_data (id, ror_full_id, status, established) values ('04ttjf776', 'https://ror.org/04ttjf776', 'active', 1887);"));
+    }
+}
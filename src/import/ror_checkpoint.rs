@@ -0,0 +1,124 @@
+/***************************************************************************
+ * Records how far an import of a source file has got, so that a large
+ * import that is interrupted (or fails) part way through can be resumed
+ * with --resume, rather than restarted from the beginning. With --checkpoint
+ * or --resume, after each batch of records is stored the number of records
+ * dealt with so far is written to a checkpoint file, next to the source file
+ * and named after it with a '.checkpoint' extension. A resumed import skips that number of records
+ * before storing any, and the checkpoint is removed once the whole file has
+ * been imported. The file is replaced rather than rewritten in place, so
+ * that a failure while it is being written leaves the previous checkpoint.
+ * The count is of the records that reach the storing stage, i.e. after any
+ * --since and --types selection, so a resumed import must use the same
+ * selection as the one it continues.
+ ***************************************************************************/
+
+use log::info;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::AppError;
+use crate::error_defs::CustomError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub path: PathBuf,
+    pub start: usize,
+}
+
+impl Checkpoint {
+
+    pub fn for_source(source_file_path: &Path, resume: bool) -> Result<Checkpoint, AppError> {
+
+        // Without --resume any checkpoint left by an earlier run is removed,
+        // as the import starts again from the first record.
+
+        let path = get_checkpoint_path(source_file_path);
+        if !path.exists() {
+            if resume {
+                info!("No checkpoint found for {} - the import starts from the first record", source_file_path.display());
+            }
+            return Ok(Checkpoint { path, start: 0 })
+        }
+        if !resume {
+            info!("Earlier checkpoint {} removed, as --resume was not given", path.display());
+            fs::remove_file(&path)?;
+            return Ok(Checkpoint { path, start: 0 })
+        }
+        let contents = fs::read_to_string(&path)?;
+        match contents.trim().parse::<usize>() {
+            Ok(start) => {
+                info!("Import resumed from checkpoint {} - the first {} records are skipped", path.display(), start);
+                Ok(Checkpoint { path, start })
+            },
+            Err(_) => {
+                let msg = format!("The checkpoint {} does not hold a record count ('{}')", path.display(), contents.trim());
+                Err(AppError::CsErr(CustomError::new(&msg)))
+            },
+        }
+    }
+
+    pub fn save(&self, records_done: usize) -> Result<(), AppError> {
+        let temp_path = self.path.with_extension("checkpoint.tmp");
+        fs::write(&temp_path, format!("{}\n", records_done))?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn clear(&self) -> Result<(), AppError> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+
+pub fn get_checkpoint_path(source_file_path: &Path) -> PathBuf {
+    let mut file_name = source_file_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".checkpoint");
+    source_file_path.with_file_name(file_name)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_checkpoint_path_named_after_source() {
+        let path = get_checkpoint_path(Path::new("E:/ROR/data/v1.58-2024-12-11-ror-data_schema_v2.json"));
+        assert_eq!(path, PathBuf::from("E:/ROR/data/v1.58-2024-12-11-ror-data_schema_v2.json.checkpoint"));
+    }
+
+    #[test]
+    fn check_checkpoint_saved_resumed_and_cleared() {
+        let folder = std::env::temp_dir().join("ror1_checkpoint_test");
+        fs::create_dir_all(&folder).unwrap();
+        let source_path = folder.join("v99-2030-01-01-test-data_schema_v2.json");
+        let checkpoint_path = get_checkpoint_path(&source_path);
+        let _ = fs::remove_file(&checkpoint_path);
+
+        let checkpoint = Checkpoint::for_source(&source_path, true).unwrap();
+        assert_eq!(checkpoint.start, 0);
+        checkpoint.save(250).unwrap();
+        checkpoint.save(500).unwrap();
+        assert_eq!(fs::read_to_string(&checkpoint_path).unwrap(), "500\n");
+
+        assert_eq!(Checkpoint::for_source(&source_path, true).unwrap().start, 500);
+        checkpoint.clear().unwrap();
+        assert!(!checkpoint_path.exists());
+
+        // Without --resume an earlier checkpoint is discarded.
+
+        checkpoint.save(250).unwrap();
+        assert_eq!(Checkpoint::for_source(&source_path, false).unwrap().start, 0);
+        assert!(!checkpoint_path.exists());
+
+        fs::write(&checkpoint_path, "half way").unwrap();
+        match Checkpoint::for_source(&source_path, true) {
+            Err(AppError::CsErr(e)) => assert!(e.to_string().ends_with("does not hold a record count ('half way')")),
+            _ => panic!("expected a custom error"),
+        }
+        fs::remove_file(&checkpoint_path).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+/***************************************************************************
+ * Counts the records in a source file, and how many of them have each of
+ * the optional sections populated, without any database access. The file
+ * is read as a stream, each element of the top level array being parsed
+ * as generic JSON and then dropped, so that the whole file is never held
+ * in memory as a single structure. The counting is lenient - records that
+ * would fail validation are still counted, using whatever fields they have.
+ ***************************************************************************/
+
+use serde::de::{Deserializer, SeqAccess, Visitor};
+use serde_json::Value;
+use std::fmt;
+use std::io::Read;
+
+pub const OPTIONAL_SECTIONS: [&str; 6] = ["established", "locations", "external_ids",
+                                          "links", "relationships", "domains"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountReport {
+    pub file_name: String,
+    pub records_found: usize,
+    pub section_counts: Vec<(&'static str, usize)>,
+}
+
+
+pub fn count_records<R: Read>(reader: R, file_name: &str) -> Result<CountReport, serde_json::Error> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let (records_found, counts) = deserializer.deserialize_seq(RecordCounter)?;
+    deserializer.end()?;
+    Ok(CountReport {
+        file_name: file_name.to_string(),
+        records_found,
+        section_counts: OPTIONAL_SECTIONS.iter().copied().zip(counts).collect(),
+    })
+}
+
+
+pub fn get_count_lines(report: &CountReport) -> Vec<String> {
+    let mut lines = vec![format!("{}: {} records", report.file_name, report.records_found)];
+    for (section, n) in report.section_counts.iter() {
+        let pc = if report.records_found > 0 { 100.0 * *n as f64 / report.records_found as f64 } else { 0.0 };
+        lines.push(format!("    with {:<15}{:>8}  ({:.1}%)", section, n, pc));
+    }
+    lines
+}
+
+
+// A section is regarded as populated if it is present, not null, and (for
+// strings, arrays and objects) not empty.
+
+fn is_populated(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(a)) => !a.is_empty(),
+        Some(Value::Object(o)) => !o.is_empty(),
+        Some(_) => true,
+    }
+}
+
+
+struct RecordCounter;
+
+impl<'de> Visitor<'de> for RecordCounter {
+    type Value = (usize, [usize; 6]);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON array of ROR records")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut records_found = 0;
+        let mut counts = [0; 6];
+        while let Some(record) = seq.next_element::<Value>()? {
+            records_found += 1;
+            for (i, section) in OPTIONAL_SECTIONS.iter().enumerate() {
+                if is_populated(record.get(section)) {
+                    counts[i] += 1;
+                }
+            }
+        }
+        Ok((records_found, counts))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    #[test]
+    fn check_records_and_sections_counted() {
+        let file = File::open("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        let report = count_records(BufReader::new(file), "v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        assert_eq!(report.records_found, 20);
+        assert_eq!(report.section_counts, vec![("established", 19), ("locations", 20), ("external_ids", 19),
+                                               ("links", 20), ("relationships", 10), ("domains", 0)]);
+        let lines = get_count_lines(&report);
+        assert_eq!(lines[0], "v99-2030-01-01-test-data_schema_v2.json: 20 records");
+        assert_eq!(lines[5], "    with relationships        10  (50.0%)");
+    }
+
+    #[test]
+    fn check_counting_is_lenient_but_needs_an_array() {
+        let data = r#"[{"id": "https://ror.org/0malfrm01", "domains": ["a.org"]}, {"id": 3, "links": []}, 7]"#;
+        let report = count_records(data.as_bytes(), "lenient.json").unwrap();
+        assert_eq!(report.records_found, 3);
+        assert_eq!(report.section_counts[5], ("domains", 1));
+        assert_eq!(report.section_counts[3], ("links", 0));
+
+        assert!(count_records(r#"{"id": "https://ror.org/04ttjf776"}"#.as_bytes(), "object.json").is_err());
+        assert!(count_records(r#"[{"id": "https://ror.org/04ttjf776"}"#.as_bytes(), "truncated.json").is_err());
+    }
+}
@@ -1,6 +1,13 @@
 use chrono::NaiveDate;
-use sqlx::{Pool, Postgres};
+use sqlx::PgConnection;
 use crate::import::ror_json_models::RorRecord;
+use crate::AppError;
+use crate::error_defs::CustomError;
+use log::warn;
+use regex::Regex;
+use std::sync::OnceLock;
+use super::ror_batch_dump::{SqlLiteral, get_insert_statement};
+use crate::setup::log_helper::truncate_for_log;
 
 // vectors to hold column values, 100 at a time
 
@@ -47,29 +54,68 @@ impl CoreDataVecs{
     }
 
 
-    pub async fn store_data(&self, pool : &Pool<Postgres>) {
-    
+    pub fn row_count(&self) -> usize {
+        self.db_ids.len()
+    }
+
+    pub fn trace_rows(&self) -> Vec<String> {
+        let mut rows = Vec::new();
+        for i in 0..self.db_ids.len() {
+            rows.push(format!("ror.core_data {:?}", (&self.db_ids[i], &self.ror_ids[i], &self.statuses[i], &self.estabs[i])));
+            rows.push(format!("ror.admin_data {:?}", (&self.db_ids[i], &self.created_dates[i], &self.created_vs[i], 
+                                                      &self.lastmod_dates[i], &self.lastmod_vs[i])));
+        }
+        rows
+    }
+
+    pub fn replay_statements(&self) -> Vec<String> {
+        let mut statements = Vec::new();
+        for i in 0..self.db_ids.len() {
+            statements.push(get_insert_statement("core_data", "id, ror_full_id, status, established", 
+                    &[self.db_ids[i].to_sql(), self.ror_ids[i].to_sql(), self.statuses[i].to_sql(), self.estabs[i].to_sql()]));
+        }
+        for i in 0..self.db_ids.len() {
+            statements.push(get_insert_statement("admin_data", "id, created, cr_schema, last_modified, lm_schema", 
+                    &[self.db_ids[i].to_sql(), self.created_dates[i].to_sql(), self.created_vs[i].to_sql(), 
+                      self.lastmod_dates[i].to_sql(), self.lastmod_vs[i].to_sql()]));
+        }
+        statements
+    }
+
+    pub fn check_alignment(&self) -> Result<(), AppError> {
+        check_aligned("core data", &[("db_ids", self.db_ids.len()), ("ror_ids", self.ror_ids.len()), 
+                ("statuses", self.statuses.len()), ("estabs", self.estabs.len()), 
+                ("created_dates", self.created_dates.len()), ("created_vs", self.created_vs.len()), 
+                ("lastmod_dates", self.lastmod_dates.len()), ("lastmod_vs", self.lastmod_vs.len())])
+    }
+
+    // The rows are inserted within a transaction of the batch, which is 
+    // committed (or rolled back) by the caller.
+
+    pub async fn insert_rows(&self, conn: &mut PgConnection) -> Result<(), AppError> {
+
         // do the core data
-        let _ = sqlx::query(r#"INSERT INTO ror.core_data (id, ror_full_id, status, established) 
+        sqlx::query(r#"INSERT INTO ror.core_data (id, ror_full_id, status, established) 
             SELECT * FROM UNNEST($1::text[], $2::text[], $3::text[], $4::int[])"#)
         .bind(&self.db_ids)
         .bind(&self.ror_ids)
         .bind(&self.statuses)
         .bind(&self.estabs)
-        .execute(pool)
-        .await;
+        .execute(&mut *conn)
+        .await?;
         
         // do the admin data
-        let _ = sqlx::query(r#"INSERT INTO ror.admin_data (id, created, cr_schema, last_modified, lm_schema) 
+        sqlx::query(r#"INSERT INTO ror.admin_data (id, created, cr_schema, last_modified, lm_schema) 
             SELECT * FROM UNNEST($1::text[], $2::timestamp[], $3::text[], $4::timestamp[], $5::text[])"#)
         .bind(&self.db_ids)
         .bind(&self.created_dates)
         .bind(&self.created_vs)
         .bind(&self.lastmod_dates)
         .bind(&self.lastmod_vs)
-        .execute(pool)
-        .await;
+        .execute(&mut *conn)
+        .await?;
 
+        Ok(())
     }
 
 }
@@ -96,6 +142,7 @@ pub struct RequiredDataVecs {
     pub cy_names: Vec<String>,
     pub cy_subdiv_codes: Vec<Option<String>>,
     pub cy_subdiv_names: Vec<Option<String>>,
+    pub loc_primaries: Vec<bool>,
 }
 
 
@@ -122,6 +169,7 @@ impl RequiredDataVecs{
             cy_names: Vec::with_capacity(vsize),
             cy_subdiv_codes: Vec::with_capacity(vsize),
             cy_subdiv_names: Vec::with_capacity(vsize),
+            loc_primaries: Vec::with_capacity(vsize),
         }
     }
 
@@ -172,8 +220,12 @@ impl RequiredDataVecs{
             }
         }
 
+        // ROR records usually have a single location, but more are allowed. The
+        // first listed is taken as the primary location, which provides the 
+        // single location of the organisation in src.core_data.
+
         if r.locations.len() > 0 {
-            for loc in r.locations.iter()
+            for (i, loc) in r.locations.iter().enumerate()
             {
                 self.loc_db_ids.push(db_id.clone());
                 self.gn_ids.push(loc.geonames_id.clone());
@@ -186,36 +238,93 @@ impl RequiredDataVecs{
                 self.cy_names.push(loc.geonames_details.country_name.clone());
                 self.cy_subdiv_codes.push(loc.geonames_details.country_subdivision_code.clone());
                 self.cy_subdiv_names.push(loc.geonames_details.country_subdivision_name.clone());
+                self.loc_primaries.push(i == 0);
             }
         }
 
     }
 
-    pub async fn store_data(&self, pool : &Pool<Postgres>) {
-        
+    pub fn row_count(&self) -> usize {
+        self.name_db_ids.len() + self.type_db_ids.len() + self.loc_db_ids.len()
+    }
+
+    pub fn trace_rows(&self) -> Vec<String> {
+        let mut rows = Vec::new();
+        for i in 0..self.name_db_ids.len() {
+            rows.push(format!("ror.names {:?}", (&self.name_db_ids[i], truncate_for_log(&self.names[i]), &self.name_types[i], 
+                                                 &self.is_rors[i], &self.langs[i])));
+        }
+        for i in 0..self.type_db_ids.len() {
+            rows.push(format!("ror.type {:?}", (&self.type_db_ids[i], &self.org_types[i])));
+        }
+        for i in 0..self.loc_db_ids.len() {
+            rows.push(format!("ror.locations {:?}", (&self.loc_db_ids[i], &self.gn_ids[i], &self.gn_names[i], 
+                                                     &self.lats[i], &self.lngs[i], &self.cont_codes[i], &self.cont_names[i], 
+                                                     &self.cy_codes[i], &self.cy_names[i], &self.cy_subdiv_codes[i], 
+                                                     &self.cy_subdiv_names[i], &self.loc_primaries[i])));
+        }
+        rows
+    }
+
+    pub fn replay_statements(&self) -> Vec<String> {
+        let mut statements = Vec::new();
+        for i in 0..self.name_db_ids.len() {
+            statements.push(get_insert_statement("names", "id, value, name_type, is_ror_name, lang", 
+                    &[self.name_db_ids[i].to_sql(), self.names[i].to_sql(), self.name_types[i].to_sql(), 
+                      self.is_rors[i].to_sql(), self.langs[i].to_sql()]));
+        }
+        for i in 0..self.type_db_ids.len() {
+            statements.push(get_insert_statement("type", "id, org_type", 
+                    &[self.type_db_ids[i].to_sql(), self.org_types[i].to_sql()]));
+        }
+        for i in 0..self.loc_db_ids.len() {
+            statements.push(get_insert_statement("locations", "id, geonames_id, name, lat, lng, continent_code, \
+                    continent_name, country_code, country_name, country_subdivision_code, country_subdivision_name, is_primary", 
+                    &[self.loc_db_ids[i].to_sql(), self.gn_ids[i].to_sql(), self.gn_names[i].to_sql(), 
+                      self.lats[i].to_sql(), self.lngs[i].to_sql(), self.cont_codes[i].to_sql(), self.cont_names[i].to_sql(), 
+                      self.cy_codes[i].to_sql(), self.cy_names[i].to_sql(), self.cy_subdiv_codes[i].to_sql(), 
+                      self.cy_subdiv_names[i].to_sql(), self.loc_primaries[i].to_sql()]));
+        }
+        statements
+    }
+
+    pub fn check_alignment(&self) -> Result<(), AppError> {
+        check_aligned("name", &[("name_db_ids", self.name_db_ids.len()), ("names", self.names.len()), 
+                ("name_types", self.name_types.len()), ("is_rors", self.is_rors.len()), ("langs", self.langs.len())])?;
+        check_aligned("type", &[("type_db_ids", self.type_db_ids.len()), ("org_types", self.org_types.len())])?;
+        check_aligned("location", &[("loc_db_ids", self.loc_db_ids.len()), ("gn_ids", self.gn_ids.len()), 
+                ("gn_names", self.gn_names.len()), ("lats", self.lats.len()), ("lngs", self.lngs.len()), 
+                ("cont_codes", self.cont_codes.len()), ("cont_names", self.cont_names.len()), 
+                ("cy_codes", self.cy_codes.len()), ("cy_names", self.cy_names.len()), 
+                ("cy_subdiv_codes", self.cy_subdiv_codes.len()), ("cy_subdiv_names", self.cy_subdiv_names.len()), 
+                ("loc_primaries", self.loc_primaries.len())])
+    }
+
+    pub async fn insert_rows(&self, conn: &mut PgConnection) -> Result<(), AppError> {
+
         // do the name data
-        let _ = sqlx::query(r#"INSERT INTO ror.names (id, value, name_type, is_ror_name, lang) 
+        sqlx::query(r#"INSERT INTO ror.names (id, value, name_type, is_ror_name, lang) 
         SELECT * FROM UNNEST($1::text[], $2::text[], $3::text[], $4::bool[], $5::text[])"#)
         .bind(&self.name_db_ids)
         .bind(&self.names)
         .bind(&self.name_types)
         .bind(&self.is_rors)
         .bind(&self.langs)
-        .execute(pool)
-        .await;
+        .execute(&mut *conn)
+        .await?;
 
         // do the type data
-        let _ = sqlx::query(r#"INSERT INTO ror.type (id, org_type) 
+        sqlx::query(r#"INSERT INTO ror.type (id, org_type) 
         SELECT * FROM UNNEST($1::text[], $2::text[])"#)
         .bind(&self.type_db_ids)
         .bind(&self.org_types)
-        .execute(pool)
-        .await;
+        .execute(&mut *conn)
+        .await?;
 
         // do the location data
-        let _ = sqlx::query(r#"INSERT INTO ror.locations (id, geonames_id, name, lat, lng, 
-        continent_code, continent_name, country_code, country_name, country_subdivision_code, country_subdivision_name ) 
-        SELECT * FROM UNNEST($1::text[], $2::int[], $3::text[], $4::real[], $5::real[], $6::text[], $7::text[], $8::text[], $9::text[], $10::text[], $11::text[])"#)
+        sqlx::query(r#"INSERT INTO ror.locations (id, geonames_id, name, lat, lng, 
+        continent_code, continent_name, country_code, country_name, country_subdivision_code, country_subdivision_name, is_primary ) 
+        SELECT * FROM UNNEST($1::text[], $2::int[], $3::text[], $4::real[], $5::real[], $6::text[], $7::text[], $8::text[], $9::text[], $10::text[], $11::text[], $12::bool[])"#)
         .bind(&self.loc_db_ids)
         .bind(&self.gn_ids)
         .bind(&self.gn_names)
@@ -227,9 +336,11 @@ impl RequiredDataVecs{
         .bind(&self.cy_names)
         .bind(&self.cy_subdiv_codes)
         .bind(&self.cy_subdiv_names)
-        .execute(pool)
-        .await;
+        .bind(&self.loc_primaries)
+        .execute(&mut *conn)
+        .await?;
 
+        Ok(())
     }
 }
 
@@ -285,9 +396,16 @@ impl NonRequiredDataVecs{
             if rels.len() > 0 {
                 for rel in rels.iter()
                 {
+                    let rel_id = match extract_id_from(&rel.id) {
+                        Some(id) => id.to_string(),
+                        None => {
+                            warn!("Relationship from {} to invalid ROR id '{}' skipped", db_id, truncate_for_log(&rel.id));
+                            continue;
+                        },
+                    };
                     self.rel_db_ids.push(db_id.clone());
                     self.rel_types.push(rel.rel_type.clone());
-                    self.rel_ids.push(extract_id_from(&rel.id).to_string());
+                    self.rel_ids.push(rel_id);
                     self.rel_labels.push(rel.label.clone());
                 };
             }
@@ -371,55 +489,137 @@ impl NonRequiredDataVecs{
     
     }
 
-    pub async fn store_data(&self, pool : &Pool<Postgres>) {
+    pub fn row_count(&self) -> usize {
+        self.link_db_ids.len() + self.id_db_ids.len() + self.rel_db_ids.len() + self.dom_db_ids.len()
+    }
+
+    pub fn trace_rows(&self) -> Vec<String> {
+        let mut rows = Vec::new();
+        for i in 0..self.rel_db_ids.len() {
+            rows.push(format!("ror.relationships {:?}", (&self.rel_db_ids[i], &self.rel_types[i], &self.rel_ids[i], 
+                                                         truncate_for_log(&self.rel_labels[i]))));
+        }
+        for i in 0..self.link_db_ids.len() {
+            rows.push(format!("ror.links {:?}", (&self.link_db_ids[i], &self.link_types[i], truncate_for_log(&self.links[i]))));
+        }
+        for i in 0..self.id_db_ids.len() {
+            rows.push(format!("ror.external_ids {:?}", (&self.id_db_ids[i], &self.id_types[i], &self.id_values[i], &self.is_prefs[i])));
+        }
+        for i in 0..self.dom_db_ids.len() {
+            rows.push(format!("ror.domains {:?}", (&self.dom_db_ids[i], &self.doms[i])));
+        }
+        rows
+    }
+
+    pub fn replay_statements(&self) -> Vec<String> {
+        let mut statements = Vec::new();
+        for i in 0..self.rel_db_ids.len() {
+            statements.push(get_insert_statement("relationships", "id, rel_type, related_id, related_label", 
+                    &[self.rel_db_ids[i].to_sql(), self.rel_types[i].to_sql(), self.rel_ids[i].to_sql(), self.rel_labels[i].to_sql()]));
+        }
+        for i in 0..self.link_db_ids.len() {
+            statements.push(get_insert_statement("links", "id, link_type, value", 
+                    &[self.link_db_ids[i].to_sql(), self.link_types[i].to_sql(), self.links[i].to_sql()]));
+        }
+        for i in 0..self.id_db_ids.len() {
+            statements.push(get_insert_statement("external_ids", "id, id_type, id_value, is_preferred", 
+                    &[self.id_db_ids[i].to_sql(), self.id_types[i].to_sql(), self.id_values[i].to_sql(), self.is_prefs[i].to_sql()]));
+        }
+        for i in 0..self.dom_db_ids.len() {
+            statements.push(get_insert_statement("domains", "id, value", 
+                    &[self.dom_db_ids[i].to_sql(), self.doms[i].to_sql()]));
+        }
+        statements
+    }
+
+    pub fn check_alignment(&self) -> Result<(), AppError> {
+        check_aligned("link", &[("link_db_ids", self.link_db_ids.len()), ("link_types", self.link_types.len()), 
+                ("links", self.links.len())])?;
+        check_aligned("external id", &[("id_db_ids", self.id_db_ids.len()), ("id_types", self.id_types.len()), 
+                ("id_values", self.id_values.len()), ("is_prefs", self.is_prefs.len())])?;
+        check_aligned("relationship", &[("rel_db_ids", self.rel_db_ids.len()), ("rel_types", self.rel_types.len()), 
+                ("rel_ids", self.rel_ids.len()), ("rel_labels", self.rel_labels.len())])?;
+        check_aligned("domain", &[("dom_db_ids", self.dom_db_ids.len()), ("doms", self.doms.len())])
+    }
+
+    pub async fn insert_rows(&self, conn: &mut PgConnection) -> Result<(), AppError> {
 
         // do the relationships data
-        let _ = sqlx::query(r#"INSERT INTO ror.relationships (id, rel_type, related_id, related_label) 
+        sqlx::query(r#"INSERT INTO ror.relationships (id, rel_type, related_id, related_label) 
         SELECT * FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[])"#)
         .bind(&self.rel_db_ids)
         .bind(&self.rel_types)
         .bind(&self.rel_ids)
         .bind(&self.rel_labels)
-        .execute(pool)
-        .await;
+        .execute(&mut *conn)
+        .await?;
 
     
         // do the links data
-        let _ = sqlx::query(r#"INSERT INTO ror.links (id, link_type, value) 
+        sqlx::query(r#"INSERT INTO ror.links (id, link_type, value) 
         SELECT * FROM UNNEST($1::text[], $2::text[], $3::text[])"#)
         .bind(&self.link_db_ids)
         .bind(&self.link_types)
         .bind(&self.links)
-        .execute(pool)
-        .await;
+        .execute(&mut *conn)
+        .await?;
 
     
         // do the external ids data
-        let _ = sqlx::query(r#"INSERT INTO ror.external_ids (id, id_type, id_value, is_preferred) 
+        sqlx::query(r#"INSERT INTO ror.external_ids (id, id_type, id_value, is_preferred) 
         SELECT * FROM UNNEST($1::text[], $2::text[], $3::text[], $4::bool[])"#)
         .bind(&self.id_db_ids)
         .bind(&self.id_types)
         .bind(&self.id_values)
         .bind(&self.is_prefs)
-        .execute(pool)
-        .await;
+        .execute(&mut *conn)
+        .await?;
     
         // do the domain data
-        let _ = sqlx::query(r#"INSERT INTO ror.domains (id, value) 
+        sqlx::query(r#"INSERT INTO ror.domains (id, value) 
         SELECT * FROM UNNEST($1::text[], $2::text[])"#)
         .bind(&self.dom_db_ids)
         .bind(&self.doms)
-        .execute(pool)
-        .await;
+        .execute(&mut *conn)
+        .await?;
 
+        Ok(())
     }
 
 }
 
 
-pub fn extract_id_from(full_id: &String) -> &str {
-    let b = full_id.as_bytes();
-    std::str::from_utf8(&b[b.len()-9..]).unwrap()
+// The column vectors of each table are filled in parallel, row by row, and 
+// unnested together when stored, so vectors of different lengths would pair
+// values from different rows. Such a batch is not stored. The check is made
+// in release as well as debug builds, as it is cheap compared to the inserts.
+
+fn check_aligned(table_group: &str, lengths: &[(&str, usize)]) -> Result<(), AppError> {
+    let (key_name, key_len) = lengths[0];
+    let misaligned: Vec<String> = lengths[1..].iter().filter(|(_, len)| *len != key_len)
+                        .map(|(name, len)| format!("{} has {}", name, len)).collect();
+    if misaligned.is_empty() {
+        return Ok(());
+    }
+    let msg = format!("The {} vectors are out of alignment - {} has {} values, but {} - the batch was not stored", 
+                      table_group, key_name, key_len, misaligned.join(", "));
+    Err(AppError::CsErr(CustomError::new(&msg)))
+}
+
+
+// Returns the 9 character ROR id, if it matches the canonical pattern - a 
+// leading 0, six characters from the Crockford base32 set (no i, l, o or u) 
+// and a 2 digit checksum. The id may be given as the full ROR URL (with or 
+// without the scheme, and with an optional trailing slash) or on its own, 
+// as in some locally held data sets. Anything else, including a URL on 
+// another domain, is not a ROR id.
+
+pub fn extract_id_from(full_id: &str) -> Option<&str> {
+    static ROR_ID_RE: OnceLock<Regex> = OnceLock::new();
+    let re = ROR_ID_RE.get_or_init(|| Regex::new(
+        r"^(?:(?:https?://)?(?:www\.)?ror\.org/)?(0[a-hj-km-np-tv-z0-9]{6}[0-9]{2})/?$").unwrap());
+
+    re.captures(full_id).and_then(|caps| caps.get(1)).map(|m| m.as_str())
 }
 
 // Tests
@@ -427,13 +627,139 @@ pub fn extract_id_from(full_id: &String) -> &str {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::import::ror_json_models::RorRecordV1;
+    use std::fs;
     
     // Ensure the extract_id_from utility function works as expected.
 
     #[test]
     fn test_extracting_id() {
-        let test_id = "https://ror.org/123456789".to_string();
-        assert_eq!(extract_id_from(&(test_id)), "123456789")
+        let test_id = "https://ror.org/04ttjf776".to_string();
+        assert_eq!(extract_id_from(&(test_id)), Some("04ttjf776"));
+        assert_eq!(extract_id_from("http://ror.org/04ttjf776"), Some("04ttjf776"));
+        assert_eq!(extract_id_from("ror.org/04ttjf776"), Some("04ttjf776"));
+    }
+
+    #[test]
+    fn test_extracting_bare_id() {
+        assert_eq!(extract_id_from("04ttjf776"), Some("04ttjf776"));
+    }
+
+    #[test]
+    fn test_extracting_id_with_trailing_slash() {
+        assert_eq!(extract_id_from("https://ror.org/04ttjf776/"), Some("04ttjf776"));
+        assert_eq!(extract_id_from("04ttjf776/"), Some("04ttjf776"));
+    }
+
+    #[test]
+    fn test_extracting_truncated_or_malformed_id() {
+        assert_eq!(extract_id_from("https://ror.org/04ttjf7"), None);
+        assert_eq!(extract_id_from("https://ror.org/123456789"), None);
+        assert_eq!(extract_id_from("https://ror.org/04ttjfl76"), None);    // 'l' not in id character set
+        assert_eq!(extract_id_from("not a ror id"), None);
+        assert_eq!(extract_id_from("https://example.org/04ttjf776"), None);   // not the ror domain
+        assert_eq!(extract_id_from("https://ror.org/xyz/04ttjf776"), None);
+        assert_eq!(extract_id_from("https://ror.org/04ttjf776//"), None);
+        assert_eq!(extract_id_from(""), None);
+    }
+
+    // Ensure v1 and v2 versions of the same records fill the vectors (and 
+    // therefore the tables) in the same way. The v1 test file holds the 
+    // same organisations as the first three records of the v2 test file.
+
+    fn fill_vectors(recs: &[RorRecord]) -> (CoreDataVecs, RequiredDataVecs, NonRequiredDataVecs) {
+        let mut cdv = CoreDataVecs::new(10);
+        let mut rdv = RequiredDataVecs::new(10);
+        let mut ndv = NonRequiredDataVecs::new(10);
+        for r in recs {
+            let db_id = extract_id_from(&r.id).unwrap().to_string();
+            cdv.add_core_data(r, &db_id);
+            rdv.add_required_data(r, &db_id);
+            ndv.add_non_required_data(r, &db_id);
+        }
+        (cdv, rdv, ndv)
+    }
+
+    #[test]
+    fn test_v1_and_v2_records_give_same_table_shapes() {
+        let v1_data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v1.json").unwrap();
+        let v1_recs: Vec<RorRecordV1> = serde_json::from_str(&v1_data).unwrap();
+        let v1_recs: Vec<RorRecord> = v1_recs.into_iter().map(|r| r.into_v2("2030-01-01")).collect();
+        
+        let v2_data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        let mut v2_recs: Vec<RorRecord> = serde_json::from_str(&v2_data).unwrap();
+        v2_recs.truncate(v1_recs.len());
+
+        let (cdv1, rdv1, ndv1) = fill_vectors(&v1_recs);
+        let (cdv2, rdv2, ndv2) = fill_vectors(&v2_recs);
+
+        assert_eq!(cdv1.db_ids, cdv2.db_ids);
+        assert_eq!(cdv1.statuses, cdv2.statuses);
+        assert_eq!(cdv1.estabs, cdv2.estabs);
+        let (mut nt1, mut nt2) = (rdv1.name_types.clone(), rdv2.name_types.clone());
+        nt1.sort();
+        nt2.sort();
+        assert_eq!(nt1, nt2);
+        assert_eq!(rdv1.org_types, rdv2.org_types);
+        assert_eq!(rdv1.loc_db_ids.len(), rdv2.loc_db_ids.len());
+        assert_eq!(rdv1.gn_ids, rdv2.gn_ids);
+        assert_eq!(rdv1.cy_codes, rdv2.cy_codes);
+        assert_eq!(ndv1.rel_types, ndv2.rel_types);
+        assert_eq!(ndv1.rel_ids, ndv2.rel_ids);
+        assert_eq!(ndv1.link_db_ids.len(), ndv2.link_db_ids.len());
+        assert_eq!(ndv1.id_db_ids.len(), ndv2.id_db_ids.len());
+        assert_eq!(ndv1.dom_db_ids.len(), ndv2.dom_db_ids.len());
+    }
+
+    #[test]
+    fn test_filled_vectors_are_aligned() {
+        let v2_data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        let v2_recs: Vec<RorRecord> = serde_json::from_str(&v2_data).unwrap();
+        let (cdv, rdv, ndv) = fill_vectors(&v2_recs);
+        assert!(cdv.check_alignment().is_ok());
+        assert!(rdv.check_alignment().is_ok());
+        assert!(ndv.check_alignment().is_ok());
+    }
+
+    #[test]
+    fn test_first_location_flagged_as_primary() {
+        let data = fs::read_to_string("tests/test_data/v99-2030-01-01-locations-test-data.json").unwrap();
+        let recs: Vec<RorRecord> = serde_json::from_str(&data).unwrap();
+        let (_, rdv, _) = fill_vectors(&recs);
+        assert!(rdv.check_alignment().is_ok());
+
+        // A single location is the primary, and of several the first listed, 
+        // whatever its geonames id.
+
+        assert_eq!(rdv.loc_db_ids, vec!["04ttjf776", "02s7vm534", "02s7vm534"]);
+        assert_eq!(rdv.gn_ids, vec![2158177, 2956715, 2911007]);
+        assert_eq!(rdv.loc_primaries, vec![true, true, false]);
+    }
+
+    #[test]
+    fn test_desynchronised_vectors_are_detected() {
+        let v2_data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        let v2_recs: Vec<RorRecord> = serde_json::from_str(&v2_data).unwrap();
+        let (mut cdv, mut rdv, mut ndv) = fill_vectors(&v2_recs[..3]);
+
+        cdv.statuses.pop();
+        match cdv.check_alignment() {
+            Err(AppError::CsErr(e)) => assert_eq!(e.to_string(), 
+                "The core data vectors are out of alignment - db_ids has 3 values, but statuses has 2 - the batch was not stored"),
+            _ => panic!("expected a custom error"),
+        }
+
+        rdv.lats.push(0.0);
+        rdv.lngs.push(0.0);
+        let num_locs = rdv.loc_db_ids.len();
+        match rdv.check_alignment() {
+            Err(AppError::CsErr(e)) => assert!(e.to_string().contains(&format!("loc_db_ids has {} values, but lats has {}, lngs has {}", 
+                                                                                num_locs, num_locs + 1, num_locs + 1))),
+            _ => panic!("expected a custom error"),
+        }
+
+        ndv.doms.push("example.org".to_string());
+        assert!(ndv.check_alignment().is_err());
     }
 }
 
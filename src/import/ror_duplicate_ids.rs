@@ -0,0 +1,74 @@
+/***************************************************************************
+ * Finds any ROR ids that occur more than once in a source file (e.g. one
+ * that has been edited by hand, or merged from several files). Storing such
+ * a file would fail part way through, on the primary key of ror.core_data,
+ * so the ids are checked before anything is stored. With --replace only the
+ * last occurrence of each id is imported, as the latest version of the record.
+ ***************************************************************************/
+
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct IdScan {
+    last_positions: HashMap<String, (usize, usize)>,    // position of last occurrence, number of occurrences
+    num_ids: usize,
+    pub duplicates: Vec<String>,
+}
+
+impl IdScan {
+    pub fn new() -> Self {
+        IdScan::default()
+    }
+
+    // Duplicated ids are listed once each, in the order their first repeat is found.
+
+    pub fn add(&mut self, position: usize, id: &str) {
+        self.num_ids += 1;
+        let entry = self.last_positions.entry(id.to_string()).or_insert((position, 0));
+        *entry = (position, entry.1 + 1);
+        if entry.1 == 2 {
+            self.duplicates.push(id.to_string());
+        }
+    }
+
+    pub fn is_last(&self, position: usize, id: &str) -> bool {
+        match self.last_positions.get(id) {
+            Some((p, _)) => *p == position,
+            None => true,
+        }
+    }
+
+    pub fn num_replaced(&self) -> usize {
+        self.num_ids - self.last_positions.len()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_duplicates_found_and_last_kept() {
+        let ids = ["04ttjf776", "01rxfrp27", "04ttjf776", "023q4bk22", "04ttjf776", "01rxfrp27"];
+        let mut scan = IdScan::new();
+        for (i, id) in ids.iter().enumerate() {
+            scan.add(i, id);
+        }
+        assert_eq!(scan.duplicates, vec!["04ttjf776", "01rxfrp27"]);
+        let kept: Vec<usize> = ids.iter().enumerate().filter(|(i, id)| scan.is_last(*i, id)).map(|(i, _)| i).collect();
+        assert_eq!(kept, vec![3, 4, 5]);
+        assert_eq!(scan.num_replaced(), 3);
+    }
+
+    #[test]
+    fn check_distinct_ids_give_no_duplicates() {
+        let mut scan = IdScan::new();
+        for (i, id) in ["04ttjf776", "01rxfrp27", "023q4bk22"].iter().enumerate() {
+            scan.add(i, id);
+        }
+        assert!(scan.duplicates.is_empty());
+        assert!(scan.is_last(0, "04ttjf776"));
+        assert_eq!(scan.num_replaced(), 0);
+    }
+}
@@ -0,0 +1,72 @@
+/***************************************************************************
+ * Compares the ROR ids in a source file with those already in the ror
+ * tables (requested by --only-new-ids), to show which organisations are
+ * new in a version, and which have been removed, before (or instead of)
+ * the version being imported. Ids are compared in their short form, as
+ * stored in ror.core_data.
+ ***************************************************************************/
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IdDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+
+// Both lists are returned sorted, so that the output files are stable.
+
+pub fn diff_ids(source_ids: &HashSet<String>, existing_ids: &HashSet<String>) -> IdDiff {
+    let mut added: Vec<String> = source_ids.difference(existing_ids).cloned().collect();
+    let mut removed: Vec<String> = existing_ids.difference(source_ids).cloned().collect();
+    added.sort();
+    removed.sort();
+    IdDiff { added, removed }
+}
+
+
+pub fn write_id_files(diff: &IdDiff, data_version: &str, output_folder: &Path) -> Result<(), std::io::Error> {
+    fs::write(output_folder.join(format!("{} new_ids.txt", data_version)), get_file_contents(&diff.added))?;
+    fs::write(output_folder.join(format!("{} removed_ids.txt", data_version)), get_file_contents(&diff.removed))
+}
+
+
+fn get_file_contents(ids: &[String]) -> String {
+    ids.iter().map(|id| format!("{}\n", id)).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id_set(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn check_added_and_removed_ids_found() {
+        let existing = id_set(&["04ttjf776", "052rpwb50", "0198t0w55", "03rd8mf35"]);
+        let source = id_set(&["04ttjf776", "052rpwb50", "0nwtest01", "00ab12c34"]);
+        let diff = diff_ids(&source, &existing);
+        assert_eq!(diff.added, vec!["00ab12c34", "0nwtest01"]);
+        assert_eq!(diff.removed, vec!["0198t0w55", "03rd8mf35"]);
+
+        let diff = diff_ids(&source, &HashSet::new());
+        assert_eq!(diff.added.len(), 4);
+        assert!(diff.removed.is_empty());
+    }
+
+    #[test]
+    fn check_id_files_written() {
+        let folder = std::env::temp_dir().join("ror1_id_diff_test");
+        fs::create_dir_all(&folder).unwrap();
+        let diff = IdDiff { added: vec!["0nwtest01".to_string()], removed: Vec::new() };
+        write_id_files(&diff, "v1.60", &folder).unwrap();
+        assert_eq!(fs::read_to_string(folder.join("v1.60 new_ids.txt")).unwrap(), "0nwtest01\n");
+        assert_eq!(fs::read_to_string(folder.join("v1.60 removed_ids.txt")).unwrap(), "");
+    }
+}
@@ -0,0 +1,72 @@
+/***************************************************************************
+ * Reports the progress of an import, as the number of records processed,
+ * the throughput in records per second and (if the total is known) the
+ * percentage complete. Progress is checked at batch boundaries, but to
+ * keep the log volume reasonable a line is written at most once in each
+ * reporting interval.
+ ***************************************************************************/
+
+use log::info;
+use std::time::{Duration, Instant};
+
+pub struct ImportProgress {
+    total: Option<usize>,
+    interval: Duration,
+    start: Instant,
+    last_logged: Instant,
+}
+
+impl ImportProgress {
+    pub fn new(total: Option<usize>, interval: Duration) -> Self {
+        let now = Instant::now();
+        ImportProgress { total, interval, start: now, last_logged: now }
+    }
+
+    // Called after each batch, with the number of records processed so far.
+
+    pub fn update(&mut self, records_processed: usize) {
+        if self.last_logged.elapsed() >= self.interval {
+            info!("{}", get_progress_line(records_processed, self.total, self.start.elapsed()));
+            self.last_logged = Instant::now();
+        }
+    }
+
+    // Logs the overall throughput, however long the import has taken.
+
+    pub fn finish(&self, records_processed: usize) {
+        info!("{}", get_progress_line(records_processed, self.total, self.start.elapsed()));
+    }
+}
+
+
+pub fn get_progress_line(records_processed: usize, total: Option<usize>, elapsed: Duration) -> String {
+    let secs = elapsed.as_secs_f64();
+    let rate = if secs > 0.0 { records_processed as f64 / secs } else { 0.0 };
+    match total {
+        Some(t) if t > 0 => format!("{} of {} records processed ({:.1}%), {:.0} records/s",
+                                    records_processed, t, 100.0 * records_processed as f64 / t as f64, rate),
+        _ => format!("{} records processed, {:.0} records/s", records_processed, rate),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_progress_line_with_known_total() {
+        let line = get_progress_line(25000, Some(120000), Duration::from_secs(10));
+        assert_eq!(line, "25000 of 120000 records processed (20.8%), 2500 records/s");
+        let line = get_progress_line(120000, Some(120000), Duration::from_millis(48000));
+        assert_eq!(line, "120000 of 120000 records processed (100.0%), 2500 records/s");
+    }
+
+    #[test]
+    fn check_progress_line_with_unknown_total_or_no_time() {
+        let line = get_progress_line(5000, None, Duration::from_millis(2500));
+        assert_eq!(line, "5000 records processed, 2000 records/s");
+        let line = get_progress_line(250, Some(0), Duration::ZERO);
+        assert_eq!(line, "250 records processed, 0 records/s");
+    }
+}
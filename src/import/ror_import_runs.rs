@@ -0,0 +1,110 @@
+/***************************************************************************
+ * Maintains a history of import runs, in the admin.import_runs table,
+ * giving an audit trail of which files and versions were loaded, when,
+ * and with what result. Unlike the ror tables the history table is never
+ * dropped - it is created when first needed and then added to each run.
+ ***************************************************************************/
+
+use sqlx::{Pool, Postgres};
+use chrono::NaiveDateTime;
+use crate::AppError;
+use crate::setup::SourceFile;
+use super::ImportReport;
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq)]
+pub struct ImportRun {
+    pub id: i32,
+    pub run_datetime: NaiveDateTime,
+    pub data_version: String,
+    pub data_date: String,
+    pub source_file_name: String,
+    pub records_found: i32,
+    pub records_processed: i32,
+    pub records_skipped: i32,
+    pub sha256: Option<String>,
+}
+
+
+pub async fn create_import_runs_table(pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    let sql = r#"SET client_min_messages TO WARNING;
+    create schema if not exists admin;
+
+    create table if not exists admin.import_runs
+    (
+          id                  int         GENERATED ALWAYS AS IDENTITY PRIMARY KEY
+        , run_datetime        timestamp   not null  default current_timestamp
+        , data_version        varchar     not null
+        , data_date           varchar     not null
+        , source_file_name    varchar     not null
+        , records_found       int         not null
+        , records_processed   int         not null
+        , records_skipped     int         not null
+        , sha256              varchar     null
+    );
+
+    alter table admin.import_runs add column if not exists sha256 varchar null;"#;
+
+    sqlx::raw_sql(sql).execute(pool).await?;
+    Ok(())
+}
+
+
+pub async fn record_import_run(sf: &SourceFile, report: &ImportReport, pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    create_import_runs_table(pool).await?;
+    let sql = r#"INSERT INTO admin.import_runs (data_version, data_date, source_file_name,
+                    records_found, records_processed, records_skipped, sha256)
+                    values ($1, $2, $3, $4, $5, $6, $7);"#;
+    sqlx::query(sql).bind(&sf.data_version).bind(&sf.data_date).bind(&sf.file_name)
+        .bind(report.records_found as i32).bind(report.records_processed as i32)
+        .bind(report.records_skipped as i32).bind(report.source_digests.join(","))
+        .execute(pool).await?;
+    Ok(())
+}
+
+
+pub async fn fetch_import_runs(num_runs: i64, pool: &Pool<Postgres>) -> Result<Vec<ImportRun>, AppError> {
+
+    // Returns the most recent runs, most recent first.
+
+    create_import_runs_table(pool).await?;
+    let sql = r#"SELECT id, run_datetime, data_version, data_date, source_file_name,
+                    records_found, records_processed, records_skipped, sha256
+                    FROM admin.import_runs
+                    ORDER BY id desc
+                    LIMIT $1;"#;
+    let runs: Vec<ImportRun> = sqlx::query_as(sql).bind(num_runs).fetch_all(pool).await?;
+    Ok(runs)
+}
+
+
+pub fn get_import_run_line(run: &ImportRun) -> String {
+    format!("{}  {:<8} {:<12} {:>8} found {:>8} processed {:>8} skipped  {}",
+            run.run_datetime.format("%Y-%m-%d %H:%M:%S"), run.data_version, run.data_date,
+            run.records_found, run.records_processed, run.records_skipped, run.source_file_name)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn check_import_run_line_format() {
+        let run = ImportRun {
+            id: 1,
+            run_datetime: NaiveDate::from_ymd_opt(2025, 1, 23).unwrap().and_hms_opt(9, 5, 0).unwrap(),
+            data_version: "v1.59".to_string(),
+            data_date: "2025-01-23".to_string(),
+            source_file_name: "v1.59-2025-01-23-ror-data_schema_v2.json".to_string(),
+            records_found: 120000,
+            records_processed: 119500,
+            records_skipped: 500,
+            sha256: None,
+        };
+        assert_eq!(get_import_run_line(&run), "2025-01-23 09:05:00  v1.59    2025-01-23     \
+                    120000 found   119500 processed      500 skipped  v1.59-2025-01-23-ror-data_schema_v2.json");
+    }
+}
@@ -1,4 +1,25 @@
-use serde::{Serialize, Deserialize};
+use chrono::NaiveDate;
+use serde::{Serialize, Deserialize, Deserializer};
+use std::collections::HashMap;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchemaVersion {
+    V1,
+    V2,
+}
+
+impl SchemaVersion {
+
+    // The schema version recorded in the admin data when the record has none.
+
+    pub fn admin_schema_version(self) -> &'static str {
+        match self {
+            SchemaVersion::V1 => "1.0",
+            SchemaVersion::V2 => "2.0",
+        }
+    }
+}
 
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -13,6 +34,7 @@ use serde::{Serialize, Deserialize};
         pub links: Option<Vec<Link>>,
         pub relationships: Option<Vec<Relationship>>,
         pub domains: Option<Vec<String>>,
+        #[serde(default, deserialize_with = "null_as_default")]
         pub admin: Admin,
     }
 
@@ -68,16 +90,254 @@ use serde::{Serialize, Deserialize};
     }
 
     
-    #[derive(Serialize, Deserialize, Debug)]
+    // Admin data that is missing (or null) is read as empty, and then
+    // filled in by fill_missing_admin.
+
+    #[derive(Serialize, Deserialize, Debug, Default)]
     pub struct Admin {
+        #[serde(default, deserialize_with = "null_as_default")]
         pub created: DateSchema,
+        #[serde(default, deserialize_with = "null_as_default")]
         pub last_modified: DateSchema,
     }
 
 
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Default)]
     pub struct DateSchema {
+        #[serde(default, deserialize_with = "null_as_default")]
         pub date: String,
+        #[serde(default, deserialize_with = "null_as_default")]
         pub schema_version: String,
     }
 
+
+    fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Default + Deserialize<'de>,
+    {
+        Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+    }
+
+
+    impl RorRecord {
+
+        // Any admin date that is missing (or not a valid date) is replaced by the 
+        // data date, and any missing schema version by that of the source file, 
+        // as the admin_data columns cannot be null. Returns true if anything was 
+        // replaced, so that the records needing the fallback can be counted.
+
+        pub fn fill_missing_admin(&mut self, data_date: &str, schema_version: SchemaVersion) -> bool {
+            let mut filled = false;
+            for ds in [&mut self.admin.created, &mut self.admin.last_modified] {
+                if NaiveDate::parse_from_str(&ds.date, "%Y-%m-%d").is_err() {
+                    ds.date = data_date.to_string();
+                    filled = true;
+                }
+                if ds.schema_version.trim().is_empty() {
+                    ds.schema_version = schema_version.admin_schema_version().to_string();
+                    filled = true;
+                }
+            }
+            filled
+        }
+    }
+
+
+
+    // The version 1 schema, used in the ROR data dumps before the v2 schema 
+    // was introduced in April 2024. Names are split into a single 'name' plus separate arrays of labels, aliases and acronyms, locations 
+    // are given as 'addresses', and external ids are an object keyed by id type.
+    // No admin data is included.
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct RorRecordV1 {
+        pub id: String,
+        pub name: String,
+        pub status: String,
+        pub established: Option<i16>,
+        pub types: Vec<String>,
+        pub labels: Option<Vec<LabelV1>>,
+        pub aliases: Option<Vec<String>>,
+        pub acronyms: Option<Vec<String>>,
+        pub addresses: Option<Vec<AddressV1>>,
+        pub country: CountryV1,
+        pub external_ids: Option<HashMap<String, ExternalIdV1>>,
+        pub links: Option<Vec<String>>,
+        pub wikipedia_url: Option<String>,
+        pub relationships: Option<Vec<Relationship>>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct LabelV1 {
+        pub label: String,
+        pub iso639: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct AddressV1 {
+        pub lat: Option<f64>,
+        pub lng: Option<f64>,
+        pub city: Option<String>,
+        pub geonames_city: Option<GeoCityV1>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct GeoCityV1 {
+        pub id: Option<i64>,
+        pub city: Option<String>,
+        pub geonames_admin1: Option<GeoAdminV1>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct GeoAdminV1 {
+        pub code: Option<String>,
+        pub name: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct CountryV1 {
+        pub country_code: String,
+        pub country_name: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct ExternalIdV1 {
+        pub preferred: Option<String>,
+        pub all: IdListV1,
+    }
+
+    // In v1 the 'all' value is usually an array, but for GRID ids is a single string.
+
+    #[derive(Serialize, Deserialize, Debug)]
+    #[serde(untagged)]
+    pub enum IdListV1 {
+        One(String),
+        Many(Vec<String>),
+    }
+
+
+    impl RorRecordV1 {
+
+        // Maps a v1 record into the v2 structure, so that the same vectors and 
+        // tables can be used for both. The 'name' becomes the ror_display label, 
+        // without a language code as none is given in v1. As there is no admin 
+        // data in v1 the data date is used for the created and last modified dates.
+
+        pub fn into_v2(self, data_date: &str) -> RorRecord {
+
+            let mut names = vec![Name {
+                value: self.name,
+                lang: None,
+                types: vec!["ror_display".to_string(), "label".to_string()],
+            }];
+            for lbl in self.labels.unwrap_or_default() {
+                names.push(Name { value: lbl.label, lang: lbl.iso639, types: vec!["label".to_string()] });
+            }
+            for alias in self.aliases.unwrap_or_default() {
+                names.push(Name { value: alias, lang: None, types: vec!["alias".to_string()] });
+            }
+            for acronym in self.acronyms.unwrap_or_default() {
+                names.push(Name { value: acronym, lang: None, types: vec!["acronym".to_string()] });
+            }
+
+            let mut locations = Vec::new();
+            for addr in self.addresses.unwrap_or_default() {
+                let city = addr.geonames_city.unwrap_or(GeoCityV1 { id: None, city: None, geonames_admin1: None });
+                let admin1 = city.geonames_admin1.unwrap_or(GeoAdminV1 { code: None, name: None });
+                locations.push(Location {
+                    geonames_id: city.id.unwrap_or(0),
+                    geonames_details: GeoDetails {
+                        continent_code: None,
+                        continent_name: None,
+                        country_code: self.country.country_code.clone(),
+                        country_name: self.country.country_name.clone(),
+                        country_subdivision_code: admin1.code,
+                        country_subdivision_name: admin1.name,
+                        lat: addr.lat.unwrap_or(0.0),
+                        lng: addr.lng.unwrap_or(0.0),
+                        name: city.city.or(addr.city).unwrap_or_default(),
+                    },
+                });
+            }
+
+            let external_ids = self.external_ids.map(|eids| {
+                let mut ids: Vec<ExternalId> = eids.into_iter().map(|(id_type, eid)| ExternalId {
+                    id_type: id_type.to_lowercase(),
+                    all: match eid.all {
+                        IdListV1::One(s) => vec![s],
+                        IdListV1::Many(v) => v,
+                    },
+                    preferred: eid.preferred,
+                }).collect();
+                ids.sort_by(|a, b| a.id_type.cmp(&b.id_type));  // HashMap order is arbitrary
+                ids
+            });
+
+            let mut links: Vec<Link> = self.links.unwrap_or_default().into_iter()
+                       .map(|value| Link { link_type: "website".to_string(), value }).collect();
+            if let Some(wp) = self.wikipedia_url {
+                if !wp.is_empty() {
+                    links.push(Link { link_type: "wikipedia".to_string(), value: wp });
+                }
+            }
+
+            let relationships = self.relationships.map(|rels| rels.into_iter()
+                       .map(|rel| Relationship { rel_type: rel.rel_type.to_lowercase(), label: rel.label, id: rel.id })
+                       .collect());
+
+            let date_schema = || DateSchema { date: data_date.to_string(), 
+                                              schema_version: SchemaVersion::V1.admin_schema_version().to_string() };
+
+            RorRecord {
+                id: self.id,
+                status: self.status,
+                established: self.established,
+                names,
+                types: self.types.iter().map(|t| t.to_lowercase()).collect(),
+                locations,
+                external_ids,
+                links: Some(links),
+                relationships,
+                domains: None,
+                admin: Admin { created: date_schema(), last_modified: date_schema() },
+            }
+        }
+    }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORD_START: &str = r#"{"id": "https://ror.org/04ttjf776", "status": "active", "established": 1887,
+        "names": [{"value": "RMIT University", "lang": "en", "types": ["ror_display", "label"]}],
+        "types": ["education"], "locations": []"#;
+
+    #[test]
+    fn check_full_admin_data_kept() {
+        let json = format!(r#"{}, "admin": {{"created": {{"date": "2018-11-14", "schema_version": "1.0"}},
+                                "last_modified": {{"date": "2024-05-13", "schema_version": "2.0"}}}}}}"#, RECORD_START);
+        let mut r: RorRecord = serde_json::from_str(&json).unwrap();
+        assert!(!r.fill_missing_admin("2030-01-01", SchemaVersion::V2));
+        assert_eq!((r.admin.created.date.as_str(), r.admin.created.schema_version.as_str()), ("2018-11-14", "1.0"));
+        assert_eq!((r.admin.last_modified.date.as_str(), r.admin.last_modified.schema_version.as_str()), ("2024-05-13", "2.0"));
+    }
+
+    #[test]
+    fn check_missing_admin_data_filled() {
+        for admin in ["", r#", "admin": null"#, r#", "admin": {"created": {"date": null}}"#] {
+            let json = format!("{}{}}}", RECORD_START, admin);
+            let mut r: RorRecord = serde_json::from_str(&json).unwrap();
+            assert!(r.fill_missing_admin("2030-01-01", SchemaVersion::V2), "{}", admin);
+            assert_eq!((r.admin.created.date.as_str(), r.admin.created.schema_version.as_str()), ("2030-01-01", "2.0"));
+            assert_eq!((r.admin.last_modified.date.as_str(), r.admin.last_modified.schema_version.as_str()), ("2030-01-01", "2.0"));
+        }
+
+        let json = format!(r#"{}, "admin": {{"created": {{"date": "2018-11-14", "schema_version": "1.0"}}}}}}"#, RECORD_START);
+        let mut r: RorRecord = serde_json::from_str(&json).unwrap();
+        assert!(r.fill_missing_admin("2030-01-01", SchemaVersion::V2));
+        assert_eq!(r.admin.created.date, "2018-11-14");
+        assert_eq!(r.admin.last_modified.date, "2030-01-01");
+    }
+}
@@ -0,0 +1,371 @@
+/***************************************************************************
+ * Reads the records of a source file one at a time, so that a file too
+ * large to be parsed as a whole (given the memory allowed by --max-memory,
+ * or 'max_memory_mb' in the environment) can still be imported. The top
+ * level array is split into the text of its elements as the file is read,
+ * and each element is parsed into a ROR record on its own, so only the
+ * current batch of records is held in memory. The SHA-256 digest of the
+ * file is calculated from the same bytes, as they are read.
+ * Parsing the whole file at once is faster, and is used whenever the file
+ * comfortably fits within the memory allowed, or no limit has been set.
+ ***************************************************************************/
+
+use chrono::NaiveDate;
+use sha2::{Digest, Sha256};
+use std::io::{self, BufRead, BufReader, Read};
+use super::ror_json_models::{RorRecord, RorRecordV1, SchemaVersion};
+use super::ror_utf8::decode_utf8;
+use crate::AppError;
+
+// Parsing a whole file needs several times the size of the file, to hold
+// the text and the records (with all their strings) derived from it.
+
+pub const IN_MEMORY_FACTOR: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseStrategy {
+    InMemory,
+    Streamed,
+}
+
+
+pub fn choose_parse_strategy(file_size: u64, max_memory_mb: Option<u64>) -> ParseStrategy {
+    match max_memory_mb {
+        Some(mb) if file_size.saturating_mul(IN_MEMORY_FACTOR) > mb * 1024 * 1024 => ParseStrategy::Streamed,
+        _ => ParseStrategy::InMemory,
+    }
+}
+
+
+// Passes the bytes read through to the caller, adding each to the digest.
+
+pub struct DigestReader<R: Read> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> Read for DigestReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+
+// Returns the text of each element of the top level array in turn. Only the
+// structure is checked - the elements are checked as they are parsed. Unless
+// strict_utf8 is set any invalid UTF-8 in an element is replaced, with the 
+// offsets of the sequences replaced (from the start of the data) collected.
+
+pub struct ArrayElements<R: BufRead> {
+    reader: R,
+    started: bool,
+    finished: bool,
+    strict_utf8: bool,
+    position: usize,
+    pub invalid_utf8_offsets: Vec<usize>,
+}
+
+impl<R: BufRead> ArrayElements<R> {
+    pub fn new(reader: R, strict_utf8: bool) -> Self {
+        ArrayElements { reader, started: false, finished: false, strict_utf8, position: 0, invalid_utf8_offsets: Vec::new() }
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        let b = match self.reader.fill_buf()?.first() {
+            Some(b) => *b,
+            None => return Ok(None),
+        };
+        self.reader.consume(1);
+        self.position += 1;
+        Ok(Some(b))
+    }
+
+    fn next_non_space(&mut self) -> io::Result<Option<u8>> {
+        while let Some(b) = self.next_byte()? {
+            if !b.is_ascii_whitespace() {
+                return Ok(Some(b));
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_element(&mut self) -> io::Result<Option<String>> {
+
+        // The first element follows the opening bracket (and any byte order
+        // mark), each later element the comma after the previous one.
+
+        let first = if !self.started {
+            self.started = true;
+            let mut b = self.next_non_space()?;
+            if b == Some(0xef) {
+                if self.next_byte()? != Some(0xbb) || self.next_byte()? != Some(0xbf) {
+                    return Err(invalid_data("the source data is not a JSON array"));
+                }
+                b = self.next_non_space()?;
+            }
+            if b != Some(b'[') {
+                return Err(invalid_data("the source data is not a JSON array"));
+            }
+            match self.next_non_space()? {
+                Some(b']') => return Ok(None),
+                b => b,
+            }
+        }
+        else {
+            match self.next_non_space()? {
+                Some(b',') => self.next_non_space()?,
+                Some(b']') => return Ok(None),
+                Some(_) => return Err(invalid_data("expected ',' or ']' after an element of the JSON array")),
+                None => None,
+            }
+        };
+        match first {
+            Some(b'{') => {},
+            Some(_) => return Err(invalid_data("the JSON array contains a value that is not a record")),
+            None => return Err(invalid_data("the JSON array is not complete")),
+        }
+
+        // A record ends when its braces balance, ignoring any within strings.
+
+        let start = self.position - 1;
+        let mut element = vec![b'{'];
+        let (mut depth, mut in_string, mut escaped) = (1, false, false);
+        while depth > 0 {
+            let b = match self.next_byte()? {
+                Some(b) => b,
+                None => return Err(invalid_data("the JSON array is not complete")),
+            };
+            element.push(b);
+            if in_string {
+                match b {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {},
+                }
+            }
+            else {
+                match b {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => depth -= 1,
+                    _ => {},
+                }
+            }
+        }
+        let (element, offsets) = decode_utf8(element, self.strict_utf8)?;
+        self.invalid_utf8_offsets.extend(offsets.iter().map(|o| start + o));
+        Ok(Some(element))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for ArrayElements<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let res = self.read_element();
+        match res {
+            Ok(Some(element)) => Some(Ok(element)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            },
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            },
+        }
+    }
+}
+
+
+// The records of the array, parsed using the schema given (with any missing
+// admin data filled in), with those last
+// modified before any 'since' date, not of any of the selected types, or 
+// with an excluded status, skipped (and counted).
+
+pub struct RecordStream<R: Read> {
+    elements: ArrayElements<BufReader<DigestReader<R>>>,
+    schema_version: SchemaVersion,
+    data_date: String,
+    since: Option<NaiveDate>,
+    types: Vec<String>,
+    excluded_statuses: Vec<String>,
+    pub records_found: usize,
+    pub records_skipped: usize,
+    pub records_type_skipped: usize,
+    pub records_status_skipped: usize,
+    pub records_admin_fallback: usize,
+}
+
+impl<R: Read> RecordStream<R> {
+    pub fn new(reader: R, schema_version: SchemaVersion, data_date: &str, since: Option<NaiveDate>, types: &[String], 
+               excluded_statuses: &[&str], strict_utf8: bool) -> Self {
+        let reader = BufReader::new(DigestReader { inner: reader, hasher: Sha256::new() });
+        RecordStream {
+            elements: ArrayElements::new(reader, strict_utf8),
+            schema_version,
+            data_date: data_date.to_string(),
+            since,
+            types: types.to_vec(),
+            excluded_statuses: excluded_statuses.iter().map(|s| s.to_string()).collect(),
+            records_found: 0,
+            records_skipped: 0,
+            records_type_skipped: 0,
+            records_status_skipped: 0,
+            records_admin_fallback: 0,
+        }
+    }
+
+    // The offsets of any invalid UTF-8 sequences replaced in the records read so far.
+
+    pub fn invalid_utf8_offsets(&self) -> &[usize] {
+        &self.elements.invalid_utf8_offsets
+    }
+
+    // Reads anything left after the array, and returns the digest of the whole source.
+
+    pub fn finish(self) -> io::Result<String> {
+        let mut reader = self.elements.into_inner();
+        io::copy(&mut reader, &mut io::sink())?;
+        Ok(format!("{:x}", reader.into_inner().hasher.finalize()))
+    }
+
+    fn parse_record(&self, element: &str) -> Result<RorRecord, AppError> {
+        let parse_result = match self.schema_version {
+            SchemaVersion::V2 => serde_json::from_str::<RorRecord>(element),
+            SchemaVersion::V1 => serde_json::from_str::<RorRecordV1>(element).map(|r| r.into_v2(&self.data_date)),
+        };
+        parse_result.map_err(|e| super::get_parse_error(e, self.schema_version))
+    }
+}
+
+impl<R: Read> Iterator for RecordStream<R> {
+    type Item = Result<RorRecord, AppError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let element = match self.elements.next()? {
+                Ok(element) => element,
+                Err(e) => return Some(Err(super::get_read_error(e))),
+            };
+            self.records_found += 1;
+            let mut r = match self.parse_record(&element) {
+                Ok(r) => r,
+                Err(e) => return Some(Err(e)),
+            };
+            if r.fill_missing_admin(&self.data_date, self.schema_version) {
+                self.records_admin_fallback += 1;
+            }
+            if !super::is_modified_since(&r, self.since) {
+                self.records_skipped += 1;
+            }
+            else if !super::has_selected_type(&r, &self.types) {
+                self.records_type_skipped += 1;
+            }
+            else if !super::has_allowed_status(&r, &self.excluded_statuses) {
+                self.records_status_skipped += 1;
+            }
+            else {
+                return Some(Ok(r));
+            }
+        }
+    }
+}
+
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_strategy_chosen_from_file_size() {
+        let mb = 1024 * 1024;
+        assert_eq!(choose_parse_strategy(200 * mb, None), ParseStrategy::InMemory);
+        assert_eq!(choose_parse_strategy(10 * mb, Some(512)), ParseStrategy::InMemory);
+        assert_eq!(choose_parse_strategy(200 * mb, Some(512)), ParseStrategy::Streamed);
+        assert_eq!(choose_parse_strategy(100 * mb, Some(500)), ParseStrategy::InMemory);
+        assert_eq!(choose_parse_strategy(100 * mb + 1, Some(500)), ParseStrategy::Streamed);
+    }
+
+    #[test]
+    fn check_array_split_into_records() {
+        let data = "\u{feff} [ {\"id\": \"a\", \"names\": [{\"value\": \"The {Best] \\\"Uni\\\"\"}]} ,\n{\"id\": \"b\"}]\n";
+        let elements: Vec<String> = ArrayElements::new(data.as_bytes(), true).map(|e| e.unwrap()).collect();
+        assert_eq!(elements, vec![r#"{"id": "a", "names": [{"value": "The {Best] \"Uni\""}]}"#, r#"{"id": "b"}"#]);
+        assert_eq!(ArrayElements::new(" [ ] ".as_bytes(), true).count(), 0);
+
+        // Only a complete byte order mark (EF BB BF) is skipped.
+
+        for bad in [&b"\xef\xbb[{\"id\": \"a\"}]"[..], b"\xef[{\"id\": \"a\"}]", b"\xef\xbf\xbf[{\"id\": \"a\"}]"] {
+            let res: Result<Vec<String>, io::Error> = ArrayElements::new(bad, true).collect();
+            assert!(res.is_err(), "{:?}", bad);
+        }
+
+        for bad in [r#"{"id": "a"}"#, r#"[{"id": "a"} {"id": "b"}]"#, r#"[{"id": "a"}, 3]"#, r#"[{"id": "a""#, "[{\"id\": \"a\"},"] {
+            let res: Result<Vec<String>, io::Error> = ArrayElements::new(bad.as_bytes(), true).collect();
+            assert!(res.is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn check_invalid_utf8_replaced_in_elements() {
+        let data = b"[{\"name\": \"Universit\xe9\"},\n {\"name\": \"Li\xe8ge\"}]";
+        let mut elements = ArrayElements::new(&data[..], false);
+        assert_eq!(elements.next().unwrap().unwrap(), "{\"name\": \"Universit\u{fffd}\"}");
+        assert_eq!(elements.next().unwrap().unwrap(), "{\"name\": \"Li\u{fffd}ge\"}");
+        assert!(elements.next().is_none());
+        assert_eq!(elements.invalid_utf8_offsets, vec![20, 38]);
+        assert_eq!(data[20], 0xe9);
+        assert_eq!(data[38], 0xe8);
+
+        let res: Result<Vec<String>, io::Error> = ArrayElements::new(&data[..], true).collect();
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn check_streamed_records_match_whole_file() {
+        let bytes = std::fs::read("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        let whole: Vec<RorRecord> = serde_json::from_slice(&bytes).unwrap();
+        let mut stream = RecordStream::new(bytes.as_slice(), SchemaVersion::V2, "2030-01-01", None, &[], &[], true);
+        let streamed: Vec<RorRecord> = stream.by_ref().map(|r| r.unwrap()).collect();
+        assert_eq!(streamed.len(), whole.len());
+        assert_eq!(streamed.iter().map(|r| &r.id).collect::<Vec<_>>(), whole.iter().map(|r| &r.id).collect::<Vec<_>>());
+        assert_eq!(stream.records_found, 20);
+        assert_eq!(stream.finish().unwrap(), format!("{:x}", Sha256::digest(&bytes)));
+    }
+
+    #[test]
+    fn check_streamed_records_filtered_by_type() {
+        let bytes = std::fs::read("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        let types = vec!["education".to_string(), "healthcare".to_string()];
+        let mut stream = RecordStream::new(bytes.as_slice(), SchemaVersion::V2, "2030-01-01", None, &types, &[], true);
+        let streamed: Vec<RorRecord> = stream.by_ref().map(|r| r.unwrap()).collect();
+        assert_eq!(streamed.len(), 10);
+        assert!(streamed.iter().all(|r| r.types.iter().any(|t| types.contains(t))));
+        assert_eq!((stream.records_found, stream.records_type_skipped), (20, 10));
+    }
+
+    #[test]
+    fn check_streamed_records_filtered_by_status() {
+        let bytes = std::fs::read("tests/test_data/v93-2030-01-01-status-test-data_schema_v2.json").unwrap();
+        let mut stream = RecordStream::new(bytes.as_slice(), SchemaVersion::V2, "2030-01-01", None, &[], &["withdrawn"], true);
+        let streamed: Vec<RorRecord> = stream.by_ref().map(|r| r.unwrap()).collect();
+        assert_eq!(streamed.iter().map(|r| r.status.as_str()).collect::<Vec<_>>(), vec!["active", "active", "inactive", "active"]);
+        assert_eq!((stream.records_found, stream.records_status_skipped), (6, 2));
+    }
+}
@@ -0,0 +1,84 @@
+/***************************************************************************
+ * Traces a single record through the import (requested by --trace-id), to
+ * help investigate why an organisation looks wrong in the ror tables. When
+ * the record is reached its rows - as generated for each of the tables,
+ * before they are stored - are logged at debug level, so -V is also needed
+ * for them to appear. The id is set once, before the import, and read once
+ * for each file imported, so without it the only cost is comparing each
+ * record's id against nothing. The rows are generated a second time, into
+ * vectors of their own, so that those of the traced record can be listed
+ * without reference to the rest of the batch.
+ ***************************************************************************/
+
+use log::debug;
+use std::sync::Mutex;
+use super::ror_json_models::RorRecord;
+use super::ror_data_vectors::{CoreDataVecs, RequiredDataVecs, NonRequiredDataVecs};
+
+static TRACE_ID: Mutex<Option<String>> = Mutex::new(None);
+
+
+pub fn set_trace_id(trace_id: &str) {
+    let trace_id = if trace_id.is_empty() { None } else { Some(trace_id.to_string()) };
+    *TRACE_ID.lock().unwrap_or_else(|e| e.into_inner()) = trace_id;
+}
+
+
+pub fn fetch_trace_id() -> Option<String> {
+    TRACE_ID.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+
+pub fn log_record_rows(r: &RorRecord, db_id: &String) {
+    let rows = get_record_rows(r, db_id);
+    debug!("Trace of {} - {} rows generated:", db_id, rows.len());
+    for row in rows.iter() {
+        debug!("    {}", row);
+    }
+}
+
+
+pub fn get_record_rows(r: &RorRecord, db_id: &String) -> Vec<String> {
+    let mut cdv = CoreDataVecs::new(1);
+    let mut rdv = RequiredDataVecs::new(1);
+    let mut ndv = NonRequiredDataVecs::new(1);
+    cdv.add_core_data(r, db_id);
+    rdv.add_required_data(r, db_id);
+    ndv.add_non_required_data(r, db_id);
+
+    let mut rows = cdv.trace_rows();
+    rows.extend(rdv.trace_rows());
+    rows.extend(ndv.trace_rows());
+    rows
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn check_rows_generated_for_fixture_record() {
+        let data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        let records: Vec<RorRecord> = serde_json::from_str(&data).unwrap();
+        let r = records.iter().find(|r| r.id == "https://ror.org/04ttjf776").unwrap();
+        let db_id = "04ttjf776".to_string();
+
+        let rows = get_record_rows(r, &db_id);
+        assert!(rows[0].starts_with(r#"ror.core_data ("04ttjf776", "https://ror.org/04ttjf776", "#));
+        assert!(rows[1].starts_with(r#"ror.admin_data ("04ttjf776", "#));
+        assert!(rows.iter().all(|row| row.contains(r#"("04ttjf776", "#)));
+        let num_names = rows.iter().filter(|row| row.starts_with("ror.names ")).count();
+        assert!(num_names >= 1);
+        assert_eq!(rows.iter().filter(|row| row.starts_with("ror.locations ")).count(), r.locations.len());
+    }
+
+    #[test]
+    fn check_trace_id_set_and_cleared() {
+        set_trace_id("04ttjf776");
+        assert_eq!(fetch_trace_id(), Some("04ttjf776".to_string()));
+        set_trace_id("");
+        assert_eq!(fetch_trace_id(), None);
+    }
+}
@@ -0,0 +1,87 @@
+/***************************************************************************
+ * Detects the ROR schema version of source data from the shape of its
+ * first record, rather than from the file name. v2 records hold all their
+ * names in a single 'names' array, each name with its own types, whereas
+ * v1 records have a 'name' string with separate 'labels', 'aliases' and
+ * 'acronyms' arrays, and a 'country' object. Only the first record is
+ * parsed, so the check costs almost nothing even for a full data dump.
+ ***************************************************************************/
+
+use super::ror_json_models::SchemaVersion;
+use serde_json::{Map, Value};
+
+const V1_ONLY_FIELDS: [&str; 5] = ["name", "labels", "aliases", "acronyms", "country"];
+
+
+// Data without a complete first record (e.g. an empty array, or a sample
+// that stops part way through the first record) is assumed to be v2.
+
+pub fn detect_schema_version(sample: &str) -> SchemaVersion {
+    match first_record(sample) {
+        Some(rec) if is_v1_shaped(&rec) => SchemaVersion::V1,
+        _ => SchemaVersion::V2,
+    }
+}
+
+
+pub fn has_first_record(sample: &str) -> bool {
+    first_record(sample).is_some()
+}
+
+
+fn is_v1_shaped(rec: &Map<String, Value>) -> bool {
+    !rec.contains_key("names") && V1_ONLY_FIELDS.iter().any(|f| rec.contains_key(*f))
+}
+
+
+fn first_record(sample: &str) -> Option<Map<String, Value>> {
+
+    // The first object in the top level array is deserialised on its own,
+    // the stream deserialiser stopping at the end of that object.
+
+    let rest = sample.trim_start_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+    let rest = rest.strip_prefix('[')?.trim_start();
+    if !rest.starts_with('{') {
+        return None;
+    }
+    let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<Map<String, Value>>();
+    match stream.next() {
+        Some(Ok(rec)) => Some(rec),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_v1_shaped_sample_detected() {
+        let sample = r#"[{"id": "https://ror.org/04ttjf776", "name": "RMIT University",
+                          "labels": [], "aliases": [], "acronyms": ["RMIT"],
+                          "country": {"country_code": "AU", "country_name": "Australia"}}, {"id": "#;
+        assert_eq!(detect_schema_version(sample), SchemaVersion::V1);
+
+        let data = std::fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v1.json").unwrap();
+        assert_eq!(detect_schema_version(&data), SchemaVersion::V1);
+    }
+
+    #[test]
+    fn check_v2_shaped_sample_detected() {
+        let sample = "\u{feff}\n [ {\"id\": \"https://ror.org/04ttjf776\", \"names\": [{\"value\": \"RMIT University\",
+                      \"types\": [\"ror_display\", \"label\"], \"lang\": \"en\"}], \"locations\": []} ]";
+        assert_eq!(detect_schema_version(sample), SchemaVersion::V2);
+
+        let data = std::fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        assert_eq!(detect_schema_version(&data), SchemaVersion::V2);
+    }
+
+    #[test]
+    fn check_samples_without_a_record_assumed_v2() {
+        assert!(!has_first_record("[]"));
+        assert!(!has_first_record(r#"[{"id": "https://ror.org/04ttjf776", "name": "RMIT"#));
+        assert!(!has_first_record(r#"{"name": "RMIT University"}"#));
+        assert_eq!(detect_schema_version(r#"[{"id": "https://ror.org/04ttjf776", "name": "RMIT"#), SchemaVersion::V2);
+    }
+}
@@ -0,0 +1,192 @@
+/***************************************************************************
+ * An import-only SQLite store. Imports the source data into a SQLite
+ * database (requested by --sqlite) rather than the Postgres database, so
+ * that the import can be tried, developed and tested without a Postgres
+ * server. The records are read, checked and batched exactly as for
+ * Postgres - only the tables and the inserts differ. SQLite has no schemas,
+ * so the ror tables have the same names and columns but no 'ror.' prefix,
+ * and as SQLite cannot bind arrays the vectors of a batch are inserted in
+ * multi-row statements (within the batch's transaction). Nothing beyond the
+ * import is supported - the processing, summaries and exports use Postgres
+ * specific SQL and schemas, and the import is not recorded in the admin
+ * tables. A path of ':memory:' gives an in-memory database, which lasts
+ * only as long as the run (or the pool, when used in tests).
+ ***************************************************************************/
+
+use log::info;
+use sqlx::{Pool, QueryBuilder, Sqlite, SqliteConnection};
+use sqlx::query_builder::Separated;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use std::str::FromStr;
+use std::time::Duration;
+use crate::AppError;
+use crate::error_defs::CustomError;
+use crate::setup::{InitParams, SourceFile, get_db_script};
+use crate::setup::db_script_runner::split_statements;
+use super::{ImportReport, ROR_TABLES, import_data, log_import_report, set_trace_id, set_dump_folder};
+use super::ror_data_vectors::{CoreDataVecs, RequiredDataVecs, NonRequiredDataVecs};
+use super::ror_store::{RorStore, get_data_days, timed};
+
+const CREATE_TABLES_SQL: &str = include_str!("../../db_scripts/create_ror_tables_sqlite.sql");
+const ROWS_PER_INSERT: usize = 500;
+
+
+pub async fn get_sqlite_pool(db_path: &str) -> Result<Pool<Sqlite>, AppError> {
+
+    // A single connection is used, as SQLite allows only one writer at a
+    // time, and an in-memory database belongs to the connection that made
+    // it - so the connection is also never closed while the pool exists.
+
+    let opts = if db_path == ":memory:" {
+        SqliteConnectOptions::from_str("sqlite::memory:")?
+    }
+    else {
+        SqliteConnectOptions::new().filename(db_path).create_if_missing(true)
+    };
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(opts).await?;
+    info!("Connected to the SQLite database {}", db_path);
+    Ok(pool)
+}
+
+
+pub async fn create_sqlite_tables(keep_existing: bool, pool: &Pool<Sqlite>) -> Result<(), AppError> {
+
+    // As for Postgres, with --no-recreate (or --resume) existing tables are
+    // kept, if they are all present. The script is run in one transaction.
+
+    if keep_existing {
+        let sql = format!("select count(*) from sqlite_master where type = 'table' and name in ('{}')", ROR_TABLES.join("', '"));
+        let num_tables: i64 = sqlx::query_scalar(&sql).fetch_one(pool).await?;
+        if num_tables == ROR_TABLES.len() as i64 {
+            info!("Existing ror tables retained in the SQLite database");
+            return Ok(());
+        }
+    }
+    let script = get_db_script("create_ror_tables_sqlite.sql", CREATE_TABLES_SQL)?;
+    let mut tx = pool.begin().await?;
+    for (i, statement) in split_statements(&script).iter().enumerate() {
+        if let Err(e) = sqlx::raw_sql(statement).execute(&mut *tx).await {
+            let msg = format!("statement {} of create_ror_tables_sqlite.sql failed: {}", i + 1, e);
+            return Err(AppError::CsErr(CustomError::new(&msg)));
+        }
+    }
+    tx.commit().await?;
+    info!("Tables created for ror data in the SQLite database");
+    Ok(())
+}
+
+
+pub async fn import_into_sqlite(params: &InitParams, pool: &Pool<Sqlite>) -> Result<ImportReport, AppError> {
+    let flags = &params.flags;
+    set_trace_id(&params.trace_id);
+    set_dump_folder(None);     // a replay file holds Postgres statements
+    create_sqlite_tables(flags.no_recreate || flags.resume, pool).await?;
+
+    let mut import_report = ImportReport::default();
+    for sf in params.source_files.iter() {
+        let report = import_data(&params.data_folder, sf, &params.since_date, &params.types,
+                                 flags, params.max_memory_mb, pool).await?;
+        import_report.add(&report);
+    }
+    if import_report.files_imported > 1 {
+        log_import_report(&import_report);
+    }
+    for table in ROR_TABLES {
+        let count: i64 = sqlx::query_scalar(&format!("select count(*) from {}", table)).fetch_one(pool).await?;
+        info!("Total records in {}: {}", table, count);
+    }
+    Ok(import_report)
+}
+
+
+impl RorStore for Pool<Sqlite> {
+
+    async fn store_version_details(&self, sf: &SourceFile) -> Result<(), AppError> {
+        let sql = r#"INSERT into version_details (version, data_date, data_days)
+                        select ?, ?, ?
+                        where not exists (select 1 from version_details);"#;
+        sqlx::query(sql).bind(&sf.data_version).bind(&sf.data_date).bind(get_data_days(&sf.data_date))
+        .execute(self).await?;
+        Ok(())
+    }
+
+    async fn insert_batch(&self, cdv: &CoreDataVecs, rdv: &RequiredDataVecs, ndv: &NonRequiredDataVecs)
+                          -> Result<(Duration, Duration, Duration), AppError> {
+        let mut tx = self.begin().await?;
+        let cd = timed(insert_core_data(cdv, &mut tx)).await?;
+        let rd = timed(insert_required_data(rdv, &mut tx)).await?;
+        let nd = timed(insert_non_required_data(ndv, &mut tx)).await?;
+        tx.commit().await?;
+        Ok((cd, rd, nd))
+    }
+}
+
+
+async fn insert_core_data(cdv: &CoreDataVecs, conn: &mut SqliteConnection) -> Result<(), AppError> {
+    let n = cdv.db_ids.len();
+    insert_rows(conn, "INSERT INTO core_data (id, ror_full_id, status, established) ", n, |mut b, i| {
+        b.push_bind(&cdv.db_ids[i]).push_bind(&cdv.ror_ids[i]).push_bind(&cdv.statuses[i]).push_bind(cdv.estabs[i]);
+    }).await?;
+    insert_rows(conn, "INSERT INTO admin_data (id, created, cr_schema, last_modified, lm_schema) ", n, |mut b, i| {
+        b.push_bind(&cdv.db_ids[i]).push_bind(cdv.created_dates[i]).push_bind(&cdv.created_vs[i])
+         .push_bind(cdv.lastmod_dates[i]).push_bind(&cdv.lastmod_vs[i]);
+    }).await
+}
+
+
+async fn insert_required_data(rdv: &RequiredDataVecs, conn: &mut SqliteConnection) -> Result<(), AppError> {
+    insert_rows(conn, "INSERT INTO names (id, value, name_type, is_ror_name, lang) ", rdv.name_db_ids.len(), |mut b, i| {
+        b.push_bind(&rdv.name_db_ids[i]).push_bind(&rdv.names[i]).push_bind(&rdv.name_types[i])
+         .push_bind(rdv.is_rors[i]).push_bind(&rdv.langs[i]);
+    }).await?;
+    insert_rows(conn, "INSERT INTO type (id, org_type) ", rdv.type_db_ids.len(), |mut b, i| {
+        b.push_bind(&rdv.type_db_ids[i]).push_bind(&rdv.org_types[i]);
+    }).await?;
+    insert_rows(conn, r#"INSERT INTO locations (id, geonames_id, name, lat, lng, continent_code, continent_name,
+                       country_code, country_name, country_subdivision_code, country_subdivision_name, is_primary) "#,
+                       rdv.loc_db_ids.len(), |mut b, i| {
+        b.push_bind(&rdv.loc_db_ids[i]).push_bind(rdv.gn_ids[i]).push_bind(&rdv.gn_names[i])
+         .push_bind(rdv.lats[i]).push_bind(rdv.lngs[i])
+         .push_bind(&rdv.cont_codes[i]).push_bind(&rdv.cont_names[i])
+         .push_bind(&rdv.cy_codes[i]).push_bind(&rdv.cy_names[i])
+         .push_bind(&rdv.cy_subdiv_codes[i]).push_bind(&rdv.cy_subdiv_names[i])
+         .push_bind(rdv.loc_primaries[i]);
+    }).await
+}
+
+
+async fn insert_non_required_data(ndv: &NonRequiredDataVecs, conn: &mut SqliteConnection) -> Result<(), AppError> {
+    insert_rows(conn, "INSERT INTO relationships (id, rel_type, related_id, related_label) ", ndv.rel_db_ids.len(), |mut b, i| {
+        b.push_bind(&ndv.rel_db_ids[i]).push_bind(&ndv.rel_types[i]).push_bind(&ndv.rel_ids[i]).push_bind(&ndv.rel_labels[i]);
+    }).await?;
+    insert_rows(conn, "INSERT INTO links (id, link_type, value) ", ndv.link_db_ids.len(), |mut b, i| {
+        b.push_bind(&ndv.link_db_ids[i]).push_bind(&ndv.link_types[i]).push_bind(&ndv.links[i]);
+    }).await?;
+    insert_rows(conn, "INSERT INTO external_ids (id, id_type, id_value, is_preferred) ", ndv.id_db_ids.len(), |mut b, i| {
+        b.push_bind(&ndv.id_db_ids[i]).push_bind(&ndv.id_types[i]).push_bind(&ndv.id_values[i]).push_bind(ndv.is_prefs[i]);
+    }).await?;
+    insert_rows(conn, "INSERT INTO domains (id, value) ", ndv.dom_db_ids.len(), |mut b, i| {
+        b.push_bind(&ndv.dom_db_ids[i]).push_bind(&ndv.doms[i]);
+    }).await
+}
+
+
+async fn insert_rows<'a, F>(conn: &mut SqliteConnection, insert: &str, num_rows: usize, mut push_row: F) -> Result<(), AppError>
+    where F: FnMut(Separated<'_, 'a, Sqlite, &'static str>, usize) {
+
+    // SQLite cannot bind arrays, so the rows are inserted in multi-row
+    // statements instead - each with at most ROWS_PER_INSERT rows, keeping
+    // the number of bound values well within SQLite's limit.
+
+    for start in (0..num_rows).step_by(ROWS_PER_INSERT) {
+        let end = usize::min(start + ROWS_PER_INSERT, num_rows);
+        let mut qb: QueryBuilder<'a, Sqlite> = QueryBuilder::new(insert);
+        qb.push_values(start..end, &mut push_row);
+        qb.build().execute(&mut *conn).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,160 @@
+/***************************************************************************
+ * The database the records of an import are stored in. Normally this is
+ * the Postgres database, but with --sqlite a SQLite database can be used
+ * instead (see ror_sqlite), so the import only needs the two operations
+ * below, which each database implements in its own dialect. Everything
+ * else about an import - reading, filtering and checking the records, and
+ * gathering them into batches - is the same for both. With --atomic the
+ * records of a file are stored within a single Postgres transaction (an
+ * AtomicStore), committed only once the whole file has been imported.
+ ***************************************************************************/
+
+use chrono::NaiveDate;
+use sqlx::{Acquire, Pool, Postgres, Transaction};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use crate::AppError;
+use crate::setup::SourceFile;
+use super::ror_data_vectors::{CoreDataVecs, RequiredDataVecs, NonRequiredDataVecs};
+
+const VERSION_DETAILS_SQL: &str = r#"INSERT into ror.version_details (version, data_date, data_days)
+                        select $1, $2, $3
+                        where not exists (select 1 from ror.version_details);"#;
+
+
+pub trait RorStore {
+
+    // Records the version and date of the data, unless already recorded.
+
+    fn store_version_details(&self, sf: &SourceFile) -> impl Future<Output = Result<(), AppError>>;
+
+    // Stores a batch, so that it is either stored in full or not at all,
+    // returning the time taken to store each of the three groups of vectors.
+
+    fn insert_batch(&self, cdv: &CoreDataVecs, rdv: &RequiredDataVecs, ndv: &NonRequiredDataVecs)
+                    -> impl Future<Output = Result<(Duration, Duration, Duration), AppError>>;
+
+    // Whether each batch is committed once stored, rather than only at the
+    // end of the import.
+
+    fn commits_batches(&self) -> bool {
+        true
+    }
+}
+
+
+impl RorStore for Pool<Postgres> {
+
+    async fn store_version_details(&self, sf: &SourceFile) -> Result<(), AppError> {
+        sqlx::query(VERSION_DETAILS_SQL).bind(&sf.data_version).bind(&sf.data_date).bind(get_data_days(&sf.data_date))
+        .execute(self).await?;
+        Ok(())
+    }
+
+    async fn insert_batch(&self, cdv: &CoreDataVecs, rdv: &RequiredDataVecs, ndv: &NonRequiredDataVecs)
+                          -> Result<(Duration, Duration, Duration), AppError> {
+
+        // The three groups are written to different tables, so are stored
+        // concurrently, each in its own transaction on its own connection.
+        // None is committed until all three have been stored - if one fails
+        // the others are dropped, and so rolled back, leaving nothing of the
+        // batch behind. (Only a lost connection between the three commits
+        // could leave part of a batch.) A pool with fewer than three
+        // connections (possible when one is passed to run_pipeline) could
+        // not supply them, so the groups are then stored one after the
+        // other, within a single transaction.
+
+        if self.options().get_max_connections() < 3 {
+            return insert_batch_in(cdv, rdv, ndv, self.begin().await?).await;
+        }
+
+        let (mut cd_tx, mut rd_tx, mut nd_tx) = tokio::try_join!(self.begin(), self.begin(), self.begin())?;
+        let times = tokio::try_join!(
+            timed(cdv.insert_rows(&mut cd_tx)),
+            timed(rdv.insert_rows(&mut rd_tx)),
+            timed(ndv.insert_rows(&mut nd_tx)),
+        )?;
+        tokio::try_join!(cd_tx.commit(), rd_tx.commit(), nd_tx.commit())?;
+        Ok(times)
+    }
+}
+
+
+// Holds the transaction within which all the records of a file are stored,
+// with --atomic. Each batch is stored within a savepoint, so that a batch
+// that fails with a deadlock can be retried, and the three groups are
+// stored one after the other, as they share the transaction's connection.
+// Nothing is kept unless commit is called - if the store is dropped (as it
+// is when the import fails or is interrupted) the transaction is rolled back.
+
+pub struct AtomicStore {
+    tx: Mutex<Transaction<'static, Postgres>>,
+}
+
+impl AtomicStore {
+    pub async fn begin(pool: &Pool<Postgres>) -> Result<AtomicStore, AppError> {
+        Ok(AtomicStore { tx: Mutex::new(pool.begin().await?) })
+    }
+
+    pub async fn commit(self) -> Result<(), AppError> {
+        self.tx.into_inner().commit().await?;
+        Ok(())
+    }
+
+    pub async fn rollback(self) -> Result<(), AppError> {
+        self.tx.into_inner().rollback().await?;
+        Ok(())
+    }
+}
+
+impl RorStore for AtomicStore {
+
+    async fn store_version_details(&self, sf: &SourceFile) -> Result<(), AppError> {
+        let mut tx = self.tx.lock().await;
+        sqlx::query(VERSION_DETAILS_SQL).bind(&sf.data_version).bind(&sf.data_date).bind(get_data_days(&sf.data_date))
+        .execute(&mut **tx).await?;
+        Ok(())
+    }
+
+    async fn insert_batch(&self, cdv: &CoreDataVecs, rdv: &RequiredDataVecs, ndv: &NonRequiredDataVecs)
+                          -> Result<(Duration, Duration, Duration), AppError> {
+        let mut tx = self.tx.lock().await;
+        let savepoint = tx.begin().await?;
+        let times = insert_batch_in(cdv, rdv, ndv, savepoint).await?;
+        Ok(times)
+    }
+
+    fn commits_batches(&self) -> bool {
+        false
+    }
+}
+
+
+// Stores the groups of a batch one after the other, within the transaction
+// (or savepoint) given, which is committed once all three have been stored.
+
+async fn insert_batch_in(cdv: &CoreDataVecs, rdv: &RequiredDataVecs, ndv: &NonRequiredDataVecs,
+                         mut tx: Transaction<'_, Postgres>) -> Result<(Duration, Duration, Duration), AppError> {
+    let cd = timed(cdv.insert_rows(&mut tx)).await?;
+    let rd = timed(rdv.insert_rows(&mut tx)).await?;
+    let nd = timed(ndv.insert_rows(&mut tx)).await?;
+    tx.commit().await?;
+    Ok((cd, rd, nd))
+}
+
+
+// The number of days between the first v2 data (2024-04-29) and the data date.
+
+pub fn get_data_days(data_date: &str) -> i64 {
+    let end_of_period = NaiveDate::parse_from_str(data_date, "%Y-%m-%d").unwrap();
+    let start_of_period = NaiveDate::parse_from_str("2024-04-29", "%Y-%m-%d").unwrap();
+    (end_of_period - start_of_period).num_days()
+}
+
+
+pub async fn timed<F: Future<Output = Result<(), AppError>>>(store: F) -> Result<Duration, AppError> {
+    let start = Instant::now();
+    store.await?;
+    Ok(start.elapsed())
+}
@@ -0,0 +1,78 @@
+/***************************************************************************
+ * Accumulates the time spent storing each group of vectors (core data,
+ * required data and non-required data) during an import, with the number
+ * of rows written, so that the table groups that dominate an import can be
+ * identified. The timings are always collected, as the cost is trivial,
+ * but are only logged if requested (by --profile).
+ ***************************************************************************/
+
+use std::time::Duration;
+use crate::setup::log_helper::format_duration;
+
+pub const STORE_GROUPS: [&str; 3] = ["core data", "required data", "non-required data"];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoreTiming {
+    pub group: &'static str,
+    pub batches: usize,
+    pub rows: usize,
+    pub total: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoreProfile {
+    pub timings: Vec<StoreTiming>,
+}
+
+impl StoreProfile {
+    pub fn new() -> Self {
+        StoreProfile {
+            timings: STORE_GROUPS.iter().map(|g| StoreTiming { group: g, ..StoreTiming::default() }).collect(),
+        }
+    }
+
+    // Adds the time taken to store one batch of a group, and its rows.
+
+    pub fn add(&mut self, group: &str, rows: usize, duration: Duration) {
+        if let Some(t) = self.timings.iter_mut().find(|t| t.group == group) {
+            t.batches += 1;
+            t.rows += rows;
+            t.total += duration;
+        }
+    }
+
+    pub fn get_profile_lines(&self) -> Vec<String> {
+        self.timings.iter().map(|t| {
+            let secs = t.total.as_secs_f64();
+            let rate = if secs > 0.0 { t.rows as f64 / secs } else { 0.0 };
+            format!("{:<18} {:>6} batches {:>9} rows in {:>12}, {:.0} rows/s",
+                    t.group, t.batches, t.rows, format_duration(&t.total), rate)
+        }).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_store_timings_accumulated() {
+        let mut profile = StoreProfile::new();
+        profile.add("core data", 250, Duration::from_millis(200));
+        profile.add("core data", 50, Duration::from_millis(100));
+        profile.add("non-required data", 900, Duration::from_millis(1500));
+        profile.add("unknown group", 10, Duration::from_secs(10));
+
+        assert_eq!(profile.timings[0], StoreTiming { group: "core data", batches: 2, rows: 300,
+                                                     total: Duration::from_millis(300) });
+        assert_eq!(profile.timings[1], StoreTiming { group: "required data", ..StoreTiming::default() });
+        assert_eq!(profile.timings[2].total, Duration::from_millis(1500));
+
+        let lines = profile.get_profile_lines();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "core data               2 batches       300 rows in       0.300s, 1000 rows/s");
+        assert_eq!(lines[1], "required data           0 batches         0 rows in       0.000s, 0 rows/s");
+        assert_eq!(lines[2], "non-required data       1 batches       900 rows in       1.500s, 600 rows/s");
+    }
+}
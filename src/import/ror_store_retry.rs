@@ -0,0 +1,126 @@
+/***************************************************************************
+ * Retries the storage of a batch when Postgres reports a deadlock (40P01)
+ * or a serialization failure (40001). These are transient - they arise from
+ * concurrent transactions, and the batch will normally succeed if it is
+ * simply run again. No part of a batch is committed unless all of it has
+ * been stored, so a failed attempt leaves nothing behind and can be safely
+ * repeated.
+ * The number of retries can be set (as 'store_retries') in the environment.
+ ***************************************************************************/
+
+use log::warn;
+use std::future::Future;
+use std::time::Duration;
+use crate::AppError;
+
+pub const TRANSIENT_SQLSTATES: [&str; 2] = ["40P01", "40001"];
+
+pub fn is_transient(e: &AppError) -> bool {
+    match e {
+        AppError::SqErr(sqlx::Error::Database(db_err)) => match db_err.code() {
+            Some(code) => TRANSIENT_SQLSTATES.contains(&code.as_ref()),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+
+// Runs the store function, and if it fails with a transient error runs it
+// again, up to max_retries times, waiting a little longer before each retry.
+
+pub async fn with_retry<F, Fut, T>(group: &str, max_retries: u32, backoff: Duration,
+                                   mut store: F) -> Result<T, AppError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, AppError>>,
+{
+    let mut retries = 0;
+    loop {
+        match store().await {
+            Ok(stored) => return Ok(stored),
+            Err(e) if is_transient(&e) && retries < max_retries => {
+                retries += 1;
+                warn!("Transient error storing {} ({}), retry {} of {}", group, e, retries, max_retries);
+                tokio::time::sleep(backoff * retries).await;
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+    use std::cell::Cell;
+    use std::error::Error;
+    use std::fmt;
+    use sqlx::error::{DatabaseError, ErrorKind};
+
+    // A stand-in for the error Postgres returns, with the given SQLSTATE.
+
+    #[derive(Debug)]
+    struct MockDbError(&'static str);
+
+    impl fmt::Display for MockDbError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "mock database error {}", self.0)
+        }
+    }
+
+    impl Error for MockDbError {}
+
+    impl DatabaseError for MockDbError {
+        fn message(&self) -> &str { "mock database error" }
+        fn code(&self) -> Option<Cow<'_, str>> { Some(Cow::Borrowed(self.0)) }
+        fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) { self }
+        fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) { self }
+        fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> { self }
+        fn kind(&self) -> ErrorKind { ErrorKind::Other }
+    }
+
+    fn db_error(code: &'static str) -> AppError {
+        AppError::SqErr(sqlx::Error::Database(Box::new(MockDbError(code))))
+    }
+
+    #[test]
+    fn check_transient_errors_identified() {
+        assert!(is_transient(&db_error("40P01")));
+        assert!(is_transient(&db_error("40001")));
+        assert!(!is_transient(&db_error("23505")));
+        assert!(!is_transient(&AppError::SqErr(sqlx::Error::PoolTimedOut)));
+    }
+
+    #[tokio::test]
+    async fn check_deadlock_is_retried() {
+        let attempts = Cell::new(0);
+        let res = with_retry("core data", 3, Duration::from_millis(1), || {
+            attempts.set(attempts.get() + 1);
+            let n = attempts.get();
+            async move { if n == 1 { Err(db_error("40P01")) } else { Ok(()) } }
+        }).await;
+        assert!(res.is_ok());
+        assert_eq!(attempts.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn check_retries_limited_and_other_errors_not_retried() {
+        let attempts = Cell::new(0);
+        let res: Result<(), AppError> = with_retry("names", 2, Duration::from_millis(1), || {
+            attempts.set(attempts.get() + 1);
+            async { Err(db_error("40001")) }
+        }).await;
+        assert!(is_transient(&res.unwrap_err()));
+        assert_eq!(attempts.get(), 3);
+
+        let attempts = Cell::new(0);
+        let res: Result<(), AppError> = with_retry("names", 2, Duration::from_millis(1), || {
+            attempts.set(attempts.get() + 1);
+            async { Err(db_error("23505")) }
+        }).await;
+        assert!(res.is_err());
+        assert_eq!(attempts.get(), 1);
+    }
+}
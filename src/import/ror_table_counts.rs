@@ -0,0 +1,102 @@
+/***************************************************************************
+ * Counts the records in the ror tables after an import. The counts are run
+ * concurrently, a few at a time (the pool has five connections), as each
+ * is a full scan of its table and on a large data set and a busy server
+ * running them one after another is slow. The results are collected by
+ * their position in the list of tables, so that they are always reported
+ * in the same order, whichever count finishes first. A table that cannot
+ * be counted (e.g. because it is missing) is logged, and left without a
+ * count, rather than ending the summary.
+ ***************************************************************************/
+
+use futures_util::stream::{self, StreamExt};
+use log::warn;
+use sqlx::{Pool, Postgres};
+use std::collections::BTreeMap;
+use std::future::Future;
+use crate::AppError;
+
+pub const MAX_CONCURRENT_COUNTS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableCount {
+    pub table_name: String,
+    pub num_records: Option<i64>,
+}
+
+
+pub async fn fetch_table_counts(table_names: &[&str], pool: &Pool<Postgres>) -> Vec<TableCount> {
+    gather_counts(table_names, MAX_CONCURRENT_COUNTS, |table_name| count_records(table_name, pool)).await
+}
+
+
+async fn count_records(table_name: &str, pool: &Pool<Postgres>) -> Result<i64, AppError> {
+    let sql = format!("SELECT COUNT(*) FROM ror.{}", table_name);
+    Ok(sqlx::query_scalar(&sql).fetch_one(pool).await?)
+}
+
+
+pub async fn gather_counts<'a, F, Fut>(table_names: &[&'a str], concurrency: usize, count: F) -> Vec<TableCount>
+where
+    F: Fn(&'a str) -> Fut,
+    Fut: Future<Output = Result<i64, AppError>>,
+{
+    let counts: BTreeMap<usize, TableCount> = stream::iter(table_names.iter().copied().enumerate())
+        .map(|(i, table_name)| {
+            let counting = count(table_name);
+            async move { (i, table_name, counting.await) }
+        })
+        .buffer_unordered(concurrency)
+        .map(|(i, table_name, res)| {
+            let num_records = match res {
+                Ok(n) => Some(n),
+                Err(e) => {
+                    warn!("Unable to count the records in ror.{}: {}", table_name, e);
+                    None
+                },
+            };
+            (i, TableCount { table_name: table_name.to_string(), num_records })
+        })
+        .collect().await;
+    counts.into_values().collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::error_defs::CustomError;
+
+    #[tokio::test]
+    async fn check_counts_gathered_in_table_order() {
+
+        // Earlier tables take longer, so the counts finish in reverse order.
+
+        let tables = ["core_data", "admin_data", "names", "locations", "links", "domains"];
+        let counts = gather_counts(&tables, 3, |table_name| async move {
+            let i = tables.iter().position(|t| *t == table_name).unwrap() as u64;
+            tokio::time::sleep(Duration::from_millis(5 * (6 - i))).await;
+            Ok(10 * i as i64)
+        }).await;
+        assert_eq!(counts.iter().map(|c| c.table_name.as_str()).collect::<Vec<_>>(), tables);
+        assert_eq!(counts.iter().map(|c| c.num_records.unwrap()).collect::<Vec<_>>(), vec![0, 10, 20, 30, 40, 50]);
+    }
+
+    #[tokio::test]
+    async fn check_failed_count_does_not_stop_others() {
+        let tables = ["core_data", "type", "domains"];
+        let counts = gather_counts(&tables, 2, |table_name| async move {
+            if table_name == "type" {
+                Err(AppError::CsErr(CustomError::new("relation \"ror.type\" does not exist")))
+            } else {
+                Ok(20)
+            }
+        }).await;
+        assert_eq!(counts, vec![
+            TableCount { table_name: "core_data".to_string(), num_records: Some(20) },
+            TableCount { table_name: "type".to_string(), num_records: None },
+            TableCount { table_name: "domains".to_string(), num_records: Some(20) },
+        ]);
+    }
+}
@@ -0,0 +1,107 @@
+/***************************************************************************
+ * Decodes the bytes of a source file as UTF-8. Files exported by third 
+ * parties occasionally contain a few bytes that are not valid UTF-8 (usually
+ * mojibake in organisation names), which would otherwise stop the import of
+ * the whole file. Unless --strict-utf8 is given, each invalid sequence is 
+ * replaced by the replacement character (U+FFFD), and the byte offsets of 
+ * the sequences replaced are returned, so that they can be logged as a 
+ * warning and the source data corrected. With --strict-utf8 any invalid
+ * sequence is an error, and the import of the file stops.
+ ***************************************************************************/
+
+use log::warn;
+use std::io;
+
+// The number of offsets listed in the warning, the rest only being counted.
+
+const MAX_OFFSETS_LISTED: usize = 10;
+
+
+pub fn decode_utf8(bytes: Vec<u8>, strict_utf8: bool) -> Result<(String, Vec<usize>), io::Error> {
+    match String::from_utf8(bytes) {
+        Ok(data) => Ok((data, Vec::new())),
+        Err(e) if strict_utf8 => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) => {
+            let bytes = e.into_bytes();
+            let offsets = find_invalid_offsets(&bytes);
+            Ok((String::from_utf8_lossy(&bytes).into_owned(), offsets))
+        },
+    }
+}
+
+
+pub fn find_invalid_offsets(bytes: &[u8]) -> Vec<usize> {
+
+    // Each invalid sequence is replaced by a single U+FFFD, in the same way
+    // as by from_utf8_lossy. An incomplete sequence at the very end of the
+    // data has no error length, and runs to the end.
+
+    let mut offsets = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match std::str::from_utf8(&bytes[pos..]) {
+            Ok(_) => break,
+            Err(e) => {
+                let offset = pos + e.valid_up_to();
+                offsets.push(offset);
+                pos = offset + e.error_len().unwrap_or(bytes.len() - offset);
+            },
+        }
+    }
+    offsets
+}
+
+
+pub fn log_replaced_sequences(source_name: &str, offsets: &[usize]) {
+    if let Some(line) = get_replaced_line(source_name, offsets) {
+        warn!("{}", line);
+    }
+}
+
+
+fn get_replaced_line(source_name: &str, offsets: &[usize]) -> Option<String> {
+    if offsets.is_empty() {
+        return None;
+    }
+    let listed: Vec<String> = offsets.iter().take(MAX_OFFSETS_LISTED).map(|o| o.to_string()).collect();
+    let more = if offsets.len() > MAX_OFFSETS_LISTED { 
+        format!(" (and {} more)", offsets.len() - MAX_OFFSETS_LISTED) 
+    } else { 
+        "".to_string() 
+    };
+    Some(format!("{} invalid UTF-8 sequence(s) in {} replaced by U+FFFD, at byte offset(s) {}{}", 
+                  offsets.len(), source_name, listed.join(", "), more))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_valid_data_decoded_unchanged() {
+        let (data, offsets) = decode_utf8("Fraunhofer-Einrichtung für Wertstoffkreisläufe".as_bytes().to_vec(), true).unwrap();
+        assert_eq!(data, "Fraunhofer-Einrichtung für Wertstoffkreisläufe");
+        assert!(offsets.is_empty());
+    }
+
+    #[test]
+    fn check_invalid_sequences_replaced_with_offsets() {
+        let bytes = b"Universit\xe9 de Li\xe8ge \xe2\x82".to_vec();
+        let (data, offsets) = decode_utf8(bytes.clone(), false).unwrap();
+        assert_eq!(data, "Universit\u{fffd} de Li\u{fffd}ge \u{fffd}");
+        assert_eq!(offsets, vec![9, 16, 20]);
+
+        let err = decode_utf8(bytes, true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn check_replaced_line_lists_offsets() {
+        assert_eq!(get_replaced_line("a.json", &[]), None);
+        assert_eq!(get_replaced_line("a.json", &[9, 18]).unwrap(), 
+                   "2 invalid UTF-8 sequence(s) in a.json replaced by U+FFFD, at byte offset(s) 9, 18");
+        let offsets: Vec<usize> = (0..12).collect();
+        assert!(get_replaced_line("a.json", &offsets).unwrap().ends_with("7, 8, 9 (and 2 more)"));
+    }
+}
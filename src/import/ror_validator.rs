@@ -0,0 +1,119 @@
+/***************************************************************************
+ * Checks that source data parses into ROR records, without any database
+ * access. The data is first parsed as generic JSON, and each element of
+ * the top level array is then converted separately, so that all the
+ * problems in a file can be reported, rather than only the first.
+ ***************************************************************************/
+
+use super::ror_json_models::{RorRecord, RorRecordV1, SchemaVersion};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationReport {
+    pub file_name: String,
+    pub schema_version: SchemaVersion,
+    pub records_found: usize,
+    pub records_valid: usize,
+    pub problems: Vec<String>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+
+pub fn validate_data(data: &str, file_name: &str, schema_version: SchemaVersion) -> ValidationReport {
+
+    let mut report = ValidationReport {
+        file_name: file_name.to_string(),
+        schema_version,
+        records_found: 0,
+        records_valid: 0,
+        problems: Vec::new(),
+    };
+
+    let records = match serde_json::from_str::<Value>(data) {
+        Ok(Value::Array(a)) => a,
+        Ok(v) => {
+            let found = match v {
+                Value::Object(_) => "an object",
+                Value::String(_) => "a string",
+                Value::Number(_) => "a number",
+                Value::Bool(_) => "a boolean",
+                _ => "null",
+            };
+            report.problems.push(format!("Expected a JSON array of ROR records, found {}", found));
+            return report;
+        },
+        Err(e) => {
+            report.problems.push(format!("The file is not valid JSON: {}", e));
+            return report;
+        },
+    };
+
+    report.records_found = records.len();
+    for (i, v) in records.into_iter().enumerate() {
+        let id = match v.get("id").and_then(|id| id.as_str()) {
+            Some(id) => id.to_string(),
+            None => "no id".to_string(),
+        };
+        let res = match schema_version {
+            SchemaVersion::V2 => serde_json::from_value::<RorRecord>(v).map(|_| ()),
+            SchemaVersion::V1 => serde_json::from_value::<RorRecordV1>(v).map(|_| ()),
+        };
+        match res {
+            Ok(()) => report.records_valid += 1,
+            Err(e) => report.problems.push(format!("Record {} ({}): {}", i + 1, id, e)),
+        }
+    }
+
+    report
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn check_good_file_passes_validation() {
+        let data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+        let report = validate_data(&data, "v99-2030-01-01-test-data_schema_v2.json", SchemaVersion::V2);
+        assert!(report.is_valid());
+        assert_eq!(report.records_found, 20);
+        assert_eq!(report.records_valid, 20);
+
+        let data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v1.json").unwrap();
+        let report = validate_data(&data, "v99-2030-01-01-test-data_schema_v1.json", SchemaVersion::V1);
+        assert!(report.is_valid());
+        assert_eq!(report.records_valid, 3);
+    }
+
+    #[test]
+    fn check_malformed_file_fails_validation() {
+        let data = fs::read_to_string("tests/test_data/v97-2030-01-01-malformed-test-data_schema_v2.json").unwrap();
+        let report = validate_data(&data, "v97-2030-01-01-malformed-test-data_schema_v2.json", SchemaVersion::V2);
+        assert!(!report.is_valid());
+        assert_eq!(report.records_found, 3);
+        assert_eq!(report.records_valid, 1);
+        assert_eq!(report.problems.len(), 2);
+        assert!(report.problems[0].starts_with("Record 2 (https://ror.org/0malfrm02)"));
+        assert!(report.problems[0].contains("names"));
+        assert!(report.problems[1].starts_with("Record 3 (https://ror.org/0malfrm03)"));
+    }
+
+    #[test]
+    fn check_non_array_and_invalid_json_fail_validation() {
+        let report = validate_data(r#"{"id": "https://ror.org/04ttjf776"}"#, "object.json", SchemaVersion::V2);
+        assert!(!report.is_valid());
+        assert_eq!(report.records_found, 0);
+        assert_eq!(report.problems[0], "Expected a JSON array of ROR records, found an object");
+
+        let report = validate_data(r#"[{"id": "https://ror.org/04ttjf776""#, "truncated.json", SchemaVersion::V2);
+        assert!(!report.is_valid());
+        assert!(report.problems[0].starts_with("The file is not valid JSON"));
+    }
+}
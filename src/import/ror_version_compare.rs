@@ -0,0 +1,223 @@
+/***************************************************************************
+ * Compares two loads of the ror tables (requested by --compare-versions),
+ * to show what changed between two versions of the ROR data. Only the
+ * current load is held in the ror schema, so earlier loads are compared as
+ * copies of that schema, kept under another name, e.g. after
+ * 'alter schema ror rename to ror_v1_58'. Each load is named either by that
+ * suffix ('v1_58'), by 'current' for the ror schema itself, or by the id of
+ * the import run that loaded it (from admin.import_runs), in which case the
+ * suffix is derived from the version of the run. Organisations, names and
+ * locations are compared using set differences between the two schemas -
+ * a row present in only one of them is added or removed, and a row whose
+ * key (id, name value or geonames id) is in both, but with other values
+ * changed, is modified.
+ ***************************************************************************/
+
+use log::{info, error};
+use sqlx::{Pool, Postgres};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::AppError;
+use crate::error_defs::CustomError;
+
+pub const CURRENT_LOAD: &str = "current";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChangeCounts {
+    pub added: i64,
+    pub removed: i64,
+    pub modified: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VersionDiff {
+    pub from_schema: String,
+    pub to_schema: String,
+    pub added_ids: Vec<String>,
+    pub removed_ids: Vec<String>,
+    pub modified_ids: Vec<String>,
+    pub names: ChangeCounts,
+    pub locations: ChangeCounts,
+}
+
+impl VersionDiff {
+    pub fn orgs(&self) -> ChangeCounts {
+        ChangeCounts {
+            added: self.added_ids.len() as i64,
+            removed: self.removed_ids.len() as i64,
+            modified: self.modified_ids.len() as i64,
+        }
+    }
+}
+
+const NAME_COLUMNS: (&str, &str) = ("id, value", "name_type, is_ror_name, lang");
+const LOCATION_COLUMNS: (&str, &str) = ("id, geonames_id", "name, lat, lng, country_code, country_subdivision_code");
+
+
+pub async fn compare_versions(loads: &[String], csv_folder: Option<&Path>, pool: &Pool<Postgres>) -> Result<VersionDiff, AppError> {
+
+    let from_schema = get_load_schema(&loads[0], pool).await?;
+    let to_schema = get_load_schema(&loads[1], pool).await?;
+    let (a, b) = (&from_schema, &to_schema);
+
+    let added_ids = fetch_ids(&format!("select id from {b}.core_data except select id from {a}.core_data"), pool).await?;
+    let removed_ids = fetch_ids(&format!("select id from {a}.core_data except select id from {b}.core_data"), pool).await?;
+
+    // An organisation is modified if its core data, or any of its names
+    // or locations, differ between the two loads.
+
+    let changed_rows: Vec<String> = [("core_data", "id, status, established"),
+                                     ("names", "id, value, name_type, is_ror_name, lang"),
+                                     ("locations", "id, geonames_id, name, lat, lng, country_code, country_subdivision_code")]
+        .iter()
+        .map(|(table, cols)| format!("select id from (select {cols} from {a}.{table} except select {cols} from {b}.{table}) r1
+                                      union select id from (select {cols} from {b}.{table} except select {cols} from {a}.{table}) r2"))
+        .collect();
+    let sql = format!(r#"select distinct c.id from ({}) c
+                         where c.id in (select id from {a}.core_data)
+                         and c.id in (select id from {b}.core_data)"#, changed_rows.join(" union "));
+    let modified_ids = fetch_ids(&sql, pool).await?;
+
+    let diff = VersionDiff {
+        names: count_changes("names", NAME_COLUMNS, a, b, pool).await?,
+        locations: count_changes("locations", LOCATION_COLUMNS, a, b, pool).await?,
+        from_schema,
+        to_schema,
+        added_ids,
+        removed_ids,
+        modified_ids,
+    };
+
+    info!("");
+    info!("************************************");
+    info!("Changes from {} to {}:", diff.from_schema, diff.to_schema);
+    for (entity, counts) in [("Organisations", diff.orgs()), ("Names", diff.names.clone()), ("Locations", diff.locations.clone())] {
+        info!("{}: {} added, {} removed, {} modified", entity, counts.added, counts.removed, counts.modified);
+    }
+    if let Some(folder) = csv_folder {
+        let file_path = write_changed_ids(&diff, folder)?;
+        info!("Changed organisation ids written to {}", file_path.display());
+    }
+    info!("************************************");
+    info!("");
+    Ok(diff)
+}
+
+
+async fn get_load_schema(load: &str, pool: &Pool<Postgres>) -> Result<String, AppError> {
+
+    // A run id is replaced by the version loaded in that run.
+
+    let suffix = if !load.is_empty() && load.chars().all(|c| c.is_ascii_digit()) {
+        super::ror_import_runs::create_import_runs_table(pool).await?;
+        let sql = "select data_version from admin.import_runs where id = $1";
+        let run_id: i32 = load.parse().map_err(|_| get_load_error(load, "is not a valid run id"))?;
+        let version: Option<String> = sqlx::query_scalar(sql).bind(run_id).fetch_optional(pool).await?;
+        match version {
+            Some(v) => v,
+            None => return Err(get_load_error(load, "is not the id of a recorded import run")),
+        }
+    } else {
+        load.to_string()
+    };
+
+    let schema = get_schema_name(&suffix);
+    let sql = r#"select count(*) from information_schema.tables
+                 where table_schema = $1 and table_name in ('core_data', 'names', 'locations')"#;
+    let num_tables: i64 = sqlx::query_scalar(sql).bind(&schema).fetch_one(pool).await?;
+    if num_tables < 3 {
+        let reason = format!("gives the schema {}, which is missing or does not hold the core_data, names and locations tables", schema);
+        return Err(get_load_error(load, &reason));
+    }
+    Ok(schema)
+}
+
+
+pub fn get_schema_name(suffix: &str) -> String {
+
+    // Versions such as 'v1.58' are reduced to a valid (unquoted) schema
+    // name, so the name can be included directly in the SQL.
+
+    if suffix.eq_ignore_ascii_case(CURRENT_LOAD) {
+        return "ror".to_string();
+    }
+    let suffix: String = suffix.trim().to_lowercase().chars()
+                            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    format!("ror_{}", suffix)
+}
+
+
+async fn count_changes(table: &str, columns: (&str, &str), a: &str, b: &str, pool: &Pool<Postgres>) -> Result<ChangeCounts, AppError> {
+    let (keys, values) = columns;
+    let count_keys = |x: &str, y: &str| format!("select count(*) from (select {keys} from {x}.{table} except select {keys} from {y}.{table}) k");
+    let added = count(&count_keys(b, a), pool).await?;
+    let removed = count(&count_keys(a, b), pool).await?;
+    let sql = format!(r#"select count(*) from (select {keys}, {values} from {b}.{table}
+                                               except select {keys}, {values} from {a}.{table}) m
+                         where ({keys}) in (select {keys} from {a}.{table})"#);
+    let modified = count(&sql, pool).await?;
+    Ok(ChangeCounts { added, removed, modified })
+}
+
+
+async fn count(sql: &str, pool: &Pool<Postgres>) -> Result<i64, AppError> {
+    Ok(sqlx::query_scalar(sql).fetch_one(pool).await?)
+}
+
+
+async fn fetch_ids(sql: &str, pool: &Pool<Postgres>) -> Result<Vec<String>, AppError> {
+    let mut ids: Vec<String> = sqlx::query_scalar(sql).fetch_all(pool).await?;
+    ids.sort();
+    Ok(ids)
+}
+
+
+fn write_changed_ids(diff: &VersionDiff, folder: &Path) -> Result<PathBuf, AppError> {
+    let file_path = folder.join(format!("{} to {} changed_ids.csv", diff.from_schema, diff.to_schema));
+    fs::write(&file_path, get_changed_ids_csv(diff))?;
+    Ok(file_path)
+}
+
+
+pub fn get_changed_ids_csv(diff: &VersionDiff) -> String {
+    let mut csv = String::from("id,change\n");
+    for (change, ids) in [("added", &diff.added_ids), ("removed", &diff.removed_ids), ("modified", &diff.modified_ids)] {
+        for id in ids.iter() {
+            csv.push_str(&format!("{},{}\n", id, change));
+        }
+    }
+    csv
+}
+
+
+fn get_load_error(load: &str, reason: &str) -> AppError {
+    let msg = format!("Cannot compare versions: '{}' {}", load, reason);
+    error!("{}", msg);
+    AppError::CsErr(CustomError::new(&msg))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_schema_names_from_suffixes() {
+        assert_eq!(get_schema_name("v1_58"), "ror_v1_58");
+        assert_eq!(get_schema_name("v1.59"), "ror_v1_59");
+        assert_eq!(get_schema_name(" V2.0-beta "), "ror_v2_0_beta");
+        assert_eq!(get_schema_name("current"), "ror");
+    }
+
+    #[test]
+    fn check_changed_ids_csv() {
+        let diff = VersionDiff {
+            added_ids: vec!["0nwtest01".to_string()],
+            removed_ids: vec!["03rd8mf35".to_string(), "04ttjf776".to_string()],
+            modified_ids: vec!["052rpwb50".to_string()],
+            ..VersionDiff::default()
+        };
+        assert_eq!(get_changed_ids_csv(&diff), "id,change\n0nwtest01,added\n03rd8mf35,removed\n04ttjf776,removed\n052rpwb50,modified\n");
+        assert_eq!(diff.orgs(), ChangeCounts { added: 1, removed: 2, modified: 1 });
+    }
+}
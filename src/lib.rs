@@ -15,9 +15,26 @@ mod summarise;
 mod export;
 pub mod error_defs;
 
+// Allows integration tests to check the data quality and country summaries directly.
+
+pub use import::{summarise_quality, fetch_import_runs, ImportQuality, ImportReport, ImportRun};
+pub use import::{create_ror_tables, import_from_reader, compare_source_ids, IdDiff};
+pub use import::{get_sqlite_pool, create_sqlite_tables, import_into_sqlite};
+pub use import::{compare_versions, VersionDiff, ChangeCounts};
+pub use import::{write_name_type_breakdown, NameTypeCount};
+pub use summarise::{summarise_by_country, summarise_by_established, CountryCount, EstablishedCount, EstablishedHistogram};
+pub use summarise::{summarise_external_id_coverage, summarise_by_type, report_summaries, IdCoverage, TypeCount};
+pub use summarise::create_smm_tables;
+pub use process::{create_src_tables, dedup_tables, check_relationships, set_ror_names, RelationshipCheck, RorNameCheck};
+pub use export::{export_json, export_country_csv, CsvFormat, ExportedFile, MANIFEST_FILE_NAME, JsonOrg, JsonLocation, JsonName, JsonExtId};
+
 use error_defs::AppError;
-use setup::log_helper;
+use log::info;
+use setup::{DbPools, InitParams, SourceFile};
+use setup::log_helper::{self, PhaseTimings};
+use sqlx::{Pool, Postgres};
 use std::ffi::OsString;
+use std::time::Instant;
 
 pub async fn run(args: Vec<OsString>) -> Result<(), AppError> {
     
@@ -32,17 +49,142 @@ pub async fn run(args: Vec<OsString>) -> Result<(), AppError> {
 
     let params = setup::get_params(args).await?;
     let flags = params.flags;
-    let test_run = flags.test_run;
+
+    // The resolved parameters may be printed, as JSON, without anything 
+    // being run (or a log being started).
+
+    if flags.print_config {
+        println!("{}", setup::get_config_json(&params)?);
+        return Ok(());
+    }
+
+    // A plan lists the phases that would be run, without running them (or 
+    // starting a log).
+
+    if flags.plan {
+        setup::print_plan(&params);
+        return Ok(());
+    }
 
     if !flags.test_run {
-       log_helper::setup_log(&params.log_folder, &params.source_file_name)?;
+       log_helper::setup_log(&params.log_folder, &params.source_file_name, params.log_level, flags.tail_log)?;
        log_helper::log_startup_params(&params);
     }
             
-    let pool = setup::get_db_pool().await?;
+    // Validation and counting only parse the source file(s), and so  
+    // return before any database connection is attempted.
+
+    if flags.validate || flags.count_only {
+        if flags.validate {
+            import::validate_files(&params.data_folder, &params.source_files)?;
+        }
+        if flags.count_only {
+            import::count_files(&params.data_folder, &params.source_files)?;
+        }
+        return Ok(());
+    }
+
+    // With --sqlite the source data is imported into a SQLite database,
+    // rather than Postgres, and nothing else is done.
+
+    if !params.sqlite_path.is_empty() {
+        let pool = get_sqlite_pool(&params.sqlite_path).await?;
+        let run_start = Instant::now();
+        import_into_sqlite(&params, &pool).await?;
+        log_helper::log_phase_duration("Import", &run_start.elapsed());
+        return Ok(());
+    }
+
+    let pools = setup::get_db_pools_with(flags.quiet_db).await?;
+    let pool = &pools.write;
+
+    // Checking the database (having connected to it) is a stand alone action.
+
+    if flags.check_db {
+        return setup::check_database(pool).await;
+    }
+
+    // Dropping the schemas created by the program is also a stand alone action.
+
+    if flags.drop_all {
+        return setup::drop_all_schemas(flags.assume_yes, pool).await;
+    }
+
+    // Showing the history of import runs is also a stand alone action.
+
+    if flags.show_history {
+        return import::show_import_runs(params.history_count, pool).await;
+    }
+
+    // Comparing the ids in the source file(s) with those in the database 
+    // is also stand alone, as nothing is imported.
+
+    if flags.only_new_ids {
+        for group in setup::group_by_version(&params.source_files) {
+            import::compare_source_ids(&params.data_folder, &group, &params.output_folder, pool).await?;
+        }
+        return Ok(());
+    }
+
+    // Comparing two loads of the ror tables is also stand alone, with the 
+    // ids of the changed organisations written as a csv file if -x is given.
+
+    if flags.compare_versions {
+        let csv_folder = if flags.export_csv { Some(params.csv_output_folder.as_path()) } else { None };
+        import::compare_versions(&params.compare_versions, csv_folder, pool).await?;
+        return Ok(());
+    }
+
+    // Reporting the summaries of the data already in the database is also 
+    // stand alone, with the summaries logged but no files written.
+
+    if flags.report {
+        return summarise::report_summaries(&params.tables, pools.reporting()).await;
+    }
+
+    // The remaining phases form the pipeline proper, which can also be 
+    // run directly, with parameters built by the calling program.
+
+    run_pipeline_with(params, &pools).await?;
+    Ok(())
+}
+
+
+pub async fn run_pipeline(params: InitParams, pool: &Pool<Postgres>) -> Result<ImportReport, AppError> {
+    run_pipeline_with(params, &DbPools::single(pool)).await
+}
+
+
+pub async fn run_pipeline_with(mut params: InitParams, pools: &DbPools) -> Result<ImportReport, AppError> {
+
+    // Runs the phases enabled in the parameters' flags, in order, against the 
+    // database, and returns the combined report of any import. The parameters 
+    // are assumed to be complete - as they are when returned by get_params.
+    // The counts after an import, and the exports, use the reporting pool.
+
+    let pool = &pools.write;
+    let read_pool = pools.reporting();
+    let run_start = Instant::now();
+    let flags = params.flags;
+    let test_run = flags.test_run;
+
+    // Without an import, and without a version being given, any processing 
+    // and export uses the version currently held in the ror tables.
+
+    if !flags.import_ror && params.data_version.is_empty() {
+        if let Some(version) = import::fetch_current_version(pool).await? {
+            info!("Using the version currently in the ror tables: {}", version);
+            params.output_file_name = setup::get_output_file_name(&version, flags.overwrite);
+            params.data_version = version;
+        }
+    }
 
     // Processing of the remaining stages depends on the 
     // presence of the relevant CLI flag(s).
+    // The time taken by each of the main phases is recorded and logged.
+
+    let mut timings = PhaseTimings::default();
+    let mut import_report = ImportReport::default();
 
     // The first two routines normally run only as an initial 
     // 'setup' of the program's DB, but can be repeated later if required.
@@ -51,12 +193,12 @@ pub async fn run(args: Vec<OsString>) -> Result<(), AppError> {
 
     if flags.create_lookups
     {  
-        setup::create_lup_tables(&pool).await?;
+        setup::create_lup_tables(pool).await?;
     }
 
     if flags.create_summary
     {  
-        summarise::create_smm_tables(&pool).await?;
+        summarise::create_smm_tables(pool).await?;
     }
     
     // In each of the following stages, the initial step is to recreate 
@@ -66,45 +208,137 @@ pub async fn run(args: Vec<OsString>) -> Result<(), AppError> {
 
     if !(flags.create_lookups && flags.create_summary) {
 
-        if flags.import_ror    // import ror from json file and store in ror schema tables
-        {
-            import::create_ror_tables(&pool).await?;
-            import::import_data(&params.data_folder, &params.source_file_name, 
-                                &params.data_version, &params.data_date, &pool).await?;
-            if !test_run {
-                import::summarise_import(&pool).await?;
+        // The source files are imported in turn. Consecutive files with the same data
+        // version are imported together into the ror tables, which are otherwise recreated 
+        // for each version. Any processing follows the import of each version, so that
+        // when several versions are imported each is processed and summarised in turn. 
+        // Without an import the processing uses the version given in the parameters.
+
+        let no_import_files = [SourceFile {
+            file_name: params.source_file_name.clone(),
+            data_version: params.data_version.clone(),
+            data_date: params.data_date.clone(),
+        }];
+        let version_groups = if flags.import_ror && !params.source_files.is_empty() {
+            setup::group_by_version(&params.source_files)
+        } else {
+            vec![no_import_files.iter().collect()]
+        };
+
+        for group in version_groups {
+
+            let data_version = &group[0].data_version;
+
+            if flags.import_ror    // import ror from json file(s) and store in ror schema tables
+            {
+                let phase_start = Instant::now();
+                import::set_trace_id(&params.trace_id);
+                import::set_dump_folder(if flags.dump_failed { Some(params.output_folder.as_path()) } else { None });
+                import::create_ror_tables(flags.no_recreate || flags.resume, pool).await?;
+                for sf in group.iter() {
+                    let report = if flags.atomic {
+                        import::import_data_atomic(&params.data_folder, sf, &params.since_date, &params.types, 
+                                        &flags, params.max_memory_mb, pool).await?
+                    } else {
+                        import::import_data(&params.data_folder, sf, &params.since_date, &params.types, 
+                                        &flags, params.max_memory_mb, pool).await?
+                    };
+                    import::record_import_run(sf, &report, pool).await?;
+                    import_report.add(&report);
+                }
+                if flags.analyze {
+                    setup::analyze_tables("ror", pool).await?;
+                }
+                if !test_run {
+                    import::summarise_import(&params.tables, read_pool).await?;
+                }
+                let d = phase_start.elapsed();
+                log_helper::log_phase_duration("Import", &d);
+                timings.import = Some(timings.import.unwrap_or_default() + d);
+            }
+        
+            if flags.dedup  // remove any exact duplicate rows from the ror child tables
+            {
+                process::dedup_tables(pool).await?;
+            }
+
+            if flags.process_data  // transfer data to src tables
+            {
+                let phase_start = Instant::now();
+                process::create_src_tables(flags.no_recreate, flags.recreate_src, pool).await?;
+                process::process_data(data_version, flags.strict, flags.keep_raw, flags.dump_lookups, pool).await?;
+                if flags.analyze {
+                    setup::analyze_tables("src", pool).await?;
+                }
+                let d = phase_start.elapsed();
+                log_helper::log_phase_duration("Transform", &d);
+                timings.transform = Some(timings.transform.unwrap_or_default() + d);
+            }
+
+            if flags.summarise_data  // summarise the src data in smm tables
+            {
+                let phase_start = Instant::now();
+                summarise::summarise_data(pool).await?;
+                summarise::summarise_by_country(pool).await?;
+                summarise::summarise_by_established(pool).await?;
+                summarise::summarise_external_id_coverage(pool).await?;
+                let d = phase_start.elapsed();
+                log_helper::log_phase_duration("Summary", &d);
+                timings.summary = Some(timings.summary.unwrap_or_default() + d);
             }
         }
-    
-        if flags.process_data  // transfer data to src tables, and summarise in smm tables
-        {
-            process::create_src_tables(&pool).await?;
-            process::process_data(&params.data_version, &pool).await?;
-            summarise::summarise_data(&pool).await?;
+
+        if import_report.files_imported > 1 {
+            import::log_import_report(&import_report);
         }
 
+        // Each file written is listed in a manifest, in the output folder.
+
+        let phase_start = Instant::now();
+        let mut exported_files = Vec::new();
+        let csv_format = export::CsvFormat { delimiter: params.csv_delimiter, bom: flags.csv_bom };
+
         if flags.export_text  // write out summary data from data in smm tables
         { 
-            export::export_as_text(&params.output_folder, &params.output_file_name, 
-                    &params.data_version, &pool).await?;
+            exported_files.push(export::export_as_text(&params.text_output_folder, &params.output_file_name, 
+                    &params.data_version, &timings, read_pool).await?);
         }
 
         if flags.export_csv  // write out summary data from data in smm tables
         { 
-            export::export_as_csv(&params.output_folder, &params.data_version, &pool).await?;
+            exported_files.extend(export::export_as_csv(&params.csv_output_folder, &params.data_version, &params.tables, 
+                                                        flags.append_csv, &csv_format, read_pool).await?);
+        }
+
+        if flags.export_json  // write out the processed records as ror style json from data in src tables
+        { 
+            exported_files.push(export::export_as_json(&params.output_folder, &params.data_version, read_pool).await?);
+        }
+
+        if flags.export_country_csv  // write out the numbers of organisations per country from data in src tables
+        { 
+            exported_files.push(export::export_country_csv(&params.csv_output_folder, &csv_format, read_pool).await?);
         }
 
         if flags.export_full_csv  // write out summary data for all versions from data in smm tables
         {       
-                export::export_all_as_csv(&params.output_folder, &pool).await?;
+            exported_files.extend(export::export_all_as_csv(&params.csv_output_folder, &params.tables, &csv_format, read_pool).await?);
+        }
+
+        if flags.export_text || flags.export_csv || flags.export_json || flags.export_country_csv || flags.export_full_csv {
+            export::write_manifest(&params.output_folder, &exported_files)?;
+            let d = phase_start.elapsed();
+            log_helper::log_phase_duration("Export", &d);
+            timings.export = Some(d);
         }
 
         if test_run {
-            summarise::smm_helper::delete_any_existing_data(&"v99".to_string(), &pool).await?; // Clear any test data from the smm tables.
+            summarise::smm_helper::delete_any_existing_data(&"v99".to_string(), pool).await?; // Clear any test data from the smm tables.
         }
 
 
     }
 
-    Ok(())  
+    log_helper::log_total_duration(&timings, &run_start.elapsed(), flags.quiet_db);
+    Ok(import_report)  
 }
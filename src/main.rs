@@ -1,12 +1,24 @@
-
 use ror1::error_defs::AppError;
 use ror1::run;
 use std::env;
+use std::process;
 
 #[tokio::main(flavor = "current_thread")]
-async fn main() -> Result<(), AppError> {
+async fn main() {
 
     let args: Vec<_> = env::args_os().collect();
-    run(args).await
-}
+    if let Err(e) = run(args).await {
 
+        // Help and version requests arrive as clap 'errors' - print them 
+        // as normal, otherwise report the error and exit with its code.
+
+        if let AppError::CpErr(ref ce) = e {
+            if ce.exit_code() == 0 {
+                let _ = ce.print();
+                process::exit(0);
+            }
+        }
+        eprintln!("Error: {:?}", e);
+        process::exit(e.exit_code());
+    }
+}
@@ -6,15 +6,42 @@ mod src_data_importer;
 mod src_data_processor;
 mod src_create_tables;
 mod src_rmv_dup_names;
+mod ror_dedup_tables;
+mod src_coords_checker;
+mod src_rel_checker;
+mod src_ror_names;
+mod src_lang_codes;
+mod src_domains;
 
 
-use log::{info, error};
+use log::{info, warn, error};
 use sqlx::{Pool, Postgres};
+use crate::setup::{lookups, schema_tables_exist};
+use crate::setup::log_helper::truncate_for_log;
 use crate::AppError;
 
+pub use src_rel_checker::RelationshipCheck;
+pub use src_ror_names::RorNameCheck;
 
-pub async fn create_src_tables(pool : &Pool<Postgres>) -> Result<(), AppError>
+
+pub async fn create_src_tables(keep_existing: bool, force: bool, pool : &Pool<Postgres>) -> Result<(), AppError>
 {
+    // If requested (by --no-recreate) any existing tables are retained as they 
+    // are, so that further data can be added. Otherwise existing tables with 
+    // the expected columns are kept, and only emptied ready for the transform, 
+    // so that anything depending on them (e.g. views) survives. Tables that are 
+    // missing or of the wrong shape, or all tables with --recreate-src, are 
+    // dropped and recreated.
+
+    if keep_existing && schema_tables_exist("src", pool).await? {
+        info!("Existing tables in src schema retained");
+        return Ok(());
+    }
+    if !force && src_create_tables::tables_have_expected_shape(pool).await? {
+        src_create_tables::empty_tables(pool).await?;
+        info!("Existing tables in src schema retained, and emptied");
+        return Ok(());
+    }
     match src_create_tables::create_tables(pool).await {
         Ok(()) => info!("Tables created for src schema"),
         Err(e) => {
@@ -33,12 +60,99 @@ pub async fn create_src_tables(pool : &Pool<Postgres>) -> Result<(), AppError>
 
 }
 
-pub async fn process_data(data_version: &String, pool : &Pool<Postgres>) -> Result<(), AppError>
+pub async fn dedup_tables(pool : &Pool<Postgres>) -> Result<u64, AppError>
+{
+    // Removes any exact duplicate rows from the ror child tables.
+
+    match ror_dedup_tables::dedup_tables(pool).await {
+        Ok(n) => {
+            info!("Duplicate rows removed from ror tables: {} in total", n);
+            Ok(n)
+        },
+        Err(e) => {
+            error!("An error occured while removing duplicate rows from the ror tables: {}", e);
+            Err(e)
+        },
+    }
+}
+
+pub async fn check_relationships(pool : &Pool<Postgres>) -> Result<RelationshipCheck, AppError>
+{
+    // Logs the numbers of dangling relationships, and of parent / child 
+    // relationships without an inverse, with the first few of each.
+
+    let check = src_rel_checker::check_relationships(pool).await?;
+    let num_examples = 5;
+    if check.dangling.is_empty() {
+        info!("No relationships to organisations not in the data found");
+    }
+    else {
+        warn!("{} relationships to organisations not in the data found, e.g.", check.dangling.len());
+        for (id, related_id) in check.dangling.iter().take(num_examples) {
+            warn!("    {} -> {}", id, related_id);
+        }
+    }
+    if check.missing_inverse.is_empty() {
+        info!("All parent / child relationships have an inverse");
+    }
+    else {
+        warn!("{} parent / child relationships without an inverse found, e.g.", check.missing_inverse.len());
+        for (id, rel, related_id) in check.missing_inverse.iter().take(num_examples) {
+            warn!("    {} has {} {}, but not the reverse", id, rel, related_id);
+        }
+    }
+    Ok(check)
+}
+
+pub async fn set_ror_names(pool : &Pool<Postgres>) -> Result<RorNameCheck, AppError>
+{
+    // Marks a single name of each organisation as its ror name, logging  
+    // any organisations without a ror name, or with more than one.
+
+    let check = src_ror_names::set_ror_names(pool).await?;
+    if !check.without_ror_name.is_empty() {
+        warn!("{} organisations have no ror_display name, and have not been transferred to the src tables:", 
+               check.without_ror_name.len());
+        for id in check.without_ror_name.iter() {
+            warn!("    {}", id);
+        }
+    }
+    if !check.multiple_ror_names.is_empty() {
+        warn!("{} organisations have more than one ror_display name, only one has been kept as the ror name:", 
+               check.multiple_ror_names.len());
+        for (id, ror_name) in check.multiple_ror_names.iter() {
+            warn!("    {} ({})", id, truncate_for_log(ror_name));
+        }
+    }
+    if check.not_single_after_update.is_empty() {
+        info!("A single ror name set for each organisation");
+    }
+    else {
+        for id in check.not_single_after_update.iter() {
+            error!("Organisation {} does not have a single ror name", id);
+        }
+    }
+    Ok(check)
+}
+
+pub async fn process_data(data_version: &String, strict: bool, keep_raw: bool, dump_lookups: bool, 
+                          pool : &Pool<Postgres>) -> Result<(), AppError>
 {
 
+    // Check that the lookup tables contain the type codes used when processing.
+
+    let lookup_maps = lookups::load_lookups(pool).await?;
+    lookups::log_lookups(&lookup_maps, dump_lookups);
+    if lookups::check_lookups(&lookup_maps) {
+        info!("Lookup tables checked against type codes");
+    }
+    else {
+        warn!("Lookup tables do not match the type codes used in processing - consider re-running with -c");
+    }
+
     // Import the data from ror schema to src schema.
 
-    match src_data_importer::import_data(data_version, pool).await
+    match src_data_importer::import_data(data_version, keep_raw, pool).await
     {
         Ok(()) => {
             info!("Data imported from ror to src tables"); 
@@ -49,6 +163,31 @@ pub async fn process_data(data_version: &String, pool : &Pool<Postgres>) -> Resu
             },
     }
 
+    // Check the relationships, and the location coordinates (before the locations are counted)
+
+    match check_relationships(pool).await
+    {
+        Ok(_) => {
+            info!("Relationships checked"); 
+        },
+        Err(e) => {
+            error!("An error occured while checking the relationships: {}", e);
+            return Err(e)
+            },
+    }
+
+
+    match src_coords_checker::check_coordinates(strict, pool).await
+    {
+        Ok(n) => {
+            info!("Location coordinates checked, {} suspect", n); 
+        },
+        Err(e) => {
+            error!("An error occured while checking the location coordinates: {}", e);
+            return Err(e)
+            },
+    }
+
     // Calculate number of attributes for each org, and populate the admin data table with results.
 
     match src_data_processor::store_org_attribute_numbers(pool).await
@@ -0,0 +1,37 @@
+use sqlx::{Pool, Postgres};
+use log::{info, error};
+use crate::AppError;
+
+// The child tables of the ror schema have no unique constraints, so an 
+// accidental double import, or overlapping files of the same version,
+// will silently duplicate rows. Exact duplicates are identified by grouping
+// on the whole row, and all but the first physical row (lowest ctid) removed.
+
+const CHILD_TABLES: [&str; 7] = ["names", "locations", "external_ids", "links", 
+                                 "type", "relationships", "domains"];
+
+pub async fn dedup_tables(pool: &Pool<Postgres>) -> Result<u64, AppError> {
+
+    let mut total_removed = 0;
+    for table_name in CHILD_TABLES {
+        let removed = dedup_table(table_name, pool).await?;
+        info!("{} duplicate rows removed from ror.{}", removed, table_name);
+        total_removed += removed;
+    }
+    Ok(total_removed)
+}
+
+
+async fn dedup_table(table_name: &str, pool: &Pool<Postgres>) -> Result<u64, AppError> {
+
+    let sql = format!(r#"DELETE FROM ror.{0} 
+                WHERE ctid NOT IN (SELECT min(t.ctid) FROM ror.{0} t GROUP BY t.*);"#, table_name);
+    match sqlx::query(&sql).execute(pool).await
+    {
+        Ok(res) => Ok(res.rows_affected()),
+        Err(e) => {
+            error!("An error occured, {}, when removing duplicates from ror.{}", e, table_name);
+            Err(AppError::SqErr(e))
+        },
+    }
+}
@@ -0,0 +1,72 @@
+use sqlx::{Pool, Postgres};
+use log::{info, warn};
+use crate::AppError;
+use crate::import::SUSPECT_COORDS_CONDITION;
+
+// Checks the coordinates in src.locations, logging any that are out of range, or
+// that are the (0,0) placeholder. By default the suspect coordinates are set to 
+// null, but in strict mode the location is removed instead. Returns the number found.
+
+pub async fn check_coordinates(strict: bool, pool: &Pool<Postgres>) -> Result<usize, AppError> {
+
+    let sql = format!("select id, lat, lng from src.locations where {} order by id", SUSPECT_COORDS_CONDITION);
+    let rows: Vec<(String, f32, f32)> = sqlx::query_as(&sql).fetch_all(pool).await?;
+    for (id, lat, lng) in rows.iter() {
+        let problem = describe_coordinate_problem(*lat, *lng).unwrap_or("suspect");
+        warn!("Coordinates ({}, {}) for {} are {}", lat, lng, id, problem);
+    }
+
+    if !rows.is_empty() {
+        let sql = if strict {
+            format!("delete from src.locations where {}", SUSPECT_COORDS_CONDITION)
+        } else {
+            format!("update src.locations set lat = null, lng = null where {}", SUSPECT_COORDS_CONDITION)
+        };
+        sqlx::query(&sql).execute(pool).await?;
+        info!("{} locations with suspect coordinates {}", rows.len(), 
+              if strict { "removed" } else { "had their coordinates set to null" });
+    }
+    Ok(rows.len())
+}
+
+
+pub fn describe_coordinate_problem(lat: f32, lng: f32) -> Option<&'static str> {
+    if !(-90.0..=90.0).contains(&lat) {
+        Some("out of range (latitude)")
+    }
+    else if !(-180.0..=180.0).contains(&lng) {
+        Some("out of range (longitude)")
+    }
+    else if lat == 0.0 && lng == 0.0 {
+        Some("a (0,0) placeholder")
+    }
+    else {
+        None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_in_range_coordinates_accepted() {
+        assert_eq!(describe_coordinate_problem(51.5072, -0.1276), None);
+        assert_eq!(describe_coordinate_problem(-90.0, 180.0), None);
+        assert_eq!(describe_coordinate_problem(0.0, 32.5), None);
+    }
+
+    #[test]
+    fn check_out_of_range_coordinates_detected() {
+        assert_eq!(describe_coordinate_problem(91.2, 10.0), Some("out of range (latitude)"));
+        assert_eq!(describe_coordinate_problem(-90.5, 10.0), Some("out of range (latitude)"));
+        assert_eq!(describe_coordinate_problem(45.0, 181.0), Some("out of range (longitude)"));
+        assert_eq!(describe_coordinate_problem(45.0, -200.0), Some("out of range (longitude)"));
+    }
+
+    #[test]
+    fn check_placeholder_coordinates_detected() {
+        assert_eq!(describe_coordinate_problem(0.0, 0.0), Some("a (0,0) placeholder"));
+    }
+}
@@ -1,6 +1,56 @@
 use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
 use crate::AppError;
 
+// The columns of each src table, as created below, used to check that any 
+// existing tables have the expected shape before they are kept.
+
+pub const SRC_TABLE_COLUMNS: [(&str, &[&str]); 12] = [
+    ("version_details", &["version", "data_date", "data_days", "process_datetime"]),
+    ("core_data", &["id", "ror_full_id", "ror_name", "status", "established", "location", "csubdiv_code", "country_code"]),
+    ("names", &["id", "value", "name_type", "is_ror_name", "lang_code", "lang_code_raw", "script_code"]),
+    ("dup_names", &["id", "value", "name_type", "dup_type", "is_ror_name", "lang_code"]),
+    ("dup_names_deleted", &["id", "value", "name_type", "dup_type", "is_ror_name", "lang_code"]),
+    ("locations", &["id", "ror_name", "geonames_id", "location", "lat", "lng", "cont_code", "cont_name", 
+                    "country_code", "country_name", "csubdiv_code", "csubdiv_name", "is_primary"]),
+    ("external_ids", &["id", "ror_name", "id_type", "id_value", "is_preferred"]),
+    ("links", &["id", "ror_name", "link_type", "link"]),
+    ("type", &["id", "ror_name", "org_type"]),
+    ("relationships", &["id", "ror_name", "rel_type", "related_id", "related_name"]),
+    ("domains", &["id", "ror_name", "domain", "domain_raw"]),
+    ("admin_data", &["id", "ror_name", "n_locs", "n_labels", "n_aliases", "n_acronyms", "n_names", "n_names_wolc", 
+                     "n_nacro", "n_nacro_wolc", "is_company", "n_types", "n_isni", "n_grid", "n_fundref", "n_wikidata", 
+                     "n_ext_ids", "n_wikipedia", "n_website", "n_links", "n_relrels", "n_parrels", "n_chrels", 
+                     "n_sucrels", "n_predrels", "n_doms", "created", "cr_schema", "last_modified", "lm_schema"]),
+];
+
+
+pub async fn tables_have_expected_shape(pool: &Pool<Postgres>) -> Result<bool, AppError> {
+
+    // True only if every src table exists, with exactly the expected columns.
+
+    let sql = r#"select table_name::varchar, array_agg(column_name::varchar order by ordinal_position)
+                 from information_schema.columns where table_schema = 'src'
+                 group by table_name"#;
+    let rows: Vec<(String, Vec<String>)> = sqlx::query_as(sql).fetch_all(pool).await?;
+    let found: HashMap<String, Vec<String>> = rows.into_iter().collect();
+    Ok(SRC_TABLE_COLUMNS.iter().all(|(table_name, columns)| {
+        match found.get(*table_name) {
+            Some(cols) => cols.len() == columns.len() && columns.iter().all(|c| cols.iter().any(|fc| fc == c)),
+            None => false,
+        }
+    }))
+}
+
+
+pub async fn empty_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
+    let table_list: Vec<String> = SRC_TABLE_COLUMNS.iter().map(|(t, _)| format!("src.{}", t)).collect();
+    let sql = format!("truncate table {}", table_list.join(", "));
+    sqlx::raw_sql(&sql).execute(pool).await?;
+    Ok(())
+}
+
+
 pub async fn create_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
 
     let sql = r#"
@@ -16,7 +66,7 @@ pub async fn create_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
         , process_datetime  timestamp   not null  default current_timestamp
     );
 
-    drop table if exists src.core_data;
+    drop table if exists src.core_data cascade;
     create table src.core_data
     (
           id                varchar     not null primary key
@@ -32,11 +82,12 @@ pub async fn create_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
     drop table if exists src.names;
     create table src.names
     (
-          id                varchar     not null
+          id                varchar     not null  references src.core_data(id) on delete cascade
         , value             varchar     not null  
         , name_type         int         not null 
         , is_ror_name       bool        not null default false
         , lang_code         varchar     null
+        , lang_code_raw     varchar     null
         , script_code       varchar     null
     );
     create index names_idx on src.names(id);
@@ -68,7 +119,7 @@ pub async fn create_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
     drop table if exists src.locations;
     create table src.locations
     (
-          id                varchar     not null
+          id                varchar     not null  references src.core_data(id) on delete cascade
         , ror_name          varchar     not null
         , geonames_id       int         null
         , location          varchar     null	
@@ -80,13 +131,14 @@ pub async fn create_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
         , country_name      varchar     null
         , csubdiv_code      varchar     null  
         , csubdiv_name      varchar     null	
+        , is_primary        bool        not null default false
     );
     create index locations_idx on src.locations(id);
 
     drop table if exists src.external_ids;
     create table src.external_ids
     (
-          id                varchar     not null
+          id                varchar     not null  references src.core_data(id) on delete cascade
         , ror_name          varchar     not null	
         , id_type           int         not null
         , id_value          varchar     not null
@@ -97,7 +149,7 @@ pub async fn create_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
     drop table if exists src.links;
     create table src.links
     (
-          id                varchar     not null
+          id                varchar     not null  references src.core_data(id) on delete cascade
         , ror_name          varchar     not null  	  
         , link_type         int         not null
         , link              varchar     not null
@@ -107,7 +159,7 @@ pub async fn create_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
     drop table if exists src.type;
     create table src.type
     (
-          id                varchar     not null
+          id                varchar     not null  references src.core_data(id) on delete cascade
         , ror_name          varchar     not null
         , org_type          int         not null
     );  
@@ -116,7 +168,7 @@ pub async fn create_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
     drop table if exists src.relationships;
     create table src.relationships
     (
-          id                varchar     not null
+          id                varchar     not null  references src.core_data(id) on delete cascade
         , ror_name          varchar     not null
         , rel_type          int         not null
         , related_id        varchar     not null
@@ -127,9 +179,10 @@ pub async fn create_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
     drop table if exists src.domains;
     create table src.domains
     (
-          id                varchar     not null
+          id                varchar     not null  references src.core_data(id) on delete cascade
         , ror_name          varchar     not null
         , domain            varchar     not null
+        , domain_raw        varchar     null
     );
     create index domains_idx on src.domains(id);
 
@@ -1,33 +1,47 @@
 use sqlx::{Pool, Postgres};
-use log::{info, error};
+use log::{info, warn, error};
+use crate::setup::lookups::StatusType;
+use crate::setup::log_helper::truncate_for_log;
 use crate::error_defs::{AppError, CustomError};
-use super::src_rmv_dup_names;
+use super::{src_rmv_dup_names, src_lang_codes, src_domains};
 
-pub async fn import_data (data_version: &String, pool: &Pool<Postgres>) -> Result<(), AppError> {
+pub async fn import_data (data_version: &String, keep_raw: bool, pool: &Pool<Postgres>) -> Result<(), AppError> {
 
     check_data_version_matches_ror_schema_data(data_version, pool).await?;
 
+    // The child tables have foreign keys on src.core_data, so it must be populated 
+    // first. Each of the child inserts is also joined to src.core_data, so that 
+    // any orphan rows in the ror tables are dropped rather than breaking the insert.
+
     execute_sql(get_version_details_sql(), pool).await?;
-    execute_sql(get_import_names_sql(), pool).await?;
-    info!("Name data transferred to src table");
-    
-    src_rmv_dup_names::remove_dups(pool).await?;  // done here to prevent PK errors in core_data
-    
-    execute_sql(get_core_data_sql(), pool).await?;
+    check_status_values(pool).await?;
+    execute_sql(&get_core_data_sql(), pool).await?;
     execute_sql(update_core_data_sql(), pool).await?;
     execute_sql(get_admin_data_sql(), pool).await?;
     info!("Core organisation data transferred to src table");
 
+    execute_sql(get_import_names_sql(), pool).await?;
+    info!("Name data transferred to src table");
+    super::set_ror_names(pool).await?;
+    
+    src_rmv_dup_names::remove_dups(pool).await?;
+    let n = src_lang_codes::normalise_lang_codes(keep_raw, pool).await?;
+    info!("Name language codes normalised, {} unrecognised", n);
+
     execute_sql(get_links_sql(), pool).await?;
     execute_sql(get_external_ids_sql(), pool).await?;
     execute_sql(get_types_sql(), pool).await?;
     info!("External Ids, links and types data transferred to src table");
     
     execute_sql(get_locations_sql(), pool).await?;
+    log_multiple_locations(pool).await?;
     execute_sql(get_relationships_sql(), pool).await?;
     execute_sql(get_domains_sql(), pool).await?;
     info!("Location, relationship and domain data transferred to src table");
 
+    let dc = src_domains::normalise_domains(keep_raw, pool).await?;
+    info!("Domains normalised, {} not recognised as domains, {} duplicates removed", dc.num_invalid, dc.num_duplicates);
+
     Ok(())
 }
 
@@ -48,6 +62,38 @@ async fn check_data_version_matches_ror_schema_data(data_version: &String, pool:
     }
 }
 
+async fn check_status_values(pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    // Any status not recognised is recorded as 0 in src.core_data, with a warning.
+
+    let sql = "select distinct status from ror.core_data";
+    let statuses: Vec<String> = sqlx::query_scalar(sql).fetch_all(pool).await?;
+    for status in statuses.iter().filter(|s| StatusType::from_name(s).is_none()) {
+        warn!("Unrecognised status '{}' in ror.core_data, recorded as 0 in src.core_data", status);
+    }
+    Ok(())
+}
+
+async fn log_multiple_locations(pool: &Pool<Postgres>) -> Result<(), AppError> {
+
+    // Organisations with more than one location are listed, with the primary
+    // location used for the organisation in src.core_data.
+
+    let sql = r#"select c.id, count(l.id), c.location from src.core_data c
+                 inner join src.locations l on c.id = l.id
+                 group by c.id, c.location having count(l.id) > 1 
+                 order by c.id"#;
+    let orgs: Vec<(String, i64, Option<String>)> = sqlx::query_as(sql).fetch_all(pool).await?;
+    if !orgs.is_empty() {
+        info!("{} organisations with more than one location - the first listed used as the primary location", orgs.len());
+    }
+    for (id, n, location) in orgs.iter() {
+        info!("Organisation {} has {} locations, with {} as its primary location", id, n, 
+               truncate_for_log(location.as_deref().unwrap_or("(unnamed)")));
+    }
+    Ok(())
+}
+
 async fn execute_sql(sql: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {
     match sqlx::query(&sql).execute(pool).await
     {
@@ -68,47 +114,62 @@ fn get_version_details_sql <'a>() -> &'a str {
 fn get_import_names_sql <'a>() -> &'a str {
         r#"insert into src.names(id, value, name_type, 
         is_ror_name, lang_code)
-        select id, value, 
+        select a.id, a.value, 
         case 
-            when name_type = 'alias' then 7
-            when name_type = 'acronym' then 10
-            when name_type = 'label' then 5
+            when a.name_type = 'alias' then 7
+            when a.name_type = 'acronym' then 10
+            when a.name_type = 'label' then 5
             else 0
         end,
         case
-            when is_ror_name = true then true
+            when a.is_ror_name = true then true
             else false
         end, 
-        lang
-        from ror.names"#
+        a.lang
+        from ror.names a
+        inner join src.core_data c
+        on a.id = c.id"#
 }
 
-fn get_core_data_sql <'a>() -> &'a str {
+fn get_core_data_sql() -> String {
 
-    // Note reference to src.names (not ror.names) as the 
-    // src table has now had duplicates removed.
+    // The ror name may be listed more than once in ror.names (duplicates 
+    // are only removed later, from src.names), so 'distinct on' is used 
+    // to prevent PK errors in core_data. If an organisation has more than 
+    // one 'ror_display' name the first listed in the source is used - the 
+    // rows of ror.names are stored in source order, so this is the row with 
+    // the lowest ctid. The status codes are taken from the StatusType 
+    // constants, matched against the lower cased status. A missing 
+    // established year remains null.
     
-        r#"insert into src.core_data (id, ror_full_id, 
+    let status_cases: String = StatusType::ALL.iter()
+        .map(|v| format!("when '{}' then {} ", v.name(), v.code())).collect();
+
+    format!(r#"insert into src.core_data (id, ror_full_id, 
         ror_name, status, established)
         select c.id, c.ror_full_id, m.value, 
-        case 
-            when c.status = 'active' then 1
-            when c.status = 'inactive' then 2
-            when c.status = 'withdrawn' then 3
-        end, 
+        case lower(trim(c.status)) {}else 0 end, 
         c.established 
         from ror.core_data c
         inner join
-            (select id, value from src.names where is_ror_name = true) m
-        on c.id = m.id;"#
+            (select distinct on (id) id, value from ror.names 
+             where is_ror_name = true order by id, ctid) m
+        on c.id = m.id;"#, status_cases)
 }
 
 fn update_core_data_sql <'a>() -> &'a str {
+
+    // The location of an organisation is that of its primary location (the 
+    // first listed in the record). If none is flagged as primary, e.g. for 
+    // rows added to ror.locations directly, that with the lowest geonames id 
+    // is used, so that the choice does not depend on the order of the rows.
+
         r#"update src.core_data c
         set location = t.name,
         csubdiv_code = t.country_subdivision_code,
         country_code = t.country_code
-        from ror.locations t
+        from (select distinct on (id) id, name, country_subdivision_code, country_code 
+              from ror.locations order by id, is_primary desc, geonames_id) t
         where c.id = t.id;"#
 }
 
@@ -178,11 +239,11 @@ fn get_types_sql <'a>() -> &'a str {
 fn get_locations_sql <'a>() -> &'a str  {
         r#"insert into src.locations(id, ror_name, geonames_id, 
         location, lat, lng, cont_code, cont_name, 
-        country_code, country_name, csubdiv_code, csubdiv_name)
+        country_code, country_name, csubdiv_code, csubdiv_name, is_primary)
         select a.id, c.ror_name, a.geonames_id, a.name,
                 a.lat, a.lng, a.continent_code, a.continent_name, 
                 a.country_code, a.country_name, 
-                a.country_subdivision_code, a.country_subdivision_name
+                a.country_subdivision_code, a.country_subdivision_name, a.is_primary
         from ror.locations a
         inner join src.core_data c
         on a.id = c.id;"#
@@ -1,47 +1,48 @@
 use sqlx::{Pool, Postgres};
 use crate::AppError;
 use log::{info, error};
+use crate::setup::lookups::{NameType, OrgType, IdType, LinkType, OrgRel};
 
 pub async fn store_org_attribute_numbers (pool: &Pool<Postgres>) -> Result<(), AppError> {
         
     execute_sql(get_name_data_sql(), pool).await?;
-    execute_sql(get_label_data_sql(), pool).await?;
-    execute_sql(get_alias_data_sql(), pool).await?;
-    execute_sql(get_acronym_data_sql(), pool).await?;
+    execute_sql(&get_typed_count_sql("n_labels", "names", "name_type", NameType::Label.code()), pool).await?;
+    execute_sql(&get_typed_count_sql("n_aliases", "names", "name_type", NameType::Alias.code()), pool).await?;
+    execute_sql(&get_typed_count_sql("n_acronyms", "names", "name_type", NameType::Acronym.code()), pool).await?;
 
     info!("Basic name data summarised in admin data table");
 
     execute_sql(get_nacro_data_sql(), pool).await?;
     execute_sql(get_names_wolc_sql(), pool).await?;
-    execute_sql(get_nacro_wolc_sql(), pool).await?;
+    execute_sql(&get_nacro_wolc_sql(), pool).await?;
 
     info!("Name language code data summarised in admin data table");
 
-    execute_sql(get_companies_sql(), pool).await?;
+    execute_sql(&get_companies_sql(), pool).await?;
     execute_sql(get_types_data_sql(), pool).await?;
 
     info!("Types data summarised in admin data table");
     
-    execute_sql(get_isni_data_sql(), pool).await?;
-    execute_sql(get_grid_data_sql(), pool).await?;
-    execute_sql(get_fundref_data_sql(), pool).await?;
-    execute_sql(get_wikidata_data_sql(), pool).await?;
+    execute_sql(&get_typed_count_sql("n_isni", "external_ids", "id_type", IdType::Isni.code()), pool).await?;
+    execute_sql(&get_typed_count_sql("n_grid", "external_ids", "id_type", IdType::Grid.code()), pool).await?;
+    execute_sql(&get_typed_count_sql("n_fundref", "external_ids", "id_type", IdType::Fundref.code()), pool).await?;
+    execute_sql(&get_typed_count_sql("n_wikidata", "external_ids", "id_type", IdType::Wikidata.code()), pool).await?;
     execute_sql(get_ext_ids_data_sql(), pool).await?;
 
     info!("External ID summarised in admin data table");
 
-    execute_sql(get_wikipedia_data_sql(), pool).await?;
-    execute_sql(get_website_data_sql(), pool).await?;
+    execute_sql(&get_typed_count_sql("n_wikipedia", "links", "link_type", LinkType::Wikipedia.code()), pool).await?;
+    execute_sql(&get_typed_count_sql("n_website", "links", "link_type", LinkType::Website.code()), pool).await?;
     execute_sql(get_links_data_sql(), pool).await?;
 
     info!("Links data summarised in admin data table");
     
     execute_sql(get_locations_data_sql(), pool).await?;
-    execute_sql(get_parrels_data_sql(), pool).await?;
-    execute_sql(get_chrels_data_sql(), pool).await?;
-    execute_sql(get_relrels_data_sql(), pool).await?;
-    execute_sql(get_predrels_data_sql(), pool).await?;
-    execute_sql(get_succrels_data_sql(), pool).await?;
+    execute_sql(&get_typed_count_sql("n_parrels", "relationships", "rel_type", OrgRel::HasParent.code()), pool).await?;
+    execute_sql(&get_typed_count_sql("n_chrels", "relationships", "rel_type", OrgRel::HasChild.code()), pool).await?;
+    execute_sql(&get_typed_count_sql("n_relrels", "relationships", "rel_type", OrgRel::IsRelatedTo.code()), pool).await?;
+    execute_sql(&get_typed_count_sql("n_predrels", "relationships", "rel_type", OrgRel::HasPredecessor.code()), pool).await?;
+    execute_sql(&get_typed_count_sql("n_sucrels", "relationships", "rel_type", OrgRel::HasSuccessor.code()), pool).await?;
     execute_sql(get_domains_data_sql(), pool).await?;
 
     info!("Relationship, location and domain data summarised in admin data table");
@@ -61,42 +62,27 @@ async fn execute_sql(sql: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {
     }
 }
 
-fn get_name_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_names = n
-    from (
-        select id, count(id) as n
-        from src.names 
-        group by id) c
-    where ad.id = c.id;"#
-}
+fn get_typed_count_sql(field: &str, table: &str, type_field: &str, type_code: i32) -> String {
 
-fn get_label_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_labels = n
-    from (
-        select id, count(id) as n
-        from src.names where name_type = 5
-        group by id) c
-    where ad.id = c.id;"#
-}
+    // Counts the records of a particular type (e.g. a name type or link type) for each 
+    // organisation, with the type code provided by the relevant lookup enum.
 
-fn get_alias_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_aliases = n
+    format!(r#"update src.admin_data ad
+    set {} = n
     from (
         select id, count(id) as n
-        from src.names where name_type = 7
+        from src.{} 
+        where {} = {}
         group by id) c
-    where ad.id = c.id;"#
+    where ad.id = c.id;"#, field, table, type_field, type_code)
 }
 
-fn get_acronym_data_sql <'a>() -> &'a str {
+fn get_name_data_sql <'a>() -> &'a str {
     r#"update src.admin_data ad
-    set n_acronyms = n
+    set n_names = n
     from (
         select id, count(id) as n
-        from src.names where name_type = 10
+        from src.names 
         group by id) c
     where ad.id = c.id;"#
 }
@@ -117,23 +103,23 @@ fn get_names_wolc_sql <'a>() -> &'a str {
     where ad.id = c.id;"#
 }
 
-fn get_nacro_wolc_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
+fn get_nacro_wolc_sql() -> String {
+    format!(r#"update src.admin_data ad
     set n_nacro_wolc = n
     from (
         select id, count(id) as n
         from src.names 
-        where lang_code is null and name_type <> 10
+        where lang_code is null and name_type <> {}
         group by id) c
-    where ad.id = c.id;"#
+    where ad.id = c.id;"#, NameType::Acronym.code())
 }
 
-fn get_companies_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
+fn get_companies_sql() -> String {
+    format!(r#"update src.admin_data ad
     set is_company = true
     from src.type t
     where ad.id = t.id
-    and t.org_type = 400;"#
+    and t.org_type = {};"#, OrgType::Company.code())
 }
 
 fn get_types_data_sql <'a>() -> &'a str {
@@ -146,77 +132,11 @@ fn get_types_data_sql <'a>() -> &'a str {
     where ad.id = c.id;"#
 }
 
-fn get_isni_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_isni = n
-    from (
-        select id, count(id) as n
-        from src.external_ids 
-        where id_type = 11
-        group by id) c
-    where ad.id = c.id;"#
-}
-
-fn get_grid_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_grid = n
-    from (
-        select id, count(id) as n
-        from src.external_ids 
-        where id_type = 13
-        group by id) c
-    where ad.id = c.id;"#
-}
-
-fn get_fundref_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_fundref = n
-    from (
-        select id, count(id) as n
-        from src.external_ids 
-        where id_type = 14
-        group by id) c
-    where ad.id = c.id;"#
-}
-
-fn get_wikidata_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_wikidata = n
-    from (
-        select id, count(id) as n
-        from src.external_ids 
-        where id_type = 12
-        group by id) c
-    where ad.id = c.id;"#
-}
-
 fn get_ext_ids_data_sql <'a>() -> &'a str {
     r#"update src.admin_data
     set n_ext_ids = n_isni + n_grid + n_fundref + n_wikidata;"#
 }
 
-fn get_wikipedia_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_wikipedia = n
-    from (
-        select id, count(id) as n
-        from src.links 
-        where link_type = 21
-        group by id) c
-    where ad.id = c.id;"#
-}
-
-fn get_website_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_website = n
-    from (
-        select id, count(id) as n
-        from src.links 
-        where link_type = 22
-        group by id) c
-        where ad.id = c.id;"#
-}
-
 fn get_links_data_sql <'a>() -> &'a str {
     r#"update src.admin_data
     set n_links = n_wikipedia + n_website"#
@@ -233,61 +153,6 @@ fn get_locations_data_sql <'a>() -> &'a str {
     where ad.id = c.id;"#
 }
     
-fn get_parrels_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_parrels = n
-    from (
-        select id, count(id) as n
-        from src.relationships
-        where rel_type = 1
-        group by id) c
-    where ad.id = c.id;"#
-}
-
-fn get_chrels_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_chrels = n
-    from (
-        select id, count(id) as n
-        from src.relationships
-        where rel_type = 2
-        group by id) c
-    where ad.id = c.id;"#
-}
-
-fn get_relrels_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_relrels = n
-    from (
-        select id, count(id) as n
-        from src.relationships
-        where rel_type = 3
-        group by id) c
-    where ad.id = c.id;"#
-}
-
-fn get_predrels_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_predrels = n
-    from (
-        select id, count(id) as n
-        from src.relationships
-        where rel_type = 4
-        group by id) c
-    where ad.id = c.id;"#
-}
-
-fn get_succrels_data_sql <'a>() -> &'a str {
-    r#"update src.admin_data ad
-    set n_sucrels = n
-    from (
-        select id, count(id) as n
-        from src.relationships
-        where rel_type = 5
-        group by id) c
-    where ad.id = c.id;"#
-}
-
 fn get_domains_data_sql <'a>() -> &'a str {
     r#"update src.admin_data ad
     set n_doms = n
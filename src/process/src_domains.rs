@@ -0,0 +1,134 @@
+/***************************************************************************
+ * Normalises the domains in src.domains. Values are trimmed and lower-cased,
+ * and any scheme (e.g. 'https://'), path, query or port is removed, so that
+ * a URL entered in place of a domain is reduced to its host name. Domains
+ * that then occur more than once for the same organisation are reduced to a
+ * single row. Values that still do not look like a domain (dot separated
+ * labels of letters, digits and hyphens) are left as they are, and logged
+ * with the ids of the organisations using them. If requested (by --keep-raw)
+ * the original values are first copied to the domain_raw column.
+ ***************************************************************************/
+
+use log::warn;
+use sqlx::{Pool, Postgres};
+use crate::AppError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalisedDomain {
+    pub domain: String,
+    pub valid: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DomainCheck {
+    pub num_invalid: usize,
+    pub num_duplicates: u64,
+}
+
+
+pub async fn normalise_domains(keep_raw: bool, pool: &Pool<Postgres>) -> Result<DomainCheck, AppError> {
+
+    if keep_raw {
+        sqlx::query("update src.domains set domain_raw = domain").execute(pool).await?;
+    }
+
+    let sql = "select distinct domain from src.domains order by domain";
+    let raw_domains: Vec<String> = sqlx::query_scalar(sql).fetch_all(pool).await?;
+
+    let mut check = DomainCheck::default();
+    for raw in raw_domains.iter() {
+        let nd = normalise_domain(raw);
+        if nd.domain != *raw {
+            sqlx::query("update src.domains set domain = $1 where domain = $2")
+                .bind(&nd.domain).bind(raw).execute(pool).await?;
+        }
+        if !nd.valid {
+            check.num_invalid += 1;
+            let sql = "select distinct id from src.domains where domain = $1 order by id";
+            let ids: Vec<String> = sqlx::query_scalar(sql).bind(&nd.domain).fetch_all(pool).await?;
+            warn!("Domain '{}' does not look like a domain, in: {}", raw, ids.join(", "));
+        }
+    }
+
+    // Different raw values may have been normalised to the same domain.
+
+    let sql = r#"delete from src.domains a
+                 using src.domains b
+                 where a.id = b.id
+                 and a.domain = b.domain
+                 and a.ctid > b.ctid"#;
+    check.num_duplicates = sqlx::query(sql).execute(pool).await?.rows_affected();
+    Ok(check)
+}
+
+
+pub fn normalise_domain(raw: &str) -> NormalisedDomain {
+
+    // Values that do not look like a domain once reduced are only trimmed
+    // and lower-cased, so that the logged value can still be found.
+
+    let trimmed = raw.trim().to_lowercase();
+    let mut host = match trimmed.split_once("://") {
+        Some((_, rest)) => rest,
+        None => trimmed.as_str(),
+    };
+    if let Some(i) = host.find(['/', '?', '#']) {
+        host = &host[..i];
+    }
+    if let Some(i) = host.find(':') {
+        host = &host[..i];
+    }
+    let host = host.trim_end_matches('.');
+    if looks_like_domain(host) {
+        NormalisedDomain { domain: host.to_string(), valid: true }
+    }
+    else {
+        NormalisedDomain { domain: trimmed, valid: false }
+    }
+}
+
+
+fn looks_like_domain(host: &str) -> bool {
+    let labels: Vec<&str> = host.split('.').collect();
+    labels.len() > 1 && host.len() <= 253
+        && labels.iter().all(|label| !label.is_empty() && label.len() <= 63
+                             && !label.starts_with('-') && !label.ends_with('-')
+                             && label.chars().all(|c| c.is_alphanumeric() || c == '-'))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid(domain: &str) -> NormalisedDomain {
+        NormalisedDomain { domain: domain.to_string(), valid: true }
+    }
+
+    #[test]
+    fn check_clean_domain_unchanged() {
+        assert_eq!(normalise_domain("rmit.edu.au"), valid("rmit.edu.au"));
+        assert_eq!(normalise_domain(" www.rmit.edu.au "), valid("www.rmit.edu.au"));
+    }
+
+    #[test]
+    fn check_url_with_path_reduced_to_domain() {
+        assert_eq!(normalise_domain("https://www.rmit.edu.au/research/centres"), valid("www.rmit.edu.au"));
+        assert_eq!(normalise_domain("http://uni-example.de:8080/?lang=en"), valid("uni-example.de"));
+        assert_eq!(normalise_domain("example.org/"), valid("example.org"));
+    }
+
+    #[test]
+    fn check_uppercase_domain_lowercased() {
+        assert_eq!(normalise_domain("RMIT.Edu.AU"), valid("rmit.edu.au"));
+        assert_eq!(normalise_domain("HTTPS://Example.ORG."), valid("example.org"));
+    }
+
+    #[test]
+    fn check_non_domains_not_valid() {
+        for bad in ["localhost", "not a domain.org", "-bad.example.org", "https://", "a..b"] {
+            assert!(!normalise_domain(bad).valid, "{}", bad);
+        }
+        assert_eq!(normalise_domain(" Not A Domain "), NormalisedDomain { domain: "not a domain".to_string(), valid: false });
+    }
+}
@@ -0,0 +1,118 @@
+/***************************************************************************
+ * Normalises the language codes of the names in src.names. Codes are
+ * trimmed and lower-cased, and any that are not themselves ISO 639-1 codes
+ * (as listed in lup.lang_codes) are mapped to one if possible - either from
+ * the 3 letter MARC (ISO 639-2/B) code in the same table, or from the list
+ * of known aliases below, which are mostly deprecated ISO 639-1 codes.
+ * Codes that cannot be mapped are left (lower-cased) in place, and logged
+ * with the ids of the organisations using them. If requested (by --keep-raw)
+ * the original values are first copied to the lang_code_raw column.
+ ***************************************************************************/
+
+use log::warn;
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use crate::AppError;
+
+pub const LANG_CODE_ALIASES: [(&str, &str); 6] = [
+    ("sh", "sr"), ("iw", "he"), ("in", "id"), ("ji", "yi"), ("jw", "jv"), ("mo", "ro"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalisedCode {
+    pub code: String,
+    pub recognised: bool,
+}
+
+
+pub async fn normalise_lang_codes(keep_raw: bool, pool: &Pool<Postgres>) -> Result<usize, AppError> {
+
+    // Returns the number of distinct codes that could not be recognised.
+
+    if keep_raw {
+        sqlx::query("update src.names set lang_code_raw = lang_code").execute(pool).await?;
+    }
+
+    let sql = "select code, marc_code from lup.lang_codes";
+    let lup_codes: Vec<(String, Option<String>)> = sqlx::query_as(sql).fetch_all(pool).await?;
+    let lookup = build_lang_lookup(&lup_codes);
+
+    let sql = "select distinct lang_code from src.names where lang_code is not null order by lang_code";
+    let raw_codes: Vec<String> = sqlx::query_scalar(sql).fetch_all(pool).await?;
+
+    let mut unrecognised = 0;
+    for raw in raw_codes.iter() {
+        let nc = normalise_lang_code(raw, &lookup);
+        if nc.code != *raw {
+            sqlx::query("update src.names set lang_code = $1 where lang_code = $2")
+                .bind(&nc.code).bind(raw).execute(pool).await?;
+        }
+        if !nc.recognised {
+            unrecognised += 1;
+            let sql = "select distinct id from src.names where lang_code = $1 order by id";
+            let ids: Vec<String> = sqlx::query_scalar(sql).bind(&nc.code).fetch_all(pool).await?;
+            warn!("Unrecognised language code '{}' in names of: {}", raw, ids.join(", "));
+        }
+    }
+    Ok(unrecognised)
+}
+
+
+pub fn build_lang_lookup(lup_codes: &[(String, Option<String>)]) -> HashMap<String, String> {
+
+    // Maps each acceptable form of a code to the ISO 639-1 code.
+
+    let mut lookup = HashMap::new();
+    for (code, marc_code) in lup_codes.iter() {
+        if let Some(m) = marc_code {
+            lookup.insert(m.to_lowercase(), code.to_lowercase());
+        }
+        lookup.insert(code.to_lowercase(), code.to_lowercase());
+    }
+    for (alias, code) in LANG_CODE_ALIASES.iter() {
+        lookup.insert(alias.to_string(), code.to_string());
+    }
+    lookup
+}
+
+
+pub fn normalise_lang_code(raw: &str, lookup: &HashMap<String, String>) -> NormalisedCode {
+    let code = raw.trim().to_lowercase();
+    match lookup.get(&code) {
+        Some(c) => NormalisedCode { code: c.clone(), recognised: true },
+        None => NormalisedCode { code, recognised: false },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_lookup() -> HashMap<String, String> {
+        build_lang_lookup(&[("de".to_string(), Some("ger".to_string())), ("fr".to_string(), Some("fre".to_string())),
+                            ("sr".to_string(), Some("srp".to_string())), ("he".to_string(), None)])
+    }
+
+    #[test]
+    fn check_standard_and_uppercase_codes_normalised() {
+        let lookup = test_lookup();
+        assert_eq!(normalise_lang_code("fr", &lookup), NormalisedCode { code: "fr".to_string(), recognised: true });
+        assert_eq!(normalise_lang_code("DE", &lookup), NormalisedCode { code: "de".to_string(), recognised: true });
+        assert_eq!(normalise_lang_code(" Fr ", &lookup), NormalisedCode { code: "fr".to_string(), recognised: true });
+    }
+
+    #[test]
+    fn check_aliases_and_marc_codes_mapped() {
+        let lookup = test_lookup();
+        assert_eq!(normalise_lang_code("sh", &lookup).code, "sr");
+        assert_eq!(normalise_lang_code("IW", &lookup).code, "he");
+        assert_eq!(normalise_lang_code("ger", &lookup).code, "de");
+    }
+
+    #[test]
+    fn check_unknown_code_lowercased_but_not_recognised() {
+        let lookup = test_lookup();
+        assert_eq!(normalise_lang_code("XQ", &lookup), NormalisedCode { code: "xq".to_string(), recognised: false });
+    }
+}
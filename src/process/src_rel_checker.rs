@@ -0,0 +1,39 @@
+/***************************************************************************
+ * Checks the integrity of the src.relationships table. Relationships to an
+ * organisation not present in src.core_data are 'dangling'. Parent and
+ * child relationships should be reciprocated, by a child or parent
+ * relationship from the related organisation - if not they are listed as
+ * missing an inverse. Nothing is changed, the problems are only reported.
+ ***************************************************************************/
+
+use sqlx::{Pool, Postgres};
+use crate::AppError;
+use crate::setup::lookups::OrgRel;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RelationshipCheck {
+    pub dangling: Vec<(String, String)>,
+    pub missing_inverse: Vec<(String, String, String)>,
+}
+
+
+pub async fn check_relationships(pool: &Pool<Postgres>) -> Result<RelationshipCheck, AppError> {
+
+    let sql = r#"select r.id, r.related_id from src.relationships r
+                 where not exists (select 1 from src.core_data c where c.id = r.related_id)
+                 order by r.id, r.related_id"#;
+    let dangling: Vec<(String, String)> = sqlx::query_as(sql).fetch_all(pool).await?;
+
+    let parent = OrgRel::HasParent.code();
+    let child = OrgRel::HasChild.code();
+    let sql = format!(r#"select r.id, case when r.rel_type = {0} then 'parent' else 'child' end, r.related_id 
+                 from src.relationships r
+                 where r.rel_type in ({0}, {1})
+                 and exists (select 1 from src.core_data c where c.id = r.related_id)
+                 and not exists (select 1 from src.relationships i where i.id = r.related_id 
+                                 and i.related_id = r.id and i.rel_type = {0} + {1} - r.rel_type)
+                 order by r.id, r.related_id"#, parent, child);
+    let missing_inverse: Vec<(String, String, String)> = sqlx::query_as(&sql).fetch_all(pool).await?;
+
+    Ok(RelationshipCheck { dangling, missing_inverse })
+}
@@ -45,7 +45,6 @@ pub async fn remove_dups (pool: &Pool<Postgres>) -> Result<(), AppError> {
 
     execute_sql(get_delete_names_with_superfluous_name_type_sql(), pool).await?;
     execute_sql(get_delete_names_with_superfluous_lang_code_sql(), pool).await?;
-    execute_sql(replace_deprecated_lang_code_sql(), pool).await?;
 
     info!("Duplicates transferred from src.names to src.dup_names_deleted table");
     Ok(())
@@ -207,8 +206,3 @@ fn get_delete_names_with_superfluous_lang_code_sql <'a>() -> &'a str {
     and n.lang_code = dnd.lang_code;"#
 }
 
-fn replace_deprecated_lang_code_sql <'a>() -> &'a str {
-    r#"update src.names 
-    set lang_code = 'sr'
-    where lang_code = 'sh';"#
-}
\ No newline at end of file
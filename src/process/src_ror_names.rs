@@ -0,0 +1,56 @@
+/***************************************************************************
+ * Ensures that exactly one name of each organisation in src.names is marked
+ * as the ror name - the name given the 'ror_display' type in the source 
+ * data. An organisation with more than one such name keeps only the one 
+ * used as the ror_name in src.core_data (the first listed in the source), 
+ * the others being marked false. An organisation with no such name cannot be 
+ * given a ror_name, and so is not transferred to the src tables at all. 
+ * Both cases are returned, so that they can be logged.
+ ***************************************************************************/
+
+use sqlx::{Pool, Postgres};
+use crate::AppError;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RorNameCheck {
+    pub without_ror_name: Vec<String>,
+    pub multiple_ror_names: Vec<(String, String)>,
+    pub not_single_after_update: Vec<String>,
+}
+
+
+pub async fn set_ror_names(pool: &Pool<Postgres>) -> Result<RorNameCheck, AppError> {
+
+    let sql = r#"select c.id from ror.core_data c
+                 where not exists (select 1 from ror.names n where n.id = c.id and n.is_ror_name = true)
+                 order by c.id"#;
+    let without_ror_name: Vec<String> = sqlx::query_scalar(sql).fetch_all(pool).await?;
+
+    // The ror name kept, for each organisation with several, is listed with its id.
+
+    let sql = r#"select n.id, c.ror_name from src.names n
+                 inner join src.core_data c on n.id = c.id
+                 where n.is_ror_name = true
+                 group by n.id, c.ror_name 
+                 having count(distinct n.value) > 1
+                 order by n.id"#;
+    let multiple_ror_names: Vec<(String, String)> = sqlx::query_as(sql).fetch_all(pool).await?;
+
+    let sql = r#"update src.names n
+                 set is_ror_name = (n.is_ror_name and n.value = c.ror_name)
+                 from src.core_data c
+                 where n.id = c.id"#;
+    sqlx::query(sql).execute(pool).await?;
+
+    // The same name may remain in more than one row (with different name 
+    // types) but every organisation should now have a single ror name.
+
+    let sql = r#"select c.id from src.core_data c
+                 left join src.names n on n.id = c.id and n.is_ror_name = true
+                 group by c.id
+                 having count(distinct n.value) <> 1
+                 order by c.id"#;
+    let not_single_after_update: Vec<String> = sqlx::query_scalar(sql).fetch_all(pool).await?;
+
+    Ok(RorNameCheck { without_ror_name, multiple_ror_names, not_single_after_update })
+}
@@ -7,38 +7,174 @@
  ***************************************************************************/
 
 use clap::{command, Arg, ArgMatches};
-use crate::error_defs::AppError;
-use crate::setup::{CliPars, Flags};
+use crate::error_defs::{AppError, CustomError};
+use log::LevelFilter;
+use crate::setup::{CliPars, Flags, normalise_path};
 use std::ffi::OsString;
 use std::path::PathBuf;
 
+// The output formats that may be listed in the --format option. Each
+// corresponds to one of the export flags, t, x, j and y respectively.
+
+pub const OUTPUT_FORMATS: [&str; 4] = ["txt", "csv", "json", "allcsv"];
+
+// The phases of the pipeline that may be listed in the --phases option, in 
+// the order in which they are carried out. Create corresponds to -i, and 
+// import to -r. Transform and summary are the two steps run by -p - the 
+// transfer of the data to the src tables, and its summary in the smm tables. 
+// Export writes the summaries out, as -t and -x do.
+
+pub const PIPELINE_PHASES: [&str; 5] = ["create", "import", "transform", "summary", "export"];
+
+// The action flags that may be given as defaults (as 'default_flags') in
+// the environment, and the arguments that, if given in the CLI, replace 
+// those defaults - all the arguments that select what the program does.
+
+pub const DEFAULT_FLAG_LETTERS: &str = "arptxyj";
+
+const ACTION_ARGS: [&str; 23] = ["a_flag", "r_flag", "p_flag", "t_flag", "x_flag", "y_flag", "j_flag", 
+                    "country_csv_flag", "output_format", "i_flag", "c_flag", "m_flag", "dedup_flag", 
+                    "validate_flag", "count_flag", "check_flag", "new_ids_flag", "history", "drop_all_flag",
+                    "compare_versions", "report_flag", "phases", "sqlite_path"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct OutputFormats {
+    text: bool,
+    csv: bool,
+    json: bool,
+    all_csv: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct PipelinePhases {
+    create: bool,
+    import: bool,
+    transform: bool,
+    summary: bool,
+    export: bool,
+}
+
+impl PipelinePhases {
+    fn listed(&self) -> Vec<&'static str> {
+        let enabled = [self.create, self.import, self.transform, self.summary, self.export];
+        PIPELINE_PHASES.iter().zip(enabled).filter(|(_, on)| *on).map(|(name, _)| *name).collect()
+    }
+}
+
 
 pub fn fetch_valid_arguments(args: Vec<OsString>) -> Result<CliPars, AppError>
 { 
     let parse_result = parse_args(args)?;
 
     // These parameters guaranteed to unwrap OK as all have a default value of "".
+    // The data folder may also be given as a (trailing) positional argument, 
+    // but if the -f option is also used that takes precedence.
 
-    let data_folder_as_string = parse_result.get_one::<String>("data_folder").unwrap();
-    let data_folder = PathBuf::from(data_folder_as_string.replace("\\", "/"));
+    let mut data_folder_as_string = parse_result.get_one::<String>("data_folder").unwrap();
+    if data_folder_as_string.is_empty() {
+        data_folder_as_string = parse_result.get_one::<String>("data_folder_pos").unwrap();
+    }
+    let data_folder = normalise_path(data_folder_as_string);
+    let config_file_as_string = parse_result.get_one::<String>("config_file").unwrap();
+    let config_file = normalise_path(config_file_as_string);
+    let env_file_as_string = parse_result.get_one::<String>("env_file").unwrap();
+    let env_file = normalise_path(env_file_as_string);
 
     let source_file = parse_result.get_one::<String>("src_file").unwrap();
     let data_version = parse_result.get_one::<String>("data_version").unwrap();
     let data_date = parse_result.get_one::<String>("data_date").unwrap();
+    let since_date = parse_result.get_one::<String>("since_date").unwrap();
+    let tables = parse_result.get_one::<String>("tables").unwrap();
+    let types = parse_result.get_one::<String>("types").unwrap();
+    let trace_id = parse_result.get_one::<String>("trace_id").unwrap();
+    let csv_delimiter = parse_result.get_one::<String>("csv_delimiter").unwrap();
+    let sqlite_path = parse_result.get_one::<String>("sqlite_path").unwrap();
 
     // Flag values are false if not present, true if present.
 
+    // Any phases listed with --phases are added to the individual action 
+    // flags, which remain available as shortcuts. The -p flag covers both
+    // the transform and summary phases, which may also be run separately.
+
+    let phases = get_pipeline_phases(parse_result.get_one::<String>("phases").unwrap())?;
+
     let a_flag = parse_result.get_flag("a_flag");
-    let i_flag = parse_result.get_flag("i_flag");
+    let i_flag = parse_result.get_flag("i_flag") || phases.create;
+
+    let mut r_flag = parse_result.get_flag("r_flag") || phases.import;
+    let mut p_flag = parse_result.get_flag("p_flag") || phases.transform;
+    let mut summary_flag = parse_result.get_flag("p_flag") || phases.summary;
+    let mut t_flag = parse_result.get_flag("t_flag") || phases.export;
+    let mut x_flag = parse_result.get_flag("x_flag") || phases.export;
+    let mut y_flag = parse_result.get_flag("y_flag");
+    let mut j_flag = parse_result.get_flag("j_flag");
+    let mut country_csv_flag = parse_result.get_flag("country_csv_flag");
 
-    let mut r_flag = parse_result.get_flag("r_flag");
-    let mut p_flag = parse_result.get_flag("p_flag");
-    let mut t_flag = parse_result.get_flag("t_flag");
-    let x_flag = parse_result.get_flag("x_flag");
-    let y_flag = parse_result.get_flag("y_flag");
+    // Any formats listed with --format are added to the individual export flags.
+
+    let formats = get_output_formats(parse_result.get_one::<String>("output_format").unwrap())?;
+    t_flag = t_flag || formats.text;
+    x_flag = x_flag || formats.csv;
+    j_flag = j_flag || formats.json;
+    y_flag = y_flag || formats.all_csv;
+    let mut dedup_flag = parse_result.get_flag("dedup_flag");
     let mut c_flag = parse_result.get_flag("c_flag");
     let mut m_flag = parse_result.get_flag("m_flag");
     let z_flag = parse_result.get_flag("z_flag");
+    let strict_flag = parse_result.get_flag("strict_flag");
+    let strict_utf8_flag = parse_result.get_flag("strict_utf8_flag");
+    let validate_flag = parse_result.get_flag("validate_flag");
+    let count_flag = parse_result.get_flag("count_flag");
+    let check_flag = parse_result.get_flag("check_flag");
+    let new_ids_flag = parse_result.get_flag("new_ids_flag");
+    let report_flag = parse_result.get_flag("report_flag");
+    let overwrite_flag = parse_result.get_flag("overwrite_flag");
+    let append_flag = parse_result.get_flag("append_flag");
+    let tail_log_flag = parse_result.get_flag("tail_log_flag");
+    let drop_all_flag = parse_result.get_flag("drop_all_flag");
+    let yes_flag = parse_result.get_flag("yes_flag");
+    let no_recreate_flag = parse_result.get_flag("no_recreate_flag");
+    let recreate_src_flag = parse_result.get_flag("recreate_src_flag");
+    let replace_flag = parse_result.get_flag("replace_flag");
+    let resume_flag = parse_result.get_flag("resume_flag");
+    let checkpoint_flag = parse_result.get_flag("checkpoint_flag");
+    let atomic_flag = parse_result.get_flag("atomic_flag");
+    let dump_failed_flag = parse_result.get_flag("dump_failed_flag");
+    let exclude_withdrawn_flag = parse_result.get_flag("exclude_withdrawn_flag");
+    let active_only_flag = parse_result.get_flag("active_only_flag");
+    let analyze_flag = parse_result.get_flag("analyze_flag");
+    let no_analyze_flag = parse_result.get_flag("no_analyze_flag");
+    let quiet_db_flag = parse_result.get_flag("quiet_db_flag");
+    let keep_raw_flag = parse_result.get_flag("keep_raw_flag");
+    let dump_lookups_flag = parse_result.get_flag("dump_lookups_flag");
+    let stdin_flag = parse_result.get_flag("stdin_flag");
+    let plan_flag = parse_result.get_flag("plan_flag");
+    let print_config_flag = parse_result.get_flag("print_config_flag");
+    let profile_flag = parse_result.get_flag("profile_flag");
+    let csv_bom_flag = parse_result.get_flag("csv_bom_flag");
+
+    // The history option may be given with or without a number of runs.
+
+    let history_count = parse_result.get_one::<i64>("history");
+    let history_flag = history_count.is_some();
+    let history_count = *history_count.unwrap_or(&0);
+
+    // Two loads of the ror tables may be compared, in which case nothing else is done.
+
+    let compare_versions = parse_result.get_one::<String>("compare_versions");
+    let compare_flag = compare_versions.is_some();
+    let compare_versions = compare_versions.cloned().unwrap_or_default();
+
+    // A memory limit, if given, decides whether a source file is parsed as a whole or streamed.
+
+    let max_memory_mb = parse_result.get_one::<u64>("max_memory").copied();
+
+    // Quiet and verbose flags (which cannot be used together) 
+    // determine the threshold level of the log.
+
+    let q_flag = parse_result.get_flag("q_flag");
+    let v_count = parse_result.get_count("v_flag");
+    let log_level = get_log_level(q_flag, v_count);
 
     // If c, m, or both flags set (may be by using 'i' (initialise) flag)
     // Only do the c and / or m actions
@@ -52,28 +188,100 @@ pub fn fetch_valid_arguments(args: Vec<OsString>) -> Result<CliPars, AppError>
         let flags = Flags {
             import_ror: false,
             process_data: false,
+            summarise_data: false,
             export_text: false,
             export_csv: false,
+            export_json: false,
+            export_country_csv: false,
             export_full_csv: false,
             create_lookups: c_flag,
             create_summary: m_flag,
             test_run: false,
+            strict: false,
+            strict_utf8: false,
+            validate: false,
+            count_only: false,
+            check_db: false,
+            no_recreate: false,
+            recreate_src: false,
+            replace: false,
+            resume: false,
+            checkpoint: false,
+            atomic: false,
+            dump_failed: false,
+            exclude_withdrawn: false,
+            active_only: false,
+            analyze: false,
+            quiet_db: quiet_db_flag,
+            keep_raw: false,
+            dump_lookups: false,
+            stdin: false,
+            plan: plan_flag,
+            print_config: print_config_flag,
+            profile: false,
+            show_history: false,
+            only_new_ids: false,
+            compare_versions: false,
+            report: false,
+            overwrite: false,
+            append_csv: false,
+            tail_log: tail_log_flag,
+            drop_all: false,
+            assume_yes: false,
+            dedup: false,
+            csv_bom: false,
         };
 
         Ok(CliPars {
             data_folder: PathBuf::new(),
+            env_file,
+            config_file,
             source_file: "".to_string(),
             data_version: "".to_string(),
             data_date: "".to_string(),
+            since_date: "".to_string(),
+            history_count: 0,
+            max_memory_mb: None,
+            tables: "".to_string(),
+            types: "".to_string(),
+            trace_id: "".to_string(),
+            csv_delimiter: "".to_string(),
+            sqlite_path: "".to_string(),
+            compare_versions: "".to_string(),
             flags: flags,
+            log_level,
         })
     }
     
     else {
-        if a_flag  // 'a' (do all) flag set
+        
+        // Only with --compare-versions does -x (writing the changed ids as csv) remain.
+
+        let compare_flag = compare_flag && !validate_flag && !count_flag && !check_flag && !history_flag && !new_ids_flag;
+        let compare_csv = compare_flag && x_flag;
+        let report_flag = report_flag && !validate_flag && !count_flag && !check_flag && !history_flag 
+                            && !new_ids_flag && !compare_flag;
+
+        if validate_flag || count_flag || history_flag || check_flag || new_ids_flag  // validate and / or count only, or show  
+            || drop_all_flag || compare_flag || report_flag                           // history, check the database, compare ids 
+        {                                                                             // or versions, report the summaries, or drop 
+                                                                                      // the schemas only - all other database 
+                                                                                      // actions suppressed
+            r_flag = false;
+            p_flag = false;
+            summary_flag = false;
+            t_flag = false;
+            x_flag = false;
+            y_flag = false;
+            j_flag = false;
+            country_csv_flag = false;
+            dedup_flag = false;
+        }
+        else if a_flag  // 'a' (do all) flag set
         {
             r_flag = true;  
             p_flag = true;
+            summary_flag = true;
             t_flag = true;
         }
         else 
@@ -81,8 +289,8 @@ pub fn fetch_valid_arguments(args: Vec<OsString>) -> Result<CliPars, AppError>
             // if none of r, p, t, x or y flags set
             // set r to be true, as the default with no flags
 
-            if r_flag == false && p_flag == false && t_flag == false
-                && x_flag == false && y_flag == false {
+            if r_flag == false && p_flag == false && t_flag == false && !summary_flag
+                && x_flag == false && y_flag == false && !j_flag && !country_csv_flag && !dedup_flag {
                 r_flag = true;  
             }
         }
@@ -90,29 +298,181 @@ pub fn fetch_valid_arguments(args: Vec<OsString>) -> Result<CliPars, AppError>
         let flags = Flags {
             import_ror: r_flag,
             process_data: p_flag,
+            summarise_data: summary_flag,
             export_text: t_flag,
-            export_csv: x_flag,
+            export_csv: x_flag || compare_csv,
+            export_json: j_flag,
+            export_country_csv: country_csv_flag,
             export_full_csv: y_flag,
             create_lookups: false,
             create_summary: false,
             test_run: z_flag,
+            strict: strict_flag,
+            strict_utf8: strict_utf8_flag && r_flag,
+            validate: validate_flag,
+            count_only: count_flag,
+            check_db: check_flag && !validate_flag && !count_flag,
+            no_recreate: no_recreate_flag,
+            recreate_src: recreate_src_flag,
+            replace: replace_flag,
+            resume: resume_flag,
+            checkpoint: checkpoint_flag || resume_flag,
+            atomic: (atomic_flag || stdin_flag) && r_flag && sqlite_path.is_empty(),
+            dump_failed: dump_failed_flag && r_flag,
+            exclude_withdrawn: exclude_withdrawn_flag,
+            active_only: active_only_flag,
+            analyze: (r_flag && !no_analyze_flag) || (analyze_flag && p_flag),
+            quiet_db: quiet_db_flag,
+            keep_raw: keep_raw_flag,
+            dump_lookups: dump_lookups_flag && p_flag,
+            stdin: stdin_flag && r_flag,
+            plan: plan_flag,
+            print_config: print_config_flag,
+            profile: profile_flag,
+            show_history: history_flag && !validate_flag && !count_flag && !check_flag,
+            only_new_ids: new_ids_flag && !validate_flag && !count_flag && !check_flag && !history_flag,
+            compare_versions: compare_flag,
+            report: report_flag,
+            overwrite: overwrite_flag,
+            append_csv: append_flag && x_flag,
+            tail_log: tail_log_flag,
+            drop_all: drop_all_flag,
+            assume_yes: yes_flag,
+            dedup: dedup_flag,
+            csv_bom: csv_bom_flag,
         };
 
         Ok(CliPars {
             data_folder: data_folder.clone(),
+            env_file,
+            config_file,
             source_file: source_file.clone(),
             data_version: data_version.clone(),
             data_date: data_date.clone(),
+            since_date: since_date.clone(),
+            history_count,
+            max_memory_mb,
+            tables: tables.clone(),
+            types: types.clone(),
+            trace_id: trace_id.clone(),
+            csv_delimiter: csv_delimiter.clone(),
+            sqlite_path: sqlite_path.clone(),
+            compare_versions,
             flags: flags,
+            log_level,
         })
     }
 }
 
 
+pub fn add_default_flags(args: Vec<OsString>, default_flags: &str) -> Result<Vec<OsString>, AppError> {
+
+    // The default flags (e.g. "rpx") are added to the arguments, as if typed,
+    // but only if none of the action arguments have been given in the CLI.
+
+    let default_flags = default_flags.trim().trim_start_matches('-');
+    if default_flags.is_empty() {
+        return Ok(args);
+    }
+    if let Some(c) = default_flags.chars().find(|c| !DEFAULT_FLAG_LETTERS.contains(*c)) {
+        let msg = format!("Invalid flag '{}' in default_flags ({}) - the flags available are {}", 
+                           c, default_flags, DEFAULT_FLAG_LETTERS);
+        return Err(AppError::CsErr(CustomError::new(&msg)));
+    }
+
+    let parse_result = parse_args(args.clone())?;
+    let action_given = ACTION_ARGS.iter()
+            .any(|id| parse_result.value_source(id) == Some(clap::parser::ValueSource::CommandLine));
+    if action_given {
+        return Ok(args);
+    }
+    let mut args = args;
+    let position = if args.is_empty() { 0 } else { 1 };
+    args.insert(position, format!("-{}", default_flags).into());
+    Ok(args)
+}
+
+
+fn get_pipeline_phases(phases_spec: &str) -> Result<PipelinePhases, AppError> {
+
+    // The phases are carried out in their usual order, whatever the order of
+    // the list. Creating the lookup and summary tables is always done in a 
+    // run of its own (as with -i), so cannot be combined with other phases.
+
+    let mut phases = PipelinePhases::default();
+    for phase in phases_spec.split(',').map(|p| p.trim().to_lowercase()).filter(|p| !p.is_empty()) {
+        match phase.as_str() {
+            "create" => phases.create = true,
+            "import" => phases.import = true,
+            "transform" => phases.transform = true,
+            "summary" => phases.summary = true,
+            "export" => phases.export = true,
+            _ => {
+                let msg = format!("Unknown phase '{}' in --phases - the phases available are {}", 
+                                   phase, PIPELINE_PHASES.join(", "));
+                return Err(AppError::CsErr(CustomError::new(&msg)));
+            },
+        }
+    }
+    if phases.create && phases.listed().len() > 1 {
+        let msg = format!("The create phase must be run on its own, but --phases lists {}", phases.listed().join(", "));
+        return Err(AppError::CsErr(CustomError::new(&msg)));
+    }
+    Ok(phases)
+}
+
+
+fn get_output_formats(formats_spec: &str) -> Result<OutputFormats, AppError> {
+    let mut formats = OutputFormats::default();
+    for format in formats_spec.split(',').map(|f| f.trim().to_lowercase()).filter(|f| !f.is_empty()) {
+        match format.as_str() {
+            "txt" => formats.text = true,
+            "csv" => formats.csv = true,
+            "json" => formats.json = true,
+            "allcsv" => formats.all_csv = true,
+            _ => {
+                let msg = format!("Unknown output format '{}' in --format - the formats available are {}", 
+                                   format, OUTPUT_FORMATS.join(", "));
+                return Err(AppError::CsErr(CustomError::new(&msg)));
+            },
+        }
+    }
+    Ok(formats)
+}
+
+
+fn get_log_level(quiet: bool, verbose_count: u8) -> LevelFilter {
+
+    // Quiet raises the threshold to warnings only. Verbose lowers it to 
+    // debug, or (if repeated, e.g. -VV) to trace. The default is info.
+
+    if quiet {
+        LevelFilter::Warn
+    }
+    else {
+        match verbose_count {
+            0 => LevelFilter::Info,
+            1 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+}
+
+
 fn parse_args(args: Vec<OsString>) -> Result<ArgMatches, clap::Error> {
 
+    // The automatic version flag (-V) is disabled, as -V is used for 
+    // verbose, and replaced by an explicit --version argument.
+
     command!()
         .about("Imports data from ROR json file (v2) and imports it into a database")
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("version")
+           .long("version")
+           .help("Print version")
+           .action(clap::ArgAction::Version)
+        )
         .arg(
             Arg::new("data_folder")
            .short('f')
@@ -120,6 +480,14 @@ fn parse_args(args: Vec<OsString>) -> Result<ArgMatches, clap::Error> {
            .visible_aliases(["data folder"])
            .help("A string with the data folder path (over-rides environment setting")
            .default_value("")
+       )
+        .arg(
+            Arg::new("data_folder_pos")
+           .value_name("DATA_FOLDER")
+           .index(1)
+           .required(false)
+           .help("The data folder path, as an alternative to -f (which takes precedence if both are given)")
+           .default_value("")
        )
         .arg(
              Arg::new("src_file")
@@ -129,6 +497,20 @@ fn parse_args(args: Vec<OsString>) -> Result<ArgMatches, clap::Error> {
             .help("A string with the source file name (over-rides environment setting")
             .default_value("")
         )
+        .arg(
+             Arg::new("env_file")
+            .long("env-file")
+            .value_name("PATH")
+            .help("The path of the .env file to use, if not the one in the current folder (over-rides ROR1_ENV_FILE)")
+            .default_value("")
+        )
+        .arg(
+             Arg::new("config_file")
+            .long("config")
+            .value_name("PATH")
+            .help("A TOML file with values for the environment settings (over-rides the .env file, but not other CLI arguments)")
+            .default_value("")
+        )
         .arg(
             Arg::new("data_version")
            .short('v')
@@ -145,6 +527,73 @@ fn parse_args(args: Vec<OsString>) -> Result<ArgMatches, clap::Error> {
            .help("A string with a date in ISO format that gives the date of the data")
            .default_value("")
         )
+        .arg(
+            Arg::new("since_date")
+           .long("since")
+           .required(false)
+           .help("A string with a date in ISO format - only records last modified on or after this date are imported")
+           .default_value("")
+        )
+        .arg(
+            Arg::new("tables")
+           .long("tables")
+           .required(false)
+           .help("A comma separated list of the ror tables and / or csv files to be included in record counts and csv exports")
+           .default_value("")
+        )
+        .arg(
+            Arg::new("types")
+           .long("types")
+           .required(false)
+           .help("A comma separated list of organisation types - only records with at least one of these types are imported")
+           .default_value("")
+        )
+        .arg(
+            Arg::new("trace_id")
+           .long("trace-id")
+           .required(false)
+           .help("A ROR id - the rows generated for that record during import are logged, at debug level (with -V)")
+           .default_value("")
+        )
+        .arg(
+            Arg::new("sqlite_path")
+           .long("sqlite")
+           .required(false)
+           .help("The path of a SQLite database (or ':memory:') into which the source data should be imported, instead of Postgres - an import-only store, as processing, summaries and exports need Postgres")
+           .default_value("")
+           .conflicts_with_all(["a_flag", "p_flag", "t_flag", "x_flag", "y_flag", "j_flag", "country_csv_flag", 
+                                "output_format", "phases", "i_flag", "c_flag", "m_flag", "dedup_flag", "check_flag", 
+                                "new_ids_flag", "history", "drop_all_flag", "compare_versions", "report_flag"])
+        )
+        .arg(
+            Arg::new("csv_delimiter")
+           .long("csv-delimiter")
+           .required(false)
+           .help("The field delimiter of the csv files exported, e.g. ';' or 'tab' (by default a comma)")
+           .default_value("")
+        )
+        .arg(
+            Arg::new("csv_bom_flag")
+           .long("csv-bom")
+           .required(false)
+           .help("A flag signifying that the csv files exported should start with a UTF-8 byte order mark")
+           .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("output_format")
+           .long("format")
+           .visible_alias("output-format")
+           .required(false)
+           .help("A comma separated list of the output formats required (txt, csv, json, allcsv), equivalent to -t, -x, -j and -y")
+           .default_value("")
+        )
+        .arg(
+            Arg::new("phases")
+           .long("phases")
+           .required(false)
+           .help("A comma separated list of the phases to run (create, import, transform, summary, export) - create is -i, import is -r, transform and summary together are -p, and export is -t and -x")
+           .default_value("")
+        )
         .arg(
             Arg::new("a_flag")
            .short('a')
@@ -193,6 +642,21 @@ fn parse_args(args: Vec<OsString>) -> Result<ArgMatches, clap::Error> {
            .help("A flag signifying output a summary of the data for all versions into csv files")
            .action(clap::ArgAction::SetTrue)
        )
+       .arg(
+            Arg::new("j_flag")
+           .short('j')
+           .long("jsonout")
+           .required(false)
+           .help("A flag signifying output the processed records of the current version into a ror style json file")
+           .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("country_csv_flag")
+           .long("country-csv")
+           .required(false)
+           .help("A flag signifying output the numbers of organisations in each country, for the version in the src tables, as a csv file")
+           .action(clap::ArgAction::SetTrue)
+       )
        .arg(
             Arg::new("i_flag")
            .short('i')
@@ -225,151 +689,1342 @@ fn parse_args(args: Vec<OsString>) -> Result<ArgMatches, clap::Error> {
             .help("A flag signifying that this is part of an integration test run - suppresses logs")
             .action(clap::ArgAction::SetTrue)
        )
-    .try_get_matches_from(args)
-
-}
-
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    
-    // Ensure the parameters are being correctly extracted from the CLI arguments
-
-    #[test]
-    fn check_cli_no_explicit_params() {
-        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
-        let args : Vec<&str> = vec![target];
-        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
-        let res = fetch_valid_arguments(test_args).unwrap();
-        assert_eq!(res.data_folder, PathBuf::new());
-        assert_eq!(res.source_file, "");
-        assert_eq!(res.flags.import_ror, true);
-        assert_eq!(res.flags.process_data, false);
-        assert_eq!(res.flags.export_text, false);
-        assert_eq!(res.flags.create_lookups, false);
-        assert_eq!(res.flags.create_summary, false);
-        assert_eq!(res.flags.test_run, false);
-        assert_eq!(res.data_date, "");
-        assert_eq!(res.data_version, "");
-    }
-  
-    #[test]
-    fn check_cli_with_a_flag() {
-        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
-        let args : Vec<&str> = vec![target, "-a"];
-        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
-
-        let res = fetch_valid_arguments(test_args).unwrap();
-        assert_eq!(res.data_folder, PathBuf::new());
-        assert_eq!(res.source_file, "");
-        assert_eq!(res.flags.import_ror, true);
-        assert_eq!(res.flags.process_data, true);
-        assert_eq!(res.flags.export_text, true);
-        assert_eq!(res.flags.create_lookups, false);
-        assert_eq!(res.flags.create_summary, false);
-        assert_eq!(res.flags.test_run, false);
-        assert_eq!(res.data_date, "");
-        assert_eq!(res.data_version, "");
-    }
-
-    #[test]
-    fn check_cli_with_i_flag() {
-        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
-        let args : Vec<&str> = vec![target, "-i"];
-        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
-
-        let res = fetch_valid_arguments(test_args).unwrap();
-        assert_eq!(res.data_folder, PathBuf::new());
-        assert_eq!(res.source_file, "");
-        assert_eq!(res.flags.import_ror, false);
-        assert_eq!(res.flags.process_data, false);
-        assert_eq!(res.flags.export_text, false);
-        assert_eq!(res.flags.create_lookups, true);
-        assert_eq!(res.flags.create_summary, true);
-        assert_eq!(res.flags.test_run, false);
-        assert_eq!(res.data_date, "");
-        assert_eq!(res.data_version, "");
-    }
-
-    #[test]
-    fn check_cli_with_c_and_m_flags() {
-        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
-        let args : Vec<&str> = vec![target, "-c", "-m"];
-        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
-
-        let res = fetch_valid_arguments(test_args).unwrap();
-        assert_eq!(res.data_folder, PathBuf::new());
-        assert_eq!(res.source_file, "");
-        assert_eq!(res.flags.import_ror, false);
-        assert_eq!(res.flags.process_data, false);
-        assert_eq!(res.flags.export_text, false);
-        assert_eq!(res.flags.create_lookups, true);
-        assert_eq!(res.flags.create_summary, true);
-        assert_eq!(res.flags.test_run, false);
-        assert_eq!(res.data_date, "");
-        assert_eq!(res.data_version, "");
-    }
-
-
-    #[test]
-    fn check_cli_with_c_and_p_flag() {
-        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
-        let args : Vec<&str> = vec![target, "-c", "-p"];
-        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
-
-        let res = fetch_valid_arguments(test_args).unwrap();
-        assert_eq!(res.data_folder, PathBuf::new());
-        assert_eq!(res.source_file, "");
-        assert_eq!(res.flags.import_ror, false);
-        assert_eq!(res.flags.process_data, false);
-        assert_eq!(res.flags.export_text, false);
-        assert_eq!(res.flags.create_lookups, true);
-        assert_eq!(res.flags.create_summary, false);
-        assert_eq!(res.flags.test_run, false);
-        assert_eq!(res.data_date, "");
-        assert_eq!(res.data_version, "");
-    }
-
-    #[test]
-    fn check_cli_with_explicit_string_pars() {
-        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
-        let args : Vec<&str> = vec![target, "-f", "E:\\ROR\\some data folder", 
-                                    "-s", "schema2 data.json", "-d", "2025-12-25", "-v", "1.62"];
-        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
-
-        let res = fetch_valid_arguments(test_args).unwrap();
-        assert_eq!(res.data_folder, PathBuf::from("E:/ROR/some data folder"));
-        assert_eq!(res.source_file, "schema2 data.json");
-        assert_eq!(res.flags.import_ror, true);
-        assert_eq!(res.flags.process_data, false);
-        assert_eq!(res.flags.export_text, false);
-        assert_eq!(res.flags.create_lookups, false);
-        assert_eq!(res.flags.create_summary, false);
-        assert_eq!(res.flags.test_run, false);
-        assert_eq!(res.data_date, "2025-12-25");
-        assert_eq!(res.data_version, "1.62");
-    }
-
-    #[test]
-    fn check_cli_with_most_params_explicit() {
-        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
-        let args : Vec<&str> = vec![target, "-f", "E:\\ROR\\some other data folder", 
-        "-s", "schema2.1 data.json", "-d", "2026-12-25", "-v", "1.63", "-r", "-p", "-t", "-z"];
-        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
-
-        let res = fetch_valid_arguments(test_args).unwrap();
-        assert_eq!(res.data_folder, PathBuf::from("E:/ROR/some other data folder"));
-        assert_eq!(res.source_file, "schema2.1 data.json");
-        assert_eq!(res.flags.import_ror, true);
+       .arg(
+            Arg::new("validate_flag")
+            .long("validate")
+            .required(false)
+            .help("A flag signifying that the source file(s) should be parsed and checked, without any database access")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("count_flag")
+            .long("count")
+            .required(false)
+            .help("A flag signifying that the records in the source file(s) should be counted, without any database access")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("check_flag")
+            .long("check")
+            .required(false)
+            .help("A flag signifying that the database connection, schemas and key tables should be checked, and nothing else done")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("new_ids_flag")
+            .long("only-new-ids")
+            .required(false)
+            .help("A flag signifying that the ids in the source file(s) should be compared with those in the ror tables, and nothing imported")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("report_flag")
+            .long("report")
+            .required(false)
+            .help("A flag signifying that the summaries of the data already in the database should be logged, without any files being written")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("overwrite_flag")
+            .long("overwrite")
+            .required(false)
+            .help("A flag signifying that the text summary should have a fixed name, without a time stamp, overwriting any existing file")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("append_flag")
+            .long("append")
+            .required(false)
+            .help("A flag signifying that the csv summary (-x) should be appended to a single file per table, rather than written to new files")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("drop_all_flag")
+            .long("drop-all")
+            .required(false)
+            .help("A flag signifying that the ror, src and lup schemas should be dropped, with all their tables, after confirmation")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with_all(["a_flag", "r_flag", "p_flag", "i_flag", "c_flag", "m_flag", "dedup_flag", 
+                                 "t_flag", "x_flag", "y_flag", "j_flag", "country_csv_flag", "output_format", 
+                                 "validate_flag", "count_flag", "check_flag", "new_ids_flag", "history",
+                                 "compare_versions", "report_flag", "phases"])
+       )
+       .arg(
+            Arg::new("yes_flag")
+            .long("yes")
+            .required(false)
+            .help("A flag signifying that the confirmation prompt of --drop-all should be skipped")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("tail_log_flag")
+            .long("tail-log")
+            .required(false)
+            .help("A flag signifying that the full path of the log file should be printed at startup, even if --quiet is used")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("no_recreate_flag")
+            .long("no-recreate")
+            .required(false)
+            .help("A flag signifying that existing ror and src tables should be kept, rather than dropped and recreated")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("recreate_src_flag")
+            .long("recreate-src")
+            .required(false)
+            .help("A flag signifying that the src tables should be dropped and recreated, even if they already have the expected columns")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with("no_recreate_flag")
+       )
+       .arg(
+            Arg::new("replace_flag")
+            .long("replace")
+            .required(false)
+            .help("A flag signifying that only the last record with each ROR id should be imported, if an id occurs more than once in a source file")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with("stdin_flag")
+       )
+       .arg(
+            Arg::new("resume_flag")
+            .long("resume")
+            .required(false)
+            .help("A flag signifying that an import should resume from the record given in the source file's checkpoint, keeping the records already stored")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with("stdin_flag")
+       )
+       .arg(
+            Arg::new("checkpoint_flag")
+            .long("checkpoint")
+            .required(false)
+            .help("A flag signifying that the progress of an import should be recorded in a checkpoint file, from which it can be resumed with --resume")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with("stdin_flag")
+       )
+       .arg(
+            Arg::new("atomic_flag")
+            .long("atomic")
+            .required(false)
+            .help("A flag signifying that each source file should be imported within a single transaction, so that an import that is interrupted or fails keeps none of its records")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with_all(["checkpoint_flag", "resume_flag", "sqlite_path"])
+       )
+       .arg(
+            Arg::new("dump_failed_flag")
+            .long("dump-failed")
+            .required(false)
+            .help("A flag signifying that a batch of records that cannot be stored should be written to a replay (.sql) file in the output folder")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("exclude_withdrawn_flag")
+            .long("exclude-withdrawn")
+            .required(false)
+            .help("A flag signifying that records with the status 'withdrawn' should not be imported")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("active_only_flag")
+            .long("active-only")
+            .required(false)
+            .help("A flag signifying that only records with the status 'active' should be imported, i.e. not those that are inactive or withdrawn")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("analyze_flag")
+            .long("analyze")
+            .required(false)
+            .help("A flag signifying that the src tables should be analysed after processing, even without an import (after which it is the default)")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("no_analyze_flag")
+            .long("no-analyze")
+            .required(false)
+            .help("A flag signifying that the tables should not be analysed after an import or processing")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with("analyze_flag")
+       )
+       .arg(
+            Arg::new("keep_raw_flag")
+            .long("keep-raw")
+            .required(false)
+            .help("A flag signifying that the original language codes of names, and domains, should be kept, in src.names.lang_code_raw and src.domains.domain_raw")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("dump_lookups_flag")
+            .long("dump-lookups")
+            .required(false)
+            .help("A flag signifying that the codes and names of each lup table used in processing should be logged")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("stdin_flag")
+            .long("stdin")
+            .required(false)
+            .help("A flag signifying that the source data should be read from stdin, with its version and date given by -v and -d")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("plan_flag")
+            .long("plan")
+            .required(false)
+            .help("A flag signifying that the phases the other parameters would run should be listed, and nothing else done")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("print_config_flag")
+            .long("print-config")
+            .required(false)
+            .help("A flag signifying that the resolved parameters should be printed as JSON, and nothing else done")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("profile_flag")
+            .long("profile")
+            .required(false)
+            .help("A flag signifying that the time spent storing each group of tables should be logged after an import")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("dedup_flag")
+            .long("dedup")
+            .required(false)
+            .help("A flag signifying that exact duplicate rows should be removed from the ror child tables")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("history")
+            .long("history")
+            .required(false)
+            .help("Show the last N import runs (default 10), then exit")
+            .num_args(0..=1)
+            .value_name("N")
+            .value_parser(clap::value_parser!(i64).range(1..))
+            .default_missing_value("10")
+       )
+       .arg(
+            Arg::new("compare_versions")
+            .long("compare-versions")
+            .required(false)
+            .help("Two loads of the ror tables to compare, as schema suffixes (e.g. v1_58), 'current' or import run ids, separated by a comma")
+            .value_name("A,B")
+       )
+       .arg(
+            Arg::new("max_memory")
+            .long("max-memory")
+            .required(false)
+            .help("The memory (in MB) available for parsing a source file - larger files are streamed rather than read as a whole")
+            .value_name("MB")
+            .value_parser(clap::value_parser!(u64).range(1..))
+       )
+       .arg(
+            Arg::new("strict_flag")
+            .long("strict")
+            .required(false)
+            .help("A flag signifying that questionable parameters, e.g. a data date in the future, should raise an error")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("strict_utf8_flag")
+            .long("strict-utf8")
+            .required(false)
+            .help("A flag signifying that any invalid UTF-8 in a source file should stop its import, rather than be replaced")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("quiet_db_flag")
+            .long("quiet-db")
+            .required(false)
+            .help("A flag signifying that slow database statements should not be logged")
+            .action(clap::ArgAction::SetTrue)
+       )
+       .arg(
+            Arg::new("q_flag")
+            .short('q')
+            .long("quiet")
+            .required(false)
+            .help("A flag signifying that only warnings and errors should be logged")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with("v_flag")
+       )
+       .arg(
+            Arg::new("v_flag")
+            .short('V')
+            .long("verbose")
+            .required(false)
+            .help("A flag signifying that debug messages should be logged - repeat (-VV) for trace messages")
+            .action(clap::ArgAction::Count)
+       )
+    .try_get_matches_from(args)
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Ensure the parameters are being correctly extracted from the CLI arguments
+
+    #[test]
+    fn check_cli_no_explicit_params() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.data_folder, PathBuf::new());
+        assert_eq!(res.source_file, "");
+        assert_eq!(res.flags.import_ror, true);
+        assert_eq!(res.flags.process_data, false);
+        assert_eq!(res.flags.export_text, false);
+        assert_eq!(res.flags.create_lookups, false);
+        assert_eq!(res.flags.create_summary, false);
+        assert_eq!(res.flags.test_run, false);
+        assert_eq!(res.data_date, "");
+        assert_eq!(res.data_version, "");
+    }
+  
+    #[test]
+    fn check_cli_with_a_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-a"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.data_folder, PathBuf::new());
+        assert_eq!(res.source_file, "");
+        assert_eq!(res.flags.import_ror, true);
         assert_eq!(res.flags.process_data, true);
         assert_eq!(res.flags.export_text, true);
         assert_eq!(res.flags.create_lookups, false);
         assert_eq!(res.flags.create_summary, false);
-        assert_eq!(res.flags.test_run, true);
-        assert_eq!(res.data_date, "2026-12-25");
-        assert_eq!(res.data_version, "1.63");
+        assert_eq!(res.flags.test_run, false);
+        assert_eq!(res.data_date, "");
+        assert_eq!(res.data_version, "");
+    }
+
+    #[test]
+    fn check_cli_with_i_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-i"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.data_folder, PathBuf::new());
+        assert_eq!(res.source_file, "");
+        assert_eq!(res.flags.import_ror, false);
+        assert_eq!(res.flags.process_data, false);
+        assert_eq!(res.flags.export_text, false);
+        assert_eq!(res.flags.create_lookups, true);
+        assert_eq!(res.flags.create_summary, true);
+        assert_eq!(res.flags.test_run, false);
+        assert_eq!(res.data_date, "");
+        assert_eq!(res.data_version, "");
+    }
+
+    #[test]
+    fn check_cli_with_c_and_m_flags() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-c", "-m"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.data_folder, PathBuf::new());
+        assert_eq!(res.source_file, "");
+        assert_eq!(res.flags.import_ror, false);
+        assert_eq!(res.flags.process_data, false);
+        assert_eq!(res.flags.export_text, false);
+        assert_eq!(res.flags.create_lookups, true);
+        assert_eq!(res.flags.create_summary, true);
+        assert_eq!(res.flags.test_run, false);
+        assert_eq!(res.data_date, "");
+        assert_eq!(res.data_version, "");
     }
 
-}
 
+    #[test]
+    fn check_cli_with_c_and_p_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-c", "-p"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.data_folder, PathBuf::new());
+        assert_eq!(res.source_file, "");
+        assert_eq!(res.flags.import_ror, false);
+        assert_eq!(res.flags.process_data, false);
+        assert_eq!(res.flags.export_text, false);
+        assert_eq!(res.flags.create_lookups, true);
+        assert_eq!(res.flags.create_summary, false);
+        assert_eq!(res.flags.test_run, false);
+        assert_eq!(res.data_date, "");
+        assert_eq!(res.data_version, "");
+    }
+
+    #[test]
+    fn check_cli_with_explicit_string_pars() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-f", "E:\\ROR\\some data folder", 
+                                    "-s", "schema2 data.json", "-d", "2025-12-25", "-v", "1.62"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.data_folder, PathBuf::from("E:/ROR/some data folder"));
+        assert_eq!(res.source_file, "schema2 data.json");
+        assert_eq!(res.flags.import_ror, true);
+        assert_eq!(res.flags.process_data, false);
+        assert_eq!(res.flags.export_text, false);
+        assert_eq!(res.flags.create_lookups, false);
+        assert_eq!(res.flags.create_summary, false);
+        assert_eq!(res.flags.test_run, false);
+        assert_eq!(res.data_date, "2025-12-25");
+        assert_eq!(res.data_version, "1.62");
+    }
+
+    #[test]
+    fn check_cli_with_most_params_explicit() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-f", "E:\\ROR\\some other data folder", 
+        "-s", "schema2.1 data.json", "-d", "2026-12-25", "-v", "1.63", "-r", "-p", "-t", "-z"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.data_folder, PathBuf::from("E:/ROR/some other data folder"));
+        assert_eq!(res.source_file, "schema2.1 data.json");
+        assert_eq!(res.flags.import_ror, true);
+        assert_eq!(res.flags.process_data, true);
+        assert_eq!(res.flags.export_text, true);
+        assert_eq!(res.flags.create_lookups, false);
+        assert_eq!(res.flags.create_summary, false);
+        assert_eq!(res.flags.test_run, true);
+        assert_eq!(res.data_date, "2026-12-25");
+        assert_eq!(res.data_version, "1.63");
+    }
+
+    #[test]
+    fn check_cli_log_level_default_is_info() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.log_level, LevelFilter::Info);
+    }
+
+    #[test]
+    fn check_cli_log_level_with_quiet_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "-q"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.log_level, LevelFilter::Warn);
+        assert_eq!(res.flags.import_ror, true);
+    }
+
+    #[test]
+    fn check_cli_log_level_with_verbose_flags() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-V"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.log_level, LevelFilter::Debug);
+
+        let args : Vec<&str> = vec![target, "-VV"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.log_level, LevelFilter::Trace);
+
+        let args : Vec<&str> = vec![target, "--verbose", "--verbose", "--verbose"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.log_level, LevelFilter::Trace);
+    }
+
+    #[test]
+    fn check_cli_log_level_with_i_flag_and_quiet() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-i", "-q"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.log_level, LevelFilter::Warn);
+        assert_eq!(res.flags.create_lookups, true);
+    }
+
+    #[test]
+    fn check_cli_quiet_and_verbose_together_fails() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-q", "-V"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+        let res = fetch_valid_arguments(test_args);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn check_cli_with_strict_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.strict, false);
+
+        let args : Vec<&str> = vec![target, "-r", "--strict"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.strict, true);
+        assert_eq!(res.flags.import_ror, true);
+    }
+
+    #[test]
+    fn check_cli_with_strict_utf8_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.strict_utf8, false);
+
+        let args : Vec<&str> = vec![target, "-r", "--strict-utf8"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.strict_utf8, true);
+        assert_eq!(res.flags.strict, false);
+
+        let args : Vec<&str> = vec![target, "-p", "--strict-utf8"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.strict_utf8, false);
+    }
+
+    #[test]
+    fn check_cli_with_validate_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--validate", "-a", "-s", "v1.58 2024-12-11.json"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.source_file, "v1.58 2024-12-11.json");
+        assert_eq!(res.flags.validate, true);
+        assert_eq!(res.flags.import_ror, false);
+        assert_eq!(res.flags.process_data, false);
+        assert_eq!(res.flags.export_text, false);
+        assert_eq!(res.flags.export_csv, false);
+        assert_eq!(res.flags.create_lookups, false);
+    }
+
+    #[test]
+    fn check_cli_with_since_date() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.since_date, "");
+
+        let args : Vec<&str> = vec![target, "-r", "--since", "2024-11-01"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.since_date, "2024-11-01");
+        assert_eq!(res.flags.import_ror, true);
+    }
+
+    #[test]
+    fn check_cli_with_dedup_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--dedup"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.dedup, true);
+        assert_eq!(res.flags.import_ror, false);
+
+        let args : Vec<&str> = vec![target, "-r", "--dedup", "-p"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.dedup, true);
+        assert_eq!(res.flags.import_ror, true);
+        assert_eq!(res.flags.process_data, true);
+    }
+
+    #[test]
+    fn check_cli_with_positional_data_folder() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "E:\\ROR\\data", "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.data_folder, PathBuf::from("E:/ROR/data"));
+        assert_eq!(res.flags.import_ror, true);
+
+        let args : Vec<&str> = vec![target, "-r", "-f", "E:/ROR/data2"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.data_folder, PathBuf::from("E:/ROR/data2"));
+
+        let args : Vec<&str> = vec![target, "-r", "-f", "E:/ROR/data2", "E:/ROR/data"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.data_folder, PathBuf::from("E:/ROR/data2"));
+    }
+
+    #[test]
+    fn check_cli_with_env_and_config_files() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--env-file", "C:\\ror1\\prod.env", "--config", "ror1.toml"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.env_file, PathBuf::from("C:/ror1/prod.env"));
+        assert_eq!(res.config_file, PathBuf::from("ror1.toml"));
+        assert_eq!(res.flags.import_ror, true);
+    }
+
+    #[test]
+    fn check_cli_with_count_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--count", "-r", "-p", "--history"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.count_only, true);
+        assert_eq!(res.flags.import_ror, false);
+        assert_eq!(res.flags.process_data, false);
+        assert_eq!(res.flags.show_history, false);
+    }
+
+    #[test]
+    fn check_cli_with_check_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--check", "-a", "--history"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.check_db, true);
+        assert_eq!(res.flags.import_ror, false);
+        assert_eq!(res.flags.process_data, false);
+        assert_eq!(res.flags.export_text, false);
+        assert_eq!(res.flags.show_history, false);
+    }
+
+    #[test]
+    fn check_cli_with_resume_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "--resume"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.resume, true);
+        assert_eq!(res.flags.import_ror, true);
+
+        let args : Vec<&str> = vec![target, "-r", "--stdin", "--resume"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(fetch_valid_arguments(test_args).is_err());
+    }
+
+    #[test]
+    fn check_cli_with_checkpoint_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.checkpoint, false);
+
+        let args : Vec<&str> = vec![target, "-r", "--checkpoint"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.checkpoint, true);
+        assert_eq!(res.flags.resume, false);
+
+        let args : Vec<&str> = vec![target, "-r", "--resume"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.checkpoint, true);
+
+        let args : Vec<&str> = vec![target, "-r", "--stdin", "--checkpoint"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(fetch_valid_arguments(test_args).is_err());
+    }
+
+    #[test]
+    fn check_cli_with_atomic_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert!(!res.flags.atomic);
+
+        let args : Vec<&str> = vec![target, "-r", "--atomic"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert!(res.flags.atomic);
+
+        let args : Vec<&str> = vec![target, "-p", "--atomic"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert!(!res.flags.atomic);
+
+        // Data read from stdin is always imported within a single transaction.
+
+        let args : Vec<&str> = vec![target, "-r", "--stdin", "-v", "v1.60", "-d", "2025-02-11"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert!(res.flags.atomic);
+
+        let args : Vec<&str> = vec![target, "-r", "--atomic", "--checkpoint"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(fetch_valid_arguments(test_args).is_err());
+
+        let args : Vec<&str> = vec![target, "-r", "--atomic", "--resume"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(fetch_valid_arguments(test_args).is_err());
+    }
+
+    #[test]
+    fn check_cli_with_dump_failed_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "--dump-failed"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.dump_failed, true);
+        assert_eq!(res.flags.import_ror, true);
+
+        let args : Vec<&str> = vec![target, "-p", "--dump-failed"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.dump_failed, false);
+    }
+
+    #[test]
+    fn check_cli_with_status_flags() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "--exclude-withdrawn"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.exclude_withdrawn, true);
+        assert_eq!(res.flags.active_only, false);
+
+        let args : Vec<&str> = vec![target, "-r", "--active-only", "--types", "education"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.exclude_withdrawn, false);
+        assert_eq!(res.flags.active_only, true);
+        assert_eq!(res.types, "education");
+    }
+
+    #[test]
+    fn check_cli_with_analyze_flags() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.analyze, true);
+
+        let args : Vec<&str> = vec![target, "-r", "--no-analyze"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.analyze, false);
+
+        let args : Vec<&str> = vec![target, "-p"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.analyze, false);
+
+        let args : Vec<&str> = vec![target, "-p", "--analyze"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.analyze, true);
+
+        let args : Vec<&str> = vec![target, "--validate", "--analyze"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.analyze, false);
+
+        let args : Vec<&str> = vec![target, "-r", "--analyze", "--no-analyze"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(fetch_valid_arguments(test_args).is_err());
+    }
+
+    #[test]
+    fn check_cli_with_no_recreate_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "--no-recreate"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.no_recreate, true);
+        assert_eq!(res.flags.import_ror, true);
+
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.no_recreate, false);
+    }
+
+    #[test]
+    fn check_cli_with_recreate_src_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-p", "--recreate-src"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.recreate_src, true);
+        assert_eq!(res.flags.process_data, true);
+
+        let args : Vec<&str> = vec![target, "-p"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.recreate_src, false);
+
+        let args : Vec<&str> = vec![target, "-p", "--recreate-src", "--no-recreate"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(fetch_valid_arguments(test_args).is_err());
+    }
+
+    #[test]
+    fn check_cli_with_replace_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "--replace"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.replace, true);
+        assert_eq!(res.flags.import_ror, true);
+
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.replace, false);
+
+        let args : Vec<&str> = vec![target, "-r", "--stdin", "--replace"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(fetch_valid_arguments(test_args).is_err());
+    }
+
+    #[test]
+    fn check_cli_with_quiet_db_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "--quiet-db"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.quiet_db, true);
+
+        let args : Vec<&str> = vec![target, "-i", "--quiet-db"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.quiet_db, true);
+
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.quiet_db, false);
+    }
+
+    #[test]
+    fn check_cli_with_keep_raw_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-p", "--keep-raw"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.keep_raw, true);
+        assert_eq!(res.flags.process_data, true);
+
+        let args : Vec<&str> = vec![target, "-p"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.keep_raw, false);
+    }
+
+    #[test]
+    fn check_cli_with_dump_lookups_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-p", "--dump-lookups"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.dump_lookups, true);
+
+        let args : Vec<&str> = vec![target, "-r", "--dump-lookups"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.dump_lookups, false);
+    }
+
+    #[test]
+    fn check_cli_with_compare_versions() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--compare-versions", "v1_58,current"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.compare_versions, true);
+        assert_eq!(res.compare_versions, "v1_58,current");
+        assert_eq!(res.flags.import_ror, false);
+        assert_eq!(res.flags.export_csv, false);
+
+        let args : Vec<&str> = vec![target, "--compare-versions", "12,15", "-x", "-p"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.compare_versions, true);
+        assert_eq!(res.flags.export_csv, true);
+        assert_eq!(res.flags.process_data, false);
+    }
+
+    #[test]
+    fn check_cli_with_report_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--report"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.report, true);
+        assert_eq!(res.flags.import_ror, false);
+        assert_eq!(res.flags.analyze, false);
+
+        let args : Vec<&str> = vec![target, "--report", "-p", "-x", "-t"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.report, true);
+        assert_eq!(res.flags.process_data, false);
+        assert_eq!(res.flags.export_csv, false);
+        assert_eq!(res.flags.export_text, false);
+
+        let args : Vec<&str> = vec![target, "--report", "--history"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.report, false);
+        assert_eq!(res.flags.show_history, true);
+    }
+
+    #[test]
+    fn check_cli_with_only_new_ids_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--only-new-ids", "-s", "v1.60-2025-02-11-ror-data_schema_v2.json"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.only_new_ids, true);
+        assert_eq!(res.flags.import_ror, false);
+
+        let args : Vec<&str> = vec![target, "-a", "--only-new-ids"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.only_new_ids, true);
+        assert_eq!(res.flags.import_ror, false);
+        assert_eq!(res.flags.process_data, false);
+    }
+
+    #[test]
+    fn check_cli_with_overwrite_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-t", "--overwrite"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.overwrite, true);
+        assert_eq!(res.flags.export_text, true);
+
+        let args : Vec<&str> = vec![target, "-t"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.overwrite, false);
+    }
+
+    #[test]
+    fn check_cli_with_append_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-x", "--append"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.append_csv, true);
+        assert_eq!(res.flags.export_csv, true);
+
+        let args : Vec<&str> = vec![target, "-t", "--append"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.append_csv, false);
+    }
+
+    #[test]
+    fn check_cli_with_max_memory() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "--max-memory", "512"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.max_memory_mb, Some(512));
+
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.max_memory_mb, None);
+
+        let args : Vec<&str> = vec![target, "-r", "--max-memory", "0"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(fetch_valid_arguments(test_args).is_err());
+    }
+
+    #[test]
+    fn check_cli_with_drop_all_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--drop-all", "--yes"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.drop_all, true);
+        assert_eq!(res.flags.assume_yes, true);
+        assert_eq!(res.flags.import_ror, false);
+
+        for other in ["-r", "-p", "-a", "-i", "--dedup", "-x", "--check"] {
+            let args : Vec<&str> = vec![target, "--drop-all", other];
+            let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+            assert!(fetch_valid_arguments(test_args).is_err());
+        }
+    }
+
+    #[test]
+    fn check_default_flags_added_without_cli_action_flags() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-s", "v1.60-2025-02-11-ror-data.json", "-q"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(add_default_flags(test_args, "rpx").unwrap()).unwrap();
+        assert_eq!(res.flags.import_ror, true);
+        assert_eq!(res.flags.process_data, true);
+        assert_eq!(res.flags.export_csv, true);
+        assert_eq!(res.flags.export_text, false);
+        assert_eq!(res.source_file, "v1.60-2025-02-11-ror-data.json");
+        assert_eq!(res.log_level, LevelFilter::Warn);
+
+        let args : Vec<&str> = vec![target];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(add_default_flags(test_args, " -a ").unwrap()).unwrap();
+        assert_eq!((res.flags.import_ror, res.flags.process_data, res.flags.export_text), (true, true, true));
+
+        let test_args = vec![OsString::from(target)];
+        assert!(add_default_flags(test_args.clone(), "rpz").unwrap_err().to_string().contains("Invalid flag 'z'"));
+        assert_eq!(add_default_flags(test_args.clone(), "").unwrap(), test_args);
+    }
+
+    #[test]
+    fn check_default_flags_replaced_by_cli_action_flags() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        for (cli_flag, field) in [("-t", "export_text"), ("-i", "create_lookups"), ("--check", "check_db"), 
+                                  ("--format", "export_json")] {
+            let mut args : Vec<&str> = vec![target, cli_flag];
+            if cli_flag == "--format" {
+                args.push("json");
+            }
+            let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+            let with_defaults = add_default_flags(test_args.clone(), "rpx").unwrap();
+            assert_eq!(with_defaults, test_args);
+            let res = fetch_valid_arguments(with_defaults).unwrap();
+            assert_eq!(res.flags.import_ror, false, "{}", cli_flag);
+            assert_eq!(res.flags.export_csv, false, "{}", cli_flag);
+            let set = match field {
+                "export_text" => res.flags.export_text,
+                "create_lookups" => res.flags.create_lookups,
+                "check_db" => res.flags.check_db,
+                _ => res.flags.export_json,
+            };
+            assert!(set, "{}", cli_flag);
+        }
+    }
+
+    #[test]
+    fn check_cli_with_tail_log_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "--quiet", "--tail-log"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.tail_log, true);
+        assert_eq!(res.log_level, LevelFilter::Warn);
+
+        let args : Vec<&str> = vec![target, "-i", "--tail-log"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.tail_log, true);
+
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.tail_log, false);
+    }
+
+    #[test]
+    fn check_cli_with_stdin_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--stdin", "-v", "v1.60", "-d", "2025-02-11"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.stdin, true);
+        assert_eq!(res.flags.import_ror, true);
+        assert_eq!(res.data_version, "v1.60");
+
+        let args : Vec<&str> = vec![target, "-p", "--stdin"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.stdin, false);
+    }
+
+    #[test]
+    fn check_cli_with_print_config_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "-p", "--print-config"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.print_config, true);
+        assert_eq!(res.flags.import_ror, true);
+
+        let args : Vec<&str> = vec![target, "-i", "--print-config"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.print_config, true);
+        assert_eq!(res.flags.create_lookups, true);
+    }
+
+    #[test]
+    fn check_cli_with_plan_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-a", "--plan"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.plan, true);
+        assert_eq!(res.flags.import_ror, true);
+        assert_eq!(res.flags.process_data, true);
+        assert_eq!(res.flags.export_text, true);
+
+        let args : Vec<&str> = vec![target, "-i", "--plan"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.plan, true);
+        assert_eq!(res.flags.create_lookups, true);
+    }
+
+    #[test]
+    fn check_cli_with_profile_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--profile"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.profile, true);
+        assert_eq!(res.flags.import_ror, true);
+
+        let args : Vec<&str> = vec![target, "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.profile, false);
+    }
+
+    #[test]
+    fn check_cli_with_format_option() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--format", "txt,json"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.export_text, true);
+        assert_eq!(res.flags.export_csv, false);
+        assert_eq!(res.flags.export_json, true);
+        assert_eq!(res.flags.export_full_csv, false);
+        assert_eq!(res.flags.import_ror, false);
+
+        let args : Vec<&str> = vec![target, "-p", "--output-format", " CSV , allcsv", "-t"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.process_data, true);
+        assert_eq!(res.flags.export_text, true);
+        assert_eq!(res.flags.export_csv, true);
+        assert_eq!(res.flags.export_json, false);
+        assert_eq!(res.flags.export_full_csv, true);
+    }
+
+    #[test]
+    fn check_pipeline_phases_in_canonical_order() {
+        let phases = get_pipeline_phases("export, Transform,import").unwrap();
+        assert_eq!(phases.listed(), vec!["import", "transform", "export"]);
+        assert_eq!(phases, PipelinePhases { import: true, transform: true, export: true, ..Default::default() });
+
+        let phases = get_pipeline_phases("summary,import,summary").unwrap();
+        assert_eq!(phases.listed(), vec!["import", "summary"]);
+        assert_eq!(get_pipeline_phases("create").unwrap().listed(), vec!["create"]);
+        assert_eq!(get_pipeline_phases("").unwrap(), PipelinePhases::default());
+    }
+
+    #[test]
+    fn check_pipeline_phases_rejected() {
+        let res = get_pipeline_phases("import,load");
+        assert!(res.unwrap_err().to_string().contains("Unknown phase 'load' in --phases"));
+        let res = get_pipeline_phases("import,create");
+        assert!(res.unwrap_err().to_string().contains("--phases lists create, import"));
+    }
+
+    #[test]
+    fn check_cli_with_phases_option() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--phases", "transform,import"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.import_ror, true);
+        assert_eq!(res.flags.process_data, true);
+        assert_eq!(res.flags.summarise_data, false);
+        assert_eq!(res.flags.export_text, false);
+        assert_eq!(res.flags.export_csv, false);
+
+        let args : Vec<&str> = vec![target, "--phases", "summary,export", "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.import_ror, true);
+        assert_eq!(res.flags.process_data, false);
+        assert_eq!(res.flags.summarise_data, true);
+        assert_eq!(res.flags.export_text, true);
+        assert_eq!(res.flags.export_csv, true);
+
+        // The summary phase on its own does not imply an import, and -p 
+        // runs both the transform and summary phases.
+
+        let args : Vec<&str> = vec![target, "--phases", "summary"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!((res.flags.import_ror, res.flags.process_data, res.flags.summarise_data), (false, false, true));
+        assert_eq!((res.flags.export_text, res.flags.export_csv), (false, false));
+
+        let args : Vec<&str> = vec![target, "-p"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!((res.flags.process_data, res.flags.summarise_data), (true, true));
+
+        let args : Vec<&str> = vec![target, "--drop-all", "--phases", "import"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(fetch_valid_arguments(test_args).is_err());
+
+        let args : Vec<&str> = vec![target, "--phases", "create"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.create_lookups, true);
+        assert_eq!(res.flags.create_summary, true);
+        assert_eq!(res.flags.import_ror, false);
+
+        let args : Vec<&str> = vec![target, "--phases", "import,export,publish"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(matches!(fetch_valid_arguments(test_args), Err(AppError::CsErr(_))));
+
+        // Listing phases, like the action flags, replaces any default flags.
+
+        let args : Vec<&str> = vec![target, "--phases", "summary"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert_eq!(add_default_flags(test_args.clone(), "rpx").unwrap(), test_args);
+    }
+
+    #[test]
+    fn check_cli_with_unknown_format_is_an_error() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--format", "txt,xml"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args);
+        assert!(matches!(res, Err(AppError::CsErr(_))));
+        assert!(res.unwrap_err().to_string().contains("Unknown output format 'xml'"));
+    }
+
+    #[test]
+    fn check_cli_with_json_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-j"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.export_json, true);
+        assert_eq!(res.flags.import_ror, false);
+        assert_eq!(res.flags.export_csv, false);
+    }
+
+    #[test]
+    fn check_cli_with_country_csv_flag() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--country-csv"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.export_country_csv, true);
+        assert_eq!(res.flags.import_ror, false);
+
+        let args : Vec<&str> = vec![target, "--validate", "--country-csv", "-s", "v1.58 2024-12-11.json"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.export_country_csv, false);
+    }
+
+    #[test]
+    fn check_cli_with_tables() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-x", "--tables", "core_data,locations"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.tables, "core_data,locations");
+        assert_eq!(res.flags.export_csv, true);
+    }
+
+    #[test]
+    fn check_cli_with_types() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "--types", "education,healthcare"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.types, "education,healthcare");
+        assert_eq!(res.flags.import_ror, true);
+    }
+
+    #[test]
+    fn check_cli_with_sqlite_path() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--sqlite", "/ror/ror.db"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.sqlite_path, "/ror/ror.db");
+        assert_eq!(res.flags.import_ror, true);
+
+        // An import into SQLite replaces any default flags, and cannot be
+        // combined with processing or export.
+
+        let args : Vec<&str> = vec![target, "--sqlite", ":memory:"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert_eq!(add_default_flags(test_args.clone(), "rpx").unwrap(), test_args);
+
+        let args : Vec<&str> = vec![target, "-r", "-p", "--sqlite", "/ror/ror.db"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(fetch_valid_arguments(test_args).is_err());
+    }
+
+    #[test]
+    fn check_cli_with_trace_id() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-r", "-V", "--trace-id", "04ttjf776"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.trace_id, "04ttjf776");
+        assert_eq!(res.log_level, LevelFilter::Debug);
+    }
+
+    #[test]
+    fn check_cli_with_csv_format() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "-x", "--csv-delimiter", ";", "--csv-bom"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.csv_delimiter, ";");
+        assert_eq!(res.flags.csv_bom, true);
+        assert_eq!(res.flags.export_csv, true);
+
+        let args : Vec<&str> = vec![target, "-x"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.csv_delimiter, "");
+        assert_eq!(res.flags.csv_bom, false);
+    }
+
+    #[test]
+    fn check_cli_with_history() {
+        let target = &"target\\debug\\ror1.exe".replace("\\", "/");
+        let args : Vec<&str> = vec![target, "--history"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.show_history, true);
+        assert_eq!(res.history_count, 10);
+        assert_eq!(res.flags.import_ror, false);
+
+        let args : Vec<&str> = vec![target, "--history", "3", "-r"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args).unwrap();
+        assert_eq!(res.flags.show_history, true);
+        assert_eq!(res.history_count, 3);
+        assert_eq!(res.flags.import_ror, false);
+
+        let args : Vec<&str> = vec![target, "--history", "0"];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        let res = fetch_valid_arguments(test_args);
+        assert!(res.is_err());
+    }
+
+}
@@ -0,0 +1,162 @@
+/***************************************************************************
+ * Module reads an (optional) TOML configuration file, named using the
+ * --config CLI option. The file's keys mirror the names of the environment
+ * variables (data_folder_path, db_name, etc.), and each value found is
+ * loaded into std::env, replacing any value from the .env file. As the CLI
+ * arguments are applied later, over the environment values, the overall
+ * precedence is CLI > config file > environment.
+ * Only the flat 'key = value' form of TOML is needed, and supported:
+ * values may be strings (basic or literal), integers, floats or booleans.
+ ***************************************************************************/
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use crate::error_defs::{AppError, CustomError};
+
+pub const CONFIG_KEYS: [&str; 30] = ["db_host", "db_user", "db_password", "db_port", "db_name",
+                    "data_folder_path", "log_folder_path", "output_folder_path",
+                    "text_output_folder_path", "csv_output_folder_path", "src_file_name",
+                    "output_file_name", "output_file_name_fixed", "data_version", "data_date", "timestamp_format",
+                    "log_json", "log_max_size_mb", "log_keep", "log_pattern", "log_console_level", "log_file_level",
+                    "db_scripts_path", "store_retries", "slow_statement_secs", "default_flags",
+                    "max_memory_mb", "db_log_slow", "read_db_conn_string", "log_value_max_len"];
+
+
+pub fn apply_config_file(config_file: &Path) -> Result<(), AppError> {
+    for (key, value) in read_config_file(config_file)? {
+        env::set_var(key, value);
+    }
+    Ok(())
+}
+
+
+pub fn read_config_file(config_file: &Path) -> Result<Vec<(String, String)>, AppError> {
+    let config = match fs::read_to_string(config_file) {
+        Ok(c) => c,
+        Err(e) => {
+            let msg = format!("Unable to read config file {}: {}", config_file.display(), e);
+            return Err(AppError::CsErr(CustomError::new(&msg)));
+        },
+    };
+    parse_config(&config).map_err(|msg| {
+        let msg = format!("Invalid config file {}: {}", config_file.display(), msg);
+        AppError::CsErr(CustomError::new(&msg))
+    })
+}
+
+
+fn parse_config(config: &str) -> Result<Vec<(String, String)>, String> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for (i, line) in config.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            return Err(format!("line {}: tables are not supported, all keys must be at the top level", i + 1));
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((k, v)) => (k.trim(), v.trim()),
+            None => return Err(format!("line {}: expected 'key = value'", i + 1)),
+        };
+        if !CONFIG_KEYS.contains(&key) {
+            return Err(format!("line {}: unknown key '{}'", i + 1, key));
+        }
+        let value = parse_value(value).map_err(|e| format!("line {}: {}", i + 1, e))?;
+        pairs.push((key.to_string(), value));
+    }
+    Ok(pairs)
+}
+
+
+fn parse_value(value: &str) -> Result<String, String> {
+
+    // Strings are returned without their quotes (and with escapes resolved
+    // for basic strings). Other values must be a single bare word, followed
+    // by an optional comment, and are returned as written.
+
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut result = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return check_trailing(chars.as_str()).map(|_| result),
+                '\\' => match chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some('"') => result.push('"'),
+                    Some('\\') => result.push('\\'),
+                    Some(c) => return Err(format!("unsupported escape '\\{}'", c)),
+                    None => break,
+                },
+                c => result.push(c),
+            }
+        }
+        Err("unterminated string".to_string())
+    }
+    else if let Some(rest) = value.strip_prefix('\'') {
+        match rest.split_once('\'') {
+            Some((s, trailing)) => check_trailing(trailing).map(|_| s.to_string()),
+            None => Err("unterminated string".to_string()),
+        }
+    }
+    else {
+        let bare = match value.split_once('#') {
+            Some((v, _)) => v.trim(),
+            None => value,
+        };
+        if bare == "true" || bare == "false" || bare.replace('_', "").parse::<f64>().is_ok() {
+            Ok(bare.replace('_', ""))
+        }
+        else {
+            Err(format!("invalid value '{}' (strings must be quoted)", bare))
+        }
+    }
+}
+
+
+fn check_trailing(trailing: &str) -> Result<(), String> {
+    let trailing = trailing.trim();
+    if trailing.is_empty() || trailing.starts_with('#') {
+        Ok(())
+    }
+    else {
+        Err(format!("unexpected text after value '{}'", trailing))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_config_values_parsed() {
+        let config = r#"
+            # database
+            db_name = "ror_test"   # trailing comment
+            db_port = 5433
+            data_folder_path = 'E:\ROR\data'
+            src_file_name = "v1.58 \"final\".json"
+            log_json = true
+        "#;
+        let pairs = parse_config(config).unwrap();
+        assert_eq!(pairs, vec![
+            ("db_name".to_string(), "ror_test".to_string()),
+            ("db_port".to_string(), "5433".to_string()),
+            ("data_folder_path".to_string(), r"E:\ROR\data".to_string()),
+            ("src_file_name".to_string(), r#"v1.58 "final".json"#.to_string()),
+            ("log_json".to_string(), "true".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn check_invalid_config_rejected() {
+        assert!(parse_config("[database]\ndb_name = \"ror\"").unwrap_err().contains("tables are not supported"));
+        assert!(parse_config("db_nme = \"ror\"").unwrap_err().contains("unknown key 'db_nme'"));
+        assert!(parse_config("db_name = ror").unwrap_err().contains("strings must be quoted"));
+        assert!(parse_config("db_name = \"ror").unwrap_err().contains("unterminated"));
+        assert!(parse_config("\n\ndb_name").unwrap_err().starts_with("line 3"));
+    }
+}
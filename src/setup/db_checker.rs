@@ -0,0 +1,93 @@
+/***************************************************************************
+ * Checks that the schemas and key tables used by the program are present
+ * in the database, by querying information_schema. Nothing is created or
+ * modified. The lup and smm schemas should exist once the database has
+ * been initialised (with -i), the ror and src schemas once data has been
+ * imported and processed.
+ ***************************************************************************/
+
+use sqlx::{Pool, Postgres};
+use crate::error_defs::AppError;
+
+pub const KEY_TABLES: [(&str, &[&str]); 4] = [
+    ("lup", &["ror_status_types", "ror_org_types", "ror_name_types", "ror_id_types",
+              "ror_link_types", "ror_org_rels", "countries", "lang_codes", "lang_scripts"]),
+    ("ror", &["version_details", "core_data", "admin_data", "names", "locations",
+              "external_ids", "links", "type", "relationships", "domains"]),
+    ("src", &["version_details", "core_data", "admin_data", "names", "locations",
+              "external_ids", "links", "type", "relationships", "domains"]),
+    ("smm", &["version_summaries", "count_distributions", "ranked_distributions", "attributes_summary",
+              "singletons", "org_type_and_relationships", "org_type_and_lang_code"]),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckItem {
+    pub name: String,
+    pub present: bool,
+}
+
+
+pub async fn fetch_check_items(pool: &Pool<Postgres>) -> Result<Vec<CheckItem>, AppError> {
+    let schema_names: Vec<&str> = KEY_TABLES.iter().map(|(s, _)| *s).collect();
+
+    let sql = "select schema_name::varchar from information_schema.schemata where schema_name = any($1)";
+    let schemas: Vec<String> = sqlx::query_scalar(sql).bind(&schema_names).fetch_all(pool).await?;
+
+    let sql = r#"select (table_schema || '.' || table_name)::varchar from information_schema.tables
+                 where table_schema = any($1)"#;
+    let tables: Vec<String> = sqlx::query_scalar(sql).bind(&schema_names).fetch_all(pool).await?;
+
+    Ok(get_check_items(&schemas, &tables))
+}
+
+
+pub async fn schema_tables_exist(schema: &str, pool: &Pool<Postgres>) -> Result<bool, AppError> {
+
+    // True only if all the key tables of the schema are present.
+
+    let table_names: Vec<&str> = match KEY_TABLES.iter().find(|(s, _)| *s == schema) {
+        Some((_, t)) => t.to_vec(),
+        None => return Ok(false),
+    };
+    let sql = r#"select count(*) from information_schema.tables
+                 where table_schema = $1 and table_name = any($2)"#;
+    let n: i64 = sqlx::query_scalar(sql).bind(schema).bind(&table_names).fetch_one(pool).await?;
+    Ok(n as usize == table_names.len())
+}
+
+
+pub fn get_check_items(schemas: &[String], tables: &[String]) -> Vec<CheckItem> {
+    let mut items = Vec::new();
+    for (schema, table_names) in KEY_TABLES.iter() {
+        items.push(CheckItem {
+            name: format!("schema {}", schema),
+            present: schemas.iter().any(|s| s == schema),
+        });
+        for table_name in table_names.iter() {
+            let name = format!("{}.{}", schema, table_name);
+            let present = tables.contains(&name);
+            items.push(CheckItem { name: format!("table {}", name), present });
+        }
+    }
+    items
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_items_reflect_schemas_and_tables_present() {
+        let items = get_check_items(&[], &[]);
+        assert_eq!(items.len(), 4 + 9 + 10 + 10 + 7);
+        assert!(items.iter().all(|i| !i.present));
+        assert_eq!(items[0], CheckItem { name: "schema lup".to_string(), present: false });
+
+        let schemas = vec!["lup".to_string(), "ror".to_string()];
+        let tables = vec!["lup.countries".to_string(), "ror.core_data".to_string(), "ror.other".to_string()];
+        let items = get_check_items(&schemas, &tables);
+        let present: Vec<&str> = items.iter().filter(|i| i.present).map(|i| i.name.as_str()).collect();
+        assert_eq!(present, vec!["schema lup", "table lup.countries", "schema ror", "table ror.core_data"]);
+    }
+}
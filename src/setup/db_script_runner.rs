@@ -0,0 +1,189 @@
+/***************************************************************************
+ * Runs a SQL script one statement at a time, so that if a statement fails
+ * the error can say which one (by its number in the script, and its first
+ * line) rather than just that the script failed. The statements are run in
+ * a single transaction, on one connection, so that - as when the script is
+ * run as a whole - a failure leaves nothing behind, and settings such as
+ * client_min_messages apply to all of the statements that follow them.
+ * The script is split at each semicolon that is outside a string literal,
+ * quoted identifier, dollar quoted body or comment.
+ ***************************************************************************/
+
+use log::error;
+use sqlx::{Pool, Postgres};
+use crate::error_defs::{AppError, CustomError};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SplitState {
+    Code,
+    Literal,
+    Identifier,
+    DollarQuoted,
+    LineComment,
+    BlockComment,
+}
+
+
+pub async fn run_db_script(script_name: &str, sql: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    for (i, statement) in split_statements(sql).iter().enumerate() {
+        if let Err(e) = sqlx::raw_sql(statement).execute(&mut *tx).await {
+            let msg = format!("statement {} of {} failed ({}): {}", i + 1, script_name, first_line(statement), e);
+            error!("{}", msg);
+            return Err(AppError::CsErr(CustomError::new(&msg)));
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+
+pub fn split_statements(sql: &str) -> Vec<String> {
+
+    // Statements containing only whitespace and comments are dropped, so that
+    // the numbering matches the statements a reader of the script would count.
+
+    let chars: Vec<char> = sql.chars().collect();
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut has_code = false;
+    let mut state = SplitState::Code;
+    let mut dollar_tag = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        current.push(c);
+        match state {
+            SplitState::Code => match c {
+                ';' => {
+                    current.pop();
+                    if has_code {
+                        statements.push(current.trim().to_string());
+                    }
+                    current.clear();
+                    has_code = false;
+                },
+                '-' if next == Some('-') => state = SplitState::LineComment,
+                '/' if next == Some('*') => state = SplitState::BlockComment,
+                _ => {
+                    if c == '\'' {
+                        state = SplitState::Literal;
+                    }
+                    else if c == '"' {
+                        state = SplitState::Identifier;
+                    }
+                    else if c == '$' {
+                        if let Some(tag) = get_dollar_tag(&chars[i..]) {
+                            current.push_str(&tag[1..]);
+                            i += tag.chars().count() - 1;
+                            dollar_tag = tag;
+                            state = SplitState::DollarQuoted;
+                        }
+                    }
+                    if !c.is_whitespace() {
+                        has_code = true;
+                    }
+                },
+            },
+            SplitState::Literal => {
+                // A doubled quote is an escaped quote, within the literal.
+                if c == '\'' {
+                    if next == Some('\'') {
+                        current.push('\'');
+                        i += 1;
+                    }
+                    else {
+                        state = SplitState::Code;
+                    }
+                }
+            },
+            SplitState::Identifier => {
+                if c == '"' {
+                    state = SplitState::Code;
+                }
+            },
+            SplitState::DollarQuoted => {
+                if c == '$' && chars[i..].iter().take(dollar_tag.chars().count()).copied().eq(dollar_tag.chars()) {
+                    current.push_str(&dollar_tag[1..]);
+                    i += dollar_tag.chars().count() - 1;
+                    state = SplitState::Code;
+                }
+            },
+            SplitState::LineComment => {
+                if c == '\n' {
+                    state = SplitState::Code;
+                }
+            },
+            SplitState::BlockComment => {
+                if c == '*' && next == Some('/') {
+                    current.push('/');
+                    i += 1;
+                    state = SplitState::Code;
+                }
+            },
+        }
+        i += 1;
+    }
+    if has_code {
+        statements.push(current.trim().to_string());
+    }
+    statements
+}
+
+
+fn get_dollar_tag(chars: &[char]) -> Option<String> {
+
+    // A tag is '$$', or a name (not starting with a digit) between two '$'
+    // signs, e.g. '$body$'. A '$' followed by digits is a parameter ($1).
+
+    let mut tag = String::from("$");
+    for (j, c) in chars.iter().enumerate().skip(1) {
+        match c {
+            '$' => {
+                tag.push('$');
+                return Some(tag);
+            },
+            c if c.is_alphabetic() || *c == '_' || (c.is_ascii_digit() && j > 1) => tag.push(*c),
+            _ => return None,
+        }
+    }
+    None
+}
+
+
+fn first_line(statement: &str) -> &str {
+    statement.lines().map(|l| l.trim())
+        .find(|l| !l.is_empty() && !l.starts_with("--"))
+        .unwrap_or("")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_script_split_into_statements() {
+        let sql = "SET client_min_messages TO WARNING; \ncreate schema if not exists ror;\n\n-- a comment; with a semicolon\ndrop table if exists ror.t;  ";
+        assert_eq!(split_statements(sql), vec!["SET client_min_messages TO WARNING", "create schema if not exists ror",
+                                               "-- a comment; with a semicolon\ndrop table if exists ror.t"]);
+        assert!(split_statements("  ;\n-- nothing to run;\n/* or here; */ ;").is_empty());
+    }
+
+    #[test]
+    fn check_quoted_semicolons_not_split() {
+        let sql = r#"insert into t values ('a;b', 'it''s; here'); select "odd;name" from t; select 1"#;
+        assert_eq!(split_statements(sql), vec!["insert into t values ('a;b', 'it''s; here')",
+                                               r#"select "odd;name" from t"#, "select 1"]);
+
+        let sql = "create function f() returns int as $body$ select 1; $body$ language sql;\ndo $$ begin perform 1; end $$;\nselect $1;";
+        assert_eq!(split_statements(sql), vec!["create function f() returns int as $body$ select 1; $body$ language sql",
+                                               "do $$ begin perform 1; end $$", "select $1"]);
+    }
+
+    #[test]
+    fn check_first_line_skips_comments() {
+        assert_eq!(first_line("-- create the table\n  create table ror.t\n  (id int)"), "create table ror.t");
+    }
+}
@@ -1,6 +1,7 @@
 /***************************************************************************
  * Module uses std::env to read environmental values from an .env file 
- * (must be in same folder as src). These include the database parameters, 
+ * (by default in the same folder as src, but its path may be given using
+ * --env-file or the ROR1_ENV_FILE variable). These include the database parameters, 
  * which are loaded into a static singleton value, to allow it to be 
  * interrogated later in the program if necessary. During setup, however, 
  * the DB parameters are only used to provide a database connection string 
@@ -19,12 +20,17 @@
  * If both, the CLI arguments take precedence.
  * The results file name has a timestamped default name that will be used if 
  * none is provided explicitly.
+ * The values needed to resolve the program's parameters are returned together,
+ * as a Settings struct, by fetch_settings.
  ***************************************************************************/
 
 use std::sync::OnceLock;
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use dotenv;
+use super::{config_reader, normalise_path};
+use log::LevelFilter;
 use crate::error_defs::{AppError, CustomError};
 
 #[derive(Debug)]
@@ -38,15 +44,47 @@ pub struct DbPars {
 
 pub static DB_PARS: OnceLock<DbPars> = OnceLock::new();
 
+// The environment values used when the program's parameters are resolved,
+// read together (by fetch_settings) once the env and config files are loaded.
+// Empty values are left empty, as the CLI arguments may supply them instead.
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub data_folder: PathBuf,
+    pub log_folder: PathBuf,
+    pub output_folder: PathBuf,
+    pub text_output_folder: PathBuf,
+    pub csv_output_folder: PathBuf,
+    pub source_file_name: String,
+    pub output_file_name: String,
+    pub output_file_name_fixed: bool,
+    pub data_version: String,
+    pub data_date: String,
+    pub timestamp_format: String,
+    pub max_memory_mb: Option<u64>,
+    pub quiet_db: bool,
+}
+
 pub fn populate_env_vars() -> Result< (), AppError> {
+    populate_env_vars_from(Path::new(""), Path::new(""))
+}
 
-    // Use the dotenv from_filename function to load the variables into std::env.
+pub fn populate_env_vars_from(env_file: &Path, config_file: &Path) -> Result< (), AppError> {
 
-    let _env_res  = match dotenv::from_filename(".env")
-    {
-        Ok(pb) => pb,
-        Err(err) => return Err(AppError::DeErr(err)),
-    };
+    // Use dotenv to load the variables into std::env, from the env file named in
+    // the CLI, or failing that by the ROR1_ENV_FILE variable, or failing that 
+    // from the default '.env' file. Any values in a config file (if one has 
+    // been named) then replace these.
+
+    let mut env_file = env_file.to_path_buf();
+    if env_file.as_os_str().is_empty() {
+        env_file = PathBuf::from(env::var("ROR1_ENV_FILE").unwrap_or("".to_string()));
+    }
+    load_env_file(&env_file, ".env")?;
+
+    if !config_file.as_os_str().is_empty() {
+        config_reader::apply_config_file(config_file)?;
+    }
        
     // Extract the DB connection variables - N.B. user (name) and password have 
     // no meaningful defaults
@@ -78,6 +116,32 @@ pub fn populate_env_vars() -> Result< (), AppError> {
 
 }
  
+fn load_env_file(env_file: &Path, default_file_name: &str) -> Result<(), AppError> {
+
+    // An explicitly named env file must exist. The default file is looked for in 
+    // the current folder and its parents, but need not exist, as the values 
+    // may all be available from the environment or a config file instead.
+
+    if !env_file.as_os_str().is_empty() {
+        if !env_file.is_file() {
+            let msg = format!("The specified env file ({}) does not exist or is not accessible", env_file.display());
+            let cf_err = CustomError::new(&msg);
+            return Result::Err(AppError::CsErr(cf_err));
+        }
+        match dotenv::from_path(env_file) {
+            Ok(()) => Ok(()),
+            Err(err) => Err(AppError::DeErr(err)),
+        }
+    }
+    else {
+        match dotenv::from_filename(default_file_name) {
+            Ok(_) => Ok(()),
+            Err(dotenv::Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(AppError::DeErr(err)),
+        }
+    }
+}
+ 
 pub fn fetch_db_name() -> Result<String, AppError> {
     let db_pars = match DB_PARS.get() {
          Some(dbp) => dbp,
@@ -111,19 +175,57 @@ pub fn fetch_db_conn_string(db_name: String) -> Result<String, AppError> {
     }
 }
 
+pub fn fetch_settings() -> Settings {
+    Settings {
+        data_folder: fetch_data_folder(),
+        log_folder: fetch_log_folder(),
+        output_folder: fetch_output_folder(),
+        text_output_folder: fetch_text_output_folder(),
+        csv_output_folder: fetch_csv_output_folder(),
+        source_file_name: fetch_source_file_name(),
+        output_file_name: fetch_output_file_name(),
+        output_file_name_fixed: fetch_output_file_name_fixed(),
+        data_version: fetch_data_version(),
+        data_date: fetch_data_date(),
+        timestamp_format: fetch_timestamp_format(),
+        max_memory_mb: fetch_max_memory_mb(),
+        quiet_db: !fetch_db_log_slow(),
+    }
+}
+
 pub fn fetch_data_folder() -> PathBuf {
     let path_as_string = env::var("data_folder_path").unwrap_or("".to_string());
-    PathBuf::from(path_as_string.replace("\\", "/"))
+    normalise_path(&path_as_string)
 }
 
 pub fn fetch_log_folder() -> PathBuf {
     let path_as_string = env::var("log_folder_path").unwrap_or("".to_string());
-    PathBuf::from(path_as_string.replace("\\", "/"))
+    normalise_path(&path_as_string)
 }
 
 pub fn fetch_output_folder() -> PathBuf {
     let path_as_string = env::var("output_folder_path").unwrap_or("".to_string());
-    PathBuf::from(path_as_string.replace("\\", "/"))
+    normalise_path(&path_as_string)
+}
+
+pub fn fetch_text_output_folder() -> PathBuf {
+    let path_as_string = env::var("text_output_folder_path").unwrap_or("".to_string());
+    normalise_path(&path_as_string)
+}
+
+pub fn fetch_csv_output_folder() -> PathBuf {
+    let path_as_string = env::var("csv_output_folder_path").unwrap_or("".to_string());
+    normalise_path(&path_as_string)
+}
+
+pub fn fetch_db_scripts_folder() -> PathBuf {
+    let path_as_string = env::var("db_scripts_path").unwrap_or("".to_string());
+    if path_as_string.trim().is_empty() {
+        PathBuf::from("./db_scripts")
+    }
+    else {
+        normalise_path(&path_as_string)
+    }
 }
 
 pub fn fetch_source_file_name() -> String {
@@ -142,6 +244,162 @@ pub fn fetch_data_date() -> String {
     env::var("data_date").unwrap_or("".to_string())
 }
 
+pub fn fetch_timestamp_format() -> String {
+    env::var("timestamp_format").unwrap_or("".to_string())
+}
+
+pub fn fetch_log_json() -> bool {
+    let log_json = env::var("log_json").unwrap_or("".to_string());
+    matches!(log_json.trim().to_lowercase().as_str(), "true" | "yes" | "1")
+}
+
+pub fn fetch_output_file_name_fixed() -> bool {
+    let fixed = env::var("output_file_name_fixed").unwrap_or("".to_string());
+    matches!(fixed.trim().to_lowercase().as_str(), "true" | "yes" | "1")
+}
+
+pub fn fetch_db_log_slow() -> bool {
+
+    // Slow statements are logged unless explicitly turned off.
+
+    let log_slow = env::var("db_log_slow").unwrap_or("".to_string());
+    !matches!(log_slow.trim().to_lowercase().as_str(), "false" | "no" | "0" | "off")
+}
 
+pub fn fetch_read_db_conn_string() -> Option<String> {
 
+    // A separate (e.g. replica) database for the reporting queries is optional,
+    // given as 'read_db_conn_string' or, failing that, as READ_DATABASE_URL.
 
+    ["read_db_conn_string", "READ_DATABASE_URL"].iter()
+        .map(|key| env::var(key).unwrap_or("".to_string()).trim().to_string())
+        .find(|conn| !conn.is_empty())
+}
+
+pub fn fetch_log_max_size_mb() -> Option<u64> {
+    let max_size = env::var("log_max_size_mb").unwrap_or("".to_string());
+    max_size.trim().parse::<u64>().ok()
+}
+
+pub fn fetch_log_keep() -> Option<u32> {
+    let keep = env::var("log_keep").unwrap_or("".to_string());
+    keep.trim().parse::<u32>().ok()
+}
+
+pub fn fetch_log_value_max_len() -> usize {
+
+    // The number of characters after which long values, such as names and 
+    // links, are cut when logged. Defaults to 100; 0 means values are not cut.
+
+    let max_len = env::var("log_value_max_len").unwrap_or("".to_string());
+    max_len.trim().parse::<usize>().unwrap_or(100)
+}
+
+pub fn fetch_store_retries() -> u32 {
+    let retries = env::var("store_retries").unwrap_or("".to_string());
+    retries.trim().parse::<u32>().unwrap_or(3)
+}
+
+pub fn fetch_max_memory_mb() -> Option<u64> {
+    let max_memory = env::var("max_memory_mb").unwrap_or("".to_string());
+    max_memory.trim().parse::<u64>().ok().filter(|mb| *mb > 0)
+}
+
+pub fn fetch_default_flags() -> String {
+    env::var("default_flags").unwrap_or("".to_string()).trim().to_string()
+}
+
+pub fn fetch_slow_statement_threshold() -> Duration {
+
+    // The number of seconds, which may be fractional, after which a 
+    // statement is logged as slow. Defaults to 3 seconds.
+
+    let secs = env::var("slow_statement_secs").unwrap_or("".to_string());
+    match secs.trim().parse::<f64>() {
+        Ok(s) if s > 0.0 && s.is_finite() => Duration::from_secs_f64(s),
+        _ => Duration::from_secs(3),
+    }
+}
+
+pub fn fetch_log_console_level() -> Option<LevelFilter> {
+    let level = env::var("log_console_level").unwrap_or("".to_string());
+    level.trim().parse::<LevelFilter>().ok()
+}
+
+pub fn fetch_log_file_level() -> Option<LevelFilter> {
+    let level = env::var("log_file_level").unwrap_or("".to_string());
+    level.trim().parse::<LevelFilter>().ok()
+}
+
+pub fn fetch_log_pattern() -> Option<String> {
+    let pattern = env::var("log_pattern").unwrap_or("".to_string());
+    if pattern.trim() == "" { None } else { Some(pattern) }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn check_explicit_env_file_is_loaded() {
+        let env_file = env::temp_dir().join("ror1_test_explicit.env");
+        fs::write(&env_file, "ror1_test_env_marker=loaded_from_explicit_file\n").unwrap();
+        temp_env::with_var("ror1_test_env_marker", None::<&str>, || {
+            load_env_file(&env_file, ".env").unwrap();
+            assert_eq!(env::var("ror1_test_env_marker").unwrap(), "loaded_from_explicit_file");
+        });
+        fs::remove_file(&env_file).unwrap();
+    }
+
+    #[test]
+    fn check_missing_explicit_env_file_is_an_error() {
+        let env_file = env::temp_dir().join("ror1_test_no_such_file.env");
+        let res = load_env_file(&env_file, ".env");
+        assert!(matches!(res, Err(AppError::CsErr(_))));
+    }
+
+    #[test]
+    fn check_settings_read_from_env() {
+        temp_env::with_vars(
+        [
+            ("data_folder_path", Some("/ror/data/")),
+            ("log_folder_path", Some("/ror/logs")),
+            ("output_folder_path", Some("/ror/outputs")),
+            ("text_output_folder_path", Some("")),
+            ("csv_output_folder_path", Some("/ror/csv")),
+            ("src_file_name", Some("v1.58 20241211.json")),
+            ("output_file_name", Some("results")),
+            ("output_file_name_fixed", Some("Yes")),
+            ("data_version", None),
+            ("data_date", Some("2024-12-11")),
+            ("timestamp_format", Some("%Y%m%dT%H%M%S")),
+            ("max_memory_mb", Some("2048")),
+            ("db_log_slow", Some("off")),
+        ],
+        || {
+            assert_eq!(fetch_settings(), Settings {
+                data_folder: PathBuf::from("/ror/data"),
+                log_folder: PathBuf::from("/ror/logs"),
+                output_folder: PathBuf::from("/ror/outputs"),
+                text_output_folder: PathBuf::new(),
+                csv_output_folder: PathBuf::from("/ror/csv"),
+                source_file_name: "v1.58 20241211.json".to_string(),
+                output_file_name: "results".to_string(),
+                output_file_name_fixed: true,
+                data_version: "".to_string(),
+                data_date: "2024-12-11".to_string(),
+                timestamp_format: "%Y%m%dT%H%M%S".to_string(),
+                max_memory_mb: Some(2048),
+                quiet_db: true,
+            });
+        });
+    }
+
+    #[test]
+    fn check_missing_default_env_file_is_not_an_error() {
+        let res = load_env_file(Path::new(""), "ror1_test_no_such_default.env");
+        assert!(res.is_ok());
+    }
+}
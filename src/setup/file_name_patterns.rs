@@ -0,0 +1,69 @@
+/***************************************************************************
+ * The regular expressions used to obtain the data version and date from a
+ * source file name, e.g. 'v1.58-2024-12-11-ror-data_schema_v2.json'. The
+ * pattern strings are public, so that they can be reused (e.g. by tests, or
+ * a program embedding the library), and each is compiled only once, on first
+ * use, rather than each time a file name is examined.
+ ***************************************************************************/
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+// A compliant name starts with the version, followed by the date. The month 
+// may be a number or one of the three letter abbreviations (in any case).
+
+pub const FILE_NAME_PATTERN: &str = r#"^v[0-9]+(\.[0-9]+){0,2}(-| |_)20[0-9]{2}[-_]?([01][0-9]|(?i:jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec))[-_]?[0-3][0-9]"#;
+
+pub const VERSION_PATTERN: &str = r#"^v[0-9]+(\.[0-9]+){0,2}"#;
+
+pub const DATE_PATTERN: &str = r#"20[0-9]{2}[-_]?[01][0-9][-_]?[0-3][0-9]"#;
+
+// The month as a three letter English abbreviation, e.g. 2025-Jan-23.
+
+pub const MONTH_NAME_DATE_PATTERN: &str = r#"(20[0-9]{2})[-_]?((?i:jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec))[-_]?([0-3][0-9])"#;
+
+
+pub fn file_name_regex() -> &'static Regex {
+    static FILE_NAME_RE: OnceLock<Regex> = OnceLock::new();
+    FILE_NAME_RE.get_or_init(|| Regex::new(FILE_NAME_PATTERN).unwrap())
+}
+
+pub fn version_regex() -> &'static Regex {
+    static VERSION_RE: OnceLock<Regex> = OnceLock::new();
+    VERSION_RE.get_or_init(|| Regex::new(VERSION_PATTERN).unwrap())
+}
+
+pub fn date_regex() -> &'static Regex {
+    static DATE_RE: OnceLock<Regex> = OnceLock::new();
+    DATE_RE.get_or_init(|| Regex::new(DATE_PATTERN).unwrap())
+}
+
+pub fn month_name_date_regex() -> &'static Regex {
+    static MONTH_NAME_DATE_RE: OnceLock<Regex> = OnceLock::new();
+    MONTH_NAME_DATE_RE.get_or_init(|| Regex::new(MONTH_NAME_DATE_PATTERN).unwrap())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_patterns_match_ror_file_names() {
+        assert!(file_name_regex().is_match("v1.58-2024-12-11-ror-data_schema_v2.json"));
+        assert!(!file_name_regex().is_match("ror-data-v1.58.json"));
+        assert_eq!(&version_regex().captures("v1.58-2024-12-11").unwrap()[0], "v1.58");
+        assert_eq!(&date_regex().captures("v1.58-2024-12-11").unwrap()[0], "2024-12-11");
+        assert_eq!(&month_name_date_regex().captures("v1.59 2025-Jan-23").unwrap()[2], "Jan");
+        assert_eq!(&month_name_date_regex().captures("v1.59 2025-SEP-23").unwrap()[2], "SEP");
+        assert!(!month_name_date_regex().is_match("v1.59-2025-Xyz-23"));
+    }
+
+    #[test]
+    fn check_compiled_regexes_reused() {
+        assert!(std::ptr::eq(file_name_regex(), file_name_regex()));
+        assert!(std::ptr::eq(version_regex(), version_regex()));
+        assert!(std::ptr::eq(date_regex(), date_regex()));
+        assert!(std::ptr::eq(month_name_date_regex(), month_name_date_regex()));
+    }
+}
@@ -0,0 +1,94 @@
+/***************************************************************************
+ * Handles an interrupt (Ctrl-C) during the import of a source file. Rather
+ * than the process ending part way through storing a batch, an interrupt
+ * received while records are being stored only records that a stop has been
+ * requested. The import checks this after each batch has been stored, and
+ * then ends with an 'interrupted' error, having logged the number of records
+ * committed to the ror tables. No part of a batch is committed until all of
+ * it has been stored, so no partially written batch is left behind. (With
+ * --atomic nothing has been committed, and the file's records are rolled
+ * back.) The request is cleared when the import ends, so that a later
+ * import in the same process is not stopped.
+ * Ctrl-C is only listened for (with tokio's ctrl_c) once an import starts to
+ * store records - before then the default handling applies, and the process
+ * ends at once. Tokio's handler remains in place for the rest of the process,
+ * so an interrupt at any other time, or a second interrupt during an import,
+ * ends the process at once, with the same exit code as an interrupted import.
+ ***************************************************************************/
+
+use log::warn;
+use std::process;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::task::JoinHandle;
+
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+static STORING_RECORDS: AtomicBool = AtomicBool::new(false);
+static LISTENER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
+
+// Marks the records of an import as being stored, for as long as it exists.
+
+pub struct StoringRecords;
+
+impl Drop for StoringRecords {
+
+    // A stop applies only to the import it was requested in, so that any
+    // later import in the same process (e.g. by run_pipeline) is not stopped.
+
+    fn drop(&mut self) {
+        STORING_RECORDS.store(false, Ordering::SeqCst);
+        clear_stop();
+    }
+}
+
+
+pub fn watch_for_interrupt() -> StoringRecords {
+    start_listener();
+    STORING_RECORDS.store(true, Ordering::SeqCst);
+    StoringRecords
+}
+
+
+fn start_listener() {
+
+    // A single listener is used, so that each interrupt is dealt with once.
+    // It ends with the runtime it was started in, so a new one is needed if
+    // the library is then used from another runtime.
+
+    let mut listener = LISTENER.lock().unwrap_or_else(|e| e.into_inner());
+    if listener.as_ref().is_some_and(|h| !h.is_finished()) {
+        return;
+    }
+    if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+        *listener = Some(runtime.spawn(listen_for_interrupts()));
+    }
+}
+
+
+async fn listen_for_interrupts() {
+    while tokio::signal::ctrl_c().await.is_ok() {
+        if STORING_RECORDS.load(Ordering::SeqCst) && !stop_requested() {
+            warn!("Interrupt received - the import will stop once the current batch of records has been stored");
+            request_stop();
+        }
+        else {
+            process::exit(130);    // the exit code of an interrupted import
+        }
+    }
+}
+
+
+pub fn request_stop() {
+    STOP_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+
+pub fn clear_stop() {
+    STOP_REQUESTED.store(false, Ordering::SeqCst);
+}
+
+
+pub fn stop_requested() -> bool {
+    STOP_REQUESTED.load(Ordering::SeqCst)
+}
@@ -6,33 +6,148 @@
  ***************************************************************************/
 
 use chrono::Local;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use crate::error_defs::AppError;
-use crate::setup::InitParams;
+use crate::setup::{InitParams, get_timestamp, is_future_date};
+use crate::setup::env_reader;
+use crate::setup::slow_statements::{SlowStatementCounter, slow_statement_count};
 
-use log::{info, LevelFilter};
+use log::{info, warn, LevelFilter};
 use log4rs::{
     append::{
+        Append,
         console::{ConsoleAppender, Target},
         file::FileAppender,
+        rolling_file::{
+            RollingFileAppender,
+            policy::compound::{
+                CompoundPolicy, 
+                roll::fixed_window::FixedWindowRoller, 
+                trigger::size::SizeTrigger,
+            },
+        },
     },
     config::{Appender, Config, Root},
-    encode::pattern::PatternEncoder,
+    encode::{Encode, json::JsonEncoder, pattern::PatternEncoder, writer::simple::SimpleWriter},
+    filter::threshold::ThresholdFilter,
 };
 
-pub fn setup_log (data_folder: &PathBuf, source_file_name : &String) -> Result<log4rs::Handle, AppError> {
+// The pattern used for each line of the log file and the console, unless 
+// another is given (as 'log_pattern') in the environment.
+
+pub const DEFAULT_LOG_PATTERN: &str = "{d(%d/%m %H:%M:%S)}  {h({l})}  {({M}.{L}):>38.48}:  {m}\n";
+
+// Size based rotation of the log file, if requested in the environment.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogRotation {
+    pub max_size_bytes: u64,
+    pub keep: u32,
+}
+
+// The thresholds of the console and the log file(s), which may differ. The 
+// root logger must pass anything either of them will accept.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogLevels {
+    pub console: LevelFilter,
+    pub file: LevelFilter,
+}
+
+impl LogLevels {
+    pub fn root(&self) -> LevelFilter {
+        std::cmp::max(self.console, self.file)
+    }
+}
+
+pub fn setup_log (data_folder: &PathBuf, source_file_name : &String, log_level: LevelFilter, 
+                  tail_log: bool) -> Result<log4rs::Handle, AppError> {
     let log_file_path = get_log_file_path(data_folder, source_file_name);
-    config_log (&log_file_path)
+    let log_json = env_reader::fetch_log_json();
+    let rotation = get_log_rotation(env_reader::fetch_log_max_size_mb(), env_reader::fetch_log_keep());
+    let log_pattern = get_log_pattern(env_reader::fetch_log_pattern())?;
+    let levels = get_log_levels(log_level, env_reader::fetch_log_console_level(), env_reader::fetch_log_file_level());
+    let handle = config_log (&log_file_path, log_json, rotation, &log_pattern, levels)?;
+
+    // The full path of the log file is recorded in the log, and if requested (by
+    // --tail-log) also printed to stdout, whatever the log level, so that it can
+    // be found (e.g. to tail it) when the program is run by a service manager.
+
+    let full_path = get_full_path(&log_file_path);
+    info!("Log file: {}", full_path.display());
+    if tail_log {
+        println!("Log file: {}", full_path.display());
+    }
+    Ok(handle)
+}
+
+fn get_full_path(file_path: &Path) -> PathBuf {
+    match fs::canonicalize(file_path) {
+        Ok(p) => p,
+        Err(_) => std::path::absolute(file_path).unwrap_or(file_path.to_path_buf()),
+    }
+}
+
+fn get_log_levels(log_level: LevelFilter, console_level: Option<LevelFilter>, file_level: Option<LevelFilter>) -> LogLevels {
+
+    // Each destination uses the level given for it in the environment, if any, 
+    // and otherwise the overall level (info, unless changed by -q or -v).
+
+    LogLevels {
+        console: console_level.unwrap_or(log_level),
+        file: file_level.unwrap_or(log_level),
+    }
+}
+
+fn get_log_pattern(env_pattern: Option<String>) -> Result<String, AppError> {
+
+    // log4rs does not reject an invalid pattern, but writes an '{ERROR: ...}'  
+    // message into each log line, so the pattern is checked by using it to 
+    // encode a sample record. A line end is added if the pattern lacks one.
+
+    let mut pattern = match env_pattern {
+        Some(p) => p,
+        None => return Ok(DEFAULT_LOG_PATTERN.to_string()),
+    };
+    if !pattern.ends_with('\n') && !pattern.ends_with("{n}") {
+        pattern.push_str("{n}");
+    }
+    let mut writer = SimpleWriter(Vec::new());
+    let record = log::Record::builder().args(format_args!("test")).level(log::Level::Info).build();
+    if let Err(e) = PatternEncoder::new(&pattern).encode(&mut writer, &record) {
+        return Err(AppError::LgErr(format!("unable to use log_pattern '{}': {}", pattern, e).into()));
+    }
+    let sample = String::from_utf8_lossy(&writer.0).to_string();
+    if let Some((_, rest)) = sample.split_once("{ERROR: ") {
+        let msg = rest.split('}').next().unwrap_or_default();
+        return Err(AppError::LgErr(format!("invalid log_pattern '{}': {}", pattern.trim_end(), msg).into()));
+    }
+    Ok(pattern)
+}
+
+fn get_log_rotation(max_size_mb: Option<u64>, keep: Option<u32>) -> Option<LogRotation> {
+
+    // If neither value is supplied a single log file is used, as before. 
+    // Otherwise any missing value is given a default (10 MB, 5 files).
+
+    if max_size_mb.is_none() && keep.is_none() {
+        return None;
+    }
+    Some(LogRotation {
+        max_size_bytes: max_size_mb.unwrap_or(10) * 1024 * 1024,
+        keep: keep.unwrap_or(5),
+    })
 }
 
 fn get_log_file_path(data_folder: &PathBuf, source_file_name : &String) -> PathBuf {
     
     // Derives the log file name, returns the full path
 
-    let datetime_string = Local::now().format("%m-%d %H%M%S").to_string();
-    let mut log_file_name = format!("ror {} ", datetime_string);
+    let mut log_file_name = format!("ror {} ", get_timestamp());
     if source_file_name != "" {
-        let source_file = &source_file_name[..(source_file_name.len() - 5)];
+        let source_file = get_source_file_stem(source_file_name);
         log_file_name = format!("{} from {}.log", log_file_name, source_file);
     }
     else {
@@ -42,11 +157,36 @@ fn get_log_file_path(data_folder: &PathBuf, source_file_name : &String) -> PathB
     
 }
 
-fn config_log (log_file_path: &PathBuf) -> Result<log4rs::Handle, AppError> {
+fn get_source_file_stem(source_file_name: &str) -> &str {
+
+    // Removes the extension (.json, .json.gz or .gz) if present. Any other 
+    // name, including a short or non-ASCII one, is used unchanged.
+
+    for ext in [".json.gz", ".json", ".gz"] {
+        if let Some(stem) = source_file_name.strip_suffix(ext) {
+            return stem;
+        }
+    }
+    source_file_name
+}
+
+fn config_log (log_file_path: &PathBuf, log_json: bool, rotation: Option<LogRotation>, 
+               log_pattern: &str, levels: LogLevels) -> Result<log4rs::Handle, AppError> {
     
-    // Initially establish a pattern for each log line.
+    let config = get_log_config(log_file_path, log_json, rotation, log_pattern, levels)?;
 
-    let log_pattern = "{d(%d/%m %H:%M:%S)}  {h({l})}  {({M}.{L}):>38.48}:  {m}\n";
+    match log4rs::init_config(config)
+    {
+        Ok(h) => return Ok(h),
+        Err(e) => return Err(AppError::from(e)),
+    };
+
+}
+
+fn get_log_config (log_file_path: &PathBuf, log_json: bool, rotation: Option<LogRotation>,
+                   log_pattern: &str, levels: LogLevels) -> Result<Config, AppError> {
+    
+    // The pattern for each log line (already checked) is used by both appenders.
 
     // Define a stderr logger, as one of the 'logging' sinks or 'appender's.
 
@@ -54,33 +194,73 @@ fn config_log (log_file_path: &PathBuf) -> Result<log4rs::Handle, AppError> {
         .target(Target::Stderr).build();
 
     // Define a second logging sink or 'appender' - to a log file (provided path will place it in the current data folder).
+    // If rotation is specified the file is rolled over when it reaches the maximum size, with older 
+    // files renamed with a numeric suffix (e.g. 'xxx.1.log') and only the specified number retained.
 
-    let try_logfile = FileAppender::builder().encoder(Box::new(PatternEncoder::new(log_pattern)))
-        .build(log_file_path);
-    let logfile = match try_logfile {
-        Ok(lf) => lf,
-        Err(e) => return Err(AppError::IoErr(e)),
+    let logfile: Box<dyn Append> = match rotation {
+        None => {
+            let try_logfile = FileAppender::builder().encoder(Box::new(PatternEncoder::new(log_pattern)))
+                .build(log_file_path);
+            match try_logfile {
+                Ok(lf) => Box::new(lf),
+                Err(e) => return Err(AppError::IoErr(e)),
+            }
+        },
+        Some(r) => {
+            let roll_pattern = log_file_path.with_extension("{}.log");
+            let roller = match FixedWindowRoller::builder().base(1).build(&roll_pattern.to_string_lossy(), r.keep) {
+                Ok(fr) => fr,
+                Err(e) => return Err(AppError::IoErr(std::io::Error::other(e.to_string()))),
+            };
+            let policy = CompoundPolicy::new(Box::new(SizeTrigger::new(r.max_size_bytes)), Box::new(roller));
+            let try_logfile = RollingFileAppender::builder().encoder(Box::new(PatternEncoder::new(log_pattern)))
+                .build(log_file_path, Box::new(policy));
+            match try_logfile {
+                Ok(lf) => Box::new(lf),
+                Err(e) => return Err(AppError::IoErr(e)),
+            }
+        },
     };
 
-    // Configure and build log4rs instance, using the two appenders described above
+    // Configure and build log4rs instance, using the two appenders described above.
+    // Each appender has its own threshold filter, the root level being the lower of the two.
+    // A third appender, which writes nothing, counts any slow statement warnings.
 
-    let config = Config::builder()
+    let mut config_builder = Config::builder()
         .appender(Appender::builder()
-                .build("logfile", Box::new(logfile)),)
+                .filter(Box::new(ThresholdFilter::new(levels.file)))
+                .build("logfile", logfile),)
         .appender(Appender::builder()
+                .filter(Box::new(ThresholdFilter::new(levels.console)))
                 .build("stderr", Box::new(stderr)),)
-        .build(Root::builder()
+        .appender(Appender::builder()
+                .build("slow_statements", Box::new(SlowStatementCounter)),);
+    let mut root_builder = Root::builder()
                 .appender("logfile")
                 .appender("stderr")
-                .build(LevelFilter::Info),
-        ).unwrap();
+                .appender("slow_statements");
 
-    match log4rs::init_config(config)
-    {
-        Ok(h) => return Ok(h),
-        Err(e) => return Err(AppError::LgErr(e)),
-    };
+    // If requested, add a third appender, writing each log record as a line 
+    // of JSON, to a file with the same name as the log but a .json extension.
+
+    if log_json {
+        let try_jsonfile = FileAppender::builder().encoder(Box::new(JsonEncoder::new()))
+            .build(log_file_path.with_extension("json"));
+        let jsonfile = match try_jsonfile {
+            Ok(jf) => jf,
+            Err(e) => return Err(AppError::IoErr(e)),
+        };
+        config_builder = config_builder.appender(Appender::builder()
+                .filter(Box::new(ThresholdFilter::new(levels.file)))
+                .build("jsonfile", Box::new(jsonfile)));
+        root_builder = root_builder.appender("jsonfile");
+    }
 
+    let config = config_builder
+        .build(root_builder.build(levels.root()))
+        .unwrap();
+
+    Ok(config)
 }
 
 
@@ -95,18 +275,450 @@ pub fn log_startup_params (ip : &InitParams) {
     info!("data_folder: {}", ip.data_folder.display());
     info!("log_folder: {}", ip.log_folder.display());
     info!("output_folder: {}", ip.output_folder.display());
+    if ip.text_output_folder != ip.output_folder {
+        info!("text_output_folder: {}", ip.text_output_folder.display());
+    }
+    if ip.csv_output_folder != ip.output_folder {
+        info!("csv_output_folder: {}", ip.csv_output_folder.display());
+    }
+    if ip.source_files.len() > 1 {
+        for sf in ip.source_files.iter() {
+            info!("source_file: {} (version {}, date {})", sf.file_name, sf.data_version, sf.data_date);
+        }
+    }
     info!("source_file_name: {}", ip.source_file_name);
     info!("output_file_name: {}", ip.output_file_name);
     info!("data_version: {}", ip.data_version);
     info!("data_date: {}", ip.data_date);
+    if !ip.flags.test_run && is_future_date(&ip.data_date, Local::now().date_naive()) {
+        warn!("data_date {} is later than today's date - it is probably an error", ip.data_date);
+    }
+    if !ip.since_date.is_empty() {
+        info!("since_date: {}", ip.since_date);
+    }
+    if !ip.tables.is_empty() {
+        info!("tables: {}", ip.tables.join(", "));
+    }
+    if !ip.types.is_empty() {
+        info!("types: {}", ip.types.join(", "));
+    }
+    if !ip.sqlite_path.is_empty() {
+        info!("sqlite_path: {} (import only - data imported into SQLite rather than Postgres)", ip.sqlite_path);
+    }
+    if !ip.trace_id.is_empty() {
+        info!("trace_id: {}", ip.trace_id);
+    }
+    info!("log_level: {}", ip.log_level);
     info!("create look up tables: {}", ip.flags.create_lookups);
     info!("create summary tables: {}", ip.flags.create_summary);
     info!("import_ror: {}", ip.flags.import_ror);
+    if ip.flags.stdin {
+        info!("source read from stdin");
+    }
+    if let Some(mb) = ip.max_memory_mb {
+        info!("max_memory: {} MB", mb);
+    }
+    info!("no_recreate: {}", ip.flags.no_recreate);
+    info!("analyze: {}", ip.flags.analyze);
+    if ip.flags.quiet_db {
+        info!("quiet_db: true (slow statements not logged)");
+    }
+    if ip.flags.active_only {
+        info!("active_only: true (inactive and withdrawn records not imported)");
+    }
+    else if ip.flags.exclude_withdrawn {
+        info!("exclude_withdrawn: true (withdrawn records not imported)");
+    }
+    if ip.flags.resume {
+        info!("resume: true (import resumed from any checkpoint)");
+    }
+    if ip.flags.checkpoint {
+        info!("checkpoint: true (import progress recorded in a checkpoint file)");
+    }
+    if ip.flags.atomic {
+        info!("atomic: true (each source file imported within a single transaction)");
+    }
+    if ip.flags.dump_failed {
+        info!("dump_failed: true (a batch that cannot be stored written to a replay file)");
+    }
+    if ip.flags.replace {
+        info!("replace: true (last record kept for duplicated ids)");
+    }
+    if ip.flags.recreate_src {
+        info!("recreate_src: true");
+    }
+    info!("profile: {}", ip.flags.profile);
+    info!("dedup: {}", ip.flags.dedup);
     info!("process_data: {}", ip.flags.process_data);
+    info!("summarise_data: {}", ip.flags.summarise_data);
+    info!("keep_raw: {}", ip.flags.keep_raw);
+    if ip.flags.dump_lookups {
+        info!("dump_lookups: true (lup codes and names logged)");
+    }
     info!("export_text: {}", ip.flags.export_text);
+    if ip.flags.overwrite {
+        info!("overwrite: true (fixed output file name)");
+    }
+    if ip.flags.tail_log {
+        info!("tail_log: true (log path printed to stdout)");
+    }
     info!("export_csv: {}", ip.flags.export_csv);
+    if ip.flags.append_csv {
+        info!("append_csv: true (csv summary appended to existing files)");
+    }
+    info!("export_json: {}", ip.flags.export_json);
+    info!("export_country_csv: {}", ip.flags.export_country_csv);
     info!("export_all_csv: {}", ip.flags.export_full_csv);
+    if ip.csv_delimiter != ',' || ip.flags.csv_bom {
+        info!("csv_delimiter: {:?}, csv_bom: {}", ip.csv_delimiter, ip.flags.csv_bom);
+    }
+    info!("validate_only: {}", ip.flags.validate);
+    info!("count_only: {}", ip.flags.count_only);
+    info!("check_db: {}", ip.flags.check_db);
+    if ip.flags.drop_all {
+        info!("drop_all: true");
+    }
+    if ip.flags.only_new_ids {
+        info!("only_new_ids: true");
+    }
+    if ip.flags.compare_versions {
+        info!("compare_versions: {}", ip.compare_versions.join(", "));
+    }
+    if ip.flags.show_history {
+        info!("show_history: last {} runs", ip.history_count);
+    }
+    if ip.flags.report {
+        info!("report: true");
+    }
+    if ip.flags.strict_utf8 {
+        info!("strict_utf8: true");
+    }
+    info!("");
+    info!("************************************");
+    info!("");
+}
+
+
+// Holds the time taken by each of the major phases of a run. 
+// Phases that have not (or not yet) been run are left as None.
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PhaseTimings {
+    pub import: Option<Duration>,
+    pub transform: Option<Duration>,
+    pub summary: Option<Duration>,
+    pub export: Option<Duration>,
+}
+
+
+pub fn log_phase_duration (phase: &str, duration: &Duration) {
+    info!("{} phase completed in {}", phase, format_duration(duration));
+}
+
+
+pub fn log_total_duration (timings: &PhaseTimings, total: &Duration, quiet_db: bool) {
+
+    // Called at the end of the run to summarise the phase timings
+
     info!("");
     info!("************************************");
     info!("");
-}
\ No newline at end of file
+    let phases = [("import", timings.import), ("transform", timings.transform),
+                  ("summary", timings.summary), ("export", timings.export)];
+    for (phase, duration) in phases {
+        if let Some(d) = duration {
+            info!("{}: {}", phase, format_duration(&d));
+        }
+    }
+    if quiet_db {
+        info!("Slow statements not logged (quiet_db)");
+    }
+    else {
+        info!("{}", get_slow_statements_line(slow_statement_count(), &env_reader::fetch_slow_statement_threshold()));
+    }
+    info!("PROGRAM END - total time {}", format_duration(total));
+    info!("");
+    info!("************************************");
+    info!("");
+}
+
+
+fn get_slow_statements_line(count: usize, threshold: &Duration) -> String {
+    let noun = if count == 1 { "statement" } else { "statements" };
+    format!("{} slow {} (>{}s)", count, noun, threshold.as_secs_f64())
+}
+
+
+pub fn format_duration (duration: &Duration) -> String {
+
+    // Returns the duration as seconds, to millisecond precision, 
+    // preceded by minutes and hours if the duration is long enough.
+
+    let total_secs = duration.as_secs();
+    let hours = total_secs / 3600;
+    let mins = (total_secs % 3600) / 60;
+    let secs = total_secs % 60;
+    let millis = duration.subsec_millis();
+
+    if hours > 0 {
+        format!("{}h {:02}m {:02}.{:03}s", hours, mins, secs, millis)
+    }
+    else if mins > 0 {
+        format!("{}m {:02}.{:03}s", mins, secs, millis)
+    }
+    else {
+        format!("{}.{:03}s", secs, millis)
+    }
+}
+
+
+pub fn truncate_for_log(value: &str) -> String {
+    truncate_value(value, env_reader::fetch_log_value_max_len())
+}
+
+
+pub fn truncate_value(value: &str, max_chars: usize) -> String {
+
+    // Long values, e.g. organisation names or links, are cut to the maximum 
+    // number of characters (not bytes, so that a multibyte character is never
+    // split), the last being replaced by an ellipsis. A maximum of 0 means 
+    // that values are never cut.
+
+    if max_chars == 0 || value.chars().count() <= max_chars {
+        return value.to_string();
+    }
+    let end = value.char_indices().nth(max_chars - 1).map_or(value.len(), |(i, _)| i);
+    format!("{}\u{2026}", &value[..end])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::{Log, Record, Level};
+    use log4rs::filter::Response;
+    use chrono::NaiveDateTime;
+    use std::fs;
+
+    // The config is used to create a logger directly, rather than being
+    // initialised as the global logger, which can only be done once.
+
+    #[test]
+    fn check_json_log_created_when_requested() {
+        let log_folder = std::env::temp_dir().join("ror1_json_log_test");
+        fs::create_dir_all(&log_folder).unwrap();
+        let log_file_path = log_folder.join("test run.log");
+        let json_file_path = log_folder.join("test run.json");
+        let _ = fs::remove_file(&json_file_path);
+
+        let config = get_log_config(&log_file_path, true, None, DEFAULT_LOG_PATTERN, uniform(LevelFilter::Info)).unwrap();
+        let logger = log4rs::Logger::new(config);
+        logger.log(&Record::builder().args(format_args!("first test message"))
+                                .level(Level::Info).target("ror1").build());
+        logger.log(&Record::builder().args(format_args!("second test message"))
+                                .level(Level::Info).target("ror1").build());
+        Log::flush(&logger);
+
+        let contents = fs::read_to_string(&json_file_path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for line in lines {
+            let v: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(v["level"], "INFO");
+        }
+        assert!(contents.contains("second test message"));
+    }
+
+    #[test]
+    fn check_json_log_not_created_by_default() {
+        let log_folder = std::env::temp_dir().join("ror1_no_json_log_test");
+        fs::create_dir_all(&log_folder).unwrap();
+        let log_file_path = log_folder.join("test run.log");
+        let json_file_path = log_folder.join("test run.json");
+        let _ = fs::remove_file(&json_file_path);
+
+        let _config = get_log_config(&log_file_path, false, None, DEFAULT_LOG_PATTERN, uniform(LevelFilter::Info)).unwrap();
+        assert!(!json_file_path.exists());
+    }
+
+    #[test]
+    fn check_slow_statements_counted() {
+        let log_folder = std::env::temp_dir().join("ror1_slow_statements_test");
+        fs::create_dir_all(&log_folder).unwrap();
+        let config = get_log_config(&log_folder.join("test run.log"), false, None, 
+                                    DEFAULT_LOG_PATTERN, uniform(LevelFilter::Info)).unwrap();
+        let logger = log4rs::Logger::new(config);
+
+        let before = slow_statement_count();
+        logger.log(&Record::builder().args(format_args!("slow statement: execution time exceeded alert threshold"))
+                                .level(Level::Warn).target("sqlx::query").build());
+        logger.log(&Record::builder().args(format_args!("select * from ror.core_data"))
+                                .level(Level::Info).target("sqlx::query").build());
+        logger.log(&Record::builder().args(format_args!("slow statement"))
+                                .level(Level::Warn).target("ror1").build());
+        assert_eq!(slow_statement_count() - before, 1);
+
+        assert_eq!(get_slow_statements_line(1, &Duration::from_secs(3)), "1 slow statement (>3s)");
+        assert_eq!(get_slow_statements_line(4, &Duration::from_millis(2500)), "4 slow statements (>2.5s)");
+    }
+
+    fn uniform(level: LevelFilter) -> LogLevels {
+        LogLevels { console: level, file: level }
+    }
+
+    #[test]
+    fn check_log_level_applied_to_root() {
+        let log_folder = std::env::temp_dir().join("ror1_log_level_test");
+        fs::create_dir_all(&log_folder).unwrap();
+        let log_file_path = log_folder.join("test run.log");
+
+        let config = get_log_config(&log_file_path, false, None, DEFAULT_LOG_PATTERN, uniform(LevelFilter::Warn)).unwrap();
+        assert_eq!(config.root().level(), LevelFilter::Warn);
+        let config = get_log_config(&log_file_path, false, None, DEFAULT_LOG_PATTERN, uniform(LevelFilter::Trace)).unwrap();
+        assert_eq!(config.root().level(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn check_console_and_file_levels_applied_separately() {
+        let log_folder = std::env::temp_dir().join("ror1_split_level_test");
+        fs::create_dir_all(&log_folder).unwrap();
+        let log_file_path = log_folder.join("test run.log");
+        let _ = fs::remove_file(&log_file_path);
+
+        let levels = get_log_levels(LevelFilter::Info, Some(LevelFilter::Warn), Some(LevelFilter::Debug));
+        assert_eq!(levels, LogLevels { console: LevelFilter::Warn, file: LevelFilter::Debug });
+        let config = get_log_config(&log_file_path, false, None, DEFAULT_LOG_PATTERN, levels).unwrap();
+        assert_eq!(config.root().level(), LevelFilter::Debug);
+
+        // The debug record passes the root and the log file filter, but not the console's.
+
+        let record = Record::builder().args(format_args!("debug detail")).level(Level::Debug).target("ror1").build();
+        let accepted = |name: &str| {
+            let appender = config.appenders().iter().find(|a| a.name() == name).unwrap();
+            appender.filters().iter().all(|f| !matches!(f.filter(&record), Response::Reject))
+        };
+        assert!(accepted("logfile"));
+        assert!(!accepted("stderr"));
+
+        let logger = log4rs::Logger::new(config);
+        logger.log(&record);
+        Log::flush(&logger);
+        let contents = fs::read_to_string(&log_file_path).unwrap();
+        assert!(contents.contains("DEBUG") && contents.contains("debug detail"));
+
+        let levels = get_log_levels(LevelFilter::Warn, None, None);
+        assert_eq!(levels, uniform(LevelFilter::Warn));
+    }
+
+    #[test]
+    fn check_source_file_stem_derived_safely() {
+        assert_eq!(get_source_file_stem("v1.59-2025-01-23-ror-data_schema_v2.json"), "v1.59-2025-01-23-ror-data_schema_v2");
+        assert_eq!(get_source_file_stem("v1.59 données é.json.gz"), "v1.59 données é");
+        assert_eq!(get_source_file_stem("v1.59 数据"), "v1.59 数据");
+        assert_eq!(get_source_file_stem("a.js"), "a.js");
+        assert_eq!(get_source_file_stem("é"), "é");
+        assert_eq!(get_source_file_stem("v1.59 data.txt"), "v1.59 data.txt");
+
+        let path = get_log_file_path(&PathBuf::from("logs"), &"ror é.gz".to_string());
+        assert!(path.to_str().unwrap().ends_with(" from ror é.log"));
+    }
+
+    #[test]
+    fn check_custom_log_pattern_used() {
+        let log_folder = std::env::temp_dir().join("ror1_log_pattern_test");
+        fs::create_dir_all(&log_folder).unwrap();
+        let log_file_path = log_folder.join("test run.log");
+        let _ = fs::remove_file(&log_file_path);
+
+        let pattern = get_log_pattern(Some("{d(%Y-%m-%dT%H:%M:%S)} [{l}] {t} - {m}".to_string())).unwrap();
+        assert_eq!(pattern, "{d(%Y-%m-%dT%H:%M:%S)} [{l}] {t} - {m}{n}");
+        let config = get_log_config(&log_file_path, false, None, &pattern, uniform(LevelFilter::Info)).unwrap();
+        let logger = log4rs::Logger::new(config);
+        logger.log(&Record::builder().args(format_args!("patterned message"))
+                                .level(Level::Warn).target("ror1").build());
+        Log::flush(&logger);
+
+        let contents = fs::read_to_string(&log_file_path).unwrap();
+        let line = contents.lines().next().unwrap();
+        assert!(line.ends_with(" [WARN] ror1 - patterned message"));
+        assert!(NaiveDateTime::parse_from_str(&line[..19], "%Y-%m-%dT%H:%M:%S").is_ok());
+    }
+
+    #[test]
+    fn check_log_pattern_default_and_validation() {
+        assert_eq!(get_log_pattern(None).unwrap(), DEFAULT_LOG_PATTERN);
+        assert_eq!(get_log_pattern(Some("{l} {m}\n".to_string())).unwrap(), "{l} {m}\n");
+        let err = get_log_pattern(Some("{l} {nonsense} {m}".to_string())).unwrap_err();
+        assert!(matches!(err, AppError::LgErr(_)));
+        assert!(err.to_string().contains("invalid log_pattern '{l} {nonsense} {m}{n}'"));
+        assert!(get_log_pattern(Some("{d(%d/%m} {m}".to_string())).is_err());
+    }
+
+    #[test]
+    fn check_long_values_truncated_at_char_boundaries() {
+        assert_eq!(truncate_value("RMIT University", 20), "RMIT University");
+        assert_eq!(truncate_value("RMIT University", 15), "RMIT University");
+        assert_eq!(truncate_value("RMIT University", 10), "RMIT Univ\u{2026}");
+        assert_eq!(truncate_value("RMIT University", 0), "RMIT University");
+
+        let name = "Fraunhofer-Einrichtung für Wertstoffkreisläufe und Ressourcenstrategie";
+        assert_eq!(truncate_value(name, 25), "Fraunhofer-Einrichtung f\u{2026}");
+        assert_eq!(truncate_value(name, 26), "Fraunhofer-Einrichtung fü\u{2026}");
+        assert_eq!(truncate_value(name, 26).chars().count(), 26);
+
+        let name = "東京大学大学院情報理工学系研究科";
+        assert_eq!(truncate_value(name, 5), "東京大学\u{2026}");
+        assert_eq!(truncate_value("Ελληνικό Ανοικτό Πανεπιστήμιο", 9), "Ελληνικό\u{2026}");
+    }
+
+    #[test]
+    fn check_maximum_length_from_env() {
+        temp_env::with_var("log_value_max_len", Some("8"), || {
+            assert_eq!(truncate_for_log("RMIT University"), "RMIT Un\u{2026}");
+        });
+        temp_env::with_var("log_value_max_len", None::<&str>, || {
+            assert_eq!(truncate_for_log(&"a".repeat(100)), "a".repeat(100));
+            assert_eq!(truncate_for_log(&"a".repeat(101)), format!("{}\u{2026}", "a".repeat(99)));
+        });
+    }
+
+    #[test]
+    fn check_duration_formatting() {
+        assert_eq!(format_duration(&Duration::from_millis(0)), "0.000s");
+        assert_eq!(format_duration(&Duration::from_millis(4_250)), "4.250s");
+        assert_eq!(format_duration(&Duration::from_millis(59_999)), "59.999s");
+        assert_eq!(format_duration(&Duration::from_millis(125_007)), "2m 05.007s");
+        assert_eq!(format_duration(&Duration::from_millis(3_723_400)), "1h 02m 03.400s");
+    }
+
+    #[test]
+    fn check_log_rotation_only_when_requested() {
+        assert_eq!(get_log_rotation(None, None), None);
+        assert_eq!(get_log_rotation(Some(2), None), 
+                   Some(LogRotation {max_size_bytes: 2 * 1024 * 1024, keep: 5}));
+        assert_eq!(get_log_rotation(None, Some(3)), 
+                   Some(LogRotation {max_size_bytes: 10 * 1024 * 1024, keep: 3}));
+    }
+
+    #[test]
+    fn check_log_rolls_when_size_exceeded() {
+        let log_folder = std::env::temp_dir().join("ror1_log_rotation_test");
+        let _ = fs::remove_dir_all(&log_folder);
+        fs::create_dir_all(&log_folder).unwrap();
+        let log_file_path = log_folder.join("test run.log");
+        let rolled_file_path = log_folder.join("test run.1.log");
+
+        let rotation = Some(LogRotation {max_size_bytes: 1024, keep: 2});
+        let config = get_log_config(&log_file_path, false, rotation, DEFAULT_LOG_PATTERN, uniform(LevelFilter::Info)).unwrap();
+        let logger = log4rs::Logger::new(config);
+        for i in 0..50 {
+            logger.log(&Record::builder().args(format_args!("rotation test message number {}", i))
+                                    .level(Level::Info).target("ror1").build());
+        }
+        Log::flush(&logger);
+
+        assert!(log_file_path.exists());
+        assert!(rolled_file_path.exists());
+        assert!(!log_folder.join("test run.3.log").exists());
+    }
+}
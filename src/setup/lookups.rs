@@ -0,0 +1,356 @@
+/***************************************************************************
+ * Provides a typed view of the integer codes used in the lup schema tables.
+ * The enums give named constants for the well-known ror categories, each
+ * with the code and name used in the corresponding lup table. The loader
+ * reads the lup tables into maps (in both directions), allowing those
+ * codes and names to be translated at run time, and the enums to be
+ * checked against the data actually present in the database. The codes
+ * and names loaded can be listed in the log, to help when looking into a
+ * mismatch between them and the data.
+ ***************************************************************************/
+
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use log::{log, log_enabled, warn, Level};
+use crate::error_defs::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusType {
+    Active = 1,
+    Inactive = 2,
+    Withdrawn = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrgType {
+    Government = 100,
+    Education = 200,
+    Healthcare = 300,
+    Company = 400,
+    Nonprofit = 500,
+    Funder = 600,
+    Facility = 700,
+    Archive = 800,
+    Other = 900,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NameType {
+    Label = 5,
+    Alias = 7,
+    Acronym = 10,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdType {
+    Isni = 11,
+    Wikidata = 12,
+    Grid = 13,
+    Fundref = 14,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkType {
+    Wikipedia = 21,
+    Website = 22,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrgRel {
+    HasParent = 1,
+    HasChild = 2,
+    IsRelatedTo = 3,
+    HasPredecessor = 4,
+    HasSuccessor = 5,
+}
+
+impl StatusType {
+    pub const ALL: [StatusType; 3] = [StatusType::Active, StatusType::Inactive, StatusType::Withdrawn];
+
+    pub fn code(self) -> i32 { self as i32 }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusType::Active => "active",
+            StatusType::Inactive => "inactive",
+            StatusType::Withdrawn => "withdrawn",
+        }
+    }
+
+    // Matches the status strings in the ror data, ignoring case and surrounding space.
+
+    pub fn from_name(name: &str) -> Option<StatusType> {
+        let name = name.trim().to_lowercase();
+        StatusType::ALL.into_iter().find(|v| v.name() == name)
+    }
+}
+
+impl OrgType {
+    pub const ALL: [OrgType; 9] = [OrgType::Government, OrgType::Education, OrgType::Healthcare,
+                                   OrgType::Company, OrgType::Nonprofit, OrgType::Funder,
+                                   OrgType::Facility, OrgType::Archive, OrgType::Other];
+
+    pub fn code(self) -> i32 { self as i32 }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OrgType::Government => "government",
+            OrgType::Education => "education",
+            OrgType::Healthcare => "healthcare",
+            OrgType::Company => "company",
+            OrgType::Nonprofit => "nonprofit",
+            OrgType::Funder => "funder",
+            OrgType::Facility => "facility",
+            OrgType::Archive => "archive",
+            OrgType::Other => "other",
+        }
+    }
+}
+
+impl NameType {
+    pub const ALL: [NameType; 3] = [NameType::Label, NameType::Alias, NameType::Acronym];
+
+    pub fn code(self) -> i32 { self as i32 }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NameType::Label => "label",
+            NameType::Alias => "alias",
+            NameType::Acronym => "acronym",
+        }
+    }
+}
+
+impl IdType {
+    pub const ALL: [IdType; 4] = [IdType::Isni, IdType::Wikidata, IdType::Grid, IdType::Fundref];
+
+    pub fn code(self) -> i32 { self as i32 }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IdType::Isni => "isni",
+            IdType::Wikidata => "wikidata",
+            IdType::Grid => "grid",
+            IdType::Fundref => "fundref",
+        }
+    }
+}
+
+impl LinkType {
+    pub const ALL: [LinkType; 2] = [LinkType::Wikipedia, LinkType::Website];
+
+    pub fn code(self) -> i32 { self as i32 }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LinkType::Wikipedia => "wikipedia",
+            LinkType::Website => "website",
+        }
+    }
+}
+
+impl OrgRel {
+    pub const ALL: [OrgRel; 5] = [OrgRel::HasParent, OrgRel::HasChild, OrgRel::IsRelatedTo,
+                                  OrgRel::HasPredecessor, OrgRel::HasSuccessor];
+
+    pub fn code(self) -> i32 { self as i32 }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OrgRel::HasParent => "has parent",
+            OrgRel::HasChild => "has child",
+            OrgRel::IsRelatedTo => "is related to",
+            OrgRel::HasPredecessor => "has predecessor",
+            OrgRel::HasSuccessor => "has successor",
+        }
+    }
+}
+
+
+// The contents of a single lup table, indexed both by code and by name.
+
+#[derive(Debug, Default, Clone)]
+pub struct LookupMap {
+    pub by_id: HashMap<i32, String>,
+    pub by_name: HashMap<String, i32>,
+}
+
+impl LookupMap {
+    pub fn from_rows(rows: Vec<(i32, String)>) -> Self {
+        let mut map = LookupMap::default();
+        for (id, name) in rows {
+            map.by_name.insert(name.clone(), id);
+            map.by_id.insert(id, name);
+        }
+        map
+    }
+
+    pub fn name_of(&self, id: i32) -> Option<&str> {
+        self.by_id.get(&id).map(|n| n.as_str())
+    }
+
+    pub fn id_of(&self, name: &str) -> Option<i32> {
+        self.by_name.get(name).copied()
+    }
+
+    // True if the code and name are present, and linked, in the table.
+
+    fn contains(&self, id: i32, name: &str) -> bool {
+        self.name_of(id) == Some(name)
+    }
+}
+
+
+#[derive(Debug, Default, Clone)]
+pub struct Lookups {
+    pub status_types: LookupMap,
+    pub org_types: LookupMap,
+    pub name_types: LookupMap,
+    pub id_types: LookupMap,
+    pub link_types: LookupMap,
+    pub org_rels: LookupMap,
+}
+
+impl Lookups {
+    pub fn tables(&self) -> [(&'static str, &LookupMap); 6] {
+        [("ror_status_types", &self.status_types), ("ror_org_types", &self.org_types),
+         ("ror_name_types", &self.name_types), ("ror_id_types", &self.id_types),
+         ("ror_link_types", &self.link_types), ("ror_org_rels", &self.org_rels)]
+    }
+}
+
+
+pub async fn load_lookups(pool: &Pool<Postgres>) -> Result<Lookups, AppError> {
+
+    Ok(Lookups {
+        status_types: load_lookup_map("ror_status_types", pool).await?,
+        org_types: load_lookup_map("ror_org_types", pool).await?,
+        name_types: load_lookup_map("ror_name_types", pool).await?,
+        id_types: load_lookup_map("ror_id_types", pool).await?,
+        link_types: load_lookup_map("ror_link_types", pool).await?,
+        org_rels: load_lookup_map("ror_org_rels", pool).await?,
+    })
+}
+
+
+async fn load_lookup_map(table_name: &str, pool: &Pool<Postgres>) -> Result<LookupMap, AppError> {
+    let sql = "SELECT id, name FROM lup.".to_owned() + table_name + " ORDER BY id";
+    let rows: Vec<(i32, String)> = sqlx::query_as(&sql).fetch_all(pool).await?;
+    Ok(LookupMap::from_rows(rows))
+}
+
+
+pub fn log_lookups(lookups: &Lookups, dump: bool) {
+
+    // The contents of each table are logged at debug level (i.e. with -V), 
+    // or, if requested by --dump-lookups, at info level.
+
+    let level = if dump { Level::Info } else { Level::Debug };
+    if log_enabled!(level) {
+        for line in get_lookup_dump(lookups) {
+            log!(level, "{}", line);
+        }
+    }
+}
+
+
+pub fn get_lookup_dump(lookups: &Lookups) -> Vec<String> {
+
+    // A line for each table, followed by a line for each of its codes, in code order.
+
+    let mut lines = Vec::new();
+    for (table, map) in lookups.tables() {
+        lines.push(format!("lup.{} ({} values):", table, map.by_id.len()));
+        let mut ids: Vec<&i32> = map.by_id.keys().collect();
+        ids.sort();
+        for id in ids {
+            lines.push(format!("    {} -> {}", id, map.by_id[id]));
+        }
+    }
+    lines
+}
+
+
+pub fn check_lookups(lookups: &Lookups) -> bool {
+
+    // Checks that each of the named constants matches the code and name
+    // in the relevant lup table, logging a warning for any that do not.
+    // Returns true only if all the constants are present as expected.
+
+    let mut all_ok = true;
+    let mut check = |map: &LookupMap, table: &str, id: i32, name: &str| {
+        if !map.contains(id, name) {
+            warn!("Lookup value {} ({}) not found as expected in lup.{}", id, name, table);
+            all_ok = false;
+        }
+    };
+
+    for v in StatusType::ALL { check(&lookups.status_types, "ror_status_types", v.code(), v.name()); }
+    for v in OrgType::ALL { check(&lookups.org_types, "ror_org_types", v.code(), v.name()); }
+    for v in NameType::ALL { check(&lookups.name_types, "ror_name_types", v.code(), v.name()); }
+    for v in IdType::ALL { check(&lookups.id_types, "ror_id_types", v.code(), v.name()); }
+    for v in LinkType::ALL { check(&lookups.link_types, "ror_link_types", v.code(), v.name()); }
+    for v in OrgRel::ALL { check(&lookups.org_rels, "ror_org_rels", v.code(), v.name()); }
+
+    all_ok
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_lookup_map_works_both_ways() {
+        let map = LookupMap::from_rows(vec![(5, "label".to_string()), (7, "alias".to_string()),
+                                            (10, "acronym".to_string())]);
+        assert_eq!(map.name_of(7), Some("alias"));
+        assert_eq!(map.id_of("acronym"), Some(10));
+        assert_eq!(map.name_of(6), None);
+        assert_eq!(map.id_of("ror_display"), None);
+    }
+
+    #[test]
+    fn check_lookup_dump_lists_codes_and_names() {
+        let lookups = Lookups {
+            name_types: LookupMap::from_rows(vec![(10, "acronym".to_string()), (5, "label".to_string()), 
+                                                  (7, "alias".to_string())]),
+            ..Default::default()
+        };
+        let lines = get_lookup_dump(&lookups);
+        assert_eq!(lines.len(), 9);
+        assert_eq!(lines[0], "lup.ror_status_types (0 values):");
+        let i = lines.iter().position(|l| l == "lup.ror_name_types (3 values):").unwrap();
+        assert_eq!(lines[i + 1..i + 4], ["    5 -> label", "    7 -> alias", "    10 -> acronym"]);
+        assert_eq!(lines[8], "lup.ror_org_rels (0 values):");
+    }
+
+    #[test]
+    fn check_status_types_matched_by_name() {
+        assert_eq!(StatusType::from_name("active"), Some(StatusType::Active));
+        assert_eq!(StatusType::from_name("inactive"), Some(StatusType::Inactive));
+        assert_eq!(StatusType::from_name("withdrawn"), Some(StatusType::Withdrawn));
+        assert_eq!(StatusType::from_name(" Withdrawn "), Some(StatusType::Withdrawn));
+        assert_eq!(StatusType::from_name("Inactive").map(|v| v.code()), Some(2));
+        assert_eq!(StatusType::from_name("obsolete"), None);
+        assert_eq!(StatusType::from_name(""), None);
+    }
+
+    #[test]
+    fn check_lookups_detects_missing_or_changed_values() {
+        let mut lookups = Lookups::default();
+        assert!(!check_lookups(&lookups));
+
+        let rows = |vals: Vec<(i32, &str)>| vals.into_iter().map(|(i, n)| (i, n.to_string())).collect::<Vec<_>>();
+        lookups.status_types = LookupMap::from_rows(StatusType::ALL.iter().map(|v| (v.code(), v.name().to_string())).collect());
+        lookups.org_types = LookupMap::from_rows(OrgType::ALL.iter().map(|v| (v.code(), v.name().to_string())).collect());
+        lookups.name_types = LookupMap::from_rows(rows(vec![(5, "label"), (7, "alias"), (10, "acronym")]));
+        lookups.id_types = LookupMap::from_rows(rows(vec![(11, "isni"), (12, "wikidata"), (13, "grid"), (14, "fundref")]));
+        lookups.link_types = LookupMap::from_rows(rows(vec![(21, "wikipedia"), (22, "website")]));
+        lookups.org_rels = LookupMap::from_rows(OrgRel::ALL.iter().map(|v| (v.code(), v.name().to_string())).collect());
+        assert!(check_lookups(&lookups));
+
+        lookups.link_types = LookupMap::from_rows(rows(vec![(21, "wikipedia"), (22, "homepage")]));
+        assert!(!check_lookups(&lookups));
+    }
+}
@@ -6,26 +6,32 @@ pub async fn fill_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
     let sql = r#"
 
     insert into lup.ror_status_types(id, name) 
-    values (1, 'active'), (2, 'inactive'), (3, 'withdrawn');
+    values (1, 'active'), (2, 'inactive'), (3, 'withdrawn')
+    on conflict (id) do update set name = excluded.name;
 
     insert into lup.ror_org_types(id, name) 
        values (100, 'government'), (200, 'education'), (300, 'healthcare'), 
        (400, 'company'), (500, 'nonprofit'), (600, 'funder'),
-       (700, 'facility'), (800, 'archive'),  (900, 'other');
+       (700, 'facility'), (800, 'archive'),  (900, 'other')
+       on conflict (id) do update set name = excluded.name;
     
     insert into lup.ror_name_types(id, name) 
-        values (5, 'label'), (7, 'alias'), (10, 'acronym');
+        values (5, 'label'), (7, 'alias'), (10, 'acronym')
+        on conflict (id) do update set name = excluded.name;
     
     insert into lup.ror_id_types(id, name) 
        values (11, 'isni'), (12, 'wikidata'),
-       (13, 'grid'), (14, 'fundref');
+       (13, 'grid'), (14, 'fundref')
+       on conflict (id) do update set name = excluded.name;
     
     insert into lup.ror_link_types(id, name) 
-      values (21, 'wikipedia'), (22, 'website');
+      values (21, 'wikipedia'), (22, 'website')
+      on conflict (id) do update set name = excluded.name;
     
     insert into lup.ror_org_rels(id, name) 
        values (1, 'has parent'), (2, 'has child'), (3, 'is related to'),
-        (4, 'has predecessor'), (5, 'has successor');
+        (4, 'has predecessor'), (5, 'has successor')
+        on conflict (id) do update set name = excluded.name;
 
     insert into lup.countries(code, name) values
              ('AD', 'Andorra'), ('AE', 'United Arab Emirates'), ('AF', 'Afghanistan'), ('AG', 'Antigua and Barbuda'), ('AI', 'Anguilla'), 
@@ -42,7 +48,8 @@ pub async fn fill_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
              ('CY', 'Cyprus'), ('CZ', 'Czechia'), ('DE', 'Germany'), ('DJ', 'Djibouti'), ('DK', 'Denmark'), 
              ('DM', 'Dominica'), ('DO', 'Dominican Republic'), ('DZ', 'Algeria'), ('EC', 'Ecuador'), ('EE', 'Estonia'), 
              ('EG', 'Egypt'), ('EH', 'Western Sahara'), ('ER', 'Eritrea'), ('ES', 'Spain'), ('ET', 'Ethiopia'), 
-             ('FI', 'Finland'), ('FJ', 'Fiji'), ('FK', 'Falkland Islands'), ('FM', 'Micronesia'), ('FO', 'Faroe Islands');
+             ('FI', 'Finland'), ('FJ', 'Fiji'), ('FK', 'Falkland Islands'), ('FM', 'Micronesia'), ('FO', 'Faroe Islands')
+             on conflict (code) do update set name = excluded.name;
 
     insert into lup.countries(code, name) values
              ('FR', 'France'), ('GA', 'Gabon'), ('GB', 'United Kingdom'), ('GD', 'Grenada'), ('GE', 'Georgia'), 
@@ -60,7 +67,8 @@ pub async fn fill_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
              ('LV', 'Latvia'), ('LY', 'Libya'), ('MA', 'Morocco'), ('MC', 'Monaco'), ('MD', 'Moldova'), 
              ('ME', 'Montenegro'), ('MF', 'Saint Martin'), ('MG', 'Madagascar'), ('MH', 'Marshall Islands'), ('MK', 'North Macedonia'), 
              ('ML', 'Mali'), ('MM', 'Myanmar'), ('MN', 'Mongolia'), ('MO', 'Macao'), ('MP', 'Northern Mariana Islands'), 
-             ('MQ', 'Martinique'), ('MR', 'Mauritania'), ('MS', 'Montserrat'), ('MT', 'Malta'), ('MU', 'Mauritius');
+             ('MQ', 'Martinique'), ('MR', 'Mauritania'), ('MS', 'Montserrat'), ('MT', 'Malta'), ('MU', 'Mauritius')
+             on conflict (code) do update set name = excluded.name;
 
     insert into lup.countries(code, name) values
              ('MV', 'Maldives'), ('MW', 'Malawi'), ('MX', 'Mexico'), ('MY', 'Malaysia'), ('MZ', 'Mozambique'), ('NA', 'Namibia'), 
@@ -81,7 +89,8 @@ pub async fn fill_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
              ('UG', 'Uganda'), ('UM', 'United States Minor Outlying Islands'), ('US', 'United States'), ('UY', 'Uruguay'), ('UZ', 'Uzbekistan'), 
              ('VA', 'Vatican'), ('VC', 'Saint Vincent and the Grenadines'), ('VE', 'Venezuela'), ('VG', 'British Virgin Islands'), ('VI', 'U.S. Virgin Islands'), 
              ('VN', 'Vietnam'), ('VU', 'Vanuatu'), ('WF', 'Wallis and Futuna'), ('WS', 'Samoa'), ('XK', 'Kosovo'), ('YE', 'Yemen'), 
-             ('YT', 'Mayotte'), ('ZA', 'South Africa'), ('ZM', 'Zambia'), ('ZW', 'Zimbabwe');
+             ('YT', 'Mayotte'), ('ZA', 'South Africa'), ('ZM', 'Zambia'), ('ZW', 'Zimbabwe')
+             on conflict (code) do update set name = excluded.name;
 
     insert into lup.lang_codes(code, marc_code, name, source) values
            ('af', 'afr', 'Afrikaans', 'ISO 639-1'), ('am', 'amh', 'Amharic', 'ISO 639-1'), ('ar', 'ara', 'Arabic', 'ISO 639-1'),
@@ -102,7 +111,8 @@ pub async fn fill_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
            ('km', 'khm', 'Central Khmer', 'ISO 639-1'), ('kn', 'kan', 'Kannada', 'ISO 639-1'), ('ko', 'kor', 'Korean', 'ISO 639-1'), 
            ('ks', 'kas', 'Kashmiri', 'ISO 639-1'), ('ku', 'kur', 'Kurdish', 'ISO 639-1'), ('la', 'lat', 'Latin', 'ECRIN'),
            ('lb', 'ltz', 'Luxembourgish', 'ISO 639-1'), ('lo', 'lao', 'Lao', 'ISO 639-1'), ('lt', 'lit', 'Lithuanian', 'ISO 639-1'),
-           ('lv', 'lav', 'Latvian', 'ISO 639-1'), ('mi', 'mao', 'Maori', 'ISO 639-1'), ('mk', 'mac', 'Macedonian', 'ISO 639-1');
+           ('lv', 'lav', 'Latvian', 'ISO 639-1'), ('mi', 'mao', 'Maori', 'ISO 639-1'), ('mk', 'mac', 'Macedonian', 'ISO 639-1')
+           on conflict (code) do update set marc_code = excluded.marc_code, name = excluded.name, source = excluded.source;
            
     insert into lup.lang_codes(code, marc_code, name, source) values
            ('ml', 'mal', 'Malayalam', 'ISO 639-1'), ('mn', 'mon', 'Mongolian', 'ISO 639-1'), ('mr', 'mar', 'Marathi', 'ISO 639-1'),
@@ -121,7 +131,8 @@ pub async fn fill_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
            ('ty', 'tah', 'Tahitian', 'ISO 639-1'), ('uk', 'ukr', 'Ukrainian', 'ISO 639-1'), ('un', 'und', 'Undetermined', 'PubMed'),
            ('ur', 'urd', 'Urdu', 'ISO 639-1'), ('uz', 'uzb', 'Uzbek', 'ISO 639-1'), ('vi', 'vie', 'Vietnamese', 'ISO 639-1'),
            ('xh', 'xho', 'Xhosa', 'ISO 639-1'), ('yo', 'yor', 'Yoruba', 'ISO 639-1'), ('zh', 'chi', 'Chinese', 'ISO 639-1'),
-           ('zu', 'zul', 'Zulu', 'ISO 639-1');
+           ('zu', 'zul', 'Zulu', 'ISO 639-1')
+           on conflict (code) do update set marc_code = excluded.marc_code, name = excluded.name, source = excluded.source;
 
     insert into lup.lang_codes(code, marc_code, name, source) values
            ('aa', 'aar', 'Afar', 'ISO 639-1'), ('ab', 'abk', 'Abkhazian', 'ISO 639-1'), ('as', 'asm', 'Assamese', 'ISO 639-1'),
@@ -134,7 +145,8 @@ pub async fn fill_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
            ('ny', 'nya', 'Chichewa', 'ISO 639-1'), ('oc', 'oci', 'Occitan', 'ISO 639-1'), ('oj', 'oji', 'Ojibwa', 'ISO 639-1'),
            ('om', 'orm', 'Oromo', 'ISO 639-1'), ('or', 'ori', 'Oriya', 'ISO 639-1'), ('sa', 'san', 'Sanskrit', 'ISO 639-1'),
            ('sd', 'snd', 'Sindhi', 'ISO 639-1'), ('st', 'sot', 'Southern Sotho', 'ISO 639-1'), ('ti', 'tir', 'Tigrinya', 'ISO 639-1'),
-           ('tl', 'tgl', 'Tagalog', 'ISO 639-1'), ('ug', 'uig', 'Uighur', 'ISO 639-1');
+           ('tl', 'tgl', 'Tagalog', 'ISO 639-1'), ('ug', 'uig', 'Uighur', 'ISO 639-1')
+           on conflict (code) do update set marc_code = excluded.marc_code, name = excluded.name, source = excluded.source;
 
 
     insert into lup.lang_scripts(code, unicode_name, iso_name, dir, chars, notes, hex_start, hex_end, ascii_start, ascii_end, source) 
@@ -152,7 +164,9 @@ pub async fn fill_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
          ('Cham', 'Cham', 'Cham', 'LtR', 83, 'Used in parts of Vietnam and Cambodia', 'AA00', 'AA5F', 43520, 43615, 'ISO 15924'), 
          ('Zyyy', 'Common', 'Code for undetermined script', 'n/a', 0, '', '', '', 0, 0, 'ISO 15924'), 
          ('Cyrl', 'Cyrillic', 'Cyrillic', 'LtR', 443, '', '0400', '04FF', 1024, 1279, 'ISO 15924'), 
-         ('Deva', 'Devanagari', 'Devanagari (Nagari)', 'LtR', 154, 'Used in parts of India, including for Hindi and Marathi', '0900', '097F', 2304, 2431, 'ISO 15924');
+         ('Deva', 'Devanagari', 'Devanagari (Nagari)', 'LtR', 154, 'Used in parts of India, including for Hindi and Marathi', '0900', '097F', 2304, 2431, 'ISO 15924')
+         on conflict (code) do update set unicode_name = excluded.unicode_name, iso_name = excluded.iso_name, dir = excluded.dir, chars = excluded.chars, 
+         notes = excluded.notes, hex_start = excluded.hex_start, hex_end = excluded.hex_end, ascii_start = excluded.ascii_start, ascii_end = excluded.ascii_end, source = excluded.source;
    
     insert into lup.lang_scripts(code, unicode_name, iso_name, dir, chars, notes, hex_start, hex_end, ascii_start, ascii_end, source) 
          values 
@@ -181,7 +195,9 @@ pub async fn fill_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
          ('Limb', 'Limbu', 'Limbu', 'LtR', 68, 'Used in parts of India, Tibet', '1900', '194F', 6400, 6479, 'ISO 15924'), 
          ('Mlym', 'Malayalam', 'Malayalam', 'LtR', 118, 'Used in parts of India (Kerala)', '0D00', '0D7F', 3328, 3455, 'ISO 15924'), 
          ('Mtei', 'Meetei Mayek', 'Meitei Mayek (Meithei, Meetei)', 'LtR', 79, 'Used in parts of India', 'ABC0', 'ABFF', 43968, 44031, 'ISO 15924'), 
-         ('Mend', 'Mende Kikakui', 'Mende Kikakui', 'RtL', 213, 'Used  in Sierra Leone', '1E800', '1E8DF', 124928, 125151, 'ISO 15924');
+         ('Mend', 'Mende Kikakui', 'Mende Kikakui', 'RtL', 213, 'Used  in Sierra Leone', '1E800', '1E8DF', 124928, 125151, 'ISO 15924')
+         on conflict (code) do update set unicode_name = excluded.unicode_name, iso_name = excluded.iso_name, dir = excluded.dir, chars = excluded.chars, 
+         notes = excluded.notes, hex_start = excluded.hex_start, hex_end = excluded.hex_end, ascii_start = excluded.ascii_start, ascii_end = excluded.ascii_end, source = excluded.source;
 
     insert into lup.lang_scripts(code, unicode_name, iso_name, dir, chars, notes, hex_start, hex_end, ascii_start, ascii_end, source) 
          values 
@@ -211,7 +227,9 @@ pub async fn fill_tables(pool: &Pool<Postgres>) -> Result<(), AppError> {
          ('Tibt', 'Tibetan', 'Tibetan', 'LtR', 207, '', '0F00', '0FFF', 3840, 4095, 'ISO 15924'), 
          ('Cans', 'Canadian Aboriginal', 'Unified Canadian Aboriginal Syllabics', 'LtR', 726, 'Used in Inuit and related languages', '1400', '167F', 5120, 5759, 'ISO 15924'), 
          ('Wara', 'Warang Citi', 'Warang Citi (Varang Kshiti)', 'LtR', 84, 'Used in parts of India', '118A0', '118FF', 71840, 71935, 'ISO 15924'), 
-         ('Yiii', 'Yi', 'Yi', 'LtR', 1220, 'Used in parts of China', 'A000', 'A48F', 40960, 42127, 'ISO 15924');"#;
+         ('Yiii', 'Yi', 'Yi', 'LtR', 1220, 'Used in parts of China', 'A000', 'A48F', 40960, 42127, 'ISO 15924')
+         on conflict (code) do update set unicode_name = excluded.unicode_name, iso_name = excluded.iso_name, dir = excluded.dir, chars = excluded.chars, 
+         notes = excluded.notes, hex_start = excluded.hex_start, hex_end = excluded.hex_end, ascii_start = excluded.ascii_start, ascii_end = excluded.ascii_end, source = excluded.source;"#;
 
     sqlx::raw_sql(sql).execute(pool).await?;
     Ok(())
@@ -0,0 +1,306 @@
+/***************************************************************************
+ * Produces the 'plan' of a run (requested with --plan) - the ordered list
+ * of the phases the program would carry out with the resolved parameters,
+ * without carrying out any of them. The order and the conditions follow
+ * those in lib.rs::run, which should be kept in step with this module.
+ ***************************************************************************/
+
+use std::path::Path;
+use super::{InitParams, SourceFile, group_by_version};
+
+
+pub fn get_plan_lines(params: &InitParams) -> Vec<String> {
+    let flags = params.flags;
+    let mut phases: Vec<String> = Vec::new();
+
+    if flags.validate || flags.count_only {
+        let file_list = get_file_list(&params.source_files);
+        if flags.validate {
+            phases.push(format!("Validate {} in {}", file_list, params.data_folder.display()));
+        }
+        if flags.count_only {
+            phases.push(format!("Count the records in {} in {}", file_list, params.data_folder.display()));
+        }
+        return number_phases(phases);
+    }
+
+    if flags.check_db {
+        phases.push("Check the database connection, schemas and key tables".to_string());
+        return number_phases(phases);
+    }
+    if flags.drop_all {
+        phases.push("Drop the ror, src and lup schemas, with all their tables".to_string());
+        return number_phases(phases);
+    }
+    if flags.only_new_ids {
+        for group in group_by_version(&params.source_files) {
+            let files: Vec<SourceFile> = group.iter().map(|sf| (*sf).clone()).collect();
+            phases.push(format!("Compare the ids in {} with those in the ror tables, writing the new and removed ids of version {} to {}",
+                                get_file_list(&files), group[0].data_version, params.output_folder.display()));
+        }
+        return number_phases(phases);
+    }
+    if flags.show_history {
+        phases.push(format!("Show the last {} import runs", params.history_count));
+        return number_phases(phases);
+    }
+    if flags.compare_versions {
+        phases.push(format!("Compare the loads of the ror tables given by {}", params.compare_versions.join(" and ")));
+        if flags.export_csv {
+            phases.push(format!("Write the ids of the changed organisations to {}", params.csv_output_folder.display()));
+        }
+        return number_phases(phases);
+    }
+    if flags.report {
+        phases.push("Log the summaries of the data in the ror and src tables".to_string());
+        return number_phases(phases);
+    }
+
+    if flags.create_lookups {
+        phases.push("Create and fill the lookup (lup) tables".to_string());
+    }
+    if flags.create_summary {
+        phases.push("Create the summary (smm) tables".to_string());
+    }
+
+    if !(flags.create_lookups && flags.create_summary) {
+        let no_import_files = [SourceFile {
+            file_name: params.source_file_name.clone(),
+            data_version: params.data_version.clone(),
+            data_date: params.data_date.clone(),
+        }];
+        let version_groups = if flags.import_ror && !params.source_files.is_empty() {
+            group_by_version(&params.source_files)
+        } else {
+            vec![no_import_files.iter().collect()]
+        };
+
+        for group in version_groups {
+            let data_version = &group[0].data_version;
+            if flags.import_ror {
+                let database = if params.sqlite_path.is_empty() { "".to_string() } 
+                               else { format!(" in the SQLite database {}", params.sqlite_path) };
+                if flags.no_recreate || flags.resume {
+                    phases.push(format!("Create the ror tables{}, if not already present", database));
+                }
+                else {
+                    phases.push(format!("Recreate the ror tables{}", database));
+                }
+                for sf in group.iter() {
+                    if flags.stdin {
+                        phases.push(format!("Import the data read from stdin (version {}, date {}){}",
+                                            sf.data_version, sf.data_date,
+                                            if flags.atomic { ", within a single transaction" } else { "" }));
+                    }
+                    else {
+                        phases.push(format!("Import {} (version {}, date {}) from {}{}", sf.file_name,
+                                            sf.data_version, sf.data_date, params.data_folder.display(),
+                                            if flags.resume { ", resuming from any checkpoint" }
+                                            else if flags.checkpoint { ", recording a checkpoint" }
+                                            else if flags.atomic { ", within a single transaction" } else { "" }));
+                    }
+                }
+                if flags.analyze && params.sqlite_path.is_empty() {
+                    phases.push("Analyse the ror tables".to_string());
+                }
+            }
+            if flags.dedup {
+                phases.push("Remove duplicate rows from the ror tables".to_string());
+            }
+            if flags.process_data {
+                if flags.no_recreate {
+                    phases.push("Create the src tables, if not already present".to_string());
+                }
+                else {
+                    phases.push(get_src_tables_phase(flags.recreate_src));
+                }
+                phases.push(format!("Transform version {} data to the src tables", data_version));
+                if flags.analyze {
+                    phases.push("Analyse the src tables".to_string());
+                }
+            }
+            if flags.summarise_data {
+                phases.push(format!("Summarise version {} data in the smm tables", data_version));
+            }
+        }
+
+        if flags.export_text {
+            let file_path = params.text_output_folder.join(&params.output_file_name);
+            phases.push(format!("Export a text summary of version {} to {}", params.data_version, file_path.display()));
+        }
+        if flags.export_csv && flags.append_csv {
+            phases.push(format!("Append csv summaries of version {} to the existing files in {}", params.data_version,
+                                params.csv_output_folder.display()));
+        }
+        else if flags.export_csv {
+            phases.push(format!("Export csv summaries of version {} to {}", params.data_version,
+                                params.csv_output_folder.display()));
+        }
+        if flags.export_json {
+            phases.push(format!("Export version {} as ror style json to {}", params.data_version,
+                                params.output_folder.display()));
+        }
+        if flags.export_country_csv {
+            phases.push(format!("Export the numbers of organisations per country as csv to {}", 
+                                params.csv_output_folder.display()));
+        }
+        if flags.export_full_csv {
+            phases.push(format!("Export csv summaries of all versions to {}", params.csv_output_folder.display()));
+        }
+    }
+    number_phases(phases)
+}
+
+
+pub fn print_plan(params: &InitParams) {
+    let phases = get_plan_lines(params);
+    if phases.is_empty() {
+        println!("Nothing would be done with these parameters");
+    }
+    else {
+        println!("Planned phases (nothing has been done):");
+        for phase in phases.iter() {
+            println!("{}", phase);
+        }
+    }
+    println!("Log folder: {}", display_folder(&params.log_folder));
+}
+
+
+fn get_file_list(source_files: &[SourceFile]) -> String {
+    let names: Vec<&str> = source_files.iter().map(|sf| sf.file_name.as_str()).collect();
+    match names.len() {
+        0 => "no files".to_string(),
+        1 => names[0].to_string(),
+        n => format!("{} files ({})", n, names.join(", ")),
+    }
+}
+
+
+fn get_src_tables_phase(recreate_src: bool) -> String {
+    if recreate_src {
+        "Recreate the src tables".to_string()
+    }
+    else {
+        "Empty the src tables, recreating them if missing or changed".to_string()
+    }
+}
+
+
+fn number_phases(phases: Vec<String>) -> Vec<String> {
+    phases.iter().enumerate().map(|(i, p)| format!("{:>2}. {}", i + 1, p)).collect()
+}
+
+
+fn display_folder(folder: &Path) -> String {
+    if folder.as_os_str().is_empty() { "(not set)".to_string() } else { folder.display().to_string() }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::setup::Flags;
+    use log::LevelFilter;
+    use std::path::PathBuf;
+
+    fn get_test_params(flags: Flags) -> InitParams {
+        let sf = |name: &str, version: &str| SourceFile {
+            file_name: name.to_string(),
+            data_version: version.to_string(),
+            data_date: "2025-01-23".to_string(),
+        };
+        InitParams {
+            data_folder: PathBuf::from("/ror/data"),
+            log_folder: PathBuf::from("/ror/logs"),
+            output_folder: PathBuf::from("/ror/outputs"),
+            text_output_folder: PathBuf::from("/ror/text"),
+            csv_output_folder: PathBuf::from("/ror/csv"),
+            source_files: vec![sf("a.json", "v1.59"), sf("b.json", "v1.59"), sf("c.json", "v1.60")],
+            source_file_name: "c.json".to_string(),
+            output_file_name: "summary.txt".to_string(),
+            data_version: "v1.60".to_string(),
+            data_date: "2025-01-23".to_string(),
+            since_date: "".to_string(),
+            history_count: 0,
+            max_memory_mb: None,
+            tables: Vec::new(),
+            types: Vec::new(),
+            trace_id: "".to_string(),
+            csv_delimiter: ',',
+            sqlite_path: "".to_string(),
+            compare_versions: Vec::new(),
+            flags,
+            log_level: LevelFilter::Info,
+        }
+    }
+
+    #[test]
+    fn check_plan_for_import_process_and_export() {
+        let flags = Flags { import_ror: true, process_data: true, summarise_data: true, export_text: true, export_csv: true, ..Default::default() };
+        let lines = get_plan_lines(&get_test_params(flags));
+        assert_eq!(lines, vec![
+            " 1. Recreate the ror tables",
+            " 2. Import a.json (version v1.59, date 2025-01-23) from /ror/data",
+            " 3. Import b.json (version v1.59, date 2025-01-23) from /ror/data",
+            " 4. Empty the src tables, recreating them if missing or changed",
+            " 5. Transform version v1.59 data to the src tables",
+            " 6. Summarise version v1.59 data in the smm tables",
+            " 7. Recreate the ror tables",
+            " 8. Import c.json (version v1.60, date 2025-01-23) from /ror/data",
+            " 9. Empty the src tables, recreating them if missing or changed",
+            "10. Transform version v1.60 data to the src tables",
+            "11. Summarise version v1.60 data in the smm tables",
+            "12. Export a text summary of version v1.60 to /ror/text/summary.txt",
+            "13. Export csv summaries of version v1.60 to /ror/csv",
+        ]);
+    }
+
+    #[test]
+    fn check_plan_with_tables_analysed() {
+        let flags = Flags { import_ror: true, process_data: true, summarise_data: true, analyze: true, ..Default::default() };
+        let mut params = get_test_params(flags);
+        params.source_files.truncate(1);
+        let lines = get_plan_lines(&params);
+        assert_eq!(lines, vec![
+            " 1. Recreate the ror tables",
+            " 2. Import a.json (version v1.59, date 2025-01-23) from /ror/data",
+            " 3. Analyse the ror tables",
+            " 4. Empty the src tables, recreating them if missing or changed",
+            " 5. Transform version v1.59 data to the src tables",
+            " 6. Analyse the src tables",
+            " 7. Summarise version v1.59 data in the smm tables",
+        ]);
+    }
+
+    #[test]
+    fn check_plan_for_sqlite_import() {
+        let flags = Flags { import_ror: true, analyze: true, ..Default::default() };
+        let mut params = get_test_params(flags);
+        params.source_files.truncate(1);
+        params.sqlite_path = "/ror/ror.db".to_string();
+        let lines = get_plan_lines(&params);
+        assert_eq!(lines, vec![
+            " 1. Recreate the ror tables in the SQLite database /ror/ror.db",
+            " 2. Import a.json (version v1.59, date 2025-01-23) from /ror/data",
+        ]);
+    }
+
+    #[test]
+    fn check_plan_for_summary_without_transform() {
+        let flags = Flags { summarise_data: true, ..Default::default() };
+        let lines = get_plan_lines(&get_test_params(flags));
+        assert_eq!(lines, vec![" 1. Summarise version v1.60 data in the smm tables"]);
+    }
+
+    #[test]
+    fn check_plan_for_initialisation_and_validation() {
+        let flags = Flags { create_lookups: true, create_summary: true, ..Default::default() };
+        let lines = get_plan_lines(&get_test_params(flags));
+        assert_eq!(lines, vec![" 1. Create and fill the lookup (lup) tables", " 2. Create the summary (smm) tables"]);
+
+        let flags = Flags { validate: true, ..Default::default() };
+        let lines = get_plan_lines(&get_test_params(flags));
+        assert_eq!(lines, vec![" 1. Validate 3 files (a.json, b.json, c.json) in /ror/data"]);
+    }
+}
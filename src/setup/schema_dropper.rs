@@ -0,0 +1,58 @@
+/***************************************************************************
+ * Drops the schemas created by the program for the data of the current 
+ * version - ror, src and lup - with all their tables (requested with 
+ * --drop-all), so that the database can be returned to a clean state 
+ * during development and re-testing. The smm schema, which holds the 
+ * summaries of all the versions imported, and the admin schema, which 
+ * holds the record of import runs, are left in place. Unless --yes is also
+ * used the drop must first be confirmed, by typing 'yes' at a prompt.
+ ***************************************************************************/
+
+use std::io::{BufRead, Write};
+use sqlx::{Pool, Postgres};
+use crate::error_defs::AppError;
+
+pub const DROPPED_SCHEMAS: [&str; 3] = ["ror", "src", "lup"];
+
+
+pub fn confirm_drop(db_name: &str, input: &mut impl BufRead, output: &mut impl Write) -> Result<bool, AppError> {
+    write!(output, "Drop the {} schemas, and all their tables, from database '{}'? Type 'yes' to confirm: ",
+                    DROPPED_SCHEMAS.join(", "), db_name)?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("yes"))
+}
+
+
+// Returns the schemas dropped, i.e. those that were present.
+
+pub async fn drop_schemas(pool: &Pool<Postgres>) -> Result<Vec<String>, AppError> {
+    let sql = "select schema_name::varchar from information_schema.schemata where schema_name = any($1)";
+    let present: Vec<String> = sqlx::query_scalar(sql).bind(&DROPPED_SCHEMAS[..]).fetch_all(pool).await?;
+
+    let mut dropped = Vec::new();
+    for schema in DROPPED_SCHEMAS.iter().filter(|s| present.iter().any(|p| p == *s)) {
+        let sql = format!("drop schema if exists {} cascade", schema);
+        sqlx::raw_sql(&sql).execute(pool).await?;
+        dropped.push(schema.to_string());
+    }
+    Ok(dropped)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_drop_confirmed_only_by_yes() {
+        let mut output = Vec::new();
+        assert!(confirm_drop("ror_test", &mut "yes\n".as_bytes(), &mut output).unwrap());
+        assert!(String::from_utf8(output).unwrap().contains("ror, src, lup schemas, and all their tables, from database 'ror_test'"));
+
+        assert!(confirm_drop("ror_test", &mut " YES \r\n".as_bytes(), &mut Vec::new()).unwrap());
+        assert!(!confirm_drop("ror_test", &mut "y\n".as_bytes(), &mut Vec::new()).unwrap());
+        assert!(!confirm_drop("ror_test", &mut "".as_bytes(), &mut Vec::new()).unwrap());
+    }
+}
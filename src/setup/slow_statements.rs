@@ -0,0 +1,37 @@
+/***************************************************************************
+ * Counts the slow statements reported during a run, so that the total can
+ * be given in the summary at the end of the run. sqlx logs each statement
+ * that takes longer than the threshold set in the connection options (by
+ * default 3 seconds, or as 'slow_statement_secs' in the environment) as a
+ * warning, with the target 'sqlx::query'. The counter is used as a log4rs 
+ * appender that writes nothing, but counts those warnings as they pass.
+ ***************************************************************************/
+
+use log::{Level, Log, Metadata, Record};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static SLOW_STATEMENTS: AtomicUsize = AtomicUsize::new(0);
+
+pub const SLOW_STATEMENT_TARGET: &str = "sqlx::query";
+
+#[derive(Debug)]
+pub struct SlowStatementCounter;
+
+impl Log for SlowStatementCounter {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == SLOW_STATEMENT_TARGET && metadata.level() == Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) && record.args().to_string().contains("slow statement") {
+            SLOW_STATEMENTS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+
+pub fn slow_statement_count() -> usize {
+    SLOW_STATEMENTS.load(Ordering::Relaxed)
+}
@@ -0,0 +1,58 @@
+/***************************************************************************
+ * Runs ANALYZE on the tables of a schema once they have been populated, so
+ * that the queries of the summaries that follow are planned using current
+ * statistics, rather than those of the empty tables (or of the previous
+ * version), which would otherwise be used until autovacuum catches up. The
+ * tables analysed are the key tables of the schema, as listed for checking
+ * the database. ANALYZE reads only a sample of each table's rows, so the
+ * cost is small compared with that of the import or processing.
+ ***************************************************************************/
+
+use log::info;
+use sqlx::{Pool, Postgres};
+use std::time::Instant;
+use crate::AppError;
+use super::db_checker::KEY_TABLES;
+use super::log_helper::format_duration;
+
+
+pub async fn analyze_tables(schema: &str, pool: &Pool<Postgres>) -> Result<(), AppError> {
+    let start = Instant::now();
+    let statements = get_analyze_statements(schema);
+    for sql in statements.iter() {
+        sqlx::raw_sql(sql).execute(pool).await?;
+    }
+    info!("The {} {} tables analysed in {}", statements.len(), schema, format_duration(&start.elapsed()));
+    Ok(())
+}
+
+
+pub fn get_analyze_statements(schema: &str) -> Vec<String> {
+    KEY_TABLES.iter().filter(|(s, _)| *s == schema)
+        .flat_map(|(s, tables)| tables.iter().map(move |t| format!("analyze {}.{};", s, t)))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_analyze_statements_for_each_ror_table() {
+        let statements = get_analyze_statements("ror");
+        assert_eq!(statements.len(), 10);
+        assert_eq!(statements[0], "analyze ror.version_details;");
+        for table in ["core_data", "admin_data", "names", "locations", "external_ids", "links", "type", "relationships", "domains"] {
+            assert!(statements.contains(&format!("analyze ror.{};", table)));
+        }
+    }
+
+    #[test]
+    fn check_analyze_statements_for_src_tables() {
+        let statements = get_analyze_statements("src");
+        assert_eq!(statements.len(), 10);
+        assert!(statements.iter().all(|s| s.starts_with("analyze src.")));
+        assert!(get_analyze_statements("sch").is_empty());
+    }
+}
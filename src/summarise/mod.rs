@@ -3,9 +3,14 @@ mod smm_structs;
 pub mod smm_helper;
 mod smm_create_tables;
 
-use log::{info, error};
+use log::{info, warn, error};
 use sqlx::{Pool, Postgres};
+use std::collections::BTreeMap;
 use crate::AppError;
+use crate::import::summarise_import;
+use crate::setup::schema_tables_exist;
+
+pub use smm_structs::{CountryCount, EstablishedCount, EstablishedHistogram, IdCoverage, TypeCount};
 
 pub async fn create_smm_tables(pool : &Pool<Postgres>) -> Result<(), AppError>
 {
@@ -35,3 +40,238 @@ pub async fn summarise_data(pool : &Pool<Postgres>) -> Result<(), AppError>
             },
     }
 }
+
+
+pub async fn summarise_by_country(pool : &Pool<Postgres>) -> Result<Vec<CountryCount>, AppError>
+{
+    // Logs, and returns, the number of organisations in each country.
+
+    let rows = fetch_country_counts(pool).await?;
+
+    info!("");
+    info!("************************************");
+    info!("Organisations by country:");
+    info!("************************************");
+    info!("");
+    for r in rows.iter() {
+        info!("{} ({}): {}", r.country_name, r.country_code, r.num_orgs);
+    }
+    info!("");
+    info!("************************************");
+    info!("");
+
+    Ok(rows)
+}
+
+
+pub async fn fetch_country_counts(pool : &Pool<Postgres>) -> Result<Vec<CountryCount>, AppError>
+{
+    // Returns the number of organisations with a location in each country, 
+    // in descending order of that number. Locations without a country code 
+    // are grouped together as 'unknown'. An organisation with locations in 
+    // more than one country is counted against each of those countries.
+
+    let sql = r#"select coalesce(country_code, 'unknown') as country_code, 
+                   coalesce(max(country_name), 'unknown') as country_name, 
+                   count(distinct id) as num_orgs
+                   from src.locations
+                   group by coalesce(country_code, 'unknown')
+                   order by count(distinct id) desc, coalesce(country_code, 'unknown')"#;
+    let rows: Vec<CountryCount> = sqlx::query_as(sql).fetch_all(pool).await?;
+    Ok(rows)
+}
+
+
+pub async fn summarise_by_type(pool : &Pool<Postgres>) -> Result<Vec<TypeCount>, AppError>
+{
+    // Logs, and returns, the number and percentage of organisations of each 
+    // type, the types being those in the lookup table. An organisation can 
+    // have more than one type, so the percentages can add up to more than 100.
+
+    let sql = r#"select t.name, count(distinct y.id) as num_orgs
+                   from lup.ror_org_types t
+                   left join src.type y on y.org_type = t.id
+                   group by t.id, t.name
+                   order by t.id"#;
+    let type_counts: Vec<(String, i64)> = sqlx::query_as(sql).fetch_all(pool).await?;
+    let sql = "select count(*) from src.core_data";
+    let total_orgs: i64 = sqlx::query_scalar(sql).fetch_one(pool).await?;
+    let types: Vec<TypeCount> = type_counts.into_iter().map(|(org_type, num_orgs)| {
+        TypeCount { org_type, num_orgs, pc_orgs: get_pc(num_orgs, total_orgs) }
+    }).collect();
+
+    info!("");
+    info!("************************************");
+    info!("Organisations by type ({} organisations):", total_orgs);
+    info!("************************************");
+    info!("");
+    for t in types.iter() {
+        info!("{}: {} ({:.1}%)", t.org_type, t.num_orgs, t.pc_orgs);
+    }
+    info!("");
+    info!("************************************");
+    info!("");
+
+    Ok(types)
+}
+
+
+pub async fn summarise_by_established(pool : &Pool<Postgres>) -> Result<EstablishedHistogram, AppError>
+{
+    // Logs, and returns, the number of organisations established in each 
+    // decade, in chronological order, with the number without a year.
+
+    let histogram = fetch_established_histogram(pool).await?;
+
+    info!("");
+    info!("************************************");
+    info!("Organisations by decade established:");
+    info!("************************************");
+    info!("");
+    for d in histogram.decades.iter() {
+        info!("{}s: {}", d.decade, d.num_orgs);
+    }
+    info!("No established year: {}", histogram.num_without);
+    info!("");
+    info!("************************************");
+    info!("");
+
+    Ok(histogram)
+}
+
+
+pub async fn fetch_established_histogram(pool : &Pool<Postgres>) -> Result<EstablishedHistogram, AppError>
+{
+    let sql = "select established from src.core_data where established is not null";
+    let years: Vec<i32> = sqlx::query_scalar(sql).fetch_all(pool).await?;
+    let sql = "select count(*) from src.core_data where established is null";
+    let num_without: i64 = sqlx::query_scalar(sql).fetch_one(pool).await?;
+
+    Ok(EstablishedHistogram {
+        decades: bucket_by_decade(&years),
+        num_without,
+    })
+}
+
+
+pub async fn summarise_external_id_coverage(pool : &Pool<Postgres>) -> Result<Vec<IdCoverage>, AppError>
+{
+    // Logs, and returns, the number and percentage of organisations with at 
+    // least one external id of each type, the types being those in the lookup 
+    // table (so that a type no organisation has is shown with a zero count).
+
+    let sql = r#"select t.name, count(distinct e.id) as num_orgs
+                   from lup.ror_id_types t
+                   left join src.external_ids e on e.id_type = t.id
+                   group by t.id, t.name
+                   order by t.id"#;
+    let type_counts: Vec<(String, i64)> = sqlx::query_as(sql).fetch_all(pool).await?;
+    let sql = "select count(*) from src.core_data";
+    let total_orgs: i64 = sqlx::query_scalar(sql).fetch_one(pool).await?;
+    let coverage = get_id_coverage(type_counts, total_orgs);
+
+    info!("");
+    info!("************************************");
+    info!("External id coverage ({} organisations):", total_orgs);
+    info!("************************************");
+    info!("");
+    for c in coverage.iter() {
+        info!("{}: {} ({:.1}%)", c.id_type, c.num_orgs, c.pc_orgs);
+    }
+    info!("");
+    info!("************************************");
+    info!("");
+
+    Ok(coverage)
+}
+
+
+pub async fn report_summaries(tables: &[String], pool : &Pool<Postgres>) -> Result<(), AppError>
+{
+    // Logs all the summaries of the data already in the database (requested 
+    // by --report), without importing, processing or writing any files. The 
+    // ror table counts need the ror tables, and the others the src tables, 
+    // so each group is skipped, with a warning, if its tables are missing.
+
+    if schema_tables_exist("ror", pool).await? {
+        summarise_import(tables, pool).await?;
+    } else {
+        warn!("The ror tables are missing or incomplete, so their record numbers cannot be reported");
+    }
+    if schema_tables_exist("src", pool).await? {
+        summarise_by_country(pool).await?;
+        summarise_by_type(pool).await?;
+        summarise_by_established(pool).await?;
+        summarise_external_id_coverage(pool).await?;
+    } else {
+        warn!("The src tables are missing or incomplete, so the country, type, established and id summaries cannot be reported");
+    }
+    Ok(())
+}
+
+
+fn get_id_coverage(type_counts: Vec<(String, i64)>, total_orgs: i64) -> Vec<IdCoverage> {
+    type_counts.into_iter().map(|(id_type, num_orgs)| {
+        IdCoverage { id_type, num_orgs, pc_orgs: get_pc(num_orgs, total_orgs) }
+    }).collect()
+}
+
+
+fn get_pc(num_orgs: i64, total_orgs: i64) -> f64 {
+    if total_orgs > 0 { num_orgs as f64 * 100.0 / total_orgs as f64 } else { 0.0 }
+}
+
+
+fn bucket_by_decade(years: &[i32]) -> Vec<EstablishedCount> {
+
+    // Each year is allocated to the decade it falls in (e.g. 1887 to 1880), 
+    // using euclidean division so that any (erroneous) negative years are 
+    // still grouped consistently. Decades without any years are omitted.
+
+    let mut counts: BTreeMap<i32, i64> = BTreeMap::new();
+    for y in years.iter() {
+        *counts.entry(y.div_euclid(10) * 10).or_insert(0) += 1;
+    }
+    counts.into_iter().map(|(decade, num_orgs)| EstablishedCount { decade, num_orgs }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_years_bucketed_by_decade() {
+        let years = [1887, 1904, 1909, 1931, 1946, 1964, 1965, 1967, 1984, 1986, 
+                     1987, 1989, 1989, 1991, 1998, 2006, 2010, 2011, 2012];
+        let expected: Vec<EstablishedCount> = [(1880, 1), (1900, 2), (1930, 1), (1940, 1), (1960, 3), 
+                                               (1980, 5), (1990, 2), (2000, 1), (2010, 3)]
+            .iter().map(|(decade, num_orgs)| EstablishedCount { decade: *decade, num_orgs: *num_orgs }).collect();
+        assert_eq!(bucket_by_decade(&years), expected);
+    }
+
+    #[test]
+    fn check_decade_boundaries_and_empty_input() {
+        let buckets = bucket_by_decade(&[2020, 2019, 2029, 2030]);
+        assert_eq!(buckets, vec![EstablishedCount { decade: 2010, num_orgs: 1 }, 
+                                 EstablishedCount { decade: 2020, num_orgs: 2 },
+                                 EstablishedCount { decade: 2030, num_orgs: 1 }]);
+        assert!(bucket_by_decade(&[]).is_empty());
+    }
+
+    #[test]
+    fn check_id_coverage_percentages() {
+        let counts = vec![("isni".to_string(), 18), ("fundref".to_string(), 0)];
+        assert_eq!(get_id_coverage(counts, 24), 
+                   vec![IdCoverage { id_type: "isni".to_string(), num_orgs: 18, pc_orgs: 75.0 },
+                        IdCoverage { id_type: "fundref".to_string(), num_orgs: 0, pc_orgs: 0.0 }]);
+        assert_eq!(get_id_coverage(vec![("grid".to_string(), 0)], 0)[0].pc_orgs, 0.0);
+    }
+
+    #[test]
+    fn check_percentages_of_organisations() {
+        assert_eq!(get_pc(5, 20), 25.0);
+        assert_eq!(get_pc(0, 20), 0.0);
+        assert_eq!(get_pc(3, 0), 0.0);
+    }
+}
@@ -5,6 +5,39 @@ pub struct FileParams {
     pub vdays: i32
 }
 
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq)]
+pub struct CountryCount {
+  pub country_code: String,
+  pub country_name: String,
+  pub num_orgs: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EstablishedCount {
+  pub decade: i32,
+  pub num_orgs: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EstablishedHistogram {
+  pub decades: Vec<EstablishedCount>,
+  pub num_without: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeCount {
+  pub org_type: String,
+  pub num_orgs: i64,
+  pub pc_orgs: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdCoverage {
+  pub id_type: String,
+  pub num_orgs: i64,
+  pub pc_orgs: f64,
+}
+
 #[derive(sqlx::FromRow)]
 pub struct DistribRow {
   pub vcode: String,
@@ -0,0 +1,34 @@
+// Checks that after an import the ror tables have been analysed, so that the
+// summaries are planned with current statistics. The tables are created (with
+// their indexes) before any data is stored, so until they are analysed (or
+// vacuumed) their row estimates in pg_class remain at -1, i.e. unknown, even
+// for those tables left empty.
+
+use ror1::run;
+use std::ffi::OsString;
+
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test]
+async fn ror_tables_analysed_after_import() {
+
+    let _db = lock_db().await;
+
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s",
+                                "v99-2030-01-01-test-data_schema_v2.json", "-r", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    let pool = fetch_db_pool().await.unwrap();
+    let sql = r#"select c.relname::varchar, c.reltuples::float8 from pg_class c
+                 inner join pg_namespace n on c.relnamespace = n.oid
+                 where n.nspname = 'ror' and c.relkind = 'r' order by c.relname"#;
+    let tables: Vec<(String, f64)> = sqlx::query_as(sql).fetch_all(&pool).await.unwrap();
+    assert_eq!(tables.len(), 10);
+    for (table_name, reltuples) in tables.iter() {
+        assert!(*reltuples >= 0.0, "ror.{} has not been analysed", table_name);
+    }
+    let core_data = tables.iter().find(|(t, _)| t == "core_data").unwrap();
+    assert_eq!(core_data.1, 20.0);
+}
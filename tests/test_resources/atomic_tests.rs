@@ -0,0 +1,108 @@
+// Checks that with --atomic the records of a source file are stored within
+// a single transaction, so that an import that is interrupted, or fails
+// part way through, keeps none of its records, while one that completes
+// keeps them all. The failure is caused by a check constraint added to 
+// ror.core_data that rejects the records of the second of three batches.
+
+use ror1::run;
+use ror1::setup::interrupt;
+use std::env;
+use std::fs;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use serde_json::Value;
+
+use sqlx::{Postgres, Pool};
+use ror1::error_defs::AppError;
+use super::db_access::{lock_db, fetch_db_pool, TEST_DATA_FILE};
+
+const TABLES: [&str; 10] = ["version_details", "core_data", "admin_data", "names", "locations", 
+                            "external_ids", "links", "type", "relationships", "domains"];
+
+
+fn get_args(data_folder: &str, source_file: &str, extra: &[&str]) -> Vec<OsString> {
+    let mut args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", data_folder, "-s", source_file, "-r", "-z", "--atomic"];
+    args.extend_from_slice(extra);
+    args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>()
+}
+
+
+async fn check_no_rows_kept(pool: &Pool<Postgres>) {
+    for table in TABLES {
+        let count: i64 = sqlx::query_scalar(&format!("select count(*) from ror.{}", table)).fetch_one(pool).await.unwrap();
+        assert_eq!(count, 0, "rows kept in ror.{}", table);
+    }
+}
+
+
+#[tokio::test]
+async fn atomic_import_keeps_all_records() {
+
+    let _db = lock_db().await;
+    run(get_args("tests/test_data", TEST_DATA_FILE, &[])).await.unwrap();
+
+    let pool = fetch_db_pool().await.unwrap();
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 20);
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.names").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 56);
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.version_details").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 1);
+}
+
+
+#[tokio::test]
+async fn interrupted_atomic_import_keeps_no_records() {
+
+    let _db = lock_db().await;
+
+    interrupt::request_stop();
+    let err = run(get_args("tests/test_data", TEST_DATA_FILE, &[])).await.unwrap_err();
+    assert!(matches!(err, AppError::IrErr(_)));
+
+    let pool = fetch_db_pool().await.unwrap();
+    check_no_rows_kept(&pool).await;
+}
+
+
+#[tokio::test]
+async fn failed_atomic_import_keeps_no_records() {
+
+    let _db = lock_db().await;
+
+    // 30 copies of the 20 test records gives 600 records, i.e. three batches,
+    // the constraint rejecting a record (the 15th copy) in the second.
+
+    let data = fs::read_to_string(format!("tests/test_data/{}", TEST_DATA_FILE)).unwrap();
+    let records: Vec<Value> = serde_json::from_str(&data).unwrap();
+    let mut big_records: Vec<Value> = Vec::new();
+    for k in 0..30 {
+        for (i, r) in records.iter().enumerate() {
+            let mut new_r = r.clone();
+            new_r["id"] = Value::String(format!("https://ror.org/0atm{:03}{:02}", k, i));
+            big_records.push(new_r);
+        }
+    }
+    let target_path : PathBuf = [env::temp_dir(), PathBuf::from("ror1_atomic_test")].iter().collect();
+    fs::create_dir_all(&target_path).unwrap();
+    let target_file = "v99-2030-01-01-atomic-test-data_schema_v2.json";
+    fs::write(target_path.join(target_file), serde_json::to_string(&big_records).unwrap()).unwrap();
+    let target_folder = target_path.to_str().unwrap();
+
+    run(get_args("tests/test_data", TEST_DATA_FILE, &[])).await.unwrap();
+    let pool = fetch_db_pool().await.unwrap();
+    for table in TABLES {
+        sqlx::raw_sql(&format!("truncate table ror.{};", table)).execute(&pool).await.unwrap();
+    }
+    sqlx::raw_sql("alter table ror.core_data add constraint no_copy_15 check (id not like '0atm015%');").execute(&pool).await.unwrap();
+
+    let res = run(get_args(target_folder, target_file, &["--no-recreate"])).await;
+    sqlx::raw_sql("alter table ror.core_data drop constraint no_copy_15;").execute(&pool).await.unwrap();
+    fs::remove_dir_all(&target_path).unwrap();
+
+    match res {
+        Err(AppError::SqErr(e)) => assert!(e.to_string().contains("no_copy_15")),
+        _ => panic!("expected the import to fail with a database error"),
+    }
+    check_no_rows_kept(&pool).await;
+}
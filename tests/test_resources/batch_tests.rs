@@ -0,0 +1,106 @@
+// Checks that a file large enough to be stored in several batches is 
+// imported correctly - with the three groups of tables in each batch being
+// stored concurrently, in their own transactions, and, with a pool of a
+// single connection, one after the other in a single transaction. The file
+// is generated from the standard v99 test data, by repeating its records
+// with new ids.
+
+use ror1::{run, run_pipeline};
+use ror1::setup::get_params;
+use std::env;
+use std::fs;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use serde_json::Value;
+
+use sqlx::{Postgres, Pool};
+use sqlx::postgres::PgPoolOptions;
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+async fn fetch_record_num(table_name: &str, pool: &Pool<Postgres>) -> i64 {
+    let sql = "SELECT COUNT(*) FROM ror.".to_owned() + table_name;
+    sqlx::query_scalar(&sql).fetch_one(pool).await.unwrap()
+}
+
+fn count_elements(records: &[Value], field: &str) -> i64 {
+    records.iter().map(|r| r[field].as_array().map_or(0, |a| a.len()) as i64).sum()
+}
+
+
+// 30 copies of the 20 test records gives 600 records, i.e. two full
+// batches of 250 and a final partial batch of 100. Returns the folder and
+// name of the file, and the original records.
+
+fn write_multi_batch_file(copies: i64) -> (PathBuf, String, Vec<Value>) {
+    let data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+    let records: Vec<Value> = serde_json::from_str(&data).unwrap();
+    let mut big_records: Vec<Value> = Vec::new();
+    for k in 0..copies {
+        for (i, r) in records.iter().enumerate() {
+            let mut new_r = r.clone();
+            new_r["id"] = Value::String(format!("https://ror.org/0bat{:03}{:02}", k, i));
+            big_records.push(new_r);
+        }
+    }
+
+    let target_path : PathBuf = [env::temp_dir(), PathBuf::from("ror1_batch_test")].iter().collect();
+    fs::create_dir_all(&target_path).unwrap();
+    let target_file = "v99-2030-01-01-batch-test-data_schema_v2.json";
+    fs::write(target_path.join(target_file), serde_json::to_string(&big_records).unwrap()).unwrap();
+    (target_path, target_file.to_string(), records)
+}
+
+
+async fn check_multi_batch_numbers(copies: i64, records: &[Value], pool: &Pool<Postgres>) {
+    assert_eq!(fetch_record_num("core_data", pool).await, copies * records.len() as i64);
+    assert_eq!(fetch_record_num("admin_data", pool).await, copies * records.len() as i64);
+    assert_eq!(fetch_record_num("names", pool).await, copies * count_elements(records, "names"));
+    assert_eq!(fetch_record_num("locations", pool).await, copies * count_elements(records, "locations"));
+    assert_eq!(fetch_record_num("links", pool).await, copies * count_elements(records, "links"));
+    assert_eq!(fetch_record_num("relationships", pool).await, copies * count_elements(records, "relationships"));
+    assert_eq!(fetch_record_num("domains", pool).await, copies * count_elements(records, "domains"));
+}
+
+
+#[tokio::test] 
+async fn import_multi_batch_data_and_check_numbers() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    let copies = 30;
+    let (target_path, target_file, records) = write_multi_batch_file(copies);
+    let target_folder = target_path.to_str().unwrap();
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", target_folder, "-s", &target_file, "-r", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    check_multi_batch_numbers(copies, &records, &pool).await;
+    fs::remove_dir_all(&target_path).unwrap();
+}
+
+
+#[tokio::test] 
+async fn import_multi_batch_data_with_single_connection_pool() {
+
+    // A pool that cannot supply a connection for each group of tables, as
+    // might be passed to run_pipeline by another program.
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+    let single_pool = PgPoolOptions::new().max_connections(1)
+                        .connect_with(pool.connect_options().as_ref().clone()).await.unwrap();
+
+    let copies = 30;
+    let (target_path, target_file, records) = write_multi_batch_file(copies);
+    let target_folder = target_path.to_str().unwrap();
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", target_folder, "-s", &target_file, "-r", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    let params = get_params(test_args).await.unwrap();
+    let report = run_pipeline(params, &single_pool).await.unwrap();
+
+    assert_eq!(report.records_processed, 600);
+    check_multi_batch_numbers(copies, &records, &pool).await;
+    fs::remove_dir_all(&target_path).unwrap();
+}
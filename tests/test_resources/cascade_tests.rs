@@ -0,0 +1,56 @@
+// Checks that deleting an organisation from src.core_data also deletes its rows 
+// in each of the src child tables.
+
+use ror1::run;
+use std::ffi::OsString;
+
+use sqlx::{Postgres, Pool};
+use super::db_access::{lock_db, fetch_db_pool};
+
+const CHILD_TABLES: [&str; 7] = ["names", "locations", "external_ids", "links", 
+                                 "type", "relationships", "domains"];
+
+
+async fn get_child_count(table_name: &str, id: &str, pool: &Pool<Postgres>) -> i64 {
+    let sql = "SELECT COUNT(*) FROM src.".to_owned() + table_name + " where id = $1";
+    sqlx::query_scalar(&sql).bind(id).fetch_one(pool).await.unwrap()
+}
+
+
+#[tokio::test] 
+async fn delete_core_data_row_and_check_cascade_to_child_tables() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    let target_file = "v99-2030-01-01-test-data_schema_v2.json";
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", target_file, "-r", "-p", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    // Use the organisation with the most child rows in total.
+
+    let sql = r#"select id from src.core_data c order by 
+                 (select count(*) from src.names n where n.id = c.id) + 
+                 (select count(*) from src.external_ids e where e.id = c.id) + 
+                 (select count(*) from src.relationships r where r.id = c.id) desc, id 
+                 limit 1"#;
+    let id: String = sqlx::query_scalar(sql).fetch_one(&pool).await.unwrap();
+    assert!(get_child_count("names", &id, &pool).await > 0);
+    assert!(get_child_count("locations", &id, &pool).await > 0);
+
+    let total_sql = "SELECT COUNT(*) FROM src.names";
+    let names_before: i64 = sqlx::query_scalar(total_sql).fetch_one(&pool).await.unwrap();
+    let names_for_id = get_child_count("names", &id, &pool).await;
+
+    sqlx::query("DELETE FROM src.core_data where id = $1").bind(&id).execute(&pool).await.unwrap();
+
+    for table_name in CHILD_TABLES {
+        assert_eq!(get_child_count(table_name, &id, &pool).await, 0, "rows remain in src.{}", table_name);
+    }
+
+    // Rows for other organisations are unaffected.
+
+    let names_after: i64 = sqlx::query_scalar(total_sql).fetch_one(&pool).await.unwrap();
+    assert_eq!(names_after, names_before - names_for_id);
+}
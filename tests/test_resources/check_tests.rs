@@ -0,0 +1,49 @@
+// Checks the --check database health check against a freshly created, empty 
+// database (which fails), and against the test database once initialised and 
+// populated (which passes).
+
+use ror1::create_smm_tables;
+
+use sqlx::PgPool;
+use ror1::setup::{check_database, create_lup_tables};
+use ror1::setup::env_reader;
+use super::db_access::{lock_db, fetch_db_pool, run_with_args};
+
+const FRESH_DB_NAME: &str = "ror1_check_test_fresh";
+
+
+#[tokio::test] 
+async fn check_fresh_database_fails_and_populated_database_passes() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    // A new, empty database has none of the schemas or tables.
+
+    let sql = format!("DROP DATABASE IF EXISTS {}", FRESH_DB_NAME);
+    sqlx::query(&sql).execute(&pool).await.unwrap();
+    let sql = format!("CREATE DATABASE {}", FRESH_DB_NAME);
+    sqlx::query(&sql).execute(&pool).await.unwrap();
+
+    let conn_string = env_reader::fetch_db_conn_string(FRESH_DB_NAME.to_string()).unwrap();
+    let fresh_pool = PgPool::connect(&conn_string).await.unwrap();
+    let res = check_database(&fresh_pool).await;
+    fresh_pool.close().await;
+    let sql = format!("DROP DATABASE IF EXISTS {}", FRESH_DB_NAME);
+    sqlx::query(&sql).execute(&pool).await.unwrap();
+    assert!(res.is_err());
+
+    // Once initialised, and with data imported and processed, all are present.
+    // The lookup and summary tables are created directly, as an initialisation 
+    // run always starts a log.
+
+    create_lup_tables(&pool).await.unwrap();
+    create_smm_tables(&pool).await.unwrap();
+    let target_file = "v99-2030-01-01-test-data_schema_v2.json";
+    run_with_args(&["target/debug/ror1.exe", "-f", "tests/test_data", "-s", target_file, "-r", "-p", "-z"]).await.unwrap();
+    assert!(check_database(&pool).await.is_ok());
+
+    // The check itself, run through the program, also passes.
+
+    run_with_args(&["target/debug/ror1.exe", "--check", "-z"]).await.unwrap();
+}
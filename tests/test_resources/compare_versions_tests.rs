@@ -0,0 +1,87 @@
+// Checks the comparison of two loads of the ror tables (--compare-versions).
+// Two small versions are seeded in scratch schemas, the second named through
+// a recorded import run, so that both ways of naming a load are used:
+// - org 01 is removed and org 04 added,
+// - org 02 has a changed established year, and org 03 a changed name language,
+//   with org 03 also given a new location.
+// The ror tables themselves are not touched.
+
+use ror1::compare_versions;
+use std::fs;
+
+use sqlx::{Postgres, Pool};
+use ror1::error_defs::AppError;
+use ror1::{ChangeCounts, fetch_import_runs};
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+async fn seed_version(schema: &str, sql: &str, pool: &Pool<Postgres>) {
+    let create_sql = format!(r#"drop schema if exists {schema} cascade;
+        create schema {schema};
+        create table {schema}.core_data (id varchar, ror_full_id varchar, status varchar, established int);
+        create table {schema}.names (id varchar, value varchar, name_type varchar, is_ror_name bool, lang varchar);
+        create table {schema}.locations (id varchar, geonames_id int, name varchar, lat real, lng real,
+                                         country_code varchar, country_subdivision_code varchar);"#);
+    sqlx::raw_sql(&create_sql).execute(pool).await.unwrap();
+    sqlx::raw_sql(&sql.replace("{s}", schema)).execute(pool).await.unwrap();
+}
+
+
+#[tokio::test]
+async fn versions_compared() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    seed_version("ror_cmp_a", r#"
+        insert into {s}.core_data values ('01', 'https://ror.org/01', 'active', 1900),
+                 ('02', 'https://ror.org/02', 'active', 1910), ('03', 'https://ror.org/03', 'active', 1920);
+        insert into {s}.names values ('01', 'Org One', 'label', true, 'en'), ('02', 'Org Two', 'label', true, 'en'),
+                 ('03', 'Org Three', 'label', true, 'en'), ('03', 'O3', 'acronym', false, null);
+        insert into {s}.locations values ('01', 100, 'Paris', 48.8, 2.3, 'FR', null),
+                 ('02', 200, 'Lyon', 45.7, 4.8, 'FR', null), ('03', 300, 'Nice', 43.7, 7.2, 'FR', null);"#, &pool).await;
+
+    seed_version("ror_vcmp_2", r#"
+        insert into {s}.core_data values ('02', 'https://ror.org/02', 'active', 1911),
+                 ('03', 'https://ror.org/03', 'active', 1920), ('04', 'https://ror.org/04', 'active', 1930);
+        insert into {s}.names values ('02', 'Org Two', 'label', true, 'en'), ('03', 'Org Three', 'label', true, 'fr'),
+                 ('03', 'O3', 'acronym', false, null), ('04', 'Org Four', 'label', true, 'en');
+        insert into {s}.locations values ('02', 200, 'Lyon', 45.7, 4.8, 'FR', null),
+                 ('03', 300, 'Nice', 43.7, 7.2, 'FR', null), ('03', 400, 'Antibes', 43.6, 7.1, 'FR', null),
+                 ('04', 500, 'Metz', 49.1, 6.2, 'FR', null);"#, &pool).await;
+
+    // The second version is named by an import run of version 'vcmp.2'.
+
+    fetch_import_runs(1, &pool).await.unwrap();    // ensures the history table exists
+    let sql = r#"insert into admin.import_runs (data_version, data_date, source_file_name,
+                    records_found, records_processed, records_skipped)
+                 values ('vcmp.2', '2030-01-01', 'vcmp.2-2030-01-01-ror-data_schema_v2.json', 3, 3, 0) returning id"#;
+    let run_id: i32 = sqlx::query_scalar(sql).fetch_one(&pool).await.unwrap();
+
+    let csv_folder = std::env::temp_dir().join("ror1_compare_versions_test");
+    fs::create_dir_all(&csv_folder).unwrap();
+    let loads = vec!["cmp_a".to_string(), run_id.to_string()];
+    let diff = compare_versions(&loads, Some(&csv_folder), &pool).await.unwrap();
+
+    assert_eq!(diff.from_schema, "ror_cmp_a");
+    assert_eq!(diff.to_schema, "ror_vcmp_2");
+    assert_eq!(diff.added_ids, vec!["04"]);
+    assert_eq!(diff.removed_ids, vec!["01"]);
+    assert_eq!(diff.modified_ids, vec!["02", "03"]);
+    assert_eq!(diff.names, ChangeCounts { added: 1, removed: 1, modified: 1 });
+    assert_eq!(diff.locations, ChangeCounts { added: 2, removed: 1, modified: 0 });
+
+    let csv = fs::read_to_string(csv_folder.join("ror_cmp_a to ror_vcmp_2 changed_ids.csv")).unwrap();
+    assert_eq!(csv, "id,change\n04,added\n01,removed\n02,modified\n03,modified\n");
+
+    // A load that does not exist is reported as such.
+
+    let loads = vec!["cmp_a".to_string(), "cmp_missing".to_string()];
+    match compare_versions(&loads, None, &pool).await {
+        Err(AppError::CsErr(e)) => assert!(e.to_string().contains("ror_cmp_missing, which is missing")),
+        _ => panic!("expected a custom error"),
+    }
+
+    sqlx::query("delete from admin.import_runs where id = $1").bind(run_id).execute(&pool).await.unwrap();
+    sqlx::raw_sql("drop schema ror_cmp_a cascade; drop schema ror_vcmp_2 cascade;").execute(&pool).await.unwrap();
+}
@@ -0,0 +1,19 @@
+use std::process::Command;
+
+// Runs the program binary with --count against the test data, which needs no 
+// database, and checks the counts printed to the console.
+
+#[test]
+fn check_count_prints_record_and_section_counts() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ror1"))
+        .args(["--count", "-z", "-f", "tests/test_data", "-s", "v99-2030-01-01-test-data_schema_v2.json"])
+        .output()
+        .expect("failed to run the ror1 binary");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "v99-2030-01-01-test-data_schema_v2.json: 20 records");
+    assert_eq!(lines[1], "    with established          19  (95.0%)");
+    assert_eq!(lines[5], "    with relationships        10  (50.0%)");
+}
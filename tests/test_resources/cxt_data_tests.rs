@@ -2,3 +2,67 @@
 // And to check the first and last record in each table are correct
 // Obviously must be run after the context / lookup data is created.
 
+
+use sqlx::{Postgres, Pool};
+use ror1::setup::create_lup_tables;
+use ror1::setup::lookups::{load_lookups, check_lookups, get_lookup_dump, OrgType, NameType};
+use super::db_access::{lock_db, fetch_db_pool};
+
+async fn fetch_lup_record_nums(pool: &Pool<Postgres>) -> Vec<i64> {
+    let tables = ["ror_status_types", "ror_org_types", "ror_name_types", "ror_id_types", 
+                  "ror_link_types", "ror_org_rels", "countries", "lang_codes", "lang_scripts"];
+    let mut nums = Vec::new();
+    for t in tables {
+        let sql = "SELECT COUNT(*) FROM lup.".to_owned() + t;
+        let num: i64 = sqlx::query_scalar(&sql).fetch_one(pool).await.unwrap();
+        nums.push(num);
+    }
+    nums
+}
+
+
+#[tokio::test] 
+async fn check_lup_creation_can_be_repeated() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    create_lup_tables(&pool).await.unwrap();
+    let first_nums = fetch_lup_record_nums(&pool).await;
+    create_lup_tables(&pool).await.unwrap();
+    let second_nums = fetch_lup_record_nums(&pool).await;
+
+    assert_eq!(first_nums, second_nums);
+    assert_eq!(first_nums[0], 3);
+    assert_eq!(first_nums[1], 9);
+    assert_eq!(first_nums[2], 3);
+    assert_eq!(first_nums[5], 5);
+}
+
+
+#[tokio::test] 
+async fn check_loaded_lookups_match_lup_rows() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+    create_lup_tables(&pool).await.unwrap();
+
+    let lookups = load_lookups(&pool).await.unwrap();
+    assert!(check_lookups(&lookups));
+
+    assert_eq!(lookups.status_types.by_id.len(), 3);
+    assert_eq!(lookups.org_types.by_id.len(), 9);
+    assert_eq!(lookups.name_types.by_id.len(), 3);
+    assert_eq!(lookups.id_types.by_id.len(), 4);
+    assert_eq!(lookups.link_types.by_id.len(), 2);
+    assert_eq!(lookups.org_rels.by_id.len(), 5);
+
+    assert_eq!(lookups.org_types.name_of(OrgType::Company.code()), Some("company"));
+    assert_eq!(lookups.name_types.id_of("acronym"), Some(NameType::Acronym.code()));
+    assert_eq!(lookups.org_rels.name_of(4), Some("has predecessor"));
+
+    let dump = get_lookup_dump(&lookups);
+    assert_eq!(dump.len(), 6 + 3 + 9 + 3 + 4 + 2 + 5);
+    assert!(dump.contains(&"lup.ror_org_types (9 values):".to_string()));
+    assert!(dump.contains(&"    400 -> company".to_string()));
+}
@@ -0,0 +1,67 @@
+// Shared by the tests that use the test database, or that run the program.
+// All the tests run in the same process, and most replace the contents of
+// the tables, so each of those holds the lock returned by lock_db for as
+// long as it uses the database, so that only one does so at a time.
+
+use ror1::run;
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::{Command, Output};
+use tokio::sync::{Mutex, MutexGuard};
+
+use sqlx::{Postgres, Pool};
+use ror1::error_defs::AppError;
+use ror1::setup::get_db_pool;
+use ror1::setup::env_reader;
+
+static DB_LOCK: Mutex<()> = Mutex::const_new(());
+
+pub const TEST_DATA_FILE: &str = "v99-2030-01-01-test-data_schema_v2.json";
+
+
+pub async fn lock_db() -> MutexGuard<'static, ()> {
+    DB_LOCK.lock().await
+}
+
+
+pub async fn fetch_db_pool() -> Result<Pool<Postgres>, AppError>  {
+
+    // Use the process set up in the library under test
+    // Helps to ensure exactly the same database connections are used
+
+    env_reader::populate_env_vars()?;
+    get_db_pool().await
+}
+
+
+pub async fn run_with_args(args: &[&str]) -> Result<(), AppError> {
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await
+}
+
+
+// Imports the standard test data into the ror tables and, if process is
+// true, transforms it into the src tables, and returns a pool for checking it.
+
+pub async fn load_test_data(process: bool) -> Pool<Postgres> {
+    let mut args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", TEST_DATA_FILE, "-r", "-z"];
+    if process {
+        args.push("-p");
+    }
+    run_with_args(&args).await.unwrap();
+    fetch_db_pool().await.unwrap()
+}
+
+
+// Runs the program binary, with its log written to the given folder. A log
+// can only be set up once in any process, so tests that check the log run
+// the binary rather than calling run.
+
+pub fn run_binary_with_log(args: &[&str], log_folder: &Path, envs: &[(&str, &str)]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ror1"))
+        .args(args)
+        .env("log_folder_path", log_folder)
+        .envs(envs.iter().copied())
+        .output()
+        .expect("failed to run the ror1 binary")
+}
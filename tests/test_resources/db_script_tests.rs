@@ -0,0 +1,61 @@
+// Checks the running of the SQL scripts used to create the tables. A failing 
+// statement in a script is identified, by its number within the script, and 
+// the statements before it are rolled back (using a scratch schema, so that 
+// the other tables are not disturbed). The ror tables are created using the 
+// embedded SQL when the db scripts folder does not contain the script.
+
+use std::env;
+
+use ror1::error_defs::AppError;
+use ror1::setup::schema_tables_exist;
+use ror1::setup::db_script_runner::run_db_script;
+use super::db_access::{lock_db, fetch_db_pool, run_with_args};
+
+
+#[tokio::test] 
+async fn failing_statement_is_named() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+    sqlx::raw_sql("drop schema if exists script_test cascade").execute(&pool).await.unwrap();
+
+    let sql = r#"create schema script_test;
+                 create table script_test.notes (id int, note varchar);
+                 insert into script_test.notes (id, note) values (1, 'a note; with a semicolon'), ('two', 'bad id');
+                 insert into script_test.notes (id, note) values (3, 'never run');"#;
+    match run_db_script("test_script.sql", sql, &pool).await {
+        Err(AppError::CsErr(e)) => {
+            let msg = e.to_string();
+            assert!(msg.starts_with("statement 3 of test_script.sql failed (insert into script_test.notes"), "{}", msg);
+        },
+        _ => panic!("expected the script to fail with a custom error"),
+    }
+
+    let sql = "select count(*) from information_schema.schemata where schema_name = 'script_test'";
+    let count: i64 = sqlx::query_scalar(sql).fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 0);
+}
+
+
+#[tokio::test] 
+async fn create_ror_tables_with_no_external_script() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    let empty_folder = env::temp_dir().join("ror1_empty_db_scripts");
+    std::fs::create_dir_all(&empty_folder).unwrap();
+
+    sqlx::raw_sql("SET client_min_messages TO WARNING; DROP SCHEMA IF EXISTS ror CASCADE;")
+        .execute(&pool).await.unwrap();
+    assert!(!schema_tables_exist("ror", &pool).await.unwrap());
+
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", 
+                                "v99-2030-01-01-test-data_schema_v2.json", "-r", "-z"];
+    temp_env::async_with_vars([("db_scripts_path", Some(empty_folder.to_str().unwrap()))],
+                              run_with_args(&args)).await.unwrap();
+
+    assert!(schema_tables_exist("ror", &pool).await.unwrap());
+    let n: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ror.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(n, 20);
+}
@@ -0,0 +1,59 @@
+// Checks that duplicated rows in the ror child tables are removed by the
+// dedup pass. The contents of the ror tables are deliberately doubled first.
+
+use ror1::{run, dedup_tables};
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use sqlx::{Postgres, Pool};
+use super::db_access::{lock_db, fetch_db_pool};
+
+const CHILD_TABLES: [&str; 7] = ["names", "locations", "external_ids", "links", 
+                                 "type", "relationships", "domains"];
+
+
+async fn get_counts(pool: &Pool<Postgres>) -> Vec<i64> {
+    let mut counts = Vec::new();
+    for table_name in CHILD_TABLES {
+        let sql = "SELECT COUNT(*) FROM ror.".to_owned() + table_name;
+        let n: i64 = sqlx::query_scalar(&sql).fetch_one(pool).await.unwrap();
+        counts.push(n);
+    }
+    counts
+}
+
+
+#[tokio::test] 
+async fn double_imported_data_and_check_dedup_restores_counts() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    let cd_path = env::current_dir().unwrap();
+    let target_path : PathBuf = [cd_path, PathBuf::from("tests/test_data/")].iter().collect();
+    let target_folder = target_path.to_str().unwrap();
+    let target_file = "v99-2030-01-01-test-data_schema_v2.json";
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", target_folder, "-s", target_file, "-r", "-z"];
+
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+    let original_counts = get_counts(&pool).await;
+
+    // Double the contents of each of the child tables.
+
+    for table_name in CHILD_TABLES {
+        let sql = format!("INSERT INTO ror.{0} SELECT * FROM ror.{0}", table_name);
+        sqlx::query(&sql).execute(&pool).await.unwrap();
+    }
+    let doubled_counts = get_counts(&pool).await;
+    assert_eq!(doubled_counts, original_counts.iter().map(|n| n * 2).collect::<Vec<i64>>());
+
+    let removed = dedup_tables(&pool).await.unwrap();
+    assert_eq!(removed as i64, original_counts.iter().sum::<i64>());
+    assert_eq!(get_counts(&pool).await, original_counts);
+
+    // A second pass finds nothing to remove.
+
+    assert_eq!(dedup_tables(&pool).await.unwrap(), 0);
+}
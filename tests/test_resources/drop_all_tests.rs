@@ -0,0 +1,61 @@
+// Checks that --drop-all removes the ror, src and lup schemas, with their 
+// tables, and leaves the smm schema in place. The drop is made in a 
+// disposable database, created and removed by the test, rather than in the
+// test database.
+
+use std::ffi::OsString;
+
+use sqlx::PgPool;
+use ror1::setup::drop_all_schemas;
+use ror1::setup::env_reader;
+use super::db_access::{lock_db, fetch_db_pool};
+
+const DISPOSABLE_DB_NAME: &str = "ror1_drop_test";
+
+
+#[tokio::test] 
+async fn drop_all_removes_ror_src_and_lup_schemas() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    let sql = format!("DROP DATABASE IF EXISTS {}", DISPOSABLE_DB_NAME);
+    sqlx::query(&sql).execute(&pool).await.unwrap();
+    let sql = format!("CREATE DATABASE {}", DISPOSABLE_DB_NAME);
+    sqlx::query(&sql).execute(&pool).await.unwrap();
+
+    let conn_string = env_reader::fetch_db_conn_string(DISPOSABLE_DB_NAME.to_string()).unwrap();
+    let drop_pool = PgPool::connect(&conn_string).await.unwrap();
+    let sql = r#"create schema ror; create table ror.core_data (id varchar);
+                 create schema src; create table src.core_data (id varchar);
+                 create schema lup; create table lup.countries (code varchar);
+                 create schema smm; create table smm.version_summaries (vcode varchar);"#;
+    sqlx::raw_sql(sql).execute(&drop_pool).await.unwrap();
+
+    let res = drop_all_schemas(true, &drop_pool).await;
+    let sql = r#"select schema_name::varchar from information_schema.schemata 
+                 where schema_name in ('ror', 'src', 'lup', 'smm')"#;
+    let schemas: Vec<String> = sqlx::query_scalar(sql).fetch_all(&drop_pool).await.unwrap();
+
+    // A second drop finds nothing to drop, but is not an error.
+
+    let res_again = drop_all_schemas(true, &drop_pool).await;
+    drop_pool.close().await;
+    let sql = format!("DROP DATABASE IF EXISTS {}", DISPOSABLE_DB_NAME);
+    sqlx::query(&sql).execute(&pool).await.unwrap();
+
+    res.unwrap();
+    res_again.unwrap();
+    assert_eq!(schemas, vec!["smm".to_string()]);
+}
+
+
+#[tokio::test] 
+async fn drop_all_refused_with_import_or_process_flags() {
+
+    let _db = lock_db().await;    for other in ["-r", "-p", "-a"] {
+        let args : Vec<&str> = vec!["target/debug/ror1.exe", "--drop-all", "--yes", other];
+        let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+        assert!(ror1::run(test_args).await.is_err());
+    }
+}
@@ -0,0 +1,61 @@
+// Checks that with --dump-failed a batch of records that cannot be stored is
+// written to a replay file in the output folder, before the import fails. 
+// As in store_error_tests, importing a file into tables that already hold its
+// records (with --no-recreate) causes a primary key violation. The file is
+// then replayed into the emptied tables, to check that it holds all the rows 
+// of the batch.
+
+use ror1::run;
+use std::ffi::OsString;
+use std::fs;
+
+use ror1::error_defs::AppError;
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test] 
+async fn failed_batch_written_to_replay_file() {
+
+    let _db = lock_db().await;
+
+    let target_file = "v99-2030-01-01-test-data_schema_v2.json";
+    let args = |extra: &[&str]| {
+        let mut args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", target_file, "-r", "-z"];
+        args.extend_from_slice(extra);
+        args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>()
+    };
+    run(args(&[])).await.unwrap();
+
+    let output_folder = std::env::temp_dir().join("ror1_dump_failed_test");
+    let _ = fs::remove_dir_all(&output_folder);
+    fs::create_dir_all(&output_folder).unwrap();
+    let res = temp_env::async_with_vars(
+    [
+        ("output_folder_path", Some(output_folder.to_str().unwrap())),
+    ],
+    async { run(args(&["--no-recreate", "--dump-failed"])).await }).await;
+    assert!(matches!(res, Err(AppError::SqErr(_))));
+
+    let replay_files: Vec<_> = fs::read_dir(&output_folder).unwrap()
+                        .map(|e| e.unwrap().path())
+                        .filter(|p| p.extension().is_some_and(|x| x == "sql")).collect();
+    assert_eq!(replay_files.len(), 1);
+    let file_name = replay_files[0].file_name().unwrap().to_string_lossy().to_string();
+    assert!(file_name.starts_with("v99-2030-01-01-test-data_schema_v2 failed batch "));
+
+    let sql = fs::read_to_string(&replay_files[0]).unwrap();
+    assert!(sql.contains("-- Error: ") && sql.contains("duplicate key"));
+    assert_eq!(sql.lines().filter(|l| l.starts_with("insert into ror.core_data ")).count(), 20);
+    assert!(sql.contains("values ('04ttjf776', 'https://ror.org/04ttjf776', 'active', 1887);"));
+
+    let pool = fetch_db_pool().await.unwrap();
+    let tables = ["core_data", "admin_data", "names", "locations", "external_ids", "links", "type", "relationships", "domains"];
+    for table in tables {
+        sqlx::raw_sql(&format!("truncate table ror.{};", table)).execute(&pool).await.unwrap();
+    }
+    sqlx::raw_sql(&sql).execute(&pool).await.unwrap();
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 20);
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.names").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 56);
+}
@@ -0,0 +1,50 @@
+// Checks that a source file with a repeated ROR id is rejected before anything 
+// is stored, and that with --replace the last occurrence of the id is imported. 
+// The fixture repeats the first of three records, with a different established 
+// year (1999 rather than 1887).
+
+use ror1::run;
+use std::ffi::OsString;
+
+use sqlx::{Postgres, Pool};
+use ror1::error_defs::AppError;
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+async fn import_file(replace: bool) -> Result<(), AppError> {
+    let target_file = "v96-2030-01-01-dup-ids-test-data_schema_v2.json";
+    let mut args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", target_file, "-r", "-z"];
+    if replace {
+        args.push("--replace");
+    }
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await
+}
+
+async fn get_core_data_count(pool: &Pool<Postgres>) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM ror.core_data").fetch_one(pool).await.unwrap()
+}
+
+
+#[tokio::test] 
+async fn import_file_with_duplicate_id() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    // Without --replace the import fails, with nothing stored.
+
+    match import_file(false).await {
+        Err(AppError::DtErr(e)) => assert!(e.to_string().starts_with("1 ROR ids occur more than once")),
+        _ => panic!("expected the import to fail with a custom error"),
+    }
+    assert_eq!(get_core_data_count(&pool).await, 0);
+
+    // With --replace the last occurrence is imported.
+
+    import_file(true).await.unwrap();
+    assert_eq!(get_core_data_count(&pool).await, 3);
+    let established: Option<i32> = sqlx::query_scalar("SELECT established FROM ror.core_data where id = '04ttjf776'")
+                                    .fetch_one(&pool).await.unwrap();
+    assert_eq!(established, Some(1999));
+}
@@ -0,0 +1,44 @@
+use std::process::Command;
+
+// Runs the program binary with the given arguments and returns its exit code.
+// The -z flag is used throughout so that no log file is written.
+
+fn run_binary(args: &[&str]) -> i32 {
+    let status = Command::new(env!("CARGO_BIN_EXE_ror1"))
+        .args(args)
+        .status()
+        .expect("failed to run the ror1 binary");
+    status.code().expect("ror1 binary terminated by a signal")
+}
+
+#[test]
+fn check_unknown_argument_gives_config_exit_code() {
+    assert_eq!(run_binary(&["--no-such-flag"]), 2);
+}
+
+#[test]
+fn check_help_gives_zero_exit_code() {
+    assert_eq!(run_binary(&["--help"]), 0);
+}
+
+#[test]
+fn check_missing_source_file_gives_io_exit_code() {
+    assert_eq!(run_binary(&["--validate", "-z", "-f", "tests/test_data", "-s", "v99-2030-01-01-missing.json"]), 3);
+}
+
+#[test]
+fn check_failed_validation_gives_data_exit_code() {
+    assert_eq!(run_binary(&["--validate", "-z", "-f", "tests/test_data", 
+                            "-s", "v97-2030-01-01-malformed-test-data_schema_v2.json"]), 5);
+}
+
+#[test]
+fn check_source_that_is_not_an_array_gives_data_exit_code() {
+    let data_folder = std::env::temp_dir().join("ror1_exit_code_test");
+    std::fs::create_dir_all(&data_folder).unwrap();
+    let file_name = "v99-2030-01-01-single-record.json";
+    std::fs::write(data_folder.join(file_name), r#"{"id": "https://ror.org/04ttjf776"}"#).unwrap();
+    let exit_code = run_binary(&["--count", "-z", "-f", data_folder.to_str().unwrap(), "-s", file_name]);
+    std::fs::remove_dir_all(&data_folder).unwrap();
+    assert_eq!(exit_code, 5);
+}
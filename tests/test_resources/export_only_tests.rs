@@ -0,0 +1,58 @@
+// Checks that an export can be run on its own, against data already in the 
+// database, without a data folder or source file being available. The 
+// version exported is then the one currently in the ror tables. The export 
+// is run by the program binary, as it sets up a log, which is written to a 
+// temporary folder.
+
+use std::fs;
+use std::path::Path;
+
+use ror1::setup::env_reader;
+use super::db_access::{lock_db, load_test_data, run_binary_with_log};
+
+
+#[tokio::test] 
+async fn export_runs_without_source_file_or_data_folder() {
+
+    let _db = lock_db().await;
+
+    // Populate the ror tables with the test data (as v99), and add the 
+    // version to the summary table, as a previous run would have done.
+
+    let pool = load_test_data(false).await;
+    let sql = r#"insert into smm.version_summaries (vcode, vdate, vdays, num_orgs) 
+                 values ('v99', '2030-01-01', 0, 20) on conflict do nothing"#;
+    sqlx::query(sql).execute(&pool).await.unwrap();
+
+    // The export goes to the output folder in the environment, as it is 
+    // written by the database server, which must be able to access it.
+
+    let out_folder = env_reader::fetch_output_folder();
+    let exported_before = fetch_v99_csv_files(&out_folder);
+
+    let log_folder = std::env::temp_dir().join("ror1_export_only_test");
+    let _ = fs::remove_dir_all(&log_folder);
+    let output = run_binary_with_log(&["-x"], &log_folder, &[
+        ("data_folder_path", "/ror1/no/such/folder"),
+        ("src_file_name", ""),
+        ("data_version", ""),
+        ("data_date", ""),
+        ("text_output_folder_path", ""),
+        ("csv_output_folder_path", ""),
+    ]);
+
+    sqlx::query("delete from smm.version_summaries where vcode = 'v99'").execute(&pool).await.unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let exported = fetch_v99_csv_files(&out_folder);
+    assert!(exported.iter().any(|f| !exported_before.contains(f)));
+}
+
+
+fn fetch_v99_csv_files(folder: &Path) -> Vec<String> {
+    fs::read_dir(folder).unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.starts_with("v99") && name.ends_with(".csv"))
+        .collect()
+}
@@ -0,0 +1,37 @@
+// Checks that an import adds a row to the history of import runs.
+
+use ror1::{run, fetch_import_runs};
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test] 
+async fn import_data_and_check_run_recorded() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    let cd_path = env::current_dir().unwrap();
+    let target_path : PathBuf = [cd_path, PathBuf::from("tests/test_data/")].iter().collect();
+    let target_folder = target_path.to_str().unwrap();
+    let target_file = "v98-2030-01-01-sparse-test-data_schema_v2.json";
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", target_folder, "-s", target_file, 
+                                "-v", "v98", "-d", "2030-01-01", "-r", "-z"];
+
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    let runs = fetch_import_runs(1, &pool).await.unwrap();
+    assert_eq!(runs.len(), 1);
+    let run = &runs[0];
+    assert_eq!(run.data_version, "v99");    // test runs are always given version v99
+    assert_eq!(run.data_date, "2030-01-01");
+    assert_eq!(run.source_file_name, target_file);
+    assert_eq!(run.records_found, 4);
+    assert_eq!(run.records_processed, 4);
+    assert_eq!(run.records_skipped, 0);
+    assert_eq!(run.sha256.as_deref(), Some("e16c878f448e4c604320a8ba8fd03f020c4eb4e1397c4d4121fa03430b57d422"));
+}
@@ -0,0 +1,82 @@
+// Checks that an import interrupted by Ctrl-C stops cleanly, once the current
+// batch of records has been stored, and that the number of records committed
+// is logged. The program binary is run against a file large enough to need
+// many batches (the standard test data, repeated with new ids), and is sent
+// SIGINT once the first batch has been stored, i.e. once the checkpoint has
+// been written. The generated file, the log and the checkpoint are all 
+// written to a temporary folder.
+
+use std::fs;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use serde_json::Value;
+
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test]
+async fn interrupted_import_stops_after_storing_batch() {
+
+    let _db = lock_db().await;
+
+    let folder = std::env::temp_dir().join("ror1_interrupt_test");
+    let _ = fs::remove_dir_all(&folder);
+    let log_folder = folder.join("logs");
+    fs::create_dir_all(&log_folder).unwrap();
+
+    // 500 copies of the 20 test records gives 10000 records, i.e. 40 batches.
+
+    let data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+    let records: Vec<Value> = serde_json::from_str(&data).unwrap();
+    let mut big_records: Vec<Value> = Vec::new();
+    for k in 0..500 {
+        for (i, r) in records.iter().enumerate() {
+            let mut new_r = r.clone();
+            new_r["id"] = Value::String(format!("https://ror.org/0cnt{:03}{:02}", k, i));
+            big_records.push(new_r);
+        }
+    }
+    let target_file = "v99-2030-01-01-interrupt-test-data_schema_v2.json";
+    fs::write(folder.join(target_file), serde_json::to_string(&big_records).unwrap()).unwrap();
+
+    let checkpoint_path = folder.join(format!("{}.checkpoint", target_file));
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ror1"))
+        .args(["-f", folder.to_str().unwrap(), "-s", target_file, "-r", "-p", "--checkpoint"])
+        .env("log_folder_path", log_folder.to_str().unwrap())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to run the ror1 binary");
+
+    // Ctrl-C is only listened for once records are being stored.
+
+    let start = Instant::now();
+    while !checkpoint_path.exists() {
+        assert!(child.try_wait().unwrap().is_none(), "the import ended before it could be interrupted");
+        assert!(start.elapsed() < Duration::from_secs(60), "no batch stored within 60 seconds");
+        thread::sleep(Duration::from_millis(10));
+    }
+    let status = Command::new("kill").args(["-INT", &child.id().to_string()]).status().unwrap();
+    assert!(status.success());
+    let status = child.wait().unwrap();
+    assert_eq!(status.code(), Some(130));
+
+    // Only complete batches were committed, and the processing that
+    // would otherwise have followed the import was not started.
+
+    let pool = fetch_db_pool().await.unwrap();
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.core_data").fetch_one(&pool).await.unwrap();
+    assert!(count > 0 && count < 10000 && count % 250 == 0, "{} records stored", count);
+
+    let log_file = fs::read_dir(&log_folder).unwrap().next().unwrap().unwrap().path();
+    let contents = fs::read_to_string(log_file).unwrap();
+    assert!(contents.contains("Interrupt received"));
+    assert!(contents.contains(&format!("Import interrupted - {} records from {} had been committed", count, target_file)));
+    assert!(!contents.contains("Transform"));
+
+    // A checkpoint is left, from which the import could be resumed.
+
+    assert_eq!(fs::read_to_string(&checkpoint_path).unwrap(), format!("{}\n", count));
+    fs::remove_dir_all(&folder).unwrap();
+}
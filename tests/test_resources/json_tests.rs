@@ -0,0 +1,54 @@
+// Checks that records exported as json from the src tables match the 
+// source data they were imported from, for the key fields.
+
+use ror1::{run, export_json, JsonOrg};
+use std::env;
+use std::fs;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use serde_json::Value;
+
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test] 
+async fn import_process_export_json_and_check_round_trip() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    let target_file = "v99-2030-01-01-test-data_schema_v2.json";
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", target_file, "-r", "-p", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    let output_path : PathBuf = env::temp_dir().join("ror1_json_export_test.json");
+    let num_exported = export_json(&output_path, &pool).await.unwrap();
+    let exported: Vec<JsonOrg> = serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    fs::remove_file(&output_path).unwrap();
+    assert_eq!(num_exported, 20);
+    assert_eq!(exported.len(), 20);
+
+    let source = fs::read_to_string(format!("tests/test_data/{}", target_file)).unwrap();
+    let source: Vec<Value> = serde_json::from_str(&source).unwrap();
+    for r in source.iter() {
+        let org = exported.iter().find(|o| o.id == r["id"].as_str().unwrap()).unwrap();
+        assert_eq!(org.status, r["status"].as_str().unwrap());
+        assert_eq!(org.established, r["established"].as_i64().map(|e| e as i32));
+        let types: Vec<&str> = r["types"].as_array().unwrap().iter().map(|t| t.as_str().unwrap()).collect();
+        assert_eq!(org.types.len(), types.len());
+        assert!(org.types.iter().all(|t| types.contains(&t.as_str())));
+        let loc = org.location.as_ref().unwrap();
+        assert_eq!(loc.geonames_id, r["locations"][0]["geonames_id"].as_i64().map(|g| g as i32));
+        assert_eq!(loc.country_code.as_deref(), r["locations"][0]["geonames_details"]["country_code"].as_str());
+        let label = r["names"].as_array().unwrap().iter()
+                    .find(|n| n["types"].as_array().unwrap().iter().any(|t| t == "ror_display")).unwrap();
+        assert_eq!(org.ror_name, label["value"].as_str().unwrap());
+    }
+
+    let bond = exported.iter().find(|o| o.id == "https://ror.org/006jxzx88").unwrap();
+    assert_eq!(bond.ror_name, "Bond University");
+    assert_eq!(bond.names.len(), 1);
+    assert_eq!(bond.names[0].name_type, "label");
+    assert!(bond.external_ids.iter().any(|e| e.id_type == "grid" && e.value == "grid.1033.1"));
+}
@@ -0,0 +1,48 @@
+// Checks which location is taken as the primary location of an organisation,
+// providing its single location in src.core_data, while all its locations 
+// are kept in src.locations. The fixture has 2 records, one with a single
+// location and one with two, listed in the opposite order to their geonames
+// ids, so that the first listed is not also the lowest id.
+
+use ror1::run;
+use std::ffi::OsString;
+
+use sqlx::{Postgres, Pool};
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+async fn fetch_core_location(id: &str, pool: &Pool<Postgres>) -> (String, Option<String>, String) {
+    let sql = "select location, csubdiv_code, country_code from src.core_data where id = $1";
+    sqlx::query_as(sql).bind(id).fetch_one(pool).await.unwrap()
+}
+
+async fn fetch_locations(id: &str, pool: &Pool<Postgres>) -> Vec<(i32, String, bool)> {
+    let sql = "select geonames_id, location, is_primary from src.locations where id = $1 order by geonames_id";
+    sqlx::query_as(sql).bind(id).fetch_all(pool).await.unwrap()
+}
+
+
+#[tokio::test]
+async fn primary_location_used_for_core_data() {
+
+    let _db = lock_db().await;
+
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s",
+                                "v99-2030-01-01-locations-test-data.json", "-r", "-p", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+    let pool = fetch_db_pool().await.unwrap();
+
+    // A single location is the primary location.
+
+    assert_eq!(fetch_core_location("04ttjf776", &pool).await, 
+               ("Melbourne".to_string(), None, "AU".to_string()));
+    assert_eq!(fetch_locations("04ttjf776", &pool).await, vec![(2158177, "Melbourne".to_string(), true)]);
+
+    // Of two locations the first listed is the primary, and both are kept.
+
+    assert_eq!(fetch_core_location("02s7vm534", &pool).await, 
+               ("Alzenau in Unterfranken".to_string(), Some("BY".to_string()), "DE".to_string()));
+    assert_eq!(fetch_locations("02s7vm534", &pool).await, 
+               vec![(2911007, "Hanau".to_string(), false), (2956715, "Alzenau in Unterfranken".to_string(), true)]);
+}
@@ -0,0 +1,30 @@
+// Checks that the full path of the log file is recorded at the start of 
+// the log, so that the file can be found when the program is run by a 
+// scheduler or service manager. 
+
+use std::fs;
+use super::db_access::run_binary_with_log;
+
+
+#[test] 
+fn log_file_path_recorded_at_startup() {
+
+    let log_folder = std::env::temp_dir().join("ror1_log_path_test");
+    let _ = fs::remove_dir_all(&log_folder);
+
+    let args : Vec<&str> = vec!["-f", "tests/test_data", "-s", 
+                                "v99-2030-01-01-test-data_schema_v2.json", "--validate", "--tail-log"];
+    let output = run_binary_with_log(&args, &log_folder, &[]);
+    assert!(output.status.success());
+
+    let log_files: Vec<_> = fs::read_dir(&log_folder).unwrap()
+                        .map(|e| e.unwrap().path())
+                        .filter(|p| p.extension().is_some_and(|x| x == "log"))
+                        .collect();
+    assert_eq!(log_files.len(), 1);
+
+    let full_path = fs::canonicalize(&log_files[0]).unwrap();
+    let contents = fs::read_to_string(&log_files[0]).unwrap();
+    assert!(contents.contains(&format!("Log file: {}", full_path.display())));
+    assert!(contents.contains("tail_log: true"));
+}
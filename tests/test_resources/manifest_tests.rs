@@ -0,0 +1,56 @@
+// Checks that the export phase lists the files it has written in a 
+// manifest.json file, in the output folder, with the version and date of 
+// the data each was derived from. The export is run by the program binary, 
+// as it sets up a log, which is written to a temporary folder.
+
+use ror1::MANIFEST_FILE_NAME;
+use std::fs;
+
+use ror1::setup::env_reader;
+use super::db_access::{lock_db, load_test_data, run_binary_with_log};
+
+
+#[tokio::test] 
+async fn export_writes_manifest_listing_files() {
+
+    let _db = lock_db().await;
+
+    // Populate the ror and src tables with the test data (as v99), and add 
+    // the version to the summary table, as a previous run would have done.
+
+    let pool = load_test_data(true).await;
+    let sql = r#"insert into smm.version_summaries (vcode, vdate, vdays, num_orgs) 
+                 values ('v99', '2030-01-01', 0, 20) on conflict do nothing"#;
+    sqlx::query(sql).execute(&pool).await.unwrap();
+
+    let out_folder = env_reader::fetch_output_folder();
+    let manifest_path = out_folder.join(MANIFEST_FILE_NAME);
+    let _ = fs::remove_file(&manifest_path);
+
+    let log_folder = std::env::temp_dir().join("ror1_manifest_test");
+    let _ = fs::remove_dir_all(&log_folder);
+    let output = run_binary_with_log(&["-x", "-j"], &log_folder, &[]);
+
+    sqlx::query("delete from smm.version_summaries where vcode = 'v99'").execute(&pool).await.unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+    let listed = manifest["files"].as_array().unwrap();
+
+    let csv_files: Vec<&serde_json::Value> = listed.iter()
+                    .filter(|f| f["path"].as_str().unwrap().ends_with(".csv")).collect();
+    let json_files: Vec<&serde_json::Value> = listed.iter()
+                    .filter(|f| f["path"].as_str().unwrap().ends_with(".json")).collect();
+    assert!(!csv_files.is_empty());
+    assert_eq!(json_files.len(), 1);
+    assert_eq!(csv_files.len() + json_files.len(), listed.len());
+
+    for f in listed {
+        assert!(f["path"].as_str().unwrap().contains("v99"));
+        assert_eq!(f["data_version"], "v99");
+        assert_eq!(f["data_date"], "2030-01-01");
+        assert!(f["row_count"].as_u64().is_some());
+    }
+    assert_eq!(json_files[0]["row_count"], 20);
+    assert!(json_files[0]["size_bytes"].as_u64().unwrap() > 0);
+}
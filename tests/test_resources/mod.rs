@@ -1,3 +1,4 @@
+mod db_access;
 mod ror_data_access;
 mod src_data_access;
 mod ror_record_structs;
@@ -6,6 +7,43 @@ mod src_record_structs;
 mod ror_data_tests;
 mod src_data_tests;
 mod cxt_data_tests;
+mod exit_code_tests;
+mod count_tests;
 
-
-    
\ No newline at end of file
+mod analyze_tests;
+mod atomic_tests;
+mod batch_tests;
+mod cascade_tests;
+mod check_tests;
+mod compare_versions_tests;
+mod db_script_tests;
+mod dedup_tests;
+mod drop_all_tests;
+mod dump_failed_tests;
+mod dup_ids_tests;
+mod export_only_tests;
+mod history_tests;
+#[cfg(unix)]
+mod interrupt_tests;
+mod json_tests;
+mod locations_tests;
+mod log_path_tests;
+mod manifest_tests;
+mod multi_file_tests;
+mod new_ids_tests;
+mod no_recreate_tests;
+mod pipeline_tests;
+mod quality_tests;
+mod read_pool_tests;
+mod recreate_src_tests;
+mod rels_tests;
+mod report_tests;
+mod resume_tests;
+mod ror_names_tests;
+mod sqlite_tests;
+mod status_tests;
+mod stdin_tests;
+mod store_error_tests;
+mod trace_tests;
+mod types_tests;
+mod utf8_tests;
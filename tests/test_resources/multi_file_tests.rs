@@ -0,0 +1,35 @@
+// Checks that several source files can be imported in a single run. 
+
+use ror1::run;
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test] 
+async fn import_two_files_in_one_run() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    // In a test run both files are given the same version (v99)  
+    // and so are imported together into the ror tables.
+
+    let cd_path = env::current_dir().unwrap();
+    let target_path : PathBuf = [cd_path, PathBuf::from("tests/test_data/")].iter().collect();
+    let target_folder = target_path.to_str().unwrap();
+    let target_files = "v98-2030-01-01-sparse-test-data_schema_v2.json, v99-*_schema_v2.json";
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", target_folder, "-s", target_files, "-r", "-z"];
+
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    let rec_number: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ror.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(rec_number, 24);
+    let rec_number: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ror.version_details").fetch_one(&pool).await.unwrap();
+    assert_eq!(rec_number, 1);
+    let rec_number: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ror.names where id like '0sparse%'").fetch_one(&pool).await.unwrap();
+    assert_eq!(rec_number, 7);
+}
@@ -0,0 +1,44 @@
+// Checks that the ids in a newer source file are compared correctly with 
+// those of an older version already in the ror tables. The newer fixture 
+// drops the last two records of the older one, and adds a new organisation.
+
+use ror1::{run, compare_source_ids};
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+
+use ror1::setup::SourceFile;
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test] 
+async fn new_and_removed_ids_found() {
+
+    let _db = lock_db().await;
+
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", 
+                                "v99-2030-01-01-test-data_schema_v2.json", "-r", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    let pool = fetch_db_pool().await.unwrap();
+    let sf = SourceFile {
+        file_name: "v100-2030-02-01-new-ids-test-data_schema_v2.json".to_string(),
+        data_version: "v100".to_string(),
+        data_date: "2030-02-01".to_string(),
+    };
+    let out_folder = std::env::temp_dir().join("ror1_new_ids_test");
+    fs::create_dir_all(&out_folder).unwrap();
+
+    let diff = compare_source_ids(&PathBuf::from("tests/test_data"), &[&sf], &out_folder, &pool).await.unwrap();
+
+    assert_eq!(diff.added, vec!["0nwtest01"]);
+    assert_eq!(diff.removed, vec!["0198t0w55", "03rd8mf35"]);
+    assert_eq!(fs::read_to_string(out_folder.join("v100 new_ids.txt")).unwrap(), "0nwtest01\n");
+    assert_eq!(fs::read_to_string(out_folder.join("v100 removed_ids.txt")).unwrap(), "0198t0w55\n03rd8mf35\n");
+
+    // Nothing is imported by the comparison.
+
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 20);
+}
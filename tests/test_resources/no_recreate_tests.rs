@@ -0,0 +1,51 @@
+// Checks that with --no-recreate the ror tables are created if absent, but 
+// otherwise retained (so that a further file is added to the existing data), 
+// and that without it the tables are recreated as normal.
+
+use ror1::run;
+use std::ffi::OsString;
+
+use sqlx::{Postgres, Pool};
+use ror1::setup::schema_tables_exist;
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+async fn import_file(target_file: &str, no_recreate: bool) {
+    let mut args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", target_file, "-r", "-z"];
+    if no_recreate {
+        args.push("--no-recreate");
+    }
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+}
+
+async fn get_core_data_count(pool: &Pool<Postgres>) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM ror.core_data").fetch_one(pool).await.unwrap()
+}
+
+
+#[tokio::test] 
+async fn import_with_and_without_no_recreate_flag() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    // Tables absent - they are created.
+
+    sqlx::raw_sql("SET client_min_messages TO WARNING; DROP SCHEMA IF EXISTS ror CASCADE;")
+        .execute(&pool).await.unwrap();
+    assert!(!schema_tables_exist("ror", &pool).await.unwrap());
+    import_file("v99-2030-01-01-test-data_schema_v2.json", true).await;
+    assert!(schema_tables_exist("ror", &pool).await.unwrap());
+    assert_eq!(get_core_data_count(&pool).await, 20);
+
+    // Tables present - they are retained, and the new records added.
+
+    import_file("v98-2030-01-01-sparse-test-data_schema_v2.json", true).await;
+    assert_eq!(get_core_data_count(&pool).await, 24);
+
+    // Without the flag the tables are recreated.
+
+    import_file("v98-2030-01-01-sparse-test-data_schema_v2.json", false).await;
+    assert_eq!(get_core_data_count(&pool).await, 4);
+}
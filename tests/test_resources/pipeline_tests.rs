@@ -0,0 +1,57 @@
+// Checks that the pipeline can be driven directly from another program, 
+// with the parameters built in code rather than derived from CLI arguments.
+
+use ror1::run_pipeline;
+use std::path::PathBuf;
+
+use log::LevelFilter;
+use ror1::setup::{Flags, InitParams, SourceFile};
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test] 
+async fn pipeline_runs_from_params_built_in_code() {
+
+    let _db = lock_db().await;
+
+    let source_file = SourceFile {
+        file_name: "v99-2030-01-01-test-data_schema_v2.json".to_string(),
+        data_version: "v99".to_string(),
+        data_date: "2030-01-01".to_string(),
+    };
+    let params = InitParams {
+        data_folder: PathBuf::from("tests/test_data"),
+        log_folder: PathBuf::from(""),
+        output_folder: PathBuf::from(""),
+        text_output_folder: PathBuf::from(""),
+        csv_output_folder: PathBuf::from(""),
+        source_file_name: source_file.file_name.clone(),
+        source_files: vec![source_file],
+        output_file_name: "".to_string(),
+        data_version: "v99".to_string(),
+        data_date: "2030-01-01".to_string(),
+        since_date: "".to_string(),
+        history_count: 0,
+        max_memory_mb: None,
+        tables: Vec::new(),
+        types: Vec::new(),
+        trace_id: "".to_string(),
+        csv_delimiter: ',',
+        sqlite_path: "".to_string(),
+        compare_versions: Vec::new(),
+        flags: Flags { import_ror: true, test_run: true, ..Default::default() },
+        log_level: LevelFilter::Info,
+    };
+
+    let pool = fetch_db_pool().await.unwrap();
+    let report = run_pipeline(params, &pool).await.unwrap();
+
+    assert_eq!(report.files_imported, 1);
+    assert_eq!(report.records_found, 20);
+    assert_eq!(report.records_processed, 20);
+    assert_eq!(report.records_skipped, 0);
+
+    let sql = "select count(*) from ror.core_data";
+    let count: i64 = sqlx::query_scalar(sql).fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 20);
+}
@@ -0,0 +1,47 @@
+// Checks the summary of missing optional data, using a small fixture 
+// of deliberately sparse records.
+
+use ror1::{run, summarise_quality, ImportQuality};
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test] 
+async fn import_sparse_data_and_check_missing_counts() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    let cd_path = env::current_dir().unwrap();
+    let target_path : PathBuf = [cd_path, PathBuf::from("tests/test_data/")].iter().collect();
+    let target_folder = target_path.to_str().unwrap();
+    let target_file = "v98-2030-01-01-sparse-test-data_schema_v2.json";
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", target_folder, "-s", target_file, 
+                                "-v", "v98", "-d", "2030-01-01", "-r", "-z"];
+
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    let quality = summarise_quality(&pool).await.unwrap();
+    assert_eq!(quality, ImportQuality {
+        num_orgs: 4,
+        no_location: 2,
+        no_external_id: 2,
+        no_link: 1,
+        no_non_ror_name: 1,
+        suspect_coords: 0,
+    });
+
+    // Locations with in range, out of range and (0,0) placeholder coordinates.
+
+    let id: String = sqlx::query_scalar("SELECT id FROM ror.core_data ORDER BY id LIMIT 1").fetch_one(&pool).await.unwrap();
+    let sql = r#"INSERT INTO ror.locations (id, geonames_id, name, lat, lng) VALUES
+                 ($1, 1, 'in range', 51.5, -0.12), ($1, 2, 'out of range', 95.0, 10.0), 
+                 ($1, 3, 'out of range', 45.0, -190.0), ($1, 4, 'placeholder', 0.0, 0.0)"#;
+    sqlx::query(sql).bind(&id).execute(&pool).await.unwrap();
+    let quality = summarise_quality(&pool).await.unwrap();
+    assert_eq!(quality.suspect_coords, 3);
+}
@@ -0,0 +1,34 @@
+// Checks that with a read database configured (as READ_DATABASE_URL) the
+// reporting queries are given a separate pool, connected to that database.
+// The same database is used, but with a distinct application name, so that
+// the connections of each pool can be told apart.
+
+use ror1::setup::{get_db_pools_with, env_reader};
+use super::db_access::lock_db;
+
+#[tokio::test]
+async fn reporting_uses_read_pool_when_configured() {
+
+    let _db = lock_db().await;
+
+    env_reader::populate_env_vars().unwrap();
+    let db_name = env_reader::fetch_db_name().unwrap();
+    let read_conn_string = format!("{}?application_name=ror1_reporting", env_reader::fetch_db_conn_string(db_name).unwrap());
+
+    let sql = "select current_setting('application_name')";
+    let pools = temp_env::async_with_vars([("READ_DATABASE_URL", Some(read_conn_string.as_str())),
+                                           ("read_db_conn_string", None)],
+                                          get_db_pools_with(true)).await.unwrap();
+    let read_app: String = sqlx::query_scalar(sql).fetch_one(pools.reporting()).await.unwrap();
+    let write_app: String = sqlx::query_scalar(sql).fetch_one(&pools.write).await.unwrap();
+    assert_eq!(read_app, "ror1_reporting");
+    assert_ne!(write_app, "ror1_reporting");
+
+    // Without it the reporting queries share the write pool.
+
+    let pools = temp_env::async_with_vars([("READ_DATABASE_URL", None::<&str>), ("read_db_conn_string", None)],
+                                          get_db_pools_with(true)).await.unwrap();
+    assert!(pools.read.is_none());
+    let app: String = sqlx::query_scalar(sql).fetch_one(pools.reporting()).await.unwrap();
+    assert_eq!(app, write_app);
+}
@@ -0,0 +1,54 @@
+// Checks that src tables with the expected columns are kept (and emptied) 
+// when the src tables are created for a transform, so that a view depending 
+// on them survives, but that they are recreated if forced (by --recreate-src) 
+// or if a table has the wrong shape.
+
+use ror1::create_src_tables;
+
+use sqlx::{Postgres, Pool};
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+async fn view_exists(pool: &Pool<Postgres>) -> bool {
+    let sql = "select count(*) from information_schema.views where table_schema = 'src' and table_name = 'recreate_test_view'";
+    let n: i64 = sqlx::query_scalar(sql).fetch_one(pool).await.unwrap();
+    n == 1
+}
+
+async fn column_exists(table_name: &str, column_name: &str, pool: &Pool<Postgres>) -> bool {
+    let sql = r#"select count(*) from information_schema.columns 
+                 where table_schema = 'src' and table_name = $1 and column_name = $2"#;
+    let n: i64 = sqlx::query_scalar(sql).bind(table_name).bind(column_name).fetch_one(pool).await.unwrap();
+    n == 1
+}
+
+
+#[tokio::test] 
+async fn src_tables_kept_unless_forced_or_changed() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+    create_src_tables(false, true, &pool).await.unwrap();
+
+    sqlx::raw_sql(r#"insert into src.core_data (id, ror_full_id, ror_name) values ('0test0001', 'https://ror.org/0test0001', 'Test');
+                     create view src.recreate_test_view as select id, ror_name from src.core_data;"#)
+        .execute(&pool).await.unwrap();
+
+    // Tables of the expected shape - kept, with the view, but emptied.
+
+    create_src_tables(false, false, &pool).await.unwrap();
+    assert!(view_exists(&pool).await);
+    let n: i64 = sqlx::query_scalar("select count(*) from src.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(n, 0);
+
+    // Forced - recreated, so the view is dropped with src.core_data.
+
+    create_src_tables(false, true, &pool).await.unwrap();
+    assert!(!view_exists(&pool).await);
+
+    // A table without one of its columns - recreated.
+
+    sqlx::raw_sql("alter table src.links drop column link_type").execute(&pool).await.unwrap();
+    create_src_tables(false, false, &pool).await.unwrap();
+    assert!(column_exists("links", "link_type", &pool).await);
+}
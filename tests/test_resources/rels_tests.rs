@@ -0,0 +1,27 @@
+// Checks that the relationship integrity check reports a relationship to an 
+// organisation missing from the data, and a parent / child relationship 
+// without its inverse.
+
+use ror1::run;
+use std::ffi::OsString;
+
+use ror1::check_relationships;
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test] 
+async fn dangling_and_one_sided_relationships_reported() {
+
+    let _db = lock_db().await;
+
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", 
+                                "v95-2030-01-01-rels-test-data_schema_v2.json", "-r", "-p", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    let pool = fetch_db_pool().await.unwrap();
+    let check = check_relationships(&pool).await.unwrap();
+
+    assert_eq!(check.dangling, vec![("0rxtest01".to_string(), "0rxtest99".to_string())]);
+    assert_eq!(check.missing_inverse, vec![("0rxtest02".to_string(), "child".to_string(), "0rxtest03".to_string())]);
+}
@@ -0,0 +1,39 @@
+// Checks that with --report each of the summaries of the data already in the 
+// database is written to the log - the ror table counts and the breakdowns 
+// by country, type and decade established, with the external id coverage - 
+// and that nothing is imported or exported. The data is loaded first, as a 
+// test run (so without a log), and the report is then run, by the program 
+// binary, with a log in a temporary folder.
+
+use std::fs;
+use super::db_access::{lock_db, load_test_data, run_binary_with_log};
+
+
+#[tokio::test]
+async fn each_summary_logged_by_report() {
+
+    let _db = lock_db().await;
+
+    load_test_data(true).await;
+
+    let log_folder = std::env::temp_dir().join("ror1_report_test");
+    let _ = fs::remove_dir_all(&log_folder);
+
+    let output = run_binary_with_log(&["--report", "-x", "-t"], &log_folder, &[]);
+    assert!(output.status.success());
+
+    let log_file = fs::read_dir(&log_folder).unwrap()
+                        .map(|e| e.unwrap().path())
+                        .find(|p| p.extension().is_some_and(|x| x == "log")).unwrap();
+    let contents = fs::read_to_string(log_file).unwrap();
+    assert!(contents.contains("report: true"));
+    assert!(contents.contains("Total record numbers for each table:"));
+    assert!(contents.contains("Total records in ror.core_data: 20"));
+    assert!(contents.contains("Organisations by country:"));
+    assert!(contents.contains("Organisations by type (20 organisations):"));
+    assert!(contents.contains("Organisations by decade established:"));
+    assert!(contents.contains("External id coverage (20 organisations):"));
+    assert!(!contents.contains("missing or incomplete"));
+    assert!(!contents.contains("Data summary generated"));
+    assert!(!contents.contains("tables analysed"));
+}
@@ -0,0 +1,87 @@
+// Checks that an interrupted import can be resumed (with --resume) from the
+// checkpoint written after each batch (with --checkpoint), giving the
+// complete set of records, and that no checkpoint is written without it.
+// The fixture's 20 records fit in a single batch, so the part of the file
+// stored before the interruption is simulated with a copy of the file that
+// holds only its first 12 records - the interrupted run leaves a checkpoint
+// of 12, and the resumed run, of the complete file, stores the other 8.
+// A stop request applies only to the import it is made in (it is cleared
+// when that import ends), so it is made again before each interrupted run,
+// and an import that follows one that was stopped runs in full.
+
+use ror1::run;
+use ror1::setup::interrupt;
+use std::ffi::OsString;
+use std::fs;
+
+use ror1::error_defs::AppError;
+use super::db_access::{lock_db, fetch_db_pool, TEST_DATA_FILE};
+
+
+#[tokio::test]
+async fn interrupted_import_resumed_from_checkpoint() {
+
+    let _db = lock_db().await;
+
+    let target_file = "v99-2030-01-01-test-data_schema_v2.json";
+    let data_folder = std::env::temp_dir().join("ror1_resume_test");
+    let _ = fs::remove_dir_all(&data_folder);
+    fs::create_dir_all(&data_folder).unwrap();
+    let source_path = data_folder.join(target_file);
+    let checkpoint_path = data_folder.join(format!("{}.checkpoint", target_file));
+
+    let data = fs::read_to_string(format!("tests/test_data/{}", target_file)).unwrap();
+    let records: Vec<serde_json::Value> = serde_json::from_str(&data).unwrap();
+    fs::write(&source_path, serde_json::to_string(&records[..12]).unwrap()).unwrap();
+
+    let args = |extra: &[&str]| {
+        let mut args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", data_folder.to_str().unwrap(),
+                                        "-s", target_file, "-r", "-z"];
+        args.extend_from_slice(extra);
+        args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>()
+    };
+
+    interrupt::request_stop();
+    let err = run(args(&[])).await.unwrap_err();
+    assert!(matches!(err, AppError::IrErr(_)));
+    assert!(!checkpoint_path.exists());
+
+    interrupt::request_stop();
+    let err = run(args(&["--checkpoint"])).await.unwrap_err();
+    assert!(matches!(err, AppError::IrErr(_)));
+    assert_eq!(fs::read_to_string(&checkpoint_path).unwrap(), "12\n");
+
+    let pool = fetch_db_pool().await.unwrap();
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 12);
+
+    fs::write(&source_path, &data).unwrap();
+    run(args(&["--resume"])).await.unwrap();
+
+    let sql = "select count(*), count(distinct id) from ror.core_data";
+    let (count, distinct): (i64, i64) = sqlx::query_as(sql).fetch_one(&pool).await.unwrap();
+    assert_eq!((count, distinct), (20, 20));
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.names").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 56);
+    assert!(!checkpoint_path.exists());
+}
+
+
+#[tokio::test]
+async fn import_after_interrupted_import_runs_in_full() {
+
+    let _db = lock_db().await;
+
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", TEST_DATA_FILE, "-r", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+
+    interrupt::request_stop();
+    let err = run(test_args.clone()).await.unwrap_err();
+    assert!(matches!(err, AppError::IrErr(_)));
+    assert!(!interrupt::stop_requested());
+
+    run(test_args).await.unwrap();
+    let pool = fetch_db_pool().await.unwrap();
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 20);
+}
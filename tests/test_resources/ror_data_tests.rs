@@ -3,37 +3,22 @@
 // Test samples should exist for each version of the v2 schema 
 // to test that all file types can be correctly processed.
 
-use ror1::run;
+use ror1::{run, write_name_type_breakdown, NameTypeCount};
 use std::env;
 use std::ffi::OsString;
 use std::path::PathBuf;
-use std::thread;
-use std::time::Duration;
 use chrono::NaiveDate;
 
-use sqlx::{Postgres, Pool};
-use ror1::error_defs::AppError;
-use ror1::setup::get_db_pool;
-use ror1::setup::env_reader;
-
 use super::ror_record_structs::{RorCoreData, RorRelationship, RorExternalId, 
                             RorName, RorLocation, RorLink, RorType, RorAdminData};
 use super::ror_data_access;
-
-
-pub async fn fetch_db_pool() -> Result<Pool<Postgres>, AppError>  {
-    
-    // Use the process set up in the library under test
-    // Helps to ensure exactly the same database connections are used
-
-    env_reader::populate_env_vars()?; 
-    get_db_pool().await
-}
+use super::db_access::{lock_db, fetch_db_pool, load_test_data};
 
 
 #[tokio::test] 
 async fn import_v2_0_data_to_ror_and_check_org_numbers() {
 
+    let _db = lock_db().await;
     let pool = fetch_db_pool().await.unwrap();
 
     let cd_path = env::current_dir().unwrap();
@@ -54,8 +39,8 @@ async fn import_v2_0_data_to_ror_and_check_org_numbers() {
 #[tokio::test] 
 async fn check_numbers_in_each_ror_table() {
 
-    thread::sleep(Duration::from_secs(1));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(false).await;
 
     let rec_number = ror_data_access::fetch_ror_record_num("names", &pool).await;
     assert_eq!(rec_number, 56);
@@ -70,11 +55,24 @@ async fn check_numbers_in_each_ror_table() {
 }
 
 
+#[tokio::test] 
+async fn check_name_type_breakdown() {
+
+    let _db = lock_db().await;
+    let pool = load_test_data(false).await;
+
+    let counts = write_name_type_breakdown(&pool).await.unwrap();
+    let expected = [("label", 25), ("alias", 16), ("acronym", 15), ("ror name", 20)];
+    assert_eq!(counts, expected.iter().map(|(t, n)| NameTypeCount { name_type: t.to_string(), num_names: *n })
+                               .collect::<Vec<NameTypeCount>>());
+}
+
+
 #[tokio::test] 
 async fn check_ror_first_and_last_ids() {
 
-    thread::sleep(Duration::from_secs(1));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(false).await;
 
     // Check first and last record Ids
     let first_id = ror_data_access::fetch_ror_first_record_id(&pool).await;
@@ -88,8 +86,8 @@ async fn check_ror_first_and_last_ids() {
 #[tokio::test] 
 async fn check_ror_core_data() {
 
-    thread::sleep(Duration::from_secs(1));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(false).await;
 
     let id = "006jxzx88";
 
@@ -124,8 +122,8 @@ async fn check_ror_core_data() {
 #[tokio::test] 
 async fn check_ror_relationship_data() {
 
-    thread::sleep(Duration::from_secs(2));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(false).await;
 
     let id = "03rd8mf35";
     let rels:Vec<RorRelationship> = ror_data_access::fetch_ror_relationship_records(id, &pool).await;
@@ -149,8 +147,8 @@ async fn check_ror_relationship_data() {
 #[tokio::test] 
 async fn check_ror_external_id_data() {
 
-    thread::sleep(Duration::from_secs(2));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(false).await;
 
     let id = "04ttjf776";
     let extids:Vec<RorExternalId> = ror_data_access::fetch_ror_external_id_records(id, &pool).await;
@@ -177,8 +175,8 @@ async fn check_ror_external_id_data() {
 #[tokio::test] 
 async fn check_ror_location_data() {
 
-    thread::sleep(Duration::from_secs(2));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(false).await;
 
     let id = "006jxzx88";
     let locs:Vec<RorLocation> = ror_data_access::fetch_ror_location_records(id, &pool).await;
@@ -205,8 +203,8 @@ async fn check_ror_location_data() {
 #[tokio::test] 
 async fn check_ror_link_data() {
 
-    thread::sleep(Duration::from_secs(2));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(false).await;
 
     let id = "006jxzx88";
     let links:Vec<RorLink> = ror_data_access::fetch_ror_link_records(id, &pool).await;
@@ -229,8 +227,8 @@ async fn check_ror_link_data() {
 #[tokio::test] 
 async fn check_ror_type_data() {
 
-    thread::sleep(Duration::from_secs(2));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(false).await;
 
     let id = "006jxzx88";
     let types:Vec<RorType> = ror_data_access::fetch_ror_type_records(id, &pool).await;
@@ -248,8 +246,8 @@ async fn check_ror_type_data() {
 #[tokio::test]
 async fn check_ror_name_data() {
 
-    thread::sleep(Duration::from_secs(2));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(false).await;
 
     let id = "0198t0w55";
     let names:Vec<RorName> = ror_data_access::fetch_ror_name_records(id, &pool).await;
@@ -274,10 +272,3 @@ async fn check_ror_name_data() {
 
 
 
-
-
-
-
-
-
-
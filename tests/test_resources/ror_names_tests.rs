@@ -0,0 +1,53 @@
+// Checks that a single ror name is set for each organisation in src.names, 
+// using a fixture with a normal record, a record without a 'ror_display' 
+// name, and a record with two, listed in reverse alphabetical order.
+
+use ror1::run;
+use std::ffi::OsString;
+
+use sqlx::{Postgres, Pool};
+use ror1::set_ror_names;
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+async fn fetch_ror_name_flags(id: &str, pool: &Pool<Postgres>) -> Vec<(String, bool)> {
+    let sql = "select value, is_ror_name from src.names where id = $1 and name_type = 5 order by value";
+    sqlx::query_as(sql).bind(id).fetch_all(pool).await.unwrap()
+}
+
+
+#[tokio::test] 
+async fn single_ror_name_set_for_each_organisation() {
+
+    let _db = lock_db().await;
+
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", 
+                                "v94-2030-01-01-ror-names-test-data_schema_v2.json", "-r", "-p", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    let pool = fetch_db_pool().await.unwrap();
+
+    // The normal record keeps its ror name, and the record with two keeps 
+    // only the one used as its ror_name - the first listed in the source, 
+    // rather than the first alphabetically. The record without one is not 
+    // in the src tables.
+
+    assert_eq!(fetch_ror_name_flags("0rntest01", &pool).await, vec![("Ror Name Test Normal".to_string(), true)]);
+    assert_eq!(fetch_ror_name_flags("0rntest03", &pool).await, 
+               vec![("Ror Name Test Alpha".to_string(), false), ("Ror Name Test Beta".to_string(), true)]);
+    let sql = "select id, ror_name from src.core_data order by id";
+    let core_data: Vec<(String, String)> = sqlx::query_as(sql).fetch_all(&pool).await.unwrap();
+    assert_eq!(core_data, vec![("0rntest01".to_string(), "Ror Name Test Normal".to_string()),
+                               ("0rntest03".to_string(), "Ror Name Test Beta".to_string())]);
+
+    // Restoring the second candidate shows it is reported, and corrected again.
+
+    sqlx::query("update src.names set is_ror_name = true where id = '0rntest03'").execute(&pool).await.unwrap();
+    let check = set_ror_names(&pool).await.unwrap();
+    assert_eq!(check.without_ror_name, vec!["0rntest02"]);
+    assert_eq!(check.multiple_ror_names, vec![("0rntest03".to_string(), "Ror Name Test Beta".to_string())]);
+    assert!(check.not_single_after_update.is_empty());
+    assert_eq!(fetch_ror_name_flags("0rntest03", &pool).await, 
+               vec![("Ror Name Test Alpha".to_string(), false), ("Ror Name Test Beta".to_string(), true)]);
+}
@@ -0,0 +1,122 @@
+// Checks that the source data can be imported into a SQLite database (with
+// --sqlite) rather than Postgres - into an in-memory database, using the 
+// library directly, and into a database file, by running the program. Data
+// of several batches is also imported, so that the rows of a batch are
+// inserted in more than one statement. No Postgres database is used, so the
+// tests do not need the database lock, and can be run on their own, without
+// a Postgres server, with 'cargo test sqlite'.
+
+use ror1::{run, get_sqlite_pool, import_into_sqlite};
+use ror1::setup::get_params;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+use serde_json::Value;
+
+use sqlx::{Pool, Sqlite};
+use super::db_access::TEST_DATA_FILE;
+
+
+async fn fetch_table_counts(pool: &Pool<Sqlite>) -> Vec<(String, i64)> {
+    let mut counts = Vec::new();
+    for table in ["core_data", "admin_data", "names", "locations", "external_ids", "links", "type", "relationships", "domains"] {
+        let count: i64 = sqlx::query_scalar(&format!("select count(*) from {}", table)).fetch_one(pool).await.unwrap();
+        counts.push((table.to_string(), count));
+    }
+    counts
+}
+
+
+fn get_expected_counts() -> Vec<(String, i64)> {
+    [("core_data", 20), ("admin_data", 20), ("names", 56), ("locations", 20), ("external_ids", 59), 
+     ("links", 33), ("type", 30), ("relationships", 25), ("domains", 0)]
+        .iter().map(|(t, n)| (t.to_string(), *n)).collect()
+}
+
+
+#[tokio::test] 
+async fn data_imported_into_in_memory_sqlite() {
+
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", TEST_DATA_FILE, "-z", "--sqlite", ":memory:"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    let params = get_params(test_args).await.unwrap();
+    let pool = get_sqlite_pool(&params.sqlite_path).await.unwrap();
+    let report = import_into_sqlite(&params, &pool).await.unwrap();
+
+    assert_eq!(report.files_imported, 1);
+    assert_eq!(report.records_processed, 20);
+    assert_eq!(fetch_table_counts(&pool).await, get_expected_counts());
+
+    let sql = "select version, data_date from version_details";
+    let version: (String, String) = sqlx::query_as(sql).fetch_one(&pool).await.unwrap();
+    assert_eq!(version, ("v99".to_string(), "2030-01-01".to_string()));
+    let sql = "select ror_full_id, status, established from core_data where id = '04ttjf776'";
+    let core_data: (String, String, Option<i64>) = sqlx::query_as(sql).fetch_one(&pool).await.unwrap();
+    assert_eq!(core_data, ("https://ror.org/04ttjf776".to_string(), "active".to_string(), Some(1887)));
+    let sql = "select value from names where id = '04ttjf776' and is_ror_name = true";
+    let ror_name: String = sqlx::query_scalar(sql).fetch_one(&pool).await.unwrap();
+    assert_eq!(ror_name, "RMIT University");
+}
+
+
+#[tokio::test] 
+async fn multi_batch_data_imported_into_in_memory_sqlite() {
+
+    // 30 copies of the test data give 600 records - two full batches and a
+    // part batch - with over 700 external ids in each full batch.
+
+    let copies = 30;
+    let data = fs::read_to_string(format!("tests/test_data/{}", TEST_DATA_FILE)).unwrap();
+    let records: Vec<Value> = serde_json::from_str(&data).unwrap();
+    let mut big_records: Vec<Value> = Vec::new();
+    for k in 0..copies {
+        for (i, r) in records.iter().enumerate() {
+            let mut new_r = r.clone();
+            new_r["id"] = Value::String(format!("https://ror.org/0sqt{:03}{:02}", k, i));
+            big_records.push(new_r);
+        }
+    }
+    let target_path : PathBuf = [env::temp_dir(), PathBuf::from("ror1_sqlite_batch_test")].iter().collect();
+    fs::create_dir_all(&target_path).unwrap();
+    fs::write(target_path.join(TEST_DATA_FILE), serde_json::to_string(&big_records).unwrap()).unwrap();
+
+    let target_folder = target_path.to_str().unwrap();
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", target_folder, "-s", TEST_DATA_FILE, "-z", "--sqlite", ":memory:"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    let params = get_params(test_args).await.unwrap();
+    let pool = get_sqlite_pool(&params.sqlite_path).await.unwrap();
+    let report = import_into_sqlite(&params, &pool).await.unwrap();
+
+    assert_eq!(report.records_processed, copies * records.len());
+    let expected: Vec<(String, i64)> = get_expected_counts().into_iter()
+                                         .map(|(t, n)| (t, n * copies as i64)).collect();
+    assert_eq!(fetch_table_counts(&pool).await, expected);
+    fs::remove_dir_all(&target_path).unwrap();
+}
+
+
+#[tokio::test] 
+async fn data_imported_into_sqlite_file() {
+
+    let db_path = std::env::temp_dir().join("ror1_sqlite_test.db");
+    let _ = fs::remove_file(&db_path);
+    let db_path = db_path.to_str().unwrap().to_string();
+
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", TEST_DATA_FILE, "-r", "-z", "--sqlite", &db_path];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args.clone()).await.unwrap();
+
+    // Importing the file again, into the existing tables, fails on the 
+    // primary key of core_data, and leaves the tables as they were.
+
+    let mut args = args.clone();
+    args.push("--no-recreate");
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    assert!(run(test_args).await.is_err());
+
+    let pool = get_sqlite_pool(&db_path).await.unwrap();
+    assert_eq!(fetch_table_counts(&pool).await, get_expected_counts());
+    pool.close().await;
+    let _ = fs::remove_file(&db_path);
+}
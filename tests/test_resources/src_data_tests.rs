@@ -1,37 +1,24 @@
 use ror1::run;
 use std::ffi::OsString;
-use std::thread;
-use std::time::Duration;
 use chrono::NaiveDate;
 
-use sqlx::{Postgres, Pool};
-use ror1::error_defs::AppError;
-use ror1::setup::get_db_pool;
-use ror1::setup::env_reader;
-
+use ror1::{summarise_by_country, summarise_by_established, summarise_external_id_coverage, export_country_csv, CsvFormat};
 use super::src_data_access;
+use super::db_access::{lock_db, load_test_data};
 use super::src_record_structs::{SrcCoreData, SrcRelationship, SrcExternalId, 
     SrcName, SrcLocation, SrcLink, SrcType, SrcAdminData};
 
-pub async fn fetch_db_pool() -> Result<Pool<Postgres>, AppError>  {
-
-    // Use the process set up in the library under test
-    // Helps to ensure exactly the same database connections are used
-
-    env_reader::populate_env_vars()?; 
-    get_db_pool().await
-}
 
 #[tokio::test] 
 async fn process_v2_0_data_to_src_and_summarise() {
 
     // Arrange     
-    // Get database pool to allow interrogation of the DB
-    let pool = fetch_db_pool().await.unwrap();
+    // Import the test data into the ror tables, and get the database pool
+    let _db = lock_db().await;
+    let pool = load_test_data(false).await;
   
     // Act 
     // Run the program with v2.0 test data
-    thread::sleep(Duration::from_secs(4));
     let args : Vec<&str> = vec!["target/debug/src1.exe", "-p", "-z"];
     let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
     run(test_args).await.unwrap();
@@ -46,8 +33,8 @@ async fn process_v2_0_data_to_src_and_summarise() {
 #[tokio::test] 
 async fn check_numbers_in_each_src_table() {
 
-    thread::sleep(Duration::from_secs(6));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
 
     let rec_number = src_data_access::fetch_src_record_num("names", &pool).await;
     assert_eq!(rec_number, 56);
@@ -62,11 +49,110 @@ async fn check_numbers_in_each_src_table() {
 }
 
 
+#[tokio::test] 
+async fn check_src_country_counts() {
+
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
+
+    let countries = summarise_by_country(&pool).await.unwrap();
+    assert_eq!(countries.len(), 11);
+    assert_eq!(countries[0].country_code, "AU");
+    assert_eq!(countries[0].country_name, "Australia");
+    assert_eq!(countries[0].num_orgs, 7);
+    assert_eq!(countries[1].country_code, "GB");
+    assert_eq!(countries[1].num_orgs, 3);
+    assert_eq!(countries[2].country_code, "US");
+    assert_eq!(countries[2].num_orgs, 2);
+    assert_eq!(countries[3].country_code, "CH");
+    assert_eq!(countries[3].num_orgs, 1);
+    let total: i64 = countries.iter().map(|c| c.num_orgs).sum();
+    assert_eq!(total, 20);
+}
+
+
+#[tokio::test] 
+async fn check_src_country_csv() {
+
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
+
+    let out_folder = std::env::temp_dir().join("ror1_country_csv_test");
+    let _ = std::fs::remove_dir_all(&out_folder);
+    std::fs::create_dir_all(&out_folder).unwrap();
+    export_country_csv(&out_folder, &CsvFormat::default(), &pool).await.unwrap();
+
+    let csv_files: Vec<_> = std::fs::read_dir(&out_folder).unwrap().map(|e| e.unwrap().path()).collect();
+    assert_eq!(csv_files.len(), 1);
+    let file_name = csv_files[0].file_name().unwrap().to_str().unwrap().to_string();
+    assert!(file_name.starts_with("v99 country counts ") && file_name.ends_with(".csv"));
+    assert_eq!(std::fs::read_to_string(&csv_files[0]).unwrap(), "country_code,country_name,org_count
+AU,Australia,7
+GB,United Kingdom,3
+US,United States,2
+CH,Switzerland,1
+DE,Germany,1
+GR,Greece,1
+IN,India,1
+IT,Italy,1
+JP,Japan,1
+TR,Türkiye,1
+UA,Ukraine,1
+");
+}
+
+
+#[tokio::test] 
+async fn check_src_country_csv_with_semicolons_and_bom() {
+
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
+
+    let out_folder = std::env::temp_dir().join("ror1_country_csv_bom_test");
+    let _ = std::fs::remove_dir_all(&out_folder);
+    std::fs::create_dir_all(&out_folder).unwrap();
+    export_country_csv(&out_folder, &CsvFormat { delimiter: ';', bom: true }, &pool).await.unwrap();
+
+    let csv_files: Vec<_> = std::fs::read_dir(&out_folder).unwrap().map(|e| e.unwrap().path()).collect();
+    let bytes = std::fs::read(&csv_files[0]).unwrap();
+    assert_eq!(&bytes[..3], &[0xef, 0xbb, 0xbf]);
+    let csv = String::from_utf8(bytes[3..].to_vec()).unwrap();
+    assert!(csv.starts_with("country_code;country_name;org_count\nAU;Australia;7\nGB;United Kingdom;3\n"));
+    assert!(!csv.contains(','));
+}
+
+
+#[tokio::test] 
+async fn check_src_established_decades() {
+
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
+
+    let histogram = summarise_by_established(&pool).await.unwrap();
+    let decades: Vec<(i32, i64)> = histogram.decades.iter().map(|d| (d.decade, d.num_orgs)).collect();
+    assert_eq!(decades, vec![(1880, 1), (1900, 2), (1930, 1), (1940, 1), (1960, 3), 
+                             (1980, 5), (1990, 2), (2000, 1), (2010, 3)]);
+    assert_eq!(histogram.num_without, 1);
+}
+
+
+#[tokio::test] 
+async fn check_src_external_id_coverage() {
+
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
+
+    let coverage = summarise_external_id_coverage(&pool).await.unwrap();
+    let counts: Vec<(&str, i64, f64)> = coverage.iter().map(|c| (c.id_type.as_str(), c.num_orgs, c.pc_orgs)).collect();
+    assert_eq!(counts, vec![("isni", 18, 90.0), ("wikidata", 15, 75.0), ("grid", 14, 70.0), ("fundref", 10, 50.0)]);
+}
+
+
 #[tokio::test] 
 async fn check_src_first_and_last_ids() {
 
-    thread::sleep(Duration::from_secs(6));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
 
     // Check first and last record Ids
     let first_id = src_data_access::fetch_src_first_record_id(&pool).await;
@@ -80,8 +166,8 @@ async fn check_src_first_and_last_ids() {
 #[tokio::test] 
 async fn check_src_core_data() {
 
-    thread::sleep(Duration::from_secs(6));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
 
     let id = "006jxzx88";
 
@@ -131,11 +217,31 @@ async fn check_src_core_data() {
 
 }
 
+#[tokio::test] 
+async fn check_src_status_and_established_values() {
+
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
+
+    // An inactive organisation, and an active one with no established year.
+
+    let core_data: SrcCoreData = src_data_access::fetch_src_core_data_record ("04pfac868", &pool).await;
+    assert_eq!(core_data.ror_name, "Bear Fight Institute");
+    assert_eq!(core_data.status, 2);
+    assert_eq!(core_data.established, Some(2010));
+
+    let core_data: SrcCoreData = src_data_access::fetch_src_core_data_record ("02k57f568", &pool).await;
+    assert_eq!(core_data.ror_name, "Regione Emilia-Romagna");
+    assert_eq!(core_data.status, 1);
+    assert_eq!(core_data.established, None);
+}
+
+
 #[tokio::test] 
 async fn check_src_relationship_data() {
 
-    thread::sleep(Duration::from_secs(7));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
 
     let id = "03rd8mf35";
     let rels:Vec<SrcRelationship> = src_data_access::fetch_src_relationship_records(id, &pool).await;
@@ -159,8 +265,8 @@ async fn check_src_relationship_data() {
 #[tokio::test] 
 async fn check_src_external_id_data() {
 
-    thread::sleep(Duration::from_secs(7));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
 
     let id = "04ttjf776";
     let extids:Vec<SrcExternalId> = src_data_access::fetch_src_external_id_records(id, &pool).await;
@@ -188,8 +294,8 @@ async fn check_src_external_id_data() {
 #[tokio::test] 
 async fn check_src_location_data() {
 
-    thread::sleep(Duration::from_secs(7));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
 
     let id = "006jxzx88";
     let locs:Vec<SrcLocation> = src_data_access::fetch_src_location_records(id, &pool).await;
@@ -217,8 +323,8 @@ async fn check_src_location_data() {
 #[tokio::test] 
 async fn check_src_link_data() {
 
-    thread::sleep(Duration::from_secs(7));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
 
     let id = "006jxzx88";
     let links:Vec<SrcLink> = src_data_access::fetch_src_link_records(id, &pool).await;
@@ -245,8 +351,8 @@ async fn check_src_link_data() {
 #[tokio::test] 
 async fn check_src_type_data() {
 
-    thread::sleep(Duration::from_secs(7));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
 
     let id = "006jxzx88";
     let types:Vec<SrcType> = src_data_access::fetch_src_type_records(id, &pool).await;
@@ -265,8 +371,8 @@ async fn check_src_type_data() {
 #[tokio::test]
 async fn check_src_name_data() {
 
-    thread::sleep(Duration::from_secs(7));
-    let pool = fetch_db_pool().await.unwrap();
+    let _db = lock_db().await;
+    let pool = load_test_data(true).await;
 
     let id = "0198t0w55";
     let names:Vec<SrcName> = src_data_access::fetch_src_name_records(id, &pool).await;
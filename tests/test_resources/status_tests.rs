@@ -0,0 +1,73 @@
+// Checks that with --exclude-withdrawn or --active-only records with the 
+// excluded statuses are not imported, and are counted in the import report.
+// The fixture has 6 records - 3 active, 1 inactive and 2 withdrawn.
+
+use ror1::run_pipeline;
+use std::path::PathBuf;
+
+use sqlx::{Postgres, Pool};
+use log::LevelFilter;
+use ror1::setup::{Flags, InitParams, SourceFile};
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+fn get_params(flags: Flags) -> InitParams {
+    let source_file = SourceFile {
+        file_name: "v93-2030-01-01-status-test-data_schema_v2.json".to_string(),
+        data_version: "v93".to_string(),
+        data_date: "2030-01-01".to_string(),
+    };
+    InitParams {
+        data_folder: PathBuf::from("tests/test_data"),
+        log_folder: PathBuf::new(),
+        output_folder: PathBuf::new(),
+        text_output_folder: PathBuf::new(),
+        csv_output_folder: PathBuf::new(),
+        source_files: vec![source_file.clone()],
+        source_file_name: source_file.file_name.clone(),
+        output_file_name: "".to_string(),
+        data_version: source_file.data_version.clone(),
+        data_date: source_file.data_date.clone(),
+        since_date: "".to_string(),
+        history_count: 0,
+        max_memory_mb: None,
+        tables: Vec::new(),
+        types: Vec::new(),
+        trace_id: "".to_string(),
+        csv_delimiter: ',',
+        sqlite_path: "".to_string(),
+        compare_versions: Vec::new(),
+        flags: Flags { import_ror: true, test_run: true, ..flags },
+        log_level: LevelFilter::Info,
+    }
+}
+
+async fn fetch_statuses(pool: &Pool<Postgres>) -> Vec<String> {
+    let sql = "SELECT status FROM ror.core_data ORDER BY status";
+    sqlx::query_scalar(sql).fetch_all(pool).await.unwrap()
+}
+
+
+#[tokio::test]
+async fn import_excludes_records_by_status() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    let report = run_pipeline(get_params(Flags { exclude_withdrawn: true, ..Flags::default() }), &pool).await.unwrap();
+    assert_eq!((report.records_found, report.records_processed, report.records_status_skipped), (6, 4, 2));
+    assert_eq!(fetch_statuses(&pool).await, vec!["active", "active", "active", "inactive"]);
+
+    let report = run_pipeline(get_params(Flags { active_only: true, ..Flags::default() }), &pool).await.unwrap();
+    assert_eq!((report.records_found, report.records_processed, report.records_status_skipped), (6, 3, 3));
+    assert_eq!(fetch_statuses(&pool).await, vec!["active", "active", "active"]);
+
+    // The excluded records' child rows are not imported either.
+
+    let sql = "SELECT COUNT(*) FROM ror.names WHERE id NOT IN (SELECT id FROM ror.core_data)";
+    let orphans: i64 = sqlx::query_scalar(sql).fetch_one(&pool).await.unwrap();
+    assert_eq!(orphans, 0);
+
+    let report = run_pipeline(get_params(Flags::default()), &pool).await.unwrap();
+    assert_eq!((report.records_processed, report.records_status_skipped), (6, 0));
+}
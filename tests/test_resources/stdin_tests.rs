@@ -0,0 +1,110 @@
+// Checks that source data can be imported from a reader, as it is when 
+// piped to the program with --stdin, using a cursor over a fixture file to 
+// stand in for stdin. The data is streamed, so a repeated id is only found 
+// as the records are stored, and stops the import. When data is piped to
+// the program the import is within a single transaction, so that none of
+// it is then kept.
+
+use ror1::{create_ror_tables, import_from_reader};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Cursor, Write};
+use std::process::{Command, Stdio};
+use serde_json::Value;
+
+use ror1::error_defs::AppError;
+use ror1::setup::{Flags, SourceFile, STDIN_SOURCE_NAME};
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test] 
+async fn data_imported_from_a_reader() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+    create_ror_tables(false, &pool).await.unwrap();
+
+    let bytes = fs::read("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+    let sf = SourceFile {
+        file_name: STDIN_SOURCE_NAME.to_string(),
+        data_version: "v99".to_string(),
+        data_date: "2030-01-01".to_string(),
+    };
+    let report = import_from_reader(Cursor::new(bytes.clone()), &sf, "", &[], &Flags::default(), &pool).await.unwrap();
+
+    assert_eq!(report.files_imported, 1);
+    assert_eq!(report.records_found, 20);
+    assert_eq!(report.records_processed, 20);
+    assert_eq!(report.source_digests.len(), 1);
+    assert_eq!(report.source_digests[0], format!("{:x}", Sha256::digest(&bytes)));
+
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 20);
+    let version: String = sqlx::query_scalar("select version from ror.version_details").fetch_one(&pool).await.unwrap();
+    assert_eq!(version, "v99");
+}
+
+
+#[tokio::test] 
+async fn repeated_id_from_a_reader_stops_import() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+    create_ror_tables(false, &pool).await.unwrap();
+
+    let bytes = fs::read("tests/test_data/v96-2030-01-01-dup-ids-test-data_schema_v2.json").unwrap();
+    let sf = SourceFile {
+        file_name: STDIN_SOURCE_NAME.to_string(),
+        data_version: "v96".to_string(),
+        data_date: "2030-01-01".to_string(),
+    };
+    let res = import_from_reader(Cursor::new(bytes), &sf, "", &[], &Flags::default(), &pool).await;
+    match res {
+        Err(AppError::DtErr(e)) => assert!(e.to_string().contains("occurs more than once in stdin")),
+        _ => panic!("expected the import to stop at the repeated id"),
+    }
+
+    // All the records are within the first batch, so none had been stored.
+
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 0);
+}
+
+
+#[tokio::test] 
+async fn repeated_id_piped_to_program_keeps_no_records() {
+
+    // Run as the program is from a pipeline, with a repeated id in the third
+    // of three batches, so that two batches have been stored when it is found.
+
+    let _db = lock_db().await;
+
+    let data = fs::read_to_string("tests/test_data/v99-2030-01-01-test-data_schema_v2.json").unwrap();
+    let records: Vec<Value> = serde_json::from_str(&data).unwrap();
+    let mut big_records: Vec<Value> = Vec::new();
+    for k in 0..30 {
+        for (i, r) in records.iter().enumerate() {
+            let mut new_r = r.clone();
+            new_r["id"] = Value::String(format!("https://ror.org/0std{:03}{:02}", k, i));
+            big_records.push(new_r);
+        }
+    }
+    big_records[560]["id"] = big_records[10]["id"].clone();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ror1"))
+        .args(["-r", "--stdin", "-v", "v95", "-d", "2030-01-01", "-z"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to run the ror1 binary");
+    child.stdin.take().unwrap().write_all(serde_json::to_string(&big_records).unwrap().as_bytes()).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code(), Some(5));
+
+    let pool = fetch_db_pool().await.unwrap();
+    for table in ["version_details", "core_data", "admin_data", "names", "locations", "links"] {
+        let count: i64 = sqlx::query_scalar(&format!("select count(*) from ror.{}", table)).fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 0, "rows kept in ror.{}", table);
+    }
+}
@@ -0,0 +1,66 @@
+// Checks that a failure to store a batch of records ends the import with an 
+// error, rather than the import reporting success with data missing. Importing 
+// a file into tables that already hold its records (with --no-recreate) causes 
+// a primary key violation in ror.core_data. (Ids repeated within a file are 
+// found before anything is stored - see dup_ids_tests.) A failure in the last
+// group of tables to be stored, caused by a constraint added to ror.links, 
+// shows that none of the rows of the batch are kept.
+
+use ror1::run;
+use ror1::error_defs::AppError;
+use std::ffi::OsString;
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test] 
+async fn import_existing_ids_and_check_import_fails() {
+
+    let _db = lock_db().await;
+
+    let target_file = "v99-2030-01-01-test-data_schema_v2.json";
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", target_file, "-r", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", target_file, "-r", "-z", "--no-recreate"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    let res = run(test_args).await;
+
+    match res {
+        Err(AppError::SqErr(e)) => assert!(e.to_string().contains("duplicate key")),
+        _ => panic!("expected the import to fail with a database error"),
+    }
+}
+
+
+#[tokio::test] 
+async fn fail_storing_links_and_check_no_rows_kept() {
+
+    let _db = lock_db().await;
+
+    let target_file = "v99-2030-01-01-test-data_schema_v2.json";
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", target_file, "-r", "-z"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    let pool = fetch_db_pool().await.unwrap();
+    let tables = ["core_data", "admin_data", "names", "locations", "external_ids", "links", "type", "relationships", "domains"];
+    for table in tables {
+        sqlx::raw_sql(&format!("truncate table ror.{};", table)).execute(&pool).await.unwrap();
+    }
+    sqlx::raw_sql("alter table ror.links add constraint no_websites check (link_type <> 'website');").execute(&pool).await.unwrap();
+
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", target_file, "-r", "-z", "--no-recreate"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    let res = run(test_args).await;
+    sqlx::raw_sql("alter table ror.links drop constraint no_websites;").execute(&pool).await.unwrap();
+
+    match res {
+        Err(AppError::SqErr(e)) => assert!(e.to_string().contains("no_websites")),
+        _ => panic!("expected the import to fail with a database error"),
+    }
+    for table in tables {
+        let count: i64 = sqlx::query_scalar(&format!("select count(*) from ror.{}", table)).fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 0, "rows kept in ror.{}", table);
+    }
+}
@@ -0,0 +1,34 @@
+// Checks that with --trace-id (and -V) the rows generated for the traced
+// record are written to the log, at debug level, and that those of other
+// records are not. The program binary is run, with its log in a temporary 
+// folder.
+
+use std::fs;
+use super::db_access::{lock_db, run_binary_with_log};
+
+
+#[tokio::test]
+async fn traced_record_rows_logged() {
+
+    let _db = lock_db().await;
+
+    let log_folder = std::env::temp_dir().join("ror1_trace_test");
+    let _ = fs::remove_dir_all(&log_folder);
+
+    let args : Vec<&str> = vec!["-f", "tests/test_data", "-s",
+                                "v99-2030-01-01-test-data_schema_v2.json", "-r", "-V",
+                                "--trace-id", "https://ror.org/04ttjf776"];
+    let output = run_binary_with_log(&args, &log_folder, &[]);
+    assert!(output.status.success());
+
+    let log_file = fs::read_dir(&log_folder).unwrap()
+                        .map(|e| e.unwrap().path())
+                        .find(|p| p.extension().is_some_and(|x| x == "log")).unwrap();
+    let contents = fs::read_to_string(log_file).unwrap();
+    assert!(contents.contains("trace_id: 04ttjf776"));
+    assert!(contents.contains("Trace of 04ttjf776"));
+    assert!(contents.contains(r#"ror.core_data ("04ttjf776", "https://ror.org/04ttjf776""#));
+    assert!(contents.contains(r#"ror.names ("04ttjf776", "#));
+    assert!(!contents.contains(r#"ror.core_data ("039p7nx39""#));
+    assert!(!contents.contains("was not among the records imported"));
+}
@@ -0,0 +1,37 @@
+// Checks that with --types only records with at least one of the selected
+// organisation types are imported. The fixture has a mix of types - 10 of its
+// 20 records are education and / or healthcare organisations.
+
+use ror1::run;
+use std::ffi::OsString;
+
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+#[tokio::test]
+async fn import_selected_types_only() {
+
+    let _db = lock_db().await;
+    let pool = fetch_db_pool().await.unwrap();
+
+    let target_file = "v99-2030-01-01-test-data_schema_v2.json";
+    let args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s", target_file,
+                                "-r", "-z", "--types", "education,healthcare"];
+    let test_args = args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>();
+    run(test_args).await.unwrap();
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ror.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 10);
+
+    let sql = r#"SELECT COUNT(*) FROM ror.core_data c
+                 WHERE not exists (select 1 from ror.type t
+                                   where t.id = c.id and t.org_type in ('education', 'healthcare'))"#;
+    let unmatched: i64 = sqlx::query_scalar(sql).fetch_one(&pool).await.unwrap();
+    assert_eq!(unmatched, 0);
+
+    // Other types of the matching records are still imported.
+
+    let sql = "SELECT COUNT(*) FROM ror.type WHERE org_type = 'funder'";
+    let funders: i64 = sqlx::query_scalar(sql).fetch_one(&pool).await.unwrap();
+    assert_eq!(funders, 5);
+}
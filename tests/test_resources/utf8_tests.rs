@@ -0,0 +1,43 @@
+// Checks that a source file with a byte that is not valid UTF-8 (a latin-1
+// 'ü' in one of the names of the Fraunhofer record) is imported, with the 
+// invalid byte replaced by U+FFFD, and that with --strict-utf8 the import 
+// fails instead.
+
+use ror1::run;
+use std::ffi::OsString;
+
+use sqlx::{Postgres, Pool};
+use ror1::error_defs::AppError;
+use super::db_access::{lock_db, fetch_db_pool};
+
+
+async fn fetch_replaced_names(pool: &Pool<Postgres>) -> Vec<String> {
+    let sql = "select value from ror.names where id = '02s7vm534' and value like $1";
+    sqlx::query_scalar(sql).bind("%\u{fffd}%").fetch_all(pool).await.unwrap()
+}
+
+
+#[tokio::test]
+async fn invalid_utf8_replaced_unless_strict() {
+
+    let _db = lock_db().await;
+
+    let args = |extra: &[&str]| {
+        let mut args : Vec<&str> = vec!["target/debug/ror1.exe", "-f", "tests/test_data", "-s",
+                                        "v99-2030-01-01-utf8-test-data.json", "-r", "-z"];
+        args.extend_from_slice(extra);
+        args.iter().map(|x| x.to_string().into()).collect::<Vec<OsString>>()
+    };
+    let pool = fetch_db_pool().await.unwrap();
+
+    run(args(&[])).await.unwrap();
+    let count: i64 = sqlx::query_scalar("select count(*) from ror.core_data").fetch_one(&pool).await.unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(fetch_replaced_names(&pool).await, 
+               vec!["Fraunhofer-Einrichtung f\u{fffd}r Wertstoffkreisläufe und Ressourcenstrategie"]);
+
+    match run(args(&["--strict-utf8"])).await {
+        Err(AppError::DtErr(e)) => assert!(e.to_string().starts_with("invalid utf-8")),
+        _ => panic!("expected a source data error"),
+    }
+}